@@ -4,6 +4,7 @@
  *--------------------------------------------------------------------------------------------*/
 
 // TaffyInterop - P/Invoke bindings to libkeystone_layout (Taffy flexbox/grid)
+// Hand-written; `build.py --bindings` regenerates the raw surface into rust_ffi/layout/bindings/ to diff against
 using System.Runtime.InteropServices;
 
 namespace Keystone.Core.Platform;
@@ -12,24 +13,211 @@ public static class Taffy
 {
     private const string Lib = "libkeystone_layout";
 
+    // Must equal layout_abi_version() of the loaded binary; check it before any other call
+    public const uint AbiVersion = 1;
+
     // Enum mappings (match Rust u8 values)
     public enum Display : byte { Flex = 0, None = 1, Grid = 2, Block = 3 }
     public enum FlexDirection : byte { Column = 0, Row = 1, ColumnReverse = 2, RowReverse = 3 }
     public enum FlexWrap : byte { NoWrap = 0, Wrap = 1, WrapReverse = 2 }
     public enum AlignItems : byte { Auto = 0, FlexStart = 1, Center = 2, FlexEnd = 3, Stretch = 4, Baseline = 5 }
-    public enum JustifyContent : byte { Auto = 0, FlexStart = 1, Center = 2, FlexEnd = 3, SpaceBetween = 4, SpaceAround = 5, SpaceEvenly = 6 }
+    public enum JustifyContent : byte { Auto = 0, FlexStart = 1, Center = 2, FlexEnd = 3, SpaceBetween = 4, SpaceAround = 5, SpaceEvenly = 6, Stretch = 7 }
     public enum PositionType : byte { Relative = 0, Absolute = 1 }
+    public enum Direction : byte { Ltr = 0, Rtl = 1, Inherit = 2 }
     public enum Edge : byte { Left = 0, Top = 1, Right = 2, Bottom = 3, All = 4 }
+    public enum StyleProperty : byte
+    {
+        Width = 0, Height = 1, MinWidth = 2, MinHeight = 3, MaxWidth = 4, MaxHeight = 5,
+        InsetLeft = 6, InsetTop = 7, InsetRight = 8, InsetBottom = 9, FlexGrow = 10, GapRow = 11, GapColumn = 12,
+    }
+    public enum ScrollAxis : byte { Vertical = 0, Horizontal = 1 }
+    public enum LayoutEasing : byte { Linear = 0, CubicIn = 1, CubicOut = 2, CubicInOut = 3, QuadIn = 4, QuadOut = 5, QuadInOut = 6, BackOut = 7 }
+    public enum AnimationRepeat : byte { Once = 0, Loop = 1, PingPong = 2 }
+    public enum AnimationStatus : byte { Finished = 0, Stopped = 1 }
+    public enum MutationKind : byte { Style = 0, Created = 1, Removed = 2, ChildAdded = 3, ChildRemoved = 4 }
+    [Flags]
+    public enum LayoutFeatures : ulong
+    {
+        Flexbox = 1 << 0, Grid = 1 << 1, Block = 1 << 2, Serialization = 1 << 3, ParallelGroup = 1 << 4,
+        DebugAssertions = 1 << 5, Text = 1 << 6,
+    }
+
+    [Flags] public enum DebugFlags : uint { None = 0, AlignFallback = 1 << 0, JustifyFallback = 1 << 1, MinSizeFloor = 1 << 2, ConstraintConflict = 1 << 3 }
+
+    // Alignment codes use the AlignItems mapping; 0 = not requested / does not apply
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutDebugInfo
+    {
+        public DebugFlags Flags;
+        public byte AlignRequested, AlignEffective, JustifyRequested, JustifyEffective;
+    }
+
+    // Unit: 0 = auto, 1 = px, 2 = percent (0-100)
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutValue { public byte Unit; public float Value; }
+
+    // Enum fields use the setter codes (0 = unset alignment); edge arrays are left, top, right, bottom
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutStyleDesc
+    {
+        public byte Display, PositionType, FlexDirection, FlexWrap;
+        public byte AlignItems, AlignSelf, AlignContent, JustifyContent, JustifyItems, JustifySelf;
+        public byte OverflowX, OverflowY;
+        public float FlexGrow, FlexShrink;
+        public LayoutValue FlexBasis, Width, Height, MinWidth, MinHeight, MaxWidth, MaxHeight;
+        public float AspectRatio; // NaN = unset
+        [MarshalAs(UnmanagedType.ByValArray, SizeConst = 4)] public LayoutValue[] Padding;
+        [MarshalAs(UnmanagedType.ByValArray, SizeConst = 4)] public LayoutValue[] Margin;
+        [MarshalAs(UnmanagedType.ByValArray, SizeConst = 4)] public LayoutValue[] Border;
+        [MarshalAs(UnmanagedType.ByValArray, SizeConst = 4)] public LayoutValue[] Inset;
+        public LayoutValue GapRow, GapColumn;
+        public float ScrollbarWidth;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutMutation { public MutationKind Kind; public ulong Node; public ulong Other; }
+
+    public enum GridAutoFlow : byte { Row = 0, Column = 1, RowDense = 2, ColumnDense = 3 }
+    // RepeatTrack adds one more track to the repeat entry before it: repeat(2, 100px 1fr) = Repeat 100px, RepeatTrack 1fr
+    public enum GridTrackKind : byte { Single = 0, Repeat = 1, AutoFill = 2, AutoFit = 3, RepeatTrack = 4 }
+
+    // Min/Max units extend LayoutValue: 3 = fr, 4 = min-content, 5 = max-content, 6 = fit-content px, 7 = fit-content %
+    // (fr and fit-content only as Max). Different Min and Max make minmax(Min, Max).
+    public enum TrackUnit : byte { Auto = 0, Px = 1, Percent = 2, Fr = 3, MinContent = 4, MaxContent = 5, FitContentPx = 6, FitContentPercent = 7 }
+    [StructLayout(LayoutKind.Sequential)]
+    public struct GridTrackDesc { public GridTrackKind Kind; public ushort Repeat; public LayoutValue Min, Max; }
+
+    public enum GridLineKind : byte { Auto = 0, Line = 1, Span = 2 }
+
+    // Line values may be negative (counted from the end); span values are track counts
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutGridLine { public GridLineKind Kind; public short Value; }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutGridPlacement { public LayoutGridLine RowStart, RowEnd, ColumnStart, ColumnEnd; }
+
+    // Resolved grid lines of a placed item; 1 = first explicit line
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutGridArea { public short RowStart, RowEnd, ColumnStart, ColumnEnd; }
+
+    // Skipped = computes that returned early because nothing was dirty; times in microseconds
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutComputeStats { public ulong Count, Skipped, TotalUs, MaxUs, LastUs; }
+
+    // Profiled subtree: entry 0 is the compute root (whole compute), then each root child
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutProfileEntry
+    {
+        public ulong Node, TimeNs;
+        public uint Nodes, CacheHits, CacheMisses, MeasureCalls;
+    }
+
+    // Whole-tree health for diagnostics panels; MemoryBytes is an estimate
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutTreeStats
+    {
+        public ulong NodeCount, RootCount, DirtyCount, LastComputeUs, MemoryBytes;
+        public uint MaxDepth;
+    }
+
+    // One flex line: in-flow item count, cross size, cross offset in the container's space
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutFlexLine { public uint ItemCount; public float CrossSize, Offset; }
+
+    // Root-space border box of one node
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutNodeRect { public ulong Node; public float X, Y, W, H; }
+
+    // Root-space rect plus the ancestor clip; unclipped = (-inf, -inf, +inf, +inf)
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutPaintItem { public ulong Node; public float X, Y, W, H, ClipX, ClipY, ClipW, ClipH; }
+
+    // Invoked after compute with the parent's content-box size; may call style setters on (tree, node)
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void StyleResolver(IntPtr userData, IntPtr tree, ulong node, float parentW, float parentH);
+
+    // Invoked per finished subtree during compute (panel children of the root as each is laid out, then the root, then its other children); read-only access to tree
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void LayoutStream(IntPtr userData, IntPtr tree, ulong subtree);
+
+    // Engine diagnostics: level 0 debug, 1 info, 2 warn, 3 error; message (UTF-8) valid only during the call.
+    // May be invoked from any thread that calls into the library
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void LayoutLog(IntPtr userData, byte level, IntPtr message);
+
+    // Version introspection; the version string is static (Marshal.PtrToStringUTF8, never freed)
+    [DllImport(Lib)] public static extern uint layout_abi_version();
+    [DllImport(Lib)] public static extern IntPtr layout_version_string();
+    [DllImport(Lib)] public static extern LayoutFeatures layout_feature_flags();
+
+    // Throws if the loaded binary was built for different bindings; call before creating any tree
+    public static void EnsureCompatible()
+    {
+        var abi = layout_abi_version();
+        if (abi != AbiVersion)
+            throw new InvalidOperationException($"{Lib} has ABI version {abi}, bindings expect {AbiVersion}");
+    }
+
+    // Panics inside the library make the call return false/0/null; the message is per thread,
+    // owned by the library (Marshal.PtrToStringUTF8), IntPtr.Zero when there is none
+    [DllImport(Lib)] public static extern IntPtr layout_last_error_message();
+    [DllImport(Lib)] public static extern void layout_clear_last_error();
+    // Process-wide; null stops logging. Keep the delegate alive while it is installed
+    [DllImport(Lib)] public static extern void layout_set_log_callback(LayoutLog? callback, IntPtr userData, byte minLevel);
 
     // Tree lifecycle
     [DllImport(Lib)] public static extern IntPtr layout_tree_new();
     [DllImport(Lib)] public static extern void layout_tree_free(IntPtr tree);
+    // Presized node storage; reserve grows an existing tree, clear drops every node but keeps storage
+    [DllImport(Lib)] public static extern IntPtr layout_tree_with_capacity(nuint nodes);
+    [DllImport(Lib)] public static extern void layout_tree_reserve(IntPtr tree, nuint extra);
+    [DllImport(Lib)] public static extern void layout_tree_clear(IntPtr tree);
+    // Every call locks the tree (shared for reads), so one thread can read while another builds
+    [DllImport(Lib)] public static extern IntPtr layout_tree_new_threadsafe();
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_tree_is_threadsafe(IntPtr tree);
+    // Independent copy with the same node IDs (fresh journal, no stream); free with layout_tree_free
+    [DllImport(Lib)] public static extern IntPtr layout_tree_clone(IntPtr tree);
+    // Deep copy of a subtree (styles and node options) appended to dstParent (0 = new root); trees may be the same
+    [DllImport(Lib)] public static extern ulong layout_copy_subtree(IntPtr srcTree, ulong srcNode, IntPtr dstTree, ulong dstParent);
+    // Move between trees: copy then remove the original; returns the new root ID (IDs are per tree), 0 on failure
+    [DllImport(Lib)] public static extern ulong layout_move_subtree(IntPtr srcTree, ulong node, IntPtr dstTree, ulong dstParent, nuint index);
+    [DllImport(Lib)] public static extern void layout_tree_set_direction(IntPtr tree, byte direction);
+    // Density: scales px padding/margin/gap at layout time; getters keep reporting unscaled values
+    [DllImport(Lib)] public static extern void layout_tree_set_spacing_scale(IntPtr tree, float factor);
+    // Window safe-area insets; edges anchored with layout_set_inset_safe_area follow them
+    [DllImport(Lib)] public static extern void layout_tree_set_safe_area(IntPtr tree, float left, float top, float right, float bottom);
+    // Pass null to stop streaming; keep the delegate alive while it is installed
+    [DllImport(Lib)] public static extern void layout_set_compute_stream(IntPtr tree, LayoutStream? callback, IntPtr userData);
 
     // Node creation
     [DllImport(Lib)] public static extern ulong layout_new_node(IntPtr tree);
     [DllImport(Lib)] public static extern ulong layout_new_node_with_children(IntPtr tree, ulong[] children, nuint count);
+    // Flexible space: at least minPx along the parent's main axis plus a flex share, never shrinks
+    [DllImport(Lib)] public static extern ulong layout_new_spacer(IntPtr tree, float minPx, float flex);
     [DllImport(Lib)] public static extern void layout_add_child(IntPtr tree, ulong parent, ulong child);
+    [DllImport(Lib)] public static extern void layout_set_children(IntPtr tree, ulong parent, ulong[] children, nuint count);
+    [DllImport(Lib)] public static extern void layout_insert_child_at(IntPtr tree, ulong parent, ulong child, nuint index);
+    [DllImport(Lib)] public static extern ulong layout_replace_child_at(IntPtr tree, ulong parent, nuint index, ulong newChild); // returns old child or 0
+    [DllImport(Lib)] public static extern ulong layout_remove_child_at(IntPtr tree, ulong parent, nuint index);                 // returns detached child or 0
+    // Move node (subtree intact) under newParent at index (nuint.MaxValue appends); false on cycles
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_reparent(IntPtr tree, ulong node, ulong newParent, nuint index);
     [DllImport(Lib)] public static extern void layout_remove_node(IntPtr tree, ulong node);
+    // Removes node and descendants; writes every dead ID (node first), returns the total removed
+    [DllImport(Lib)] public static extern nuint layout_remove_subtree(IntPtr tree, ulong node, [Out] ulong[]? removed, nuint cap);
+
+    // Detached subtrees: detach is O(1); destroy frees the nodes on the background worker and returns
+    // a token (0 = unknown handle). The tree belongs to the worker until done, as for layout_compute_async
+    [DllImport(Lib)] public static extern ulong layout_detach_subtree(IntPtr tree, ulong node); // 0 = failed
+    [DllImport(Lib)] public static extern ulong layout_destroy_detached(IntPtr tree, ulong handle, LayoutComputeDone? done, IntPtr userData);
+    // Idle ticks: frees detached subtrees and trims side tables within ~budgetUs; false = call again later
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_tree_maintain(IntPtr tree, ulong budgetUs);
+
+    // Mutation journal (drain with null buffer = pending count)
+    [DllImport(Lib)] public static extern void layout_set_journal_enabled(IntPtr tree, [MarshalAs(UnmanagedType.U1)] bool enabled);
+    [DllImport(Lib)] public static extern nuint layout_drain_mutations(IntPtr tree, [Out] LayoutMutation[]? buffer, nuint cap);
 
     // Style: display, direction, wrap
     [DllImport(Lib)] public static extern void layout_set_display(IntPtr tree, ulong node, byte display);
@@ -45,6 +233,9 @@ public static class Taffy
     [DllImport(Lib)] public static extern void layout_set_align_items(IntPtr tree, ulong node, byte val);
     [DllImport(Lib)] public static extern void layout_set_justify_content(IntPtr tree, ulong node, byte val);
     [DllImport(Lib)] public static extern void layout_set_align_self(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_align_content(IntPtr tree, ulong node, byte val); // JustifyContent values
+    [DllImport(Lib)] public static extern void layout_set_justify_items(IntPtr tree, ulong node, byte val);  // AlignItems values
+    [DllImport(Lib)] public static extern void layout_set_justify_self(IntPtr tree, ulong node, byte val);   // AlignItems values
 
     // Style: dimensions (fixed)
     [DllImport(Lib)] public static extern void layout_set_width(IntPtr tree, ulong node, float val);
@@ -58,22 +249,81 @@ public static class Taffy
     [DllImport(Lib)] public static extern void layout_set_width_percent(IntPtr tree, ulong node, float val);
     [DllImport(Lib)] public static extern void layout_set_height_percent(IntPtr tree, ulong node, float val);
 
+    // Style: dimensions (back to auto / content sizing)
+    [DllImport(Lib)] public static extern void layout_set_width_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_height_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_min_width_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_min_height_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_max_width_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_max_height_auto(IntPtr tree, ulong node);
+
     // Style: spacing
     [DllImport(Lib)] public static extern void layout_set_padding(IntPtr tree, ulong node, byte edge, float val);
     [DllImport(Lib)] public static extern void layout_set_margin(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_border(IntPtr tree, ulong node, byte edge, float val);
     [DllImport(Lib)] public static extern void layout_set_gap_row(IntPtr tree, ulong node, float val);
     [DllImport(Lib)] public static extern void layout_set_gap_column(IntPtr tree, ulong node, float val);
     [DllImport(Lib)] public static extern void layout_set_gap_all(IntPtr tree, ulong node, float val);
+    // Spacing between child and its next sibling instead of the flex container's gap; negative removes
+    [DllImport(Lib)] public static extern void layout_set_gap_after(IntPtr tree, ulong child, float px);
+
+    // Style: spacing (percentage)
+    [DllImport(Lib)] public static extern void layout_set_padding_percent(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_margin_percent(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_gap_row_percent(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_gap_column_percent(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_gap_all_percent(IntPtr tree, ulong node, float val);
 
     // Style: position
     [DllImport(Lib)] public static extern void layout_set_position_type(IntPtr tree, ulong node, byte val);
     [DllImport(Lib)] public static extern void layout_set_position(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_position_percent(IntPtr tree, ulong node, byte edge, float val);
+    // start/end flip under RTL; NaN leaves an edge auto
+    [DllImport(Lib)] public static extern void layout_set_inset_logical(IntPtr tree, ulong node, float start, float top, float end, float bottom);
+    // Inset edge = safe-area inset on that side + offset; NaN offset stops anchoring the edge
+    [DllImport(Lib)] public static extern void layout_set_inset_safe_area(IntPtr tree, ulong node, byte edge, float offset);
+
+    // Style: direction of a subtree (Inherit follows the parent) and start/end edges resolved against it; NaN margin/inset = auto
+    [DllImport(Lib)] public static extern void layout_set_direction(IntPtr tree, ulong node, Direction direction);
+    [DllImport(Lib)] public static extern Direction layout_get_direction(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern Direction layout_get_resolved_direction(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_padding_start(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_padding_end(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_margin_start(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_margin_end(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_inset_start(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_inset_end(IntPtr tree, ulong node, float val);
 
     // Style: aspect ratio
     [DllImport(Lib)] public static extern void layout_set_aspect_ratio(IntPtr tree, ulong node, float val);
 
-    // Style: overflow (0=visible, 1=hidden, 2=scroll)
+    // Style: overflow (0=visible, 1=hidden, 2=scroll, 3=clip)
     [DllImport(Lib)] public static extern void layout_set_overflow(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_overflow_x(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_overflow_y(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_scrollbar_width(IntPtr tree, ulong node, float val);
+    // 0 = hidden/scroll axes may shrink to zero (CSS), 1 = keep the content-based minimum (no scrollbar gutter)
+    [DllImport(Lib)] public static extern void layout_set_overflow_min_size(IntPtr tree, ulong node, byte mode);
+
+    // Whole style back to defaults in one call (node pools)
+    [DllImport(Lib)] public static extern void layout_reset_style(IntPtr tree, ulong node);
+    // Reset one property to its default by CSS name ("grid-row", "margin-left", ...); false if unknown
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_reset_style_property(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.LPUTF8Str)] string name);
+    // CSS declaration block ("display:flex; padding:8px 12px"); all or nothing, false sets the last error
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_set_style_text(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.LPUTF8Str)] string text);
+
+    // Row wrap containers: pad inline so lines balance instead of leaving a lone last item
+    // Block containers: 0 = vertical margins collapse (CSS), 1 = margins accumulate (toolkit)
+    [DllImport(Lib)] public static extern void layout_set_margin_collapse(IntPtr tree, ulong node, byte mode);
+    [DllImport(Lib)] public static extern void layout_set_flex_wrap_balance(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.U1)] bool enabled);
+    // Leaves: content height = percent% of own content width + offset px (56.25, 40 for 16:9 plus caption)
+    [DllImport(Lib)] public static extern void layout_set_height_from_width(IntPtr tree, ulong node, float percent, float offset);
+    [DllImport(Lib)] public static extern void layout_clear_height_from_width(IntPtr tree, ulong node);
+    // Image-like leaves: natural size kept in aspect ratio, narrowed to the available width
+    [DllImport(Lib)] public static extern void layout_set_intrinsic_size(IntPtr tree, ulong node, float naturalW, float naturalH);
+    [DllImport(Lib)] public static extern void layout_clear_intrinsic_size(IntPtr tree, ulong node);
 
     // CSS Grid: template
     [DllImport(Lib)] public static extern void layout_set_grid_template_columns(IntPtr tree, ulong node, float[] vals, nuint count);
@@ -81,13 +331,301 @@ public static class Taffy
 
     // CSS Grid: placement
     [DllImport(Lib)] public static extern void layout_set_grid_placement(IntPtr tree, ulong node, short row, short col, ushort spanRows, ushort spanCols);
+    [DllImport(Lib)] public static extern void layout_set_grid_template_columns_desc(IntPtr tree, ulong node, GridTrackDesc[] tracks, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_template_rows_desc(IntPtr tree, ulong node, GridTrackDesc[] tracks, nuint count);
+    // Implicit tracks: scalar encoding as the templates (>0 px, <0 fr, 0 auto), or descriptors (Kind/Repeat ignored)
+    [DllImport(Lib)] public static extern void layout_set_grid_auto_rows(IntPtr tree, ulong node, float[] vals, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_auto_columns(IntPtr tree, ulong node, float[] vals, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_auto_rows_desc(IntPtr tree, ulong node, GridTrackDesc[] tracks, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_auto_columns_desc(IntPtr tree, ulong node, GridTrackDesc[] tracks, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_auto_flow(IntPtr tree, ulong node, GridAutoFlow flow);
+    // Named areas: one string per row, "." = empty cell; false if rows are ragged or an area is not a rectangle
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_set_grid_template_areas(IntPtr tree, ulong node,
+        [MarshalAs(UnmanagedType.LPArray, ArraySubType = UnmanagedType.LPUTF8Str)] string[] rows, nuint count);
+    // null/empty name returns the item to auto-placement
+    [DllImport(Lib)] public static extern void layout_set_grid_area_name(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.LPUTF8Str)] string? name);
+    [DllImport(Lib)] public static extern void layout_set_grid_row(IntPtr tree, ulong node, GridLineKind startKind, short start, GridLineKind endKind, short end);
+    [DllImport(Lib)] public static extern void layout_set_grid_column(IntPtr tree, ulong node, GridLineKind startKind, short start, GridLineKind endKind, short end);
+    // Back to auto-placement on both axes
+    [DllImport(Lib)] public static extern void layout_clear_grid_placement(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_grid_lines(IntPtr tree, ulong node, in LayoutGridPlacement placement);
+    // Engine placement after compute, auto-placed items included; false if the node is not a placed grid item
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_get_resolved_grid_position(IntPtr tree, ulong node, out LayoutGridArea area);
+
+    // Style read-back
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_get_style(IntPtr tree, ulong node, out LayoutStyleDesc style);
+    // Row-major nodes x properties matrix (buffer holds count * propCount); NaN = unknown node or not a px value
+    [DllImport(Lib)] public static extern void layout_dump_properties(IntPtr tree, ulong[] nodes, nuint count,
+        StyleProperty[] properties, nuint propCount, [Out] float[] buffer);
+
+    // Style resolvers (null callback removes; keep the delegate alive while installed)
+    [DllImport(Lib)] public static extern void layout_set_style_resolver(IntPtr tree, ulong node, StyleResolver? callback, IntPtr userData);
 
     // Layout computation
     [DllImport(Lib)] public static extern void layout_compute(IntPtr tree, ulong node, float width, float height);
+    // Per-axis available space; the size only counts for Definite. Sizes popups to their content
+    public enum AvailableSpaceKind : byte { Definite = 0, MinContent = 1, MaxContent = 2 }
+    [DllImport(Lib)] public static extern void layout_compute_with_space(IntPtr tree, ulong node,
+        AvailableSpaceKind widthKind, float width, AvailableSpaceKind heightKind, float height);
+    // Returns how many node rects changed since the previous call; writes at most cap IDs
+    [DllImport(Lib)] public static extern nuint layout_compute_with_changes(IntPtr tree, ulong node, float width, float height,
+        [Out] ulong[]? changed, nuint cap);
+    // One step over several trees (sizes = width, height per tree); streams deliver after all finish.
+    // parallel runs each tree on its own thread, so resolvers must be thread-safe
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_compute_group(IntPtr[] trees, ulong[] roots, float[] sizes, nuint count,
+        [MarshalAs(UnmanagedType.U1)] bool parallel);
+    [DllImport(Lib)] public static extern void layout_tree_set_skip_clean_compute(IntPtr tree, [MarshalAs(UnmanagedType.U1)] bool enabled);
+    // Computing a node with a parent: 0 lays it out as a root, 1 relayouts its whole tree from the top
+    [DllImport(Lib)] public static extern void layout_tree_set_nested_compute(IntPtr tree, byte mode);
+    [DllImport(Lib)] public static extern byte layout_tree_get_nested_compute(IntPtr tree);
+    // Background computes, one at a time on a library worker thread; done runs there, exactly once per token.
+    // The tree belongs to the worker until then (a thread-safe tree's calls wait instead)
+    public enum ComputeStatus : byte { Done = 0, Cancelled = 1, Failed = 2 }
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void LayoutComputeDone(IntPtr userData, ulong token, ComputeStatus status);
+    [DllImport(Lib)] public static extern ulong layout_compute_async(IntPtr tree, ulong node, float width, float height,
+        LayoutComputeDone? done, IntPtr userData);
+    // Only before the compute starts; done gets Cancelled before this returns
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_compute_cancel(ulong token);
+    // Relayout from the changed node's nearest fixed-size ancestor; true if the change reached past it
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_compute_incremental(IntPtr tree, ulong changedNode);
+    // Declares the node's size independent of its children, so incremental relayouts stop at it
+    [DllImport(Lib)] public static extern void layout_set_is_layout_boundary(IntPtr tree, ulong node,
+        [MarshalAs(UnmanagedType.U1)] bool boundary);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_get_is_layout_boundary(IntPtr tree, ulong node);
+    // Fixed-size flex/grid panels of 32+ nodes lay out on up to `threads` threads; results match 1 thread
+    [DllImport(Lib)] public static extern void layout_tree_set_parallelism(IntPtr tree, uint threads);
+    [DllImport(Lib)] public static extern uint layout_tree_get_parallelism(IntPtr tree);
+    // Drag previews: a same-ID copy of the tree with node computed as a root; free with layout_tree_free
+    [DllImport(Lib)] public static extern IntPtr layout_compute_detached(IntPtr tree, ulong node, float width, float height);
+    // "How big would this be?" against hypothetical space, tree untouched; infinity measures max-content
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_measure_node(IntPtr tree, ulong node, float availW, float availH, out float outW, out float outH);
+    // Whole-pixel rounding of computed layouts (on by default); off also skips pixel distribution
+    [DllImport(Lib)] public static extern void layout_tree_set_rounding(IntPtr tree, [MarshalAs(UnmanagedType.U1)] bool enabled);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_tree_get_rounding(IntPtr tree);
+    // Device pixels per logical pixel; layouts snap to device pixels, getters stay logical unless *_physical
+    [DllImport(Lib)] public static extern void layout_tree_set_scale_factor(IntPtr tree, float scale);
+    [DllImport(Lib)] public static extern float layout_tree_get_scale_factor(IntPtr tree);
+    // Leftover-pixel strategy for flex lines: 0 engine, 1 first items, 2 spread, 3 largest remainder
+    [DllImport(Lib)] public static extern void layout_tree_set_pixel_distribution(IntPtr tree, byte strategy);
+    [DllImport(Lib)] public static extern byte layout_tree_get_pixel_distribution(IntPtr tree);
+    // Per-label compute timings (microseconds); a null label computes without recording
+    [DllImport(Lib)] public static extern void layout_compute_labeled(IntPtr tree, ulong node, float width, float height,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string? label);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_get_compute_stats(IntPtr tree, [MarshalAs(UnmanagedType.LPUTF8Str)] string label, out LayoutComputeStats stats);
+    [DllImport(Lib)] public static extern void layout_reset_compute_stats(IntPtr tree);
+    // Opt-in profile of the last compute; returns the entry count, writes at most cap
+    [DllImport(Lib)] public static extern void layout_enable_profiling(IntPtr tree, [MarshalAs(UnmanagedType.U1)] bool enabled);
+    [DllImport(Lib)] public static extern nuint layout_get_profile(IntPtr tree, [Out] LayoutProfileEntry[]? buffer, nuint cap);
+    // Surface roots (windows, palettes, popups) sharing one tree; unregistered once removed or parented
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_add_root(IntPtr tree, ulong node);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_remove_root(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern nuint layout_roots(IntPtr tree, [Out] ulong[]? buffer, nuint cap);
+    // sizes = width, height per root in layout_roots order; returns the registered root count
+    [DllImport(Lib)] public static extern nuint layout_compute_all(IntPtr tree, float[] sizes, nuint count);
+    // Frames: computes between begin and end are deferred to the outermost end, once per root
+    [DllImport(Lib)] public static extern void layout_begin_frame(IntPtr tree);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_end_frame(IntPtr tree);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_in_frame(IntPtr tree);
+
+    // Dirty tracking
+    [DllImport(Lib)] public static extern void layout_mark_dirty(IntPtr tree, ulong node);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_is_dirty(IntPtr tree, ulong node);
+    // Epochs: one per compute that runs; a node's epoch is the one in which its layout last changed (0 = never)
+    [DllImport(Lib)] public static extern ulong layout_get_compute_epoch(IntPtr tree);
+    [DllImport(Lib)] public static extern ulong layout_get_node_epoch(IntPtr tree, ulong node);
+    // Opaque per-node pointer (e.g. GCHandle.ToIntPtr) returned as-is; IntPtr.Zero clears
+    [DllImport(Lib)] public static extern void layout_set_context(IntPtr tree, ulong node, IntPtr context);
+    [DllImport(Lib)] public static extern IntPtr layout_get_context(IntPtr tree, ulong node);
+    // Node names for tooling and serialized trees; null or "" clears. Free the result with layout_string_free
+    [DllImport(Lib)] public static extern void layout_set_node_name(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.LPUTF8Str)] string? name);
+    [DllImport(Lib)] public static extern IntPtr layout_get_node_name(IntPtr tree, ulong node);
+    // First node with the name in root's subtree (document order), 0 if none
+    [DllImport(Lib)] public static extern ulong layout_find_by_name(IntPtr tree, ulong root, [MarshalAs(UnmanagedType.LPUTF8Str)] string name);
+    // Handles are generational: false once the node is removed, even if its slot is reused
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_node_is_valid(IntPtr tree, ulong node);
+    // Changing a node's content version invalidates only that node's cached sizing (and its ancestors')
+    [DllImport(Lib)] public static extern void layout_set_content_version(IntPtr tree, ulong node, ulong version);
+    [DllImport(Lib)] public static extern ulong layout_get_content_version(IntPtr tree, ulong node);
 
     // Layout results
     [DllImport(Lib)] public static extern void layout_get_result(IntPtr tree, ulong node,
         out float x, out float y, out float w, out float h);
+    [DllImport(Lib)] public static extern void layout_get_result_physical(IntPtr tree, ulong node,
+        out float x, out float y, out float w, out float h);
+    // Sub-pixel rect before rounding, for interpolating animations
+    [DllImport(Lib)] public static extern void layout_get_result_unrounded(IntPtr tree, ulong node,
+        out float x, out float y, out float w, out float h);
+    // Test helpers: compare parent-relative rects with tolerance
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_assert_rect(IntPtr tree, ulong node, float x, float y, float w, float h, float epsilon);
+    // expected = x, y, w, h per node; returns first mismatching index, or count if all match
+    [DllImport(Lib)] public static extern nuint layout_assert_rects(IntPtr tree, ulong[] nodes, float[] expected, nuint count, float epsilon);
+    [DllImport(Lib)] public static extern void layout_get_border(IntPtr tree, ulong node,
+        out float left, out float top, out float right, out float bottom);
+    // Border box less border, padding and scrollbar gutters; parent space, or root space if absolute
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_get_content_box(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.U1)] bool absolute,
+        out float x, out float y, out float w, out float h);
+    // Content extent from the border-box origin, and the maximum scroll offset per axis
+    [DllImport(Lib)] public static extern void layout_get_content_size(IntPtr tree, ulong node, out float w, out float h);
+    [DllImport(Lib)] public static extern void layout_get_scroll_size(IntPtr tree, ulong node, out float w, out float h);
+    // Flex line breaking as laid out; returns the line count, writes at most cap
+    [DllImport(Lib)] public static extern nuint layout_get_flex_lines(IntPtr tree, ulong node, [Out] LayoutFlexLine[]? buffer, nuint cap);
     [DllImport(Lib)] public static extern nuint layout_child_count(IntPtr tree, ulong node);
     [DllImport(Lib)] public static extern ulong layout_get_child(IntPtr tree, ulong node, nuint index);
+    [DllImport(Lib)] public static extern ulong layout_get_parent(IntPtr tree, ulong node); // 0 = root or unknown
+    // Nearest parent first; returns the full count, writes at most cap
+    [DllImport(Lib)] public static extern nuint layout_get_ancestors(IntPtr tree, ulong node, [Out] ulong[]? buffer, nuint cap);
+    [DllImport(Lib)] public static extern ulong layout_common_ancestor(IntPtr tree, ulong a, ulong b); // 0 = disconnected
+
+    // Coordinate spaces (false if either node is unknown or they live under different roots)
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_convert_point(IntPtr tree, ulong fromNode, ulong toNode, float x, float y,
+        out float outX, out float outY);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_convert_rect(IntPtr tree, ulong fromNode, ulong toNode, float x, float y, float w, float h,
+        out float outX, out float outY, out float outW, out float outH);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_get_absolute_rect(IntPtr tree, ulong node,
+        out float outX, out float outY, out float outW, out float outH);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_get_absolute_rect_physical(IntPtr tree, ulong node,
+        out float outX, out float outY, out float outW, out float outH);
+    // Returns the subtree size; at most cap rects are written, root first then depth-first
+    [DllImport(Lib)] public static extern nuint layout_get_absolute_rects(IntPtr tree, ulong root, [Out] LayoutNodeRect[]? buffer, nuint cap);
+
+    // Hit testing in root space, front to back; skips display:none and honours overflow clipping
+    [DllImport(Lib)] public static extern ulong layout_hit_test(IntPtr tree, ulong root, float x, float y);
+    [DllImport(Lib)] public static extern nuint layout_hit_test_all(IntPtr tree, ulong root, float x, float y, [Out] ulong[]? buffer, nuint cap);
+
+    // Paint order, parents first; clip-escaping nodes (adorners) drop ancestor clips here and in hit testing
+    [DllImport(Lib)] public static extern void layout_set_clip_escape(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.U1)] bool escape);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_get_clip_escape(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern nuint layout_get_paint_list(IntPtr tree, ulong root, [Out] LayoutPaintItem[]? buffer, nuint cap);
+
+    // Scroll state; links drive a style property as clamp(base + factor * offset), NaN = unclamped
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_set_scroll_offset(IntPtr tree, ulong node, float x, float y);
+    [DllImport(Lib)] public static extern void layout_get_scroll_offset(IntPtr tree, ulong node, out float x, out float y);
+    // Children intersecting the scrolled viewport, in child order; returns the total
+    [DllImport(Lib)] public static extern nuint layout_get_visible_children(IntPtr tree, ulong container, [Out] ulong[]? buffer, nuint cap);
+
+    // Virtual lists: count items of fixed or measured extent with a window of children; range is first..end; keep the delegate alive
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate float LayoutItemExtent(IntPtr userData, ulong node, nuint index);
+    [DllImport(Lib)] public static extern void layout_set_virtual_item_count(IntPtr tree, ulong node, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_virtual_item_extent(IntPtr tree, ulong node, float px);
+    [DllImport(Lib)] public static extern void layout_set_virtual_item_measure(IntPtr tree, ulong node, LayoutItemExtent? callback, IntPtr userData);
+    [DllImport(Lib)] public static extern void layout_remeasure_virtual_items(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_virtual_first_index(IntPtr tree, ulong node, nuint first);
+    [DllImport(Lib)] public static extern void layout_clear_virtual_list(IntPtr tree, ulong node);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_get_virtual_range(IntPtr tree, ulong node, float overscan, out nuint first, out nuint end);
+    [DllImport(Lib)] public static extern float layout_get_virtual_item_offset(IntPtr tree, ulong node, nuint index);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_set_scroll_link(IntPtr tree, ulong node, ulong source, StyleProperty property, ScrollAxis axis,
+        float baseValue, float factor, float min, float max);
+    [DllImport(Lib)] public static extern void layout_remove_scroll_link(IntPtr tree, ulong node, StyleProperty property);
+
+    // Style animations (springs: stiffness per s², damping per s); layout_tick writes the interpolated values and returns true while any are running
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_animate_property(IntPtr tree, ulong node, StyleProperty property, float target, float durationMs,
+        LayoutEasing easing);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_animate_property_spring(IntPtr tree, ulong node, StyleProperty property, float target,
+        float stiffness, float damping);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_tick(IntPtr tree, float dtMs);
+    // Animated properties of a node with their in-flight values; returns the total count, writes at most cap
+    [DllImport(Lib)] public static extern nuint layout_get_animated_values(IntPtr tree, ulong node, [Out] StyleProperty[]? properties,
+        [Out] float[]? values, nuint cap);
+
+    // Text leaves (LayoutFeatures.Text builds); family null/empty = default, weight 0 = 400, line height 0 = the font's
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutFontDesc
+    {
+        [MarshalAs(UnmanagedType.LPUTF8Str)] public string? Family;
+        public float Size;
+        public ushort Weight;
+        [MarshalAs(UnmanagedType.U1)] public bool Italic;
+        public float LineHeight;
+    }
+    // Line rects are relative to the node; Start/End are UTF-8 byte offsets into the node's text
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutTextLine { public float X, Y, Width, Height, Baseline; public uint Start, End; }
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_set_text(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.LPUTF8Str)] string text, in LayoutFontDesc font);
+    [DllImport(Lib)] public static extern void layout_clear_text(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern nuint layout_get_text_lines(IntPtr tree, ulong node, [Out] LayoutTextLine[]? buffer, nuint cap);
+    // Process-wide fonts for text leaves; registered families hide system ones, applying to text set afterwards
+    [DllImport(Lib)] public static extern uint layout_fonts_add_file([MarshalAs(UnmanagedType.LPUTF8Str)] string path);
+    [DllImport(Lib)] public static extern uint layout_fonts_add_memory(byte[] bytes, nuint len);
+    // Empty array restores the built-in fallback families
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_fonts_set_fallback_chain(
+        [MarshalAs(UnmanagedType.LPArray, ArraySubType = UnmanagedType.LPUTF8Str)] string[] families, nuint count);
+    // Leaf baselines for baseline alignment, px below the content-box top; NaN clears (text leaves use their first line)
+    [DllImport(Lib)] public static extern void layout_set_baseline(IntPtr tree, ulong node, float offset);
+    [DllImport(Lib)] public static extern float layout_get_baseline(IntPtr tree, ulong node);
+
+    // Keyframe animations (ID 0 = failure); offsets run 0..1, stop with animation 0 = all; keep the delegate alive while playing
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void LayoutAnimationDone(IntPtr userData, ulong node, ulong animation, AnimationStatus status);
+    [DllImport(Lib)] public static extern ulong layout_animation_new(IntPtr tree, float durationMs, AnimationRepeat repeat);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_animation_add_keyframe(IntPtr tree, ulong animation, StyleProperty property, float offset,
+        float value, LayoutEasing easing);
+    [DllImport(Lib)] public static extern void layout_animation_free(IntPtr tree, ulong animation);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_animation_play(IntPtr tree, ulong animation, ulong node, LayoutAnimationDone? callback,
+        IntPtr userData);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_animation_stop(IntPtr tree, ulong node, ulong animation);
+
+    // Snapshots for keystone-layout-diff: JSON string, free with layout_string_free (IntPtr.Zero for unknown root)
+    [DllImport(Lib)] public static extern IntPtr layout_export_snapshot(IntPtr tree, ulong root, float width, float height);
+    // Regression fixture (snapshot + current rects) for keystone-layout-diff --check; free with layout_string_free
+    [DllImport(Lib)] public static extern IntPtr layout_export_fixture(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_string_free(IntPtr s);
+    // Whole-tree JSON (every root, styles, names); from_json returns IntPtr.Zero and sets the last error on bad input
+    [DllImport(Lib)] public static extern IntPtr layout_tree_to_json(IntPtr tree);
+    [DllImport(Lib)] public static extern IntPtr layout_tree_from_json([MarshalAs(UnmanagedType.LPUTF8Str)] string json);
+    // Compact binary form of the same; serialize returns the size and writes only if cap is large enough
+    [DllImport(Lib)] public static extern nuint layout_tree_serialize(IntPtr tree, [Out] byte[]? buffer, nuint cap);
+    [DllImport(Lib)] public static extern IntPtr layout_tree_deserialize(byte[] data, nuint len);
+    // Warm start: computed layouts of a subtree; import into a same-shaped subtree to paint before the first compute
+    [DllImport(Lib)] public static extern nuint layout_export_result_cache(IntPtr tree, ulong root, [Out] byte[]? buffer, nuint cap);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_import_result_cache(IntPtr tree, ulong root, byte[] data, nuint len);
+    // Build subtrees from a JSON node document under parent (0 = roots); returns the node count, IDs in document order
+    [DllImport(Lib)] public static extern nuint layout_build_from_text(IntPtr tree, ulong parent,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string document, [Out] ulong[]? ids, nuint cap);
+
+    // Diagnostics (valid after layout_compute)
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]
+    public static extern bool layout_get_debug_info(IntPtr tree, ulong node, out LayoutDebugInfo info);
+    // Indented text dump of a subtree with rects and non-default style; free with layout_string_free
+    [DllImport(Lib)] public static extern IntPtr layout_debug_print(IntPtr tree, ulong root);
+    // Graphviz DOT graph of a subtree (kind, size, position per node); free with layout_string_free
+    [DllImport(Lib)] public static extern IntPtr layout_export_dot(IntPtr tree, ulong root);
+    // Node/root/dirty counts, max depth, last compute time and approximate memory; walks every node
+    [DllImport(Lib)] public static extern void layout_tree_stats(IntPtr tree, out LayoutTreeStats stats);
 }
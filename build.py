@@ -55,7 +55,7 @@ def clean():
         shutil.rmtree(DYLIB_DIR)
         print(f"  Removed {DYLIB_DIR.name}/")
 
-def build_rust(debug=False):
+def build_rust(debug=False, bindings=False):
     print("\n=== Building Rust Native Libraries ===")
     os.chdir(RUST_FFI_DIR)
 
@@ -63,6 +63,8 @@ def build_rust(debug=False):
     cargo_args = ["cargo", "build", "-p", "keystone-layout"]
     if not debug:
         cargo_args.append("--release")
+    if bindings:
+        cargo_args += ["--features", "bindings"]
     run(cargo_args)
 
     os.chdir(ROOT)
@@ -180,6 +182,7 @@ def main():
     parser.add_argument("--app-only", action="store_true", help="Only build app bundle")
     parser.add_argument("--no-rust", action="store_true", help="Skip Rust build")
     parser.add_argument("--debug", action="store_true", help="Build in Debug mode")
+    parser.add_argument("--bindings", action="store_true", help="Regenerate rust_ffi/layout/bindings/ (C header, raw P/Invoke)")
     args = parser.parse_args()
 
     os.chdir(ROOT)
@@ -193,14 +196,14 @@ def main():
         APP_BUNDLE = APP_OUT / f"{APP_NAME}.app"
 
     if args.rust_only:
-        build_rust(debug=args.debug)
+        build_rust(debug=args.debug, bindings=args.bindings)
     elif args.core_only:
         build_core(debug=args.debug)
     elif args.app_only:
         build_app(debug=args.debug)
     else:
         if not args.no_rust:
-            build_rust(debug=args.debug)
+            build_rust(debug=args.debug, bindings=args.bindings)
         build_core(debug=args.debug)
         build_app(debug=args.debug)
 
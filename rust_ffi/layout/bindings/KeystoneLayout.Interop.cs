@@ -0,0 +1,517 @@
+// <auto-generated>
+// Generated by rust_ffi/layout/build.rs (cargo build --features bindings). Do not edit.
+// </auto-generated>
+using System.Runtime.InteropServices;
+
+namespace Keystone.Core.Platform.Generated;
+
+public static unsafe class KeystoneLayoutNative
+{
+    private const string Lib = "libkeystone_layout";
+
+    public const uint LAYOUT_ABI_VERSION = 1;
+    public const ulong FEATURE_FLEXBOX = 1 << 0;
+    public const ulong FEATURE_GRID = 1 << 1;
+    public const ulong FEATURE_BLOCK = 1 << 2;
+    public const ulong FEATURE_SERIALIZATION = 1 << 3;
+    public const ulong FEATURE_PARALLEL_GROUP = 1 << 4;
+    public const ulong FEATURE_DEBUG_ASSERTIONS = 1 << 5;
+    public const ulong FEATURE_TEXT = 1 << 6;
+    public const byte MUTATION_STYLE = 0;
+    public const byte MUTATION_CREATED = 1;
+    public const byte MUTATION_REMOVED = 2;
+    public const byte MUTATION_CHILD_ADDED = 3;
+    public const byte MUTATION_CHILD_REMOVED = 4;
+    public const byte DIRECTION_LTR = 0;
+    public const byte DIRECTION_RTL = 1;
+    public const byte DIRECTION_INHERIT = 2;
+    public const byte NESTED_COMPUTE_AS_ROOT = 0;
+    public const byte NESTED_COMPUTE_IN_PLACE = 1;
+    public const byte PROPERTY_WIDTH = 0;
+    public const byte PROPERTY_HEIGHT = 1;
+    public const byte PROPERTY_MIN_WIDTH = 2;
+    public const byte PROPERTY_MIN_HEIGHT = 3;
+    public const byte PROPERTY_MAX_WIDTH = 4;
+    public const byte PROPERTY_MAX_HEIGHT = 5;
+    public const byte PROPERTY_INSET_LEFT = 6;
+    public const byte PROPERTY_INSET_TOP = 7;
+    public const byte PROPERTY_INSET_RIGHT = 8;
+    public const byte PROPERTY_INSET_BOTTOM = 9;
+    public const byte PROPERTY_FLEX_GROW = 10;
+    public const byte PROPERTY_GAP_ROW = 11;
+    public const byte PROPERTY_GAP_COLUMN = 12;
+    public const byte EASING_LINEAR = 0;
+    public const byte EASING_CUBIC_IN = 1;
+    public const byte EASING_CUBIC_OUT = 2;
+    public const byte EASING_CUBIC_IN_OUT = 3;
+    public const byte EASING_QUAD_IN = 4;
+    public const byte EASING_QUAD_OUT = 5;
+    public const byte EASING_QUAD_IN_OUT = 6;
+    public const byte EASING_BACK_OUT = 7;
+    public const byte ANIMATION_ONCE = 0;
+    public const byte ANIMATION_LOOP = 1;
+    public const byte ANIMATION_PING_PONG = 2;
+    public const byte ANIMATION_FINISHED = 0;
+    public const byte ANIMATION_STOPPED = 1;
+    public const byte COMPUTE_DONE = 0;
+    public const byte COMPUTE_CANCELLED = 1;
+    public const byte COMPUTE_FAILED = 2;
+    public const ushort BINARY_VERSION = 1;
+    public const uint DEBUG_ALIGN_FALLBACK = 1 << 0;
+    public const uint DEBUG_JUSTIFY_FALLBACK = 1 << 1;
+    public const uint DEBUG_MIN_SIZE_FLOOR = 1 << 2;
+    public const uint DEBUG_CONSTRAINT_CONFLICT = 1 << 3;
+    public const byte LOG_DEBUG = 0;
+    public const byte LOG_INFO = 1;
+    public const byte LOG_WARN = 2;
+    public const byte LOG_ERROR = 3;
+    public const uint SNAPSHOT_VERSION = 1;
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutMutation
+    {
+        public byte kind;
+        public ulong node;
+        public ulong other;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutGridLine
+    {
+        public byte kind;
+        public short value;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutGridPlacement
+    {
+        public LayoutGridLine row_start;
+        public LayoutGridLine row_end;
+        public LayoutGridLine column_start;
+        public LayoutGridLine column_end;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutGridArea
+    {
+        public short row_start;
+        public short row_end;
+        public short column_start;
+        public short column_end;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutValue
+    {
+        public byte unit;
+        public float value;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct GridTrackDesc
+    {
+        public byte kind;
+        public ushort repeat;
+        public LayoutValue min;
+        public LayoutValue max;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutStyleDesc
+    {
+        public byte display;
+        public byte position_type;
+        public byte flex_direction;
+        public byte flex_wrap;
+        public byte align_items;
+        public byte align_self;
+        public byte align_content;
+        public byte justify_content;
+        public byte justify_items;
+        public byte justify_self;
+        public byte overflow_x;
+        public byte overflow_y;
+        public float flex_grow;
+        public float flex_shrink;
+        public LayoutValue flex_basis;
+        public LayoutValue width;
+        public LayoutValue height;
+        public LayoutValue min_width;
+        public LayoutValue min_height;
+        public LayoutValue max_width;
+        public LayoutValue max_height;
+        public float aspect_ratio;
+        public LayoutValue padding0;
+        public LayoutValue padding1;
+        public LayoutValue padding2;
+        public LayoutValue padding3;
+        public LayoutValue margin0;
+        public LayoutValue margin1;
+        public LayoutValue margin2;
+        public LayoutValue margin3;
+        public LayoutValue border0;
+        public LayoutValue border1;
+        public LayoutValue border2;
+        public LayoutValue border3;
+        public LayoutValue inset0;
+        public LayoutValue inset1;
+        public LayoutValue inset2;
+        public LayoutValue inset3;
+        public LayoutValue gap_row;
+        public LayoutValue gap_column;
+        public float scrollbar_width;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutComputeStats
+    {
+        public ulong count;
+        public ulong skipped;
+        public ulong total_us;
+        public ulong max_us;
+        public ulong last_us;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutFlexLine
+    {
+        public uint item_count;
+        public float cross_size;
+        public float offset;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutProfileEntry
+    {
+        public ulong node;
+        public ulong time_ns;
+        public uint nodes;
+        public uint cache_hits;
+        public uint cache_misses;
+        public uint measure_calls;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutNodeRect
+    {
+        public ulong node;
+        public float x;
+        public float y;
+        public float w;
+        public float h;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutDebugInfo
+    {
+        public uint flags;
+        public byte align_requested;
+        public byte align_effective;
+        public byte justify_requested;
+        public byte justify_effective;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutTreeStats
+    {
+        public ulong node_count;
+        public ulong root_count;
+        public ulong dirty_count;
+        public ulong last_compute_us;
+        public ulong memory_bytes;
+        public uint max_depth;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutPaintItem
+    {
+        public ulong node;
+        public float x;
+        public float y;
+        public float w;
+        public float h;
+        public float clip_x;
+        public float clip_y;
+        public float clip_w;
+        public float clip_h;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutFontDesc
+    {
+        public byte* family;
+        public float size;
+        public ushort weight;
+        public bool italic;
+        public float line_height;
+    }
+
+    [StructLayout(LayoutKind.Sequential)]
+    public struct LayoutTextLine
+    {
+        public float x;
+        public float y;
+        public float width;
+        public float height;
+        public float baseline;
+        public uint start;
+        public uint end;
+    }
+
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void StyleResolverFn(void* user_data, IntPtr tree, ulong node, float parent_w, float parent_h);
+
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void LayoutStreamFn(void* user_data, IntPtr tree, ulong subtree);
+
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void LayoutAnimationDoneFn(void* user_data, ulong node, ulong animation, byte status);
+
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void LayoutComputeDoneFn(void* user_data, ulong token, byte status);
+
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate void LayoutLogFn(void* user_data, byte level, byte* message);
+
+    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+    public delegate float LayoutItemExtentFn(void* user_data, ulong node, nuint index);
+
+    [DllImport(Lib)] public static extern uint layout_abi_version();
+    [DllImport(Lib)] public static extern byte* layout_version_string();
+    [DllImport(Lib)] public static extern ulong layout_feature_flags();
+    [DllImport(Lib)] public static extern IntPtr layout_tree_new();
+    [DllImport(Lib)] public static extern IntPtr layout_tree_with_capacity(nuint nodes);
+    [DllImport(Lib)] public static extern void layout_tree_reserve(IntPtr tree, nuint extra);
+    [DllImport(Lib)] public static extern void layout_tree_clear(IntPtr tree);
+    [DllImport(Lib)] public static extern void layout_tree_free(IntPtr ptr);
+    [DllImport(Lib)] public static extern void layout_tree_set_direction(IntPtr tree, byte direction);
+    [DllImport(Lib)] public static extern void layout_tree_set_safe_area(IntPtr tree, float left, float top, float right, float bottom);
+    [DllImport(Lib)] public static extern void layout_tree_set_spacing_scale(IntPtr tree, float factor);
+    [DllImport(Lib)] public static extern ulong layout_new_node(IntPtr tree);
+    [DllImport(Lib)] public static extern ulong layout_new_spacer(IntPtr tree, float min_px, float flex);
+    [DllImport(Lib)] public static extern ulong layout_new_node_with_children(IntPtr tree, ulong* children, nuint count);
+    [DllImport(Lib)] public static extern void layout_add_child(IntPtr tree, ulong parent, ulong child);
+    [DllImport(Lib)] public static extern void layout_set_children(IntPtr tree, ulong parent, ulong* children, nuint count);
+    [DllImport(Lib)] public static extern void layout_insert_child_at(IntPtr tree, ulong parent, ulong child, nuint index);
+    [DllImport(Lib)] public static extern ulong layout_replace_child_at(IntPtr tree, ulong parent, nuint index, ulong new_child);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_reparent(IntPtr tree, ulong node, ulong new_parent, nuint index);
+    [DllImport(Lib)] public static extern ulong layout_remove_child_at(IntPtr tree, ulong parent, nuint index);
+    [DllImport(Lib)] public static extern void layout_remove_node(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern nuint layout_remove_subtree(IntPtr tree, ulong node, ulong* out_removed, nuint cap);
+    [DllImport(Lib)] public static extern void layout_set_context(IntPtr tree, ulong node, void* context);
+    [DllImport(Lib)] public static extern void* layout_get_context(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_node_name(IntPtr tree, ulong node, byte* name);
+    [DllImport(Lib)] public static extern ulong layout_find_by_name(IntPtr tree, ulong root, byte* name);
+    [DllImport(Lib)] public static extern byte* layout_get_node_name(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern ulong layout_detach_subtree(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern ulong layout_destroy_detached(IntPtr tree, ulong handle, IntPtr callback, void* user_data);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_tree_maintain(IntPtr tree, ulong budget_us);
+    [DllImport(Lib)] public static extern void layout_set_journal_enabled(IntPtr tree, [MarshalAs(UnmanagedType.U1)] bool enabled);
+    [DllImport(Lib)] public static extern nuint layout_drain_mutations(IntPtr tree, LayoutMutation* @out, nuint cap);
+    [DllImport(Lib)] public static extern void layout_set_display(IntPtr tree, ulong node, byte display);
+    [DllImport(Lib)] public static extern void layout_set_flex_direction(IntPtr tree, ulong node, byte dir);
+    [DllImport(Lib)] public static extern void layout_set_flex_wrap(IntPtr tree, ulong node, byte wrap);
+    [DllImport(Lib)] public static extern void layout_set_flex_grow(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_flex_shrink(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_flex_basis(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_align_items(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_justify_content(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_align_self(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_align_content(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_justify_items(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_justify_self(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_width(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_height(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_width_percent(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_height_percent(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_min_width(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_min_height(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_max_width(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_max_height(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_width_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_height_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_min_width_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_min_height_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_max_width_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_max_height_auto(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_padding(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_margin(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_border(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_padding_percent(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_margin_percent(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_gap_row(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_gap_column(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_gap_all(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_gap_row_percent(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_gap_column_percent(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_gap_all_percent(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_position_type(IntPtr tree, ulong node, byte val);
+    [DllImport(Lib)] public static extern void layout_set_position(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_position_percent(IntPtr tree, ulong node, byte edge, float val);
+    [DllImport(Lib)] public static extern void layout_set_inset_logical(IntPtr tree, ulong node, float start, float top, float end, float bottom);
+    [DllImport(Lib)] public static extern void layout_set_inset_safe_area(IntPtr tree, ulong node, byte edge, float offset);
+    [DllImport(Lib)] public static extern void layout_set_aspect_ratio(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_direction(IntPtr tree, ulong node, byte direction);
+    [DllImport(Lib)] public static extern byte layout_get_direction(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern byte layout_get_resolved_direction(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_padding_start(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_padding_end(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_margin_start(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_margin_end(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_inset_start(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_inset_end(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_grid_template_columns(IntPtr tree, ulong node, float* vals, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_template_rows(IntPtr tree, ulong node, float* vals, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_placement(IntPtr tree, ulong node, short row, short col, ushort span_rows, ushort span_cols);
+    [DllImport(Lib)] public static extern void layout_set_grid_template_columns_desc(IntPtr tree, ulong node, GridTrackDesc* tracks, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_template_rows_desc(IntPtr tree, ulong node, GridTrackDesc* tracks, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_auto_rows(IntPtr tree, ulong node, float* vals, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_auto_columns(IntPtr tree, ulong node, float* vals, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_auto_rows_desc(IntPtr tree, ulong node, GridTrackDesc* tracks, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_auto_columns_desc(IntPtr tree, ulong node, GridTrackDesc* tracks, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_auto_flow(IntPtr tree, ulong node, byte flow);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_set_grid_template_areas(IntPtr tree, ulong node, byte** rows, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_grid_area_name(IntPtr tree, ulong node, byte* name);
+    [DllImport(Lib)] public static extern void layout_set_grid_lines(IntPtr tree, ulong node, LayoutGridPlacement* placement);
+    [DllImport(Lib)] public static extern void layout_set_grid_row(IntPtr tree, ulong node, byte start_kind, short start, byte end_kind, short end);
+    [DllImport(Lib)] public static extern void layout_set_grid_column(IntPtr tree, ulong node, byte start_kind, short start, byte end_kind, short end);
+    [DllImport(Lib)] public static extern void layout_clear_grid_placement(IntPtr tree, ulong node);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_get_resolved_grid_position(IntPtr tree, ulong node, LayoutGridArea* @out);
+    [DllImport(Lib)] public static extern void layout_reset_style(IntPtr tree, ulong node);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_reset_style_property(IntPtr tree, ulong node, byte* name);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_set_style_text(IntPtr tree, ulong node, byte* text);
+    [DllImport(Lib)] public static extern void layout_set_overflow(IntPtr tree, ulong node, byte overflow);
+    [DllImport(Lib)] public static extern void layout_set_overflow_x(IntPtr tree, ulong node, byte overflow);
+    [DllImport(Lib)] public static extern void layout_set_overflow_y(IntPtr tree, ulong node, byte overflow);
+    [DllImport(Lib)] public static extern void layout_set_overflow_min_size(IntPtr tree, ulong node, byte mode);
+    [DllImport(Lib)] public static extern void layout_set_scrollbar_width(IntPtr tree, ulong node, float val);
+    [DllImport(Lib)] public static extern void layout_set_gap_after(IntPtr tree, ulong child, float px);
+    [DllImport(Lib)] public static extern void layout_set_margin_collapse(IntPtr tree, ulong node, byte mode);
+    [DllImport(Lib)] public static extern void layout_set_height_from_width(IntPtr tree, ulong node, float percent, float offset);
+    [DllImport(Lib)] public static extern void layout_clear_height_from_width(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_intrinsic_size(IntPtr tree, ulong node, float natural_w, float natural_h);
+    [DllImport(Lib)] public static extern void layout_clear_intrinsic_size(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_flex_wrap_balance(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.U1)] bool enabled);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_get_style(IntPtr tree, ulong node, LayoutStyleDesc* @out);
+    [DllImport(Lib)] public static extern void layout_dump_properties(IntPtr tree, ulong* nodes, nuint count, byte* property_ids, nuint prop_count, float* @out);
+    [DllImport(Lib)] public static extern void layout_set_style_resolver(IntPtr tree, ulong node, IntPtr callback, void* user_data);
+    [DllImport(Lib)] public static extern void layout_compute(IntPtr tree, ulong node, float width, float height);
+    [DllImport(Lib)] public static extern void layout_compute_with_space(IntPtr tree, ulong node, byte width_kind, float width, byte height_kind, float height);
+    [DllImport(Lib)] public static extern nuint layout_compute_with_changes(IntPtr tree, ulong node, float width, float height, ulong* @out, nuint cap);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_compute_group(IntPtr* trees, ulong* roots, float* sizes, nuint count, [MarshalAs(UnmanagedType.U1)] bool parallel);
+    [DllImport(Lib)] public static extern void layout_tree_set_skip_clean_compute(IntPtr tree, [MarshalAs(UnmanagedType.U1)] bool enabled);
+    [DllImport(Lib)] public static extern void layout_tree_set_nested_compute(IntPtr tree, byte mode);
+    [DllImport(Lib)] public static extern byte layout_tree_get_nested_compute(IntPtr tree);
+    [DllImport(Lib)] public static extern IntPtr layout_compute_detached(IntPtr tree, ulong node, float width, float height);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_measure_node(IntPtr tree, ulong node, float avail_w, float avail_h, float* out_w, float* out_h);
+    [DllImport(Lib)] public static extern void layout_mark_dirty(IntPtr tree, ulong node);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_node_is_valid(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_content_version(IntPtr tree, ulong node, ulong version);
+    [DllImport(Lib)] public static extern ulong layout_get_content_version(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern ulong layout_get_compute_epoch(IntPtr tree);
+    [DllImport(Lib)] public static extern ulong layout_get_node_epoch(IntPtr tree, ulong node);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_is_dirty(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_compute_labeled(IntPtr tree, ulong node, float width, float height, byte* label);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_get_compute_stats(IntPtr tree, byte* label, LayoutComputeStats* @out);
+    [DllImport(Lib)] public static extern void layout_reset_compute_stats(IntPtr tree);
+    [DllImport(Lib)] public static extern void layout_set_compute_stream(IntPtr tree, IntPtr callback, void* user_data);
+    [DllImport(Lib)] public static extern void layout_get_result(IntPtr tree, ulong node, float* out_x, float* out_y, float* out_w, float* out_h);
+    [DllImport(Lib)] public static extern void layout_get_result_unrounded(IntPtr tree, ulong node, float* out_x, float* out_y, float* out_w, float* out_h);
+    [DllImport(Lib)] public static extern void layout_get_result_physical(IntPtr tree, ulong node, float* out_x, float* out_y, float* out_w, float* out_h);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_assert_rect(IntPtr tree, ulong node, float x, float y, float w, float h, float epsilon);
+    [DllImport(Lib)] public static extern nuint layout_assert_rects(IntPtr tree, ulong* nodes, float* expected, nuint count, float epsilon);
+    [DllImport(Lib)] public static extern void layout_get_border(IntPtr tree, ulong node, float* out_left, float* out_top, float* out_right, float* out_bottom);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_get_content_box(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.U1)] bool absolute, float* out_x, float* out_y, float* out_w, float* out_h);
+    [DllImport(Lib)] public static extern void layout_get_content_size(IntPtr tree, ulong node, float* out_w, float* out_h);
+    [DllImport(Lib)] public static extern void layout_get_scroll_size(IntPtr tree, ulong node, float* out_w, float* out_h);
+    [DllImport(Lib)] public static extern nuint layout_get_flex_lines(IntPtr tree, ulong node, LayoutFlexLine* @out, nuint cap);
+    [DllImport(Lib)] public static extern nuint layout_child_count(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern ulong layout_get_child(IntPtr tree, ulong node, nuint index);
+    [DllImport(Lib)] public static extern ulong layout_get_parent(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern nuint layout_get_ancestors(IntPtr tree, ulong node, ulong* @out, nuint cap);
+    [DllImport(Lib)] public static extern ulong layout_common_ancestor(IntPtr tree, ulong a, ulong b);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_add_root(IntPtr tree, ulong node);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_remove_root(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern nuint layout_roots(IntPtr tree, ulong* @out, nuint cap);
+    [DllImport(Lib)] public static extern nuint layout_compute_all(IntPtr tree, float* sizes, nuint count);
+    [DllImport(Lib)] public static extern void layout_begin_frame(IntPtr tree);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_end_frame(IntPtr tree);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_in_frame(IntPtr tree);
+    [DllImport(Lib)] public static extern void layout_enable_profiling(IntPtr tree, [MarshalAs(UnmanagedType.U1)] bool enabled);
+    [DllImport(Lib)] public static extern nuint layout_get_profile(IntPtr tree, LayoutProfileEntry* @out, nuint cap);
+    [DllImport(Lib)] public static extern void layout_tree_set_rounding(IntPtr tree, [MarshalAs(UnmanagedType.U1)] bool enabled);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_tree_get_rounding(IntPtr tree);
+    [DllImport(Lib)] public static extern void layout_tree_set_scale_factor(IntPtr tree, float scale);
+    [DllImport(Lib)] public static extern float layout_tree_get_scale_factor(IntPtr tree);
+    [DllImport(Lib)] public static extern void layout_tree_set_pixel_distribution(IntPtr tree, byte strategy);
+    [DllImport(Lib)] public static extern byte layout_tree_get_pixel_distribution(IntPtr tree);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_convert_point(IntPtr tree, ulong from_node, ulong to_node, float x, float y, float* out_x, float* out_y);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_convert_rect(IntPtr tree, ulong from_node, ulong to_node, float x, float y, float w, float h, float* out_x, float* out_y, float* out_w, float* out_h);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_get_absolute_rect(IntPtr tree, ulong node, float* out_x, float* out_y, float* out_w, float* out_h);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_get_absolute_rect_physical(IntPtr tree, ulong node, float* out_x, float* out_y, float* out_w, float* out_h);
+    [DllImport(Lib)] public static extern nuint layout_get_absolute_rects(IntPtr tree, ulong root, LayoutNodeRect* @out, nuint cap);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_animate_property(IntPtr tree, ulong node, byte property_id, float target, float duration_ms, byte easing);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_animate_property_spring(IntPtr tree, ulong node, byte property_id, float target, float stiffness, float damping);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_tick(IntPtr tree, float dt_ms);
+    [DllImport(Lib)] public static extern nuint layout_get_animated_values(IntPtr tree, ulong node, byte* out_properties, float* out_values, nuint cap);
+    [DllImport(Lib)] public static extern ulong layout_animation_new(IntPtr tree, float duration_ms, byte repeat);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_animation_add_keyframe(IntPtr tree, ulong animation, byte property_id, float offset, float value, byte easing);
+    [DllImport(Lib)] public static extern void layout_animation_free(IntPtr tree, ulong animation);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_animation_play(IntPtr tree, ulong animation, ulong node, IntPtr callback, void* user_data);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_animation_stop(IntPtr tree, ulong node, ulong animation);
+    [DllImport(Lib)] public static extern ulong layout_compute_async(IntPtr tree, ulong node, float width, float height, IntPtr callback, void* user_data);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_compute_cancel(ulong token);
+    [DllImport(Lib)] public static extern void layout_set_baseline(IntPtr tree, ulong node, float offset);
+    [DllImport(Lib)] public static extern float layout_get_baseline(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern nuint layout_tree_serialize(IntPtr tree, byte* @out, nuint cap);
+    [DllImport(Lib)] public static extern IntPtr layout_tree_deserialize(byte* data, nuint len);
+    [DllImport(Lib)] public static extern nuint layout_export_result_cache(IntPtr tree, ulong root, byte* @out, nuint cap);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_import_result_cache(IntPtr tree, ulong root, byte* data, nuint len);
+    [DllImport(Lib)] public static extern IntPtr layout_tree_clone(IntPtr tree);
+    [DllImport(Lib)] public static extern ulong layout_copy_subtree(IntPtr src_tree, ulong src_node, IntPtr dst_tree, ulong dst_parent);
+    [DllImport(Lib)] public static extern ulong layout_move_subtree(IntPtr src_tree, ulong node, IntPtr dst_tree, ulong dst_parent, nuint index);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_get_debug_info(IntPtr tree, ulong node, LayoutDebugInfo* @out);
+    [DllImport(Lib)] public static extern void layout_tree_stats(IntPtr tree, LayoutTreeStats* @out);
+    [DllImport(Lib)] public static extern byte* layout_debug_print(IntPtr tree, ulong root);
+    [DllImport(Lib)] public static extern byte* layout_export_dot(IntPtr tree, ulong root);
+    [DllImport(Lib)] public static extern void layout_set_log_callback(IntPtr callback, void* user_data, byte min_level);
+    [DllImport(Lib)] public static extern byte* layout_last_error_message();
+    [DllImport(Lib)] public static extern void layout_clear_last_error();
+    [DllImport(Lib)] public static extern uint layout_fonts_add_file(byte* path);
+    [DllImport(Lib)] public static extern uint layout_fonts_add_memory(byte* bytes, nuint len);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_fonts_set_fallback_chain(byte** families, nuint count);
+    [DllImport(Lib)] public static extern ulong layout_hit_test(IntPtr tree, ulong root, float x, float y);
+    [DllImport(Lib)] public static extern nuint layout_hit_test_all(IntPtr tree, ulong root, float x, float y, ulong* @out, nuint cap);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_compute_incremental(IntPtr tree, ulong changed_node);
+    [DllImport(Lib)] public static extern void layout_set_is_layout_boundary(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.U1)] bool boundary);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_get_is_layout_boundary(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_clip_escape(IntPtr tree, ulong node, [MarshalAs(UnmanagedType.U1)] bool escape);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_get_clip_escape(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern nuint layout_get_paint_list(IntPtr tree, ulong root, LayoutPaintItem* @out, nuint cap);
+    [DllImport(Lib)] public static extern void layout_tree_set_parallelism(IntPtr tree, uint threads);
+    [DllImport(Lib)] public static extern uint layout_tree_get_parallelism(IntPtr tree);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_set_scroll_offset(IntPtr tree, ulong node, float x, float y);
+    [DllImport(Lib)] public static extern void layout_get_scroll_offset(IntPtr tree, ulong node, float* out_x, float* out_y);
+    [DllImport(Lib)] public static extern nuint layout_get_visible_children(IntPtr tree, ulong container, ulong* out_ids, nuint cap);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_set_scroll_link(IntPtr tree, ulong node, ulong source, byte property, byte axis, float @base, float factor, float min, float max);
+    [DllImport(Lib)] public static extern void layout_remove_scroll_link(IntPtr tree, ulong node, byte property);
+    [DllImport(Lib)] public static extern byte* layout_export_snapshot(IntPtr tree, ulong root, float width, float height);
+    [DllImport(Lib)] public static extern byte* layout_export_fixture(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern byte* layout_tree_to_json(IntPtr tree);
+    [DllImport(Lib)] public static extern IntPtr layout_tree_from_json(byte* json);
+    [DllImport(Lib)] public static extern nuint layout_build_from_text(IntPtr tree, ulong parent, byte* text, ulong* out_ids, nuint cap);
+    [DllImport(Lib)] public static extern void layout_string_free(byte* s);
+    [DllImport(Lib)] public static extern IntPtr layout_tree_new_threadsafe();
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_tree_is_threadsafe(IntPtr tree);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_set_text(IntPtr tree, ulong node, byte* utf8, LayoutFontDesc* font);
+    [DllImport(Lib)] public static extern void layout_clear_text(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern nuint layout_get_text_lines(IntPtr tree, ulong node, LayoutTextLine* @out, nuint cap);
+    [DllImport(Lib)] public static extern void layout_set_virtual_item_count(IntPtr tree, ulong node, nuint count);
+    [DllImport(Lib)] public static extern void layout_set_virtual_item_extent(IntPtr tree, ulong node, float px);
+    [DllImport(Lib)] public static extern void layout_set_virtual_item_measure(IntPtr tree, ulong node, IntPtr callback, void* user_data);
+    [DllImport(Lib)] public static extern void layout_remeasure_virtual_items(IntPtr tree, ulong node);
+    [DllImport(Lib)] public static extern void layout_set_virtual_first_index(IntPtr tree, ulong node, nuint first);
+    [DllImport(Lib)] public static extern void layout_clear_virtual_list(IntPtr tree, ulong node);
+    [DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)] public static extern bool layout_get_virtual_range(IntPtr tree, ulong node, float overscan, nuint* out_first, nuint* out_end);
+    [DllImport(Lib)] public static extern float layout_get_virtual_item_offset(IntPtr tree, ulong node, nuint index);
+}
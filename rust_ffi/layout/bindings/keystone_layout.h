@@ -0,0 +1,471 @@
+/* Generated by rust_ffi/layout/build.rs (cargo build --features bindings). Do not edit. */
+
+#ifndef KEYSTONE_LAYOUT_H
+#define KEYSTONE_LAYOUT_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define LAYOUT_ABI_VERSION (1)
+#define FEATURE_FLEXBOX (1 << 0)
+#define FEATURE_GRID (1 << 1)
+#define FEATURE_BLOCK (1 << 2)
+#define FEATURE_SERIALIZATION (1 << 3)
+#define FEATURE_PARALLEL_GROUP (1 << 4)
+#define FEATURE_DEBUG_ASSERTIONS (1 << 5)
+#define FEATURE_TEXT (1 << 6)
+#define MUTATION_STYLE (0)
+#define MUTATION_CREATED (1)
+#define MUTATION_REMOVED (2)
+#define MUTATION_CHILD_ADDED (3)
+#define MUTATION_CHILD_REMOVED (4)
+#define DIRECTION_LTR (0)
+#define DIRECTION_RTL (1)
+#define DIRECTION_INHERIT (2)
+#define NESTED_COMPUTE_AS_ROOT (0)
+#define NESTED_COMPUTE_IN_PLACE (1)
+#define PROPERTY_WIDTH (0)
+#define PROPERTY_HEIGHT (1)
+#define PROPERTY_MIN_WIDTH (2)
+#define PROPERTY_MIN_HEIGHT (3)
+#define PROPERTY_MAX_WIDTH (4)
+#define PROPERTY_MAX_HEIGHT (5)
+#define PROPERTY_INSET_LEFT (6)
+#define PROPERTY_INSET_TOP (7)
+#define PROPERTY_INSET_RIGHT (8)
+#define PROPERTY_INSET_BOTTOM (9)
+#define PROPERTY_FLEX_GROW (10)
+#define PROPERTY_GAP_ROW (11)
+#define PROPERTY_GAP_COLUMN (12)
+#define EASING_LINEAR (0)
+#define EASING_CUBIC_IN (1)
+#define EASING_CUBIC_OUT (2)
+#define EASING_CUBIC_IN_OUT (3)
+#define EASING_QUAD_IN (4)
+#define EASING_QUAD_OUT (5)
+#define EASING_QUAD_IN_OUT (6)
+#define EASING_BACK_OUT (7)
+#define ANIMATION_ONCE (0)
+#define ANIMATION_LOOP (1)
+#define ANIMATION_PING_PONG (2)
+#define ANIMATION_FINISHED (0)
+#define ANIMATION_STOPPED (1)
+#define COMPUTE_DONE (0)
+#define COMPUTE_CANCELLED (1)
+#define COMPUTE_FAILED (2)
+#define BINARY_VERSION (1)
+#define DEBUG_ALIGN_FALLBACK (1 << 0)
+#define DEBUG_JUSTIFY_FALLBACK (1 << 1)
+#define DEBUG_MIN_SIZE_FLOOR (1 << 2)
+#define DEBUG_CONSTRAINT_CONFLICT (1 << 3)
+#define LOG_DEBUG (0)
+#define LOG_INFO (1)
+#define LOG_WARN (2)
+#define LOG_ERROR (3)
+#define SNAPSHOT_VERSION (1)
+
+typedef struct LayoutTree LayoutTree;
+
+typedef struct LayoutMutation {
+    uint8_t kind;
+    uint64_t node;
+    uint64_t other;
+} LayoutMutation;
+
+typedef struct LayoutGridLine {
+    uint8_t kind;
+    int16_t value;
+} LayoutGridLine;
+
+typedef struct LayoutGridPlacement {
+    LayoutGridLine row_start;
+    LayoutGridLine row_end;
+    LayoutGridLine column_start;
+    LayoutGridLine column_end;
+} LayoutGridPlacement;
+
+typedef struct LayoutGridArea {
+    int16_t row_start;
+    int16_t row_end;
+    int16_t column_start;
+    int16_t column_end;
+} LayoutGridArea;
+
+typedef struct LayoutValue {
+    uint8_t unit;
+    float value;
+} LayoutValue;
+
+typedef struct GridTrackDesc {
+    uint8_t kind;
+    uint16_t repeat;
+    LayoutValue min;
+    LayoutValue max;
+} GridTrackDesc;
+
+typedef struct LayoutStyleDesc {
+    uint8_t display;
+    uint8_t position_type;
+    uint8_t flex_direction;
+    uint8_t flex_wrap;
+    uint8_t align_items;
+    uint8_t align_self;
+    uint8_t align_content;
+    uint8_t justify_content;
+    uint8_t justify_items;
+    uint8_t justify_self;
+    uint8_t overflow_x;
+    uint8_t overflow_y;
+    float flex_grow;
+    float flex_shrink;
+    LayoutValue flex_basis;
+    LayoutValue width;
+    LayoutValue height;
+    LayoutValue min_width;
+    LayoutValue min_height;
+    LayoutValue max_width;
+    LayoutValue max_height;
+    float aspect_ratio;
+    LayoutValue padding[4];
+    LayoutValue margin[4];
+    LayoutValue border[4];
+    LayoutValue inset[4];
+    LayoutValue gap_row;
+    LayoutValue gap_column;
+    float scrollbar_width;
+} LayoutStyleDesc;
+
+typedef struct LayoutComputeStats {
+    uint64_t count;
+    uint64_t skipped;
+    uint64_t total_us;
+    uint64_t max_us;
+    uint64_t last_us;
+} LayoutComputeStats;
+
+typedef struct LayoutFlexLine {
+    uint32_t item_count;
+    float cross_size;
+    float offset;
+} LayoutFlexLine;
+
+typedef struct LayoutProfileEntry {
+    uint64_t node;
+    uint64_t time_ns;
+    uint32_t nodes;
+    uint32_t cache_hits;
+    uint32_t cache_misses;
+    uint32_t measure_calls;
+} LayoutProfileEntry;
+
+typedef struct LayoutNodeRect {
+    uint64_t node;
+    float x;
+    float y;
+    float w;
+    float h;
+} LayoutNodeRect;
+
+typedef struct LayoutDebugInfo {
+    uint32_t flags;
+    uint8_t align_requested;
+    uint8_t align_effective;
+    uint8_t justify_requested;
+    uint8_t justify_effective;
+} LayoutDebugInfo;
+
+typedef struct LayoutTreeStats {
+    uint64_t node_count;
+    uint64_t root_count;
+    uint64_t dirty_count;
+    uint64_t last_compute_us;
+    uint64_t memory_bytes;
+    uint32_t max_depth;
+} LayoutTreeStats;
+
+typedef struct LayoutPaintItem {
+    uint64_t node;
+    float x;
+    float y;
+    float w;
+    float h;
+    float clip_x;
+    float clip_y;
+    float clip_w;
+    float clip_h;
+} LayoutPaintItem;
+
+typedef struct LayoutFontDesc {
+    const char *family;
+    float size;
+    uint16_t weight;
+    bool italic;
+    float line_height;
+} LayoutFontDesc;
+
+typedef struct LayoutTextLine {
+    float x;
+    float y;
+    float width;
+    float height;
+    float baseline;
+    uint32_t start;
+    uint32_t end;
+} LayoutTextLine;
+
+typedef void (*StyleResolverFn)(void *user_data, LayoutTree *tree, uint64_t node, float parent_w, float parent_h);
+typedef void (*LayoutStreamFn)(void *user_data, const LayoutTree *tree, uint64_t subtree);
+typedef void (*LayoutAnimationDoneFn)(void *user_data, uint64_t node, uint64_t animation, uint8_t status);
+typedef void (*LayoutComputeDoneFn)(void *user_data, uint64_t token, uint8_t status);
+typedef void (*LayoutLogFn)(void *user_data, uint8_t level, const char *message);
+typedef float (*LayoutItemExtentFn)(void *user_data, uint64_t node, size_t index);
+
+uint32_t layout_abi_version(void);
+const char * layout_version_string(void);
+uint64_t layout_feature_flags(void);
+LayoutTree * layout_tree_new(void);
+LayoutTree * layout_tree_with_capacity(size_t nodes);
+void layout_tree_reserve(LayoutTree *tree, size_t extra);
+void layout_tree_clear(LayoutTree *tree);
+void layout_tree_free(LayoutTree *ptr);
+void layout_tree_set_direction(LayoutTree *tree, uint8_t direction);
+void layout_tree_set_safe_area(LayoutTree *tree, float left, float top, float right, float bottom);
+void layout_tree_set_spacing_scale(LayoutTree *tree, float factor);
+uint64_t layout_new_node(LayoutTree *tree);
+uint64_t layout_new_spacer(LayoutTree *tree, float min_px, float flex);
+uint64_t layout_new_node_with_children(LayoutTree *tree, const uint64_t *children, size_t count);
+void layout_add_child(LayoutTree *tree, uint64_t parent, uint64_t child);
+void layout_set_children(LayoutTree *tree, uint64_t parent, const uint64_t *children, size_t count);
+void layout_insert_child_at(LayoutTree *tree, uint64_t parent, uint64_t child, size_t index);
+uint64_t layout_replace_child_at(LayoutTree *tree, uint64_t parent, size_t index, uint64_t new_child);
+bool layout_reparent(LayoutTree *tree, uint64_t node, uint64_t new_parent, size_t index);
+uint64_t layout_remove_child_at(LayoutTree *tree, uint64_t parent, size_t index);
+void layout_remove_node(LayoutTree *tree, uint64_t node);
+size_t layout_remove_subtree(LayoutTree *tree, uint64_t node, uint64_t *out_removed, size_t cap);
+void layout_set_context(LayoutTree *tree, uint64_t node, void *context);
+void * layout_get_context(const LayoutTree *tree, uint64_t node);
+void layout_set_node_name(LayoutTree *tree, uint64_t node, const char *name);
+uint64_t layout_find_by_name(const LayoutTree *tree, uint64_t root, const char *name);
+char * layout_get_node_name(const LayoutTree *tree, uint64_t node);
+uint64_t layout_detach_subtree(LayoutTree *tree, uint64_t node);
+uint64_t layout_destroy_detached(LayoutTree *tree, uint64_t handle, LayoutComputeDoneFn callback, void *user_data);
+bool layout_tree_maintain(LayoutTree *tree, uint64_t budget_us);
+void layout_set_journal_enabled(LayoutTree *tree, bool enabled);
+size_t layout_drain_mutations(LayoutTree *tree, LayoutMutation *out, size_t cap);
+void layout_set_display(LayoutTree *tree, uint64_t node, uint8_t display);
+void layout_set_flex_direction(LayoutTree *tree, uint64_t node, uint8_t dir);
+void layout_set_flex_wrap(LayoutTree *tree, uint64_t node, uint8_t wrap);
+void layout_set_flex_grow(LayoutTree *tree, uint64_t node, float val);
+void layout_set_flex_shrink(LayoutTree *tree, uint64_t node, float val);
+void layout_set_flex_basis(LayoutTree *tree, uint64_t node, float val);
+void layout_set_align_items(LayoutTree *tree, uint64_t node, uint8_t val);
+void layout_set_justify_content(LayoutTree *tree, uint64_t node, uint8_t val);
+void layout_set_align_self(LayoutTree *tree, uint64_t node, uint8_t val);
+void layout_set_align_content(LayoutTree *tree, uint64_t node, uint8_t val);
+void layout_set_justify_items(LayoutTree *tree, uint64_t node, uint8_t val);
+void layout_set_justify_self(LayoutTree *tree, uint64_t node, uint8_t val);
+void layout_set_width(LayoutTree *tree, uint64_t node, float val);
+void layout_set_height(LayoutTree *tree, uint64_t node, float val);
+void layout_set_width_percent(LayoutTree *tree, uint64_t node, float val);
+void layout_set_height_percent(LayoutTree *tree, uint64_t node, float val);
+void layout_set_min_width(LayoutTree *tree, uint64_t node, float val);
+void layout_set_min_height(LayoutTree *tree, uint64_t node, float val);
+void layout_set_max_width(LayoutTree *tree, uint64_t node, float val);
+void layout_set_max_height(LayoutTree *tree, uint64_t node, float val);
+void layout_set_width_auto(LayoutTree *tree, uint64_t node);
+void layout_set_height_auto(LayoutTree *tree, uint64_t node);
+void layout_set_min_width_auto(LayoutTree *tree, uint64_t node);
+void layout_set_min_height_auto(LayoutTree *tree, uint64_t node);
+void layout_set_max_width_auto(LayoutTree *tree, uint64_t node);
+void layout_set_max_height_auto(LayoutTree *tree, uint64_t node);
+void layout_set_padding(LayoutTree *tree, uint64_t node, uint8_t edge, float val);
+void layout_set_margin(LayoutTree *tree, uint64_t node, uint8_t edge, float val);
+void layout_set_border(LayoutTree *tree, uint64_t node, uint8_t edge, float val);
+void layout_set_padding_percent(LayoutTree *tree, uint64_t node, uint8_t edge, float val);
+void layout_set_margin_percent(LayoutTree *tree, uint64_t node, uint8_t edge, float val);
+void layout_set_gap_row(LayoutTree *tree, uint64_t node, float val);
+void layout_set_gap_column(LayoutTree *tree, uint64_t node, float val);
+void layout_set_gap_all(LayoutTree *tree, uint64_t node, float val);
+void layout_set_gap_row_percent(LayoutTree *tree, uint64_t node, float val);
+void layout_set_gap_column_percent(LayoutTree *tree, uint64_t node, float val);
+void layout_set_gap_all_percent(LayoutTree *tree, uint64_t node, float val);
+void layout_set_position_type(LayoutTree *tree, uint64_t node, uint8_t val);
+void layout_set_position(LayoutTree *tree, uint64_t node, uint8_t edge, float val);
+void layout_set_position_percent(LayoutTree *tree, uint64_t node, uint8_t edge, float val);
+void layout_set_inset_logical(LayoutTree *tree, uint64_t node, float start, float top, float end, float bottom);
+void layout_set_inset_safe_area(LayoutTree *tree, uint64_t node, uint8_t edge, float offset);
+void layout_set_aspect_ratio(LayoutTree *tree, uint64_t node, float val);
+void layout_set_direction(LayoutTree *tree, uint64_t node, uint8_t direction);
+uint8_t layout_get_direction(const LayoutTree *tree, uint64_t node);
+uint8_t layout_get_resolved_direction(const LayoutTree *tree, uint64_t node);
+void layout_set_padding_start(LayoutTree *tree, uint64_t node, float val);
+void layout_set_padding_end(LayoutTree *tree, uint64_t node, float val);
+void layout_set_margin_start(LayoutTree *tree, uint64_t node, float val);
+void layout_set_margin_end(LayoutTree *tree, uint64_t node, float val);
+void layout_set_inset_start(LayoutTree *tree, uint64_t node, float val);
+void layout_set_inset_end(LayoutTree *tree, uint64_t node, float val);
+void layout_set_grid_template_columns(LayoutTree *tree, uint64_t node, const float *vals, size_t count);
+void layout_set_grid_template_rows(LayoutTree *tree, uint64_t node, const float *vals, size_t count);
+void layout_set_grid_placement(LayoutTree *tree, uint64_t node, int16_t row, int16_t col, uint16_t span_rows, uint16_t span_cols);
+void layout_set_grid_template_columns_desc(LayoutTree *tree, uint64_t node, const GridTrackDesc *tracks, size_t count);
+void layout_set_grid_template_rows_desc(LayoutTree *tree, uint64_t node, const GridTrackDesc *tracks, size_t count);
+void layout_set_grid_auto_rows(LayoutTree *tree, uint64_t node, const float *vals, size_t count);
+void layout_set_grid_auto_columns(LayoutTree *tree, uint64_t node, const float *vals, size_t count);
+void layout_set_grid_auto_rows_desc(LayoutTree *tree, uint64_t node, const GridTrackDesc *tracks, size_t count);
+void layout_set_grid_auto_columns_desc(LayoutTree *tree, uint64_t node, const GridTrackDesc *tracks, size_t count);
+void layout_set_grid_auto_flow(LayoutTree *tree, uint64_t node, uint8_t flow);
+bool layout_set_grid_template_areas(LayoutTree *tree, uint64_t node, const char *const *rows, size_t count);
+void layout_set_grid_area_name(LayoutTree *tree, uint64_t node, const char *name);
+void layout_set_grid_lines(LayoutTree *tree, uint64_t node, const LayoutGridPlacement *placement);
+void layout_set_grid_row(LayoutTree *tree, uint64_t node, uint8_t start_kind, int16_t start, uint8_t end_kind, int16_t end);
+void layout_set_grid_column(LayoutTree *tree, uint64_t node, uint8_t start_kind, int16_t start, uint8_t end_kind, int16_t end);
+void layout_clear_grid_placement(LayoutTree *tree, uint64_t node);
+bool layout_get_resolved_grid_position(const LayoutTree *tree, uint64_t node, LayoutGridArea *out);
+void layout_reset_style(LayoutTree *tree, uint64_t node);
+bool layout_reset_style_property(LayoutTree *tree, uint64_t node, const char *name);
+bool layout_set_style_text(LayoutTree *tree, uint64_t node, const char *text);
+void layout_set_overflow(LayoutTree *tree, uint64_t node, uint8_t overflow);
+void layout_set_overflow_x(LayoutTree *tree, uint64_t node, uint8_t overflow);
+void layout_set_overflow_y(LayoutTree *tree, uint64_t node, uint8_t overflow);
+void layout_set_overflow_min_size(LayoutTree *tree, uint64_t node, uint8_t mode);
+void layout_set_scrollbar_width(LayoutTree *tree, uint64_t node, float val);
+void layout_set_gap_after(LayoutTree *tree, uint64_t child, float px);
+void layout_set_margin_collapse(LayoutTree *tree, uint64_t node, uint8_t mode);
+void layout_set_height_from_width(LayoutTree *tree, uint64_t node, float percent, float offset);
+void layout_clear_height_from_width(LayoutTree *tree, uint64_t node);
+void layout_set_intrinsic_size(LayoutTree *tree, uint64_t node, float natural_w, float natural_h);
+void layout_clear_intrinsic_size(LayoutTree *tree, uint64_t node);
+void layout_set_flex_wrap_balance(LayoutTree *tree, uint64_t node, bool enabled);
+bool layout_get_style(const LayoutTree *tree, uint64_t node, LayoutStyleDesc *out);
+void layout_dump_properties(const LayoutTree *tree, const uint64_t *nodes, size_t count, const uint8_t *property_ids, size_t prop_count, float *out);
+void layout_set_style_resolver(LayoutTree *tree, uint64_t node, StyleResolverFn callback, void *user_data);
+void layout_compute(LayoutTree *tree, uint64_t node, float width, float height);
+void layout_compute_with_space(LayoutTree *tree, uint64_t node, uint8_t width_kind, float width, uint8_t height_kind, float height);
+size_t layout_compute_with_changes(LayoutTree *tree, uint64_t node, float width, float height, uint64_t *out, size_t cap);
+bool layout_compute_group(LayoutTree *const *trees, const uint64_t *roots, const float *sizes, size_t count, bool parallel);
+void layout_tree_set_skip_clean_compute(LayoutTree *tree, bool enabled);
+void layout_tree_set_nested_compute(LayoutTree *tree, uint8_t mode);
+uint8_t layout_tree_get_nested_compute(const LayoutTree *tree);
+LayoutTree * layout_compute_detached(const LayoutTree *tree, uint64_t node, float width, float height);
+bool layout_measure_node(const LayoutTree *tree, uint64_t node, float avail_w, float avail_h, float *out_w, float *out_h);
+void layout_mark_dirty(LayoutTree *tree, uint64_t node);
+bool layout_node_is_valid(const LayoutTree *tree, uint64_t node);
+void layout_set_content_version(LayoutTree *tree, uint64_t node, uint64_t version);
+uint64_t layout_get_content_version(const LayoutTree *tree, uint64_t node);
+uint64_t layout_get_compute_epoch(const LayoutTree *tree);
+uint64_t layout_get_node_epoch(const LayoutTree *tree, uint64_t node);
+bool layout_is_dirty(const LayoutTree *tree, uint64_t node);
+void layout_compute_labeled(LayoutTree *tree, uint64_t node, float width, float height, const char *label);
+bool layout_get_compute_stats(const LayoutTree *tree, const char *label, LayoutComputeStats *out);
+void layout_reset_compute_stats(LayoutTree *tree);
+void layout_set_compute_stream(LayoutTree *tree, LayoutStreamFn callback, void *user_data);
+void layout_get_result(const LayoutTree *tree, uint64_t node, float *out_x, float *out_y, float *out_w, float *out_h);
+void layout_get_result_unrounded(const LayoutTree *tree, uint64_t node, float *out_x, float *out_y, float *out_w, float *out_h);
+void layout_get_result_physical(const LayoutTree *tree, uint64_t node, float *out_x, float *out_y, float *out_w, float *out_h);
+bool layout_assert_rect(const LayoutTree *tree, uint64_t node, float x, float y, float w, float h, float epsilon);
+size_t layout_assert_rects(const LayoutTree *tree, const uint64_t *nodes, const float *expected, size_t count, float epsilon);
+void layout_get_border(const LayoutTree *tree, uint64_t node, float *out_left, float *out_top, float *out_right, float *out_bottom);
+bool layout_get_content_box(const LayoutTree *tree, uint64_t node, bool absolute, float *out_x, float *out_y, float *out_w, float *out_h);
+void layout_get_content_size(const LayoutTree *tree, uint64_t node, float *out_w, float *out_h);
+void layout_get_scroll_size(const LayoutTree *tree, uint64_t node, float *out_w, float *out_h);
+size_t layout_get_flex_lines(const LayoutTree *tree, uint64_t node, LayoutFlexLine *out, size_t cap);
+size_t layout_child_count(const LayoutTree *tree, uint64_t node);
+uint64_t layout_get_child(const LayoutTree *tree, uint64_t node, size_t index);
+uint64_t layout_get_parent(const LayoutTree *tree, uint64_t node);
+size_t layout_get_ancestors(const LayoutTree *tree, uint64_t node, uint64_t *out, size_t cap);
+uint64_t layout_common_ancestor(const LayoutTree *tree, uint64_t a, uint64_t b);
+bool layout_add_root(LayoutTree *tree, uint64_t node);
+bool layout_remove_root(LayoutTree *tree, uint64_t node);
+size_t layout_roots(const LayoutTree *tree, uint64_t *out, size_t cap);
+size_t layout_compute_all(LayoutTree *tree, const float *sizes, size_t count);
+void layout_begin_frame(LayoutTree *tree);
+bool layout_end_frame(LayoutTree *tree);
+bool layout_in_frame(const LayoutTree *tree);
+void layout_enable_profiling(LayoutTree *tree, bool enabled);
+size_t layout_get_profile(const LayoutTree *tree, LayoutProfileEntry *out, size_t cap);
+void layout_tree_set_rounding(LayoutTree *tree, bool enabled);
+bool layout_tree_get_rounding(const LayoutTree *tree);
+void layout_tree_set_scale_factor(LayoutTree *tree, float scale);
+float layout_tree_get_scale_factor(const LayoutTree *tree);
+void layout_tree_set_pixel_distribution(LayoutTree *tree, uint8_t strategy);
+uint8_t layout_tree_get_pixel_distribution(const LayoutTree *tree);
+bool layout_convert_point(const LayoutTree *tree, uint64_t from_node, uint64_t to_node, float x, float y, float *out_x, float *out_y);
+bool layout_convert_rect(const LayoutTree *tree, uint64_t from_node, uint64_t to_node, float x, float y, float w, float h, float *out_x, float *out_y, float *out_w, float *out_h);
+bool layout_get_absolute_rect(const LayoutTree *tree, uint64_t node, float *out_x, float *out_y, float *out_w, float *out_h);
+bool layout_get_absolute_rect_physical(const LayoutTree *tree, uint64_t node, float *out_x, float *out_y, float *out_w, float *out_h);
+size_t layout_get_absolute_rects(const LayoutTree *tree, uint64_t root, LayoutNodeRect *out, size_t cap);
+bool layout_animate_property(LayoutTree *tree, uint64_t node, uint8_t property_id, float target, float duration_ms, uint8_t easing);
+bool layout_animate_property_spring(LayoutTree *tree, uint64_t node, uint8_t property_id, float target, float stiffness, float damping);
+bool layout_tick(LayoutTree *tree, float dt_ms);
+size_t layout_get_animated_values(const LayoutTree *tree, uint64_t node, uint8_t *out_properties, float *out_values, size_t cap);
+uint64_t layout_animation_new(LayoutTree *tree, float duration_ms, uint8_t repeat);
+bool layout_animation_add_keyframe(LayoutTree *tree, uint64_t animation, uint8_t property_id, float offset, float value, uint8_t easing);
+void layout_animation_free(LayoutTree *tree, uint64_t animation);
+bool layout_animation_play(LayoutTree *tree, uint64_t animation, uint64_t node, LayoutAnimationDoneFn callback, void *user_data);
+bool layout_animation_stop(LayoutTree *tree, uint64_t node, uint64_t animation);
+uint64_t layout_compute_async(LayoutTree *tree, uint64_t node, float width, float height, LayoutComputeDoneFn callback, void *user_data);
+bool layout_compute_cancel(uint64_t token);
+void layout_set_baseline(LayoutTree *tree, uint64_t node, float offset);
+float layout_get_baseline(const LayoutTree *tree, uint64_t node);
+size_t layout_tree_serialize(const LayoutTree *tree, uint8_t *out, size_t cap);
+LayoutTree * layout_tree_deserialize(const uint8_t *data, size_t len);
+size_t layout_export_result_cache(const LayoutTree *tree, uint64_t root, uint8_t *out, size_t cap);
+bool layout_import_result_cache(LayoutTree *tree, uint64_t root, const uint8_t *data, size_t len);
+LayoutTree * layout_tree_clone(const LayoutTree *tree);
+uint64_t layout_copy_subtree(const LayoutTree *src_tree, uint64_t src_node, LayoutTree *dst_tree, uint64_t dst_parent);
+uint64_t layout_move_subtree(LayoutTree *src_tree, uint64_t node, LayoutTree *dst_tree, uint64_t dst_parent, size_t index);
+bool layout_get_debug_info(const LayoutTree *tree, uint64_t node, LayoutDebugInfo *out);
+void layout_tree_stats(const LayoutTree *tree, LayoutTreeStats *out);
+char * layout_debug_print(const LayoutTree *tree, uint64_t root);
+char * layout_export_dot(const LayoutTree *tree, uint64_t root);
+void layout_set_log_callback(LayoutLogFn callback, void *user_data, uint8_t min_level);
+const char * layout_last_error_message(void);
+void layout_clear_last_error(void);
+uint32_t layout_fonts_add_file(const char *path);
+uint32_t layout_fonts_add_memory(const uint8_t *bytes, size_t len);
+bool layout_fonts_set_fallback_chain(const char *const *families, size_t count);
+uint64_t layout_hit_test(const LayoutTree *tree, uint64_t root, float x, float y);
+size_t layout_hit_test_all(const LayoutTree *tree, uint64_t root, float x, float y, uint64_t *out, size_t cap);
+bool layout_compute_incremental(LayoutTree *tree, uint64_t changed_node);
+void layout_set_is_layout_boundary(LayoutTree *tree, uint64_t node, bool boundary);
+bool layout_get_is_layout_boundary(const LayoutTree *tree, uint64_t node);
+void layout_set_clip_escape(LayoutTree *tree, uint64_t node, bool escape);
+bool layout_get_clip_escape(const LayoutTree *tree, uint64_t node);
+size_t layout_get_paint_list(const LayoutTree *tree, uint64_t root, LayoutPaintItem *out, size_t cap);
+void layout_tree_set_parallelism(LayoutTree *tree, uint32_t threads);
+uint32_t layout_tree_get_parallelism(const LayoutTree *tree);
+bool layout_set_scroll_offset(LayoutTree *tree, uint64_t node, float x, float y);
+void layout_get_scroll_offset(const LayoutTree *tree, uint64_t node, float *out_x, float *out_y);
+size_t layout_get_visible_children(const LayoutTree *tree, uint64_t container, uint64_t *out_ids, size_t cap);
+bool layout_set_scroll_link(LayoutTree *tree, uint64_t node, uint64_t source, uint8_t property, uint8_t axis, float base, float factor, float min, float max);
+void layout_remove_scroll_link(LayoutTree *tree, uint64_t node, uint8_t property);
+char * layout_export_snapshot(const LayoutTree *tree, uint64_t root, float width, float height);
+char * layout_export_fixture(const LayoutTree *tree, uint64_t node);
+char * layout_tree_to_json(const LayoutTree *tree);
+LayoutTree * layout_tree_from_json(const char *json);
+size_t layout_build_from_text(LayoutTree *tree, uint64_t parent, const char *text, uint64_t *out_ids, size_t cap);
+void layout_string_free(char *s);
+LayoutTree * layout_tree_new_threadsafe(void);
+bool layout_tree_is_threadsafe(const LayoutTree *tree);
+bool layout_set_text(LayoutTree *tree, uint64_t node, const char *utf8, const LayoutFontDesc *font);
+void layout_clear_text(LayoutTree *tree, uint64_t node);
+size_t layout_get_text_lines(const LayoutTree *tree, uint64_t node, LayoutTextLine *out, size_t cap);
+void layout_set_virtual_item_count(LayoutTree *tree, uint64_t node, size_t count);
+void layout_set_virtual_item_extent(LayoutTree *tree, uint64_t node, float px);
+void layout_set_virtual_item_measure(LayoutTree *tree, uint64_t node, LayoutItemExtentFn callback, void *user_data);
+void layout_remeasure_virtual_items(LayoutTree *tree, uint64_t node);
+void layout_set_virtual_first_index(LayoutTree *tree, uint64_t node, size_t first);
+void layout_clear_virtual_list(LayoutTree *tree, uint64_t node);
+bool layout_get_virtual_range(const LayoutTree *tree, uint64_t node, float overscan, size_t *out_first, size_t *out_end);
+float layout_get_virtual_item_offset(const LayoutTree *tree, uint64_t node, size_t index);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KEYSTONE_LAYOUT_H */
@@ -0,0 +1,393 @@
+//! Build script. With the `bindings` feature it regenerates the C header and the
+//! raw C# P/Invoke declarations from the exported surface, into `bindings/`:
+//!
+//! - `keystone_layout.h`: every `#[no_mangle] pub extern "C"` function, `#[repr(C)]`
+//!   struct, callback type and integer constant.
+//! - `KeystoneLayout.Interop.cs`: the same surface as blittable `DllImport`s
+//!   (pointers, `IntPtr` handles), for diffing against the hand-written
+//!   `TaffyInterop.cs` or for hosts that want the raw layer.
+//!
+//! Run `cargo build --features bindings` after changing an exported signature,
+//! and commit the regenerated files with the change. A type the generator does
+//! not know fails the build, so nothing is silently dropped from the bindings;
+//! so does a `TaffyInterop.cs` that has drifted from the surface, with a function
+//! missing on either side or declared with another number of parameters.
+//!
+//! The generator walks the sources with syn itself instead of running cbindgen.
+//! cbindgen only writes C, and the C# declarations and the drift check need the
+//! same walk of the surface anyway; one walk feeding all three keeps the header
+//! and the declarations from disagreeing, and avoids cbindgen's own dependency
+//! tree and configuration file for a surface of plain functions, `#[repr(C)]`
+//! structs and integer constants.
+
+fn main() {
+    #[cfg(feature = "bindings")]
+    bindings::generate();
+}
+
+#[cfg(feature = "bindings")]
+mod bindings {
+    use std::collections::BTreeMap;
+    use std::fmt::Write;
+    use std::path::Path;
+
+    use quote::ToTokens;
+    use syn::{Fields, FnArg, Item, ReturnType, Type};
+
+    type Params = Vec<(String, Type)>;
+    type Signature = (String, Params, Option<Type>);
+
+    /// Everything exported, in source order: lib.rs first, then the modules by name.
+    #[derive(Default)]
+    struct Surface {
+        consts: Vec<(String, String, String)>,
+        structs: Vec<(String, Params)>,
+        callbacks: Vec<Signature>,
+        functions: Vec<Signature>,
+    }
+
+    pub fn generate() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let src = root.join("src");
+        println!("cargo:rerun-if-changed={}", src.display());
+
+        let mut files: Vec<_> = std::fs::read_dir(&src).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|e| e == "rs"))
+            .collect();
+        files.sort_by_key(|path| (path.file_name().unwrap() != "lib.rs", path.clone()));
+
+        let mut surface = Surface::default();
+        for path in &files {
+            let text = std::fs::read_to_string(path).unwrap();
+            let file = syn::parse_file(&text).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+            collect(&file.items, &mut surface);
+        }
+        order_structs(&mut surface.structs);
+
+        let out = root.join("bindings");
+        std::fs::create_dir_all(&out).unwrap();
+        std::fs::write(out.join("keystone_layout.h"), header(&surface)).unwrap();
+        std::fs::write(out.join("KeystoneLayout.Interop.cs"), csharp(&surface)).unwrap();
+        check_interop(&surface, &root.join("../../Keystone.Core.Platform/TaffyInterop.cs"));
+    }
+
+    /// Fail the build unless the hand-written bindings declare exactly the
+    /// exported functions, each with as many parameters.
+    fn check_interop(s: &Surface, path: &Path) {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let text = std::fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+        let declared = declarations(&text);
+        let mut problems = Vec::new();
+        for (name, params, _) in &s.functions {
+            match declared.get(name.as_str()) {
+                None => problems.push(format!("{name} is not declared")),
+                Some(&count) if count != params.len() => {
+                    problems.push(format!("{name} is declared with {count} parameters instead of {}", params.len()));
+                }
+                Some(_) => {}
+            }
+        }
+        for name in declared.keys() {
+            if !s.functions.iter().any(|(n, ..)| n == name) {
+                problems.push(format!("{name} is declared but not exported"));
+            }
+        }
+        if !problems.is_empty() {
+            panic!("bindings: {} does not match the exported surface:\n  {}", path.display(), problems.join("\n  "));
+        }
+    }
+
+    /// Name and parameter count of every `static extern` method in C# source.
+    fn declarations(text: &str) -> BTreeMap<&str, usize> {
+        let mut found = BTreeMap::new();
+        for (at, _) in text.match_indices("static extern ") {
+            let rest = &text[at..];
+            let Some(open) = rest.find('(') else { continue };
+            let name = rest[..open].split_whitespace().last().unwrap();
+            // Commas between parameters; attribute arguments sit inside brackets
+            let (mut depth, mut commas, mut empty) = (0, 0, true);
+            for c in rest[open + 1..].chars() {
+                match c {
+                    ')' if depth == 0 => break,
+                    '(' | '[' => depth += 1,
+                    ')' | ']' => depth -= 1,
+                    ',' if depth == 0 => commas += 1,
+                    _ => {}
+                }
+                empty &= c.is_whitespace();
+            }
+            found.insert(name, if empty { 0 } else { commas + 1 });
+        }
+        found
+    }
+
+    fn collect(items: &[Item], surface: &mut Surface) {
+        for item in items {
+            match item {
+                Item::Fn(f) if is_pub(&f.vis) && has_attr(&f.attrs, "no_mangle") => {
+                    let params = f.sig.inputs.iter().map(|arg| match arg {
+                        FnArg::Typed(p) => (p.pat.to_token_stream().to_string(), (*p.ty).clone()),
+                        FnArg::Receiver(_) => panic!("{}: exported methods are not supported", f.sig.ident),
+                    }).collect();
+                    surface.functions.push((f.sig.ident.to_string(), params, output(&f.sig.output)));
+                }
+                Item::Struct(s) if is_pub(&s.vis) && repr_c(&s.attrs) => {
+                    let Fields::Named(fields) = &s.fields else { panic!("{}: repr(C) structs need named fields", s.ident) };
+                    let fields = fields.named.iter()
+                        .map(|f| (f.ident.as_ref().unwrap().to_string(), f.ty.clone()))
+                        .collect();
+                    surface.structs.push((s.ident.to_string(), fields));
+                }
+                Item::Type(t) if is_pub(&t.vis) => {
+                    if let Type::BareFn(f) = &*t.ty {
+                        let params = f.inputs.iter().enumerate().map(|(i, arg)| {
+                            let name = arg.name.as_ref().map_or(format!("arg{i}"), |(name, _)| name.to_string());
+                            (name, arg.ty.clone())
+                        }).collect();
+                        surface.callbacks.push((t.ident.to_string(), params, output(&f.output)));
+                    }
+                }
+                Item::Const(c) if is_pub(&c.vis) => {
+                    let ty = c.ty.to_token_stream().to_string();
+                    if INTEGERS.contains(&ty.as_str()) {
+                        surface.consts.push((c.ident.to_string(), ty, c.expr.to_token_stream().to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn is_pub(vis: &syn::Visibility) -> bool {
+        matches!(vis, syn::Visibility::Public(_))
+    }
+
+    fn has_attr(attrs: &[syn::Attribute], name: &str) -> bool {
+        attrs.iter().any(|a| a.path().is_ident(name))
+    }
+
+    fn repr_c(attrs: &[syn::Attribute]) -> bool {
+        attrs.iter().any(|a| a.path().is_ident("repr") && a.to_token_stream().to_string().contains('C'))
+    }
+
+    fn output(ret: &ReturnType) -> Option<Type> {
+        match ret {
+            ReturnType::Default => None,
+            ReturnType::Type(_, ty) => Some((**ty).clone()),
+        }
+    }
+
+    /// Structs that embed others go after them.
+    fn order_structs(structs: &mut Vec<(String, Params)>) {
+        let mut ordered = Vec::new();
+        while !structs.is_empty() {
+            let names: Vec<String> = structs.iter().map(|(n, _)| n.clone()).collect();
+            let ready = structs.iter().position(|(_, fields)| {
+                fields.iter().all(|(_, ty)| !names.contains(&element(ty).to_token_stream().to_string()))
+            }).expect("repr(C) structs embed each other in a cycle");
+            ordered.push(structs.remove(ready));
+        }
+        *structs = ordered;
+    }
+
+    /// Element type of a fixed-size array field, or the type itself.
+    fn element(ty: &Type) -> &Type {
+        match ty {
+            Type::Array(a) => &a.elem,
+            _ => ty,
+        }
+    }
+
+    fn array_len(ty: &Type) -> Option<usize> {
+        let Type::Array(a) = ty else { return None };
+        Some(a.len.to_token_stream().to_string().parse().expect("array lengths must be literals"))
+    }
+
+    const INTEGERS: &[&str] = &["u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "usize"];
+
+    /// Primitive Rust type: C spelling and C# spelling.
+    fn primitive(name: &str) -> Option<(&'static str, &'static str)> {
+        Some(match name {
+            "u8" => ("uint8_t", "byte"),
+            "u16" => ("uint16_t", "ushort"),
+            "u32" => ("uint32_t", "uint"),
+            "u64" => ("uint64_t", "ulong"),
+            "i8" => ("int8_t", "sbyte"),
+            "i16" => ("int16_t", "short"),
+            "i32" => ("int32_t", "int"),
+            "i64" => ("int64_t", "long"),
+            "usize" => ("size_t", "nuint"),
+            "f32" => ("float", "float"),
+            "f64" => ("double", "double"),
+            "bool" => ("bool", "bool"),
+            "c_char" => ("char", "byte"),
+            "c_void" => ("void", "void"),
+            _ => return None,
+        })
+    }
+
+    /// Pointee spelling in C and C#, for named types.
+    fn named(ty: &Type, s: &Surface) -> (String, String) {
+        let name = ty.to_token_stream().to_string();
+        if let Some((c, cs)) = primitive(&name) {
+            return (c.into(), cs.into());
+        }
+        if name == "LayoutTree" || s.structs.iter().any(|(n, _)| *n == name) {
+            return (name.clone(), name);
+        }
+        panic!("bindings: no C mapping for type `{name}`");
+    }
+
+    fn callback_name(ty: &Type, s: &Surface) -> Option<String> {
+        let name = ty.to_token_stream().to_string();
+        if s.callbacks.iter().any(|(n, ..)| *n == name) {
+            return Some(name);
+        }
+        let inner = name.strip_prefix("Option < ")?.strip_suffix(" >")?;
+        s.callbacks.iter().any(|(n, ..)| n == inner).then(|| inner.to_string())
+    }
+
+    fn c_type(ty: &Type, s: &Surface) -> String {
+        if let Some(callback) = callback_name(ty, s) {
+            return callback;
+        }
+        match ty {
+            Type::Reference(r) => {
+                let (pointee, _) = named(&r.elem, s);
+                if r.mutability.is_some() { format!("{pointee} *") } else { format!("const {pointee} *") }
+            }
+            Type::Ptr(p) => {
+                // `*const *const c_char` is `const char *const *`: const binds to the inner pointer
+                let pointee = c_type(&p.elem, s);
+                match (pointee.ends_with('*'), p.mutability.is_some()) {
+                    (true, true) => format!("{pointee}*"),
+                    (true, false) => format!("{pointee}const *"),
+                    (false, true) => format!("{pointee} *"),
+                    (false, false) => format!("const {pointee} *"),
+                }
+            }
+            _ => named(ty, s).0,
+        }
+    }
+
+    fn c_decl(ty: &Type, name: &str, s: &Surface) -> String {
+        if let Some(len) = array_len(ty) {
+            return format!("{}[{len}]", c_decl(element(ty), name, s));
+        }
+        let ty = c_type(ty, s);
+        if ty.ends_with('*') { format!("{ty}{name}") } else { format!("{ty} {name}") }
+    }
+
+    /// C# spelling of a parameter, field or return type. Tree handles are `IntPtr`,
+    /// callbacks are function pointers (`Marshal.GetFunctionPointerForDelegate`).
+    fn cs_type(ty: &Type, s: &Surface) -> String {
+        if callback_name(ty, s).is_some() {
+            return "IntPtr".into();
+        }
+        let pointee = match ty {
+            Type::Reference(r) => &r.elem,
+            Type::Ptr(p) => &p.elem,
+            _ => return named(ty, s).1,
+        };
+        if pointee.to_token_stream().to_string() == "LayoutTree" {
+            return "IntPtr".into();
+        }
+        format!("{}*", cs_type(pointee, s))
+    }
+
+    fn cs_name(name: &str) -> String {
+        const KEYWORDS: &[&str] = &["out", "ref", "in", "params", "object", "string", "base", "event", "fixed", "lock", "checked"];
+        if KEYWORDS.contains(&name) { format!("@{name}") } else { name.into() }
+    }
+
+    fn cs_param(ty: &Type, name: &str, s: &Surface) -> String {
+        let marshal = if is_bool(ty) { "[MarshalAs(UnmanagedType.U1)] " } else { "" };
+        format!("{marshal}{} {}", cs_type(ty, s), cs_name(name))
+    }
+
+    fn is_bool(ty: &Type) -> bool {
+        ty.to_token_stream().to_string() == "bool"
+    }
+
+    fn header(s: &Surface) -> String {
+        let mut h = String::new();
+        h.push_str("/* Generated by rust_ffi/layout/build.rs (cargo build --features bindings). Do not edit. */\n\n");
+        h.push_str("#ifndef KEYSTONE_LAYOUT_H\n#define KEYSTONE_LAYOUT_H\n\n");
+        h.push_str("#include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n\n");
+        h.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+        for (name, _, value) in &s.consts {
+            let _ = writeln!(h, "#define {name} ({value})");
+        }
+        h.push_str("\ntypedef struct LayoutTree LayoutTree;\n");
+        for (name, fields) in &s.structs {
+            let _ = writeln!(h, "\ntypedef struct {name} {{");
+            for (field, ty) in fields {
+                let _ = writeln!(h, "    {};", c_decl(ty, field, s));
+            }
+            let _ = writeln!(h, "}} {name};");
+        }
+        h.push('\n');
+        for (name, params, ret) in &s.callbacks {
+            let params: Vec<String> = params.iter().map(|(n, ty)| c_decl(ty, n, s)).collect();
+            let ret = ret.as_ref().map_or("void".into(), |ty| c_type(ty, s));
+            let _ = writeln!(h, "typedef {ret} (*{name})({});", params.join(", "));
+        }
+        h.push('\n');
+        for (name, params, ret) in &s.functions {
+            let params: Vec<String> = params.iter().map(|(n, ty)| c_decl(ty, n, s)).collect();
+            let params = if params.is_empty() { "void".into() } else { params.join(", ") };
+            let ret = ret.as_ref().map_or("void".into(), |ty| c_type(ty, s));
+            let _ = writeln!(h, "{} {name}({params});", ret.trim_end());
+        }
+        h.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n#endif /* KEYSTONE_LAYOUT_H */\n");
+        h
+    }
+
+    fn csharp(s: &Surface) -> String {
+        let mut cs = String::new();
+        cs.push_str("// <auto-generated>\n// Generated by rust_ffi/layout/build.rs (cargo build --features bindings). Do not edit.\n// </auto-generated>\n");
+        cs.push_str("using System.Runtime.InteropServices;\n\nnamespace Keystone.Core.Platform.Generated;\n\n");
+        cs.push_str("public static unsafe class KeystoneLayoutNative\n{\n    private const string Lib = \"libkeystone_layout\";\n\n");
+        for (name, ty, value) in &s.consts {
+            let _ = writeln!(cs, "    public const {} {name} = {value};", primitive(ty).unwrap().1);
+        }
+        for (name, fields) in &s.structs {
+            let _ = writeln!(cs, "\n    [StructLayout(LayoutKind.Sequential)]\n    public struct {name}\n    {{");
+            for (field, ty) in fields {
+                let ty_name = cs_type(element(ty), s);
+                // Unrolled rather than ByValArray, so the struct stays blittable
+                match array_len(ty) {
+                    Some(len) => for i in 0..len {
+                        let _ = writeln!(cs, "        public {ty_name} {field}{i};");
+                    },
+                    None => {
+                        let _ = writeln!(cs, "        public {ty_name} {field};");
+                    }
+                }
+            }
+            cs.push_str("    }\n");
+        }
+        for (name, params, ret) in &s.callbacks {
+            let params: Vec<String> = params.iter().map(|(n, ty)| cs_param(ty, n, s)).collect();
+            let ret = ret.as_ref().map_or("void".into(), |ty| cs_type(ty, s));
+            let _ = writeln!(
+                cs, "\n    [UnmanagedFunctionPointer(CallingConvention.Cdecl)]\n    public delegate {ret} {name}({});",
+                params.join(", "),
+            );
+        }
+        cs.push('\n');
+        for (name, params, ret) in &s.functions {
+            let params: Vec<String> = params.iter().map(|(n, ty)| cs_param(ty, n, s)).collect();
+            let attrs = if ret.as_ref().is_some_and(is_bool) {
+                "[DllImport(Lib)] [return: MarshalAs(UnmanagedType.U1)]"
+            } else {
+                "[DllImport(Lib)]"
+            };
+            let ret = ret.as_ref().map_or("void".into(), |ty| cs_type(ty, s));
+            let _ = writeln!(cs, "    {attrs} public static extern {ret} {name}({});", params.join(", "));
+        }
+        cs.push_str("}\n");
+        cs
+    }
+}
@@ -0,0 +1,636 @@
+//! Style property animation.
+//!
+//! `layout_animate_property` starts a transition of one numeric style property
+//! (`PROPERTY_*` codes) towards a target, and `layout_tick` advances every running
+//! transition and writes the interpolated values into the styles, dirtying their
+//! nodes, so the host drives a whole animated panel with one call per frame. The
+//! style always holds the in-flight value: getters read it and a compute lays it
+//! out. `layout_animate_property_spring` runs a property on a damped spring
+//! instead, for motion that follows retargeting smoothly.
+//! `layout_get_animated_values` lists the animated properties of a node with
+//! their in-flight values.
+//!
+//! Keyframe animations (`layout_animation_new`) describe several properties at
+//! once as keyframe tracks, and `layout_animation_play` runs a copy of one on a
+//! node, advanced by the same tick. Each property of a node is driven by one
+//! animation at a time: a transition or playback that starts on a property takes
+//! it over from whatever drove it before.
+
+use std::collections::HashMap;
+use std::ffi::c_void;
+
+use taffy::prelude::*;
+
+use crate::error::{checked_code, ffi_guard};
+use crate::{get_property, mutate_style, out_buffer, set_property, sync, LayoutTree, TreeState, PROPERTY_COUNT};
+
+pub const EASING_LINEAR: u8 = 0;
+pub const EASING_CUBIC_IN: u8 = 1;
+pub const EASING_CUBIC_OUT: u8 = 2;
+pub const EASING_CUBIC_IN_OUT: u8 = 3;
+pub const EASING_QUAD_IN: u8 = 4;
+pub const EASING_QUAD_OUT: u8 = 5;
+pub const EASING_QUAD_IN_OUT: u8 = 6;
+/// Overshoots the target slightly before settling on it.
+pub const EASING_BACK_OUT: u8 = 7;
+const EASING_COUNT: u8 = 8;
+
+pub const ANIMATION_ONCE: u8 = 0;
+pub const ANIMATION_LOOP: u8 = 1;
+/// Runs forwards, then backwards, and so on.
+pub const ANIMATION_PING_PONG: u8 = 2;
+const REPEAT_COUNT: u8 = 3;
+
+pub const ANIMATION_FINISHED: u8 = 0;
+/// Stopped by `layout_animation_stop`, by other animations taking over all of its
+/// properties, or by the removal of its node.
+pub const ANIMATION_STOPPED: u8 = 1;
+
+/// End of a keyframe animation's playback on `node`, with an `ANIMATION_*` status.
+/// Called from `layout_tick` or `layout_animation_stop` once the tree is unlocked,
+/// so it may start other animations.
+pub type LayoutAnimationDoneFn = extern "C" fn(user_data: *mut c_void, node: u64, animation: u64, status: u8);
+
+/// Springs come to rest within this distance (px) of their target, moving slower
+/// than `REST_SPEED` (px/s).
+const REST_DISTANCE: f32 = 0.01;
+const REST_SPEED: f32 = 0.5;
+/// Longest spring integration step, in seconds.
+const SPRING_STEP: f32 = 0.001;
+
+#[derive(Clone, Copy)]
+pub(crate) struct Transition {
+    property: u8,
+    motion: Motion,
+}
+
+#[derive(Clone, Copy)]
+enum Motion {
+    Eased { from: f32, to: f32, elapsed_ms: f32, duration_ms: f32, easing: u8 },
+    /// A unit mass on a damped spring.
+    Spring { value: f32, velocity: f32, target: f32, stiffness: f32, damping: f32 },
+}
+
+/// Animate `property_id` of `node` from its current value to `target` over
+/// `duration_ms`, with an `EASING_*` curve (unknown curves are linear). Replaces
+/// the property's running transition, starting from the value it had reached. A
+/// property that is not a plain length (auto, a percentage) or a non-positive
+/// duration jumps to the target at once. Returns false for unknown nodes and
+/// properties.
+#[no_mangle]
+pub extern "C" fn layout_animate_property(
+    tree: *mut LayoutTree, node: u64, property_id: u8, target: f32, duration_ms: f32, easing: u8,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) || property_id >= PROPERTY_COUNT {
+            return false;
+        }
+        let easing = checked_code("easing", easing, EASING_COUNT);
+        let from = get_property(&tree.host_style(id), property_id);
+        take_over(tree, id, property_id);
+        if from.is_nan() || duration_ms.is_nan() || duration_ms <= 0.0 {
+            mutate_style(tree, node, |s| {
+                set_property(s, property_id, target);
+            });
+            return true;
+        }
+        let motion = Motion::Eased { from, to: target, elapsed_ms: 0.0, duration_ms, easing };
+        tree.animations.entry(id).or_default().push(Transition { property: property_id, motion });
+        true
+    })
+}
+
+/// Animate `property_id` of `node` towards `target` on a damped spring of unit
+/// mass: `stiffness` (per s²) pulls it towards the target and `damping` (per s)
+/// slows it down. It stops once at rest on the target, so it has no fixed
+/// duration. Retargeting a running spring keeps its velocity. Like
+/// `layout_animate_property`, a property that is not a plain length jumps to the
+/// target. Returns false for unknown nodes and properties and non-positive
+/// stiffness or damping.
+#[no_mangle]
+pub extern "C" fn layout_animate_property_spring(
+    tree: *mut LayoutTree, node: u64, property_id: u8, target: f32, stiffness: f32, damping: f32,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) || property_id >= PROPERTY_COUNT || !(stiffness > 0.0 && damping > 0.0) {
+            return false;
+        }
+        let value = get_property(&tree.host_style(id), property_id);
+        let velocity = tree.animations.get(&id)
+            .and_then(|transitions| transitions.iter().find(|t| t.property == property_id))
+            .map_or(0.0, |t| match t.motion {
+                Motion::Spring { velocity, .. } => velocity,
+                Motion::Eased { .. } => 0.0,
+            });
+        take_over(tree, id, property_id);
+        if value.is_nan() {
+            mutate_style(tree, node, |s| {
+                set_property(s, property_id, target);
+            });
+            return true;
+        }
+        let motion = Motion::Spring { value, velocity, target, stiffness, damping };
+        tree.animations.entry(id).or_default().push(Transition { property: property_id, motion });
+        true
+    })
+}
+
+/// Advance every running transition, spring and keyframe playback by `dt_ms` and
+/// write the new values. Finished transitions and springs land exactly on their
+/// target and are dropped; finished playbacks land on their last keyframe and
+/// make their completion calls. Returns true while anything is still running, i.e.
+/// the host should keep ticking.
+#[no_mangle]
+pub extern "C" fn layout_tick(tree: *mut LayoutTree, dt_ms: f32) -> bool {
+    ffi_guard(|| {
+        let (running, ended) = {
+            let (_lock, tree) = sync::exclusive(tree);
+            let dt_ms = if dt_ms.is_finite() { dt_ms.max(0.0) } else { 0.0 };
+            tick_transitions(tree, dt_ms);
+            tick_playbacks(tree, dt_ms);
+            let running = !tree.animations.is_empty() || !tree.keyframes.playing.is_empty();
+            (running, std::mem::take(&mut tree.keyframes.ended))
+        };
+        for end in ended {
+            end.deliver();
+        }
+        running
+    })
+}
+
+/// The properties of `node` that transitions, springs or keyframe playbacks are
+/// driving, in code order, with their interpolated values as of the last tick,
+/// for visuals the host derives from them (corner radii, shadows). Returns the
+/// number of animated properties; only the first `cap` are written. Nodes that
+/// are unknown or not animated have none.
+#[no_mangle]
+pub extern "C" fn layout_get_animated_values(
+    tree: *const LayoutTree, node: u64, out_properties: *mut u8, out_values: *mut f32, cap: usize,
+) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return 0;
+        }
+        let mut animated = [false; PROPERTY_COUNT as usize];
+        for transition in tree.animations.get(&id).into_iter().flatten() {
+            animated[transition.property as usize] = true;
+        }
+        for playback in tree.keyframes.playing.iter().filter(|p| p.node == id) {
+            for track in &playback.tracks {
+                animated[track.property as usize] = true;
+            }
+        }
+        let style = tree.host_style(id);
+        let properties = out_buffer(out_properties, cap);
+        let values = out_buffer(out_values, cap);
+        let mut count = 0;
+        for property in (0..PROPERTY_COUNT).filter(|&p| animated[p as usize]) {
+            if let Some(slot) = properties.get_mut(count) {
+                *slot = property;
+            }
+            if let Some(slot) = values.get_mut(count) {
+                *slot = get_property(&style, property);
+            }
+            count += 1;
+        }
+        count
+    })
+}
+
+/// A new keyframe animation of `duration_ms` per cycle, repeating per an
+/// `ANIMATION_*` mode. Returns its ID (never 0), or 0 for a non-positive duration.
+#[no_mangle]
+pub extern "C" fn layout_animation_new(tree: *mut LayoutTree, duration_ms: f32, repeat: u8) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if duration_ms.is_nan() || duration_ms <= 0.0 {
+            return 0;
+        }
+        let repeat = checked_code("animation repeat mode", repeat, REPEAT_COUNT);
+        let keyframes = &mut tree.keyframes;
+        keyframes.next_id += 1;
+        keyframes.animations.insert(keyframes.next_id, Animation { duration_ms, repeat, tracks: Vec::new() });
+        keyframes.next_id
+    })
+}
+
+/// Set `property_id` to `value` at `offset` (0 to 1) through the animation, with
+/// an `EASING_*` curve for the way on to the next keyframe. A keyframe at the same
+/// offset is replaced. Without a keyframe at 0 or 1 the property starts or ends
+/// at the value it had when playback started. Returns false for unknown animations
+/// and properties and offsets outside 0 to 1.
+#[no_mangle]
+pub extern "C" fn layout_animation_add_keyframe(
+    tree: *mut LayoutTree, animation: u64, property_id: u8, offset: f32, value: f32, easing: u8,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(definition) = tree.keyframes.animations.get_mut(&animation) else { return false };
+        if property_id >= PROPERTY_COUNT || !(0.0..=1.0).contains(&offset) {
+            return false;
+        }
+        let easing = checked_code("easing", easing, EASING_COUNT);
+        let index = match definition.tracks.iter().position(|t| t.property == property_id) {
+            Some(index) => index,
+            None => {
+                definition.tracks.push(Track { property: property_id, keys: Vec::new() });
+                definition.tracks.len() - 1
+            }
+        };
+        let keys = &mut definition.tracks[index].keys;
+        keys.retain(|k| k.offset != offset);
+        let at = keys.partition_point(|k| k.offset < offset);
+        keys.insert(at, Keyframe { offset, value, easing });
+        true
+    })
+}
+
+/// Forget the animation. Playbacks already running carry on.
+#[no_mangle]
+pub extern "C" fn layout_animation_free(tree: *mut LayoutTree, animation: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        tree.keyframes.animations.remove(&animation);
+    })
+}
+
+/// Play the animation on `node` from its start, taking over its properties.
+/// Later changes to the animation do not affect this playback. `callback`, which
+/// may be null, is called once when the playback ends; looping playbacks end only
+/// when stopped. Returns false for unknown animations and nodes and animations
+/// without keyframes.
+#[no_mangle]
+pub extern "C" fn layout_animation_play(
+    tree: *mut LayoutTree, animation: u64, node: u64, callback: Option<LayoutAnimationDoneFn>, user_data: *mut c_void,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        let Some(definition) = tree.keyframes.animations.get(&animation) else { return false };
+        if !tree.contains(id) || definition.tracks.is_empty() {
+            return false;
+        }
+        let mut playback = Playback {
+            node: id,
+            animation,
+            duration_ms: definition.duration_ms,
+            repeat: definition.repeat,
+            tracks: definition.tracks.clone(),
+            elapsed_ms: 0.0,
+            done: callback.map(|callback| DoneCallback { callback, user_data }),
+        };
+        let style = tree.host_style(id);
+        for track in &mut playback.tracks {
+            let current = get_property(&style, track.property);
+            let first = track.keys[0];
+            if first.offset > 0.0 {
+                let value = if current.is_nan() { first.value } else { current };
+                track.keys.insert(0, Keyframe { offset: 0.0, value, easing: EASING_LINEAR });
+            }
+            let last = *track.keys.last().unwrap();
+            if last.offset < 1.0 {
+                let value = if current.is_nan() { last.value } else { current };
+                track.keys.push(Keyframe { offset: 1.0, value, easing: EASING_LINEAR });
+            }
+            take_over(tree, id, track.property);
+        }
+        tree.keyframes.playing.push(playback);
+        true
+    })
+}
+
+/// Stop the animation's playbacks on `node`, or all of the node's playbacks for
+/// animation 0, leaving the properties at their current values. Returns false if
+/// nothing was playing.
+#[no_mangle]
+pub extern "C" fn layout_animation_stop(tree: *mut LayoutTree, node: u64, animation: u64) -> bool {
+    ffi_guard(|| {
+        let ended = {
+            let (_lock, tree) = sync::exclusive(tree);
+            let id = NodeId::from(node);
+            let keyframes = &mut tree.keyframes;
+            let before = keyframes.playing.len();
+            keyframes.end(|p| p.node == id && (animation == 0 || p.animation == animation), ANIMATION_STOPPED);
+            if keyframes.playing.len() == before {
+                return false;
+            }
+            std::mem::take(&mut keyframes.ended)
+        };
+        for end in ended {
+            end.deliver();
+        }
+        true
+    })
+}
+
+/// Stop the removed node's animations; the completion calls of its playbacks wait
+/// for the next tick.
+pub(crate) fn forget_node(tree: &mut TreeState, id: NodeId) {
+    tree.animations.remove(&id);
+    tree.keyframes.end(|p| p.node == id, ANIMATION_STOPPED);
+}
+
+/// Stop whatever animates `property` of `id`, ahead of something else driving it.
+fn take_over(tree: &mut TreeState, id: NodeId, property: u8) {
+    if let Some(transitions) = tree.animations.get_mut(&id) {
+        transitions.retain(|t| t.property != property);
+        if transitions.is_empty() {
+            tree.animations.remove(&id);
+        }
+    }
+    for playback in tree.keyframes.playing.iter_mut().filter(|p| p.node == id) {
+        playback.tracks.retain(|t| t.property != property);
+    }
+    tree.keyframes.end(|p| p.tracks.is_empty(), ANIMATION_STOPPED);
+}
+
+fn tick_transitions(tree: &mut TreeState, dt_ms: f32) {
+    let mut nodes: Vec<NodeId> = tree.animations.keys().copied().collect();
+    nodes.sort_by_key(|&id| u64::from(id));
+    for id in nodes {
+        let transitions = tree.animations.get_mut(&id).unwrap();
+        let mut values = Vec::with_capacity(transitions.len());
+        transitions.retain_mut(|t| {
+            let (value, done) = t.advance(dt_ms);
+            values.push((t.property, value));
+            !done
+        });
+        if transitions.is_empty() {
+            tree.animations.remove(&id);
+        }
+        write(tree, id, values);
+    }
+}
+
+fn tick_playbacks(tree: &mut TreeState, dt_ms: f32) {
+    for index in 0..tree.keyframes.playing.len() {
+        let playback = &mut tree.keyframes.playing[index];
+        playback.elapsed_ms += dt_ms;
+        // Keep repeating playbacks' clocks small so long runs do not lose precision
+        match playback.repeat {
+            ANIMATION_LOOP => playback.elapsed_ms %= playback.duration_ms,
+            ANIMATION_PING_PONG => playback.elapsed_ms %= 2.0 * playback.duration_ms,
+            _ => {}
+        }
+        let progress = playback.progress();
+        let values: Vec<(u8, f32)> = playback.tracks.iter().map(|t| (t.property, t.sample(progress))).collect();
+        let id = playback.node;
+        write(tree, id, values);
+    }
+    tree.keyframes.end(|p| p.repeat == ANIMATION_ONCE && p.elapsed_ms >= p.duration_ms, ANIMATION_FINISHED);
+}
+
+fn write(tree: &mut TreeState, id: NodeId, values: Vec<(u8, f32)>) {
+    mutate_style(tree, id.into(), |s| {
+        for (property, value) in values {
+            set_property(s, property, value);
+        }
+    });
+}
+
+impl Transition {
+    /// Move on by `dt_ms`: the new value, and whether the transition is done.
+    fn advance(&mut self, dt_ms: f32) -> (f32, bool) {
+        match &mut self.motion {
+            Motion::Eased { from, to, elapsed_ms, duration_ms, easing } => {
+                *elapsed_ms += dt_ms;
+                if *elapsed_ms >= *duration_ms {
+                    return (*to, true);
+                }
+                (*from + (*to - *from) * ease(*easing, *elapsed_ms / *duration_ms), false)
+            }
+            Motion::Spring { value, velocity, target, stiffness, damping } => {
+                // Semi-implicit Euler in equal steps, stable for any sensible stiffness
+                let seconds = dt_ms / 1000.0;
+                let steps = (seconds / SPRING_STEP).ceil().max(1.0);
+                let step = seconds / steps;
+                for _ in 0..steps as u32 {
+                    *velocity += (*stiffness * (*target - *value) - *damping * *velocity) * step;
+                    *value += *velocity * step;
+                }
+                if (*target - *value).abs() < REST_DISTANCE && velocity.abs() < REST_SPEED {
+                    return (*target, true);
+                }
+                (*value, false)
+            }
+        }
+    }
+}
+
+/// Eased progress for linear progress `t` in 0..=1.
+pub(crate) fn ease(easing: u8, t: f32) -> f32 {
+    match easing {
+        EASING_CUBIC_IN => t * t * t,
+        EASING_CUBIC_OUT => 1.0 - (1.0 - t).powi(3),
+        EASING_CUBIC_IN_OUT => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 },
+        EASING_QUAD_IN => t * t,
+        EASING_QUAD_OUT => 1.0 - (1.0 - t) * (1.0 - t),
+        EASING_QUAD_IN_OUT => if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 },
+        EASING_BACK_OUT => {
+            const C1: f32 = 1.70158;
+            1.0 + (C1 + 1.0) * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+        }
+        _ => t,
+    }
+}
+
+/// Keyframe animations and their playbacks.
+#[derive(Clone, Default)]
+pub(crate) struct Keyframes {
+    animations: HashMap<u64, Animation>,
+    next_id: u64,
+    /// In the order they started, which is the order their values are written.
+    playing: Vec<Playback>,
+    /// Completion calls waiting for the tree to be unlocked.
+    ended: Vec<Ended>,
+}
+
+impl Keyframes {
+    /// Stop every playback, e.g. because every node was removed.
+    pub(crate) fn stop_all(&mut self) {
+        self.end(|_| true, ANIMATION_STOPPED);
+    }
+
+    /// Drop the completion callbacks, e.g. of a cloned tree's playbacks.
+    pub(crate) fn forget_callbacks(&mut self) {
+        for playback in &mut self.playing {
+            playback.done = None;
+        }
+        self.ended.clear();
+    }
+
+    fn end(&mut self, ends: impl Fn(&Playback) -> bool, status: u8) {
+        let ended = &mut self.ended;
+        self.playing.retain(|p| {
+            if !ends(p) {
+                return true;
+            }
+            if let Some(done) = p.done {
+                ended.push(Ended { node: p.node, animation: p.animation, done, status });
+            }
+            false
+        });
+    }
+}
+
+#[derive(Clone)]
+struct Animation {
+    duration_ms: f32,
+    repeat: u8,
+    tracks: Vec<Track>,
+}
+
+/// One property's keyframes, sorted by offset.
+#[derive(Clone)]
+struct Track {
+    property: u8,
+    keys: Vec<Keyframe>,
+}
+
+#[derive(Clone, Copy)]
+struct Keyframe {
+    offset: f32,
+    value: f32,
+    easing: u8,
+}
+
+#[derive(Clone)]
+struct Playback {
+    node: NodeId,
+    animation: u64,
+    duration_ms: f32,
+    repeat: u8,
+    /// With keyframes at 0 and 1.
+    tracks: Vec<Track>,
+    elapsed_ms: f32,
+    done: Option<DoneCallback>,
+}
+
+#[derive(Clone, Copy)]
+struct DoneCallback {
+    callback: LayoutAnimationDoneFn,
+    user_data: *mut c_void,
+}
+
+#[derive(Clone)]
+struct Ended {
+    node: NodeId,
+    animation: u64,
+    done: DoneCallback,
+    status: u8,
+}
+
+impl Ended {
+    fn deliver(self) {
+        (self.done.callback)(self.done.user_data, self.node.into(), self.animation, self.status);
+    }
+}
+
+impl Playback {
+    /// Position in the keyframes, 0 to 1.
+    fn progress(&self) -> f32 {
+        let cycles = self.elapsed_ms / self.duration_ms;
+        match self.repeat {
+            ANIMATION_ONCE => cycles.min(1.0),
+            ANIMATION_LOOP => cycles,
+            _ if cycles > 1.0 => 2.0 - cycles,
+            _ => cycles,
+        }
+    }
+}
+
+impl Track {
+    fn sample(&self, progress: f32) -> f32 {
+        let next = self.keys.partition_point(|k| k.offset <= progress).clamp(1, self.keys.len() - 1);
+        let (from, to) = (self.keys[next - 1], self.keys[next]);
+        if to.offset <= from.offset {
+            return to.value;
+        }
+        let t = ease(from.easing, ((progress - from.offset) / (to.offset - from.offset)).min(1.0));
+        from.value + (to.value - from.value) * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{layout_new_node, layout_set_width, layout_tree_free, layout_tree_new, PROPERTY_FLEX_GROW, PROPERTY_WIDTH};
+
+    fn animated(tree: *const LayoutTree, node: u64, cap: usize) -> (usize, Vec<u8>, Vec<f32>) {
+        let (mut properties, mut values) = (vec![u8::MAX; cap], vec![f32::NAN; cap]);
+        let count = layout_get_animated_values(tree, node, properties.as_mut_ptr(), values.as_mut_ptr(), cap);
+        (count, properties, values)
+    }
+
+    #[test]
+    fn animated_values_list_every_driven_property() {
+        let tree = layout_tree_new();
+        let node = layout_new_node(tree);
+        let still = layout_new_node(tree);
+        layout_set_width(tree, node, 0.0);
+        assert!(layout_animate_property(tree, node, PROPERTY_WIDTH, 100.0, 100.0, EASING_LINEAR));
+        let animation = layout_animation_new(tree, 200.0, ANIMATION_ONCE);
+        assert!(layout_animation_add_keyframe(tree, animation, PROPERTY_FLEX_GROW, 0.0, 0.0, EASING_LINEAR));
+        assert!(layout_animation_add_keyframe(tree, animation, PROPERTY_FLEX_GROW, 1.0, 4.0, EASING_LINEAR));
+        assert!(layout_animation_play(tree, animation, node, None, std::ptr::null_mut()));
+
+        assert!(layout_tick(tree, 50.0));
+        let (count, properties, values) = animated(tree, node, 2);
+        assert_eq!((count, properties, values), (2, vec![PROPERTY_WIDTH, PROPERTY_FLEX_GROW], vec![50.0, 1.0]));
+        // Past `cap` only the count is reported
+        let (count, properties, _) = animated(tree, node, 1);
+        assert_eq!((count, properties), (2, vec![PROPERTY_WIDTH]));
+        assert_eq!(animated(tree, still, 4).0, 0);
+        assert_eq!(layout_get_animated_values(tree, node, std::ptr::null_mut(), std::ptr::null_mut(), 0), 2);
+
+        // The transition finishes first and drops out
+        assert!(layout_tick(tree, 50.0));
+        let (count, properties, values) = animated(tree, node, 2);
+        assert_eq!((count, properties[0], values[0]), (1, PROPERTY_FLEX_GROW, 2.0));
+        assert!(!layout_tick(tree, 100.0));
+        assert_eq!(animated(tree, node, 2).0, 0);
+        layout_tree_free(tree);
+    }
+
+    fn width(tree: *const LayoutTree, node: u64) -> f32 {
+        get_property(&sync::shared(tree).1.host_style(NodeId::from(node)), PROPERTY_WIDTH)
+    }
+
+    #[test]
+    fn springs_overshoot_and_settle_on_their_target() {
+        let tree = layout_tree_new();
+        let node = layout_new_node(tree);
+        layout_set_width(tree, node, 0.0);
+        assert!(!layout_animate_property_spring(tree, node, PROPERTY_WIDTH, 100.0, 0.0, 10.0));
+        assert!(!layout_animate_property_spring(tree, node, PROPERTY_WIDTH, 100.0, 300.0, f32::NAN));
+        // Underdamped: damping well below 2 * sqrt(stiffness)
+        assert!(layout_animate_property_spring(tree, node, PROPERTY_WIDTH, 100.0, 300.0, 10.0));
+
+        let (mut peak, mut frames) = (0.0f32, 0);
+        while layout_tick(tree, 16.0) {
+            peak = peak.max(width(tree, node));
+            frames += 1;
+            assert!(frames < 1000, "spring never came to rest");
+        }
+        assert!(peak > 100.0, "peak {peak}");
+        assert_eq!(width(tree, node), 100.0);
+        assert_eq!(animated(tree, node, 1).0, 0);
+
+        // Retargeting keeps the velocity: the spring carries on past its old value
+        assert!(layout_animate_property_spring(tree, node, PROPERTY_WIDTH, 200.0, 300.0, 30.0));
+        for _ in 0..5 {
+            layout_tick(tree, 16.0);
+        }
+        let moving = width(tree, node);
+        assert!(layout_animate_property_spring(tree, node, PROPERTY_WIDTH, moving, 300.0, 30.0));
+        layout_tick(tree, 16.0);
+        assert!(width(tree, node) > moving);
+        layout_tree_free(tree);
+    }
+}
@@ -0,0 +1,230 @@
+//! Background computes, for relayouts too heavy for the UI thread, and the
+//! freeing of detached subtrees (`layout_destroy_detached`).
+//!
+//! `layout_compute_async` queues a compute on a worker thread the library starts
+//! on first use. Jobs run one at a time in queue order, so two queued for the
+//! same tree never overlap. Every queued job gets exactly one completion call:
+//! from the worker once it has run, or with `COMPUTE_CANCELLED` if
+//! `layout_compute_cancel` or `layout_tree_free` removes it before it starts. A
+//! job that has started cannot be cancelled; freeing its tree waits for it.
+//!
+//! Until its completion call the tree belongs to the worker: the host must not use
+//! a tree from `layout_tree_new` at all, except to free it, while a thread-safe
+//! tree's calls just wait for the compute to finish. Callbacks the compute makes
+//! (measure functions, style resolvers, compute streams) run on the worker thread.
+//! A subtree is freed in slices of about `FREE_SLICE`, each holding the tree's
+//! lock, so a thread-safe tree's calls wait at most for one slice.
+
+use std::collections::VecDeque;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex, Once};
+use std::time::Duration;
+
+use taffy::prelude::*;
+
+use crate::error::ffi_guard;
+use crate::{compute, free_detached, sync, LayoutTree};
+
+pub const COMPUTE_DONE: u8 = 0;
+pub const COMPUTE_CANCELLED: u8 = 1;
+/// The node was removed before the compute ran, the subtree of a free was no
+/// longer detached, or the job failed with the message sent to the log callback.
+pub const COMPUTE_FAILED: u8 = 2;
+
+/// Completion of a background job: its token and a `COMPUTE_*` status.
+pub type LayoutComputeDoneFn = extern "C" fn(user_data: *mut c_void, token: u64, status: u8);
+
+/// Queue a compute of `node` against `width` x `height` on the worker thread.
+/// Returns its token (never 0), or 0 for an unknown node. `callback` may be null.
+/// Open frames do not defer background computes.
+#[no_mangle]
+pub extern "C" fn layout_compute_async(
+    tree: *mut LayoutTree, node: u64, width: f32, height: f32, callback: Option<LayoutComputeDoneFn>,
+    user_data: *mut c_void,
+) -> u64 {
+    ffi_guard(|| {
+        let root = NodeId::from(node);
+        {
+            let (_lock, state) = sync::shared(tree);
+            if !state.contains(root) {
+                return 0;
+            }
+        }
+        let avail = Size { width: AvailableSpace::Definite(width), height: AvailableSpace::Definite(height) };
+        queue(tree, Work::Compute(root, avail), callback, user_data)
+    })
+}
+
+/// Queue the freeing of the detached subtree `root` on the worker thread.
+pub(crate) fn queue_free(
+    tree: *mut LayoutTree, root: NodeId, callback: Option<LayoutComputeDoneFn>, user_data: *mut c_void,
+) -> u64 {
+    queue(tree, Work::Free(root), callback, user_data)
+}
+
+fn queue(tree: *mut LayoutTree, work: Work, callback: Option<LayoutComputeDoneFn>, user_data: *mut c_void) -> u64 {
+    let token = NEXT_TOKEN.fetch_add(1, Ordering::Relaxed);
+    let job = Job { token, tree, work, callback, user_data };
+    WORKER.start.call_once(|| {
+        std::thread::Builder::new()
+            .name(WORKER_NAME.into())
+            .spawn(run_worker)
+            .expect("failed to start the layout worker thread");
+    });
+    WORKER.queue.lock().unwrap_or_else(|e| e.into_inner()).jobs.push_back(job);
+    WORKER.queued.notify_one();
+    token
+}
+
+/// Cancel a queued job that has not started; a cancelled free leaves the subtree
+/// detached. Its callback is called with
+/// `COMPUTE_CANCELLED` before this returns. False if the compute has started or
+/// finished, or the token is unknown.
+#[no_mangle]
+pub extern "C" fn layout_compute_cancel(token: u64) -> bool {
+    ffi_guard(|| {
+        let job = {
+            let mut queue = WORKER.queue.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(index) = queue.jobs.iter().position(|job| job.token == token) else { return false };
+            queue.jobs.remove(index).unwrap()
+        };
+        job.complete(COMPUTE_CANCELLED);
+        true
+    })
+}
+
+/// Before `tree` is freed: cancel its queued computes and wait for a running one.
+/// Panics if called from the running compute's own callbacks, which would wait
+/// for themselves.
+pub(crate) fn forget(tree: *mut LayoutTree) {
+    let cancelled: VecDeque<Job> = {
+        let mut queue = WORKER.queue.lock().unwrap_or_else(|e| e.into_inner());
+        let (cancelled, kept) = std::mem::take(&mut queue.jobs).into_iter().partition(|job| job.tree == tree);
+        queue.jobs = kept;
+        if queue.running == Some(tree as usize) && std::thread::current().name() == Some(WORKER_NAME) {
+            drop(queue);
+            panic!("a tree cannot be freed from inside its own background compute");
+        }
+        while queue.running == Some(tree as usize) {
+            queue = WORKER.finished.wait(queue).unwrap_or_else(|e| e.into_inner());
+        }
+        cancelled
+    };
+    for job in cancelled {
+        job.complete(COMPUTE_CANCELLED);
+    }
+}
+
+static NEXT_TOKEN: AtomicU64 = AtomicU64::new(1);
+
+/// How long the worker holds a tree while freeing a detached subtree.
+const FREE_SLICE: Duration = Duration::from_millis(1);
+
+const WORKER_NAME: &str = "keystone-layout";
+
+static WORKER: Worker = Worker {
+    start: Once::new(),
+    queue: Mutex::new(Queue { jobs: VecDeque::new(), running: None }),
+    queued: Condvar::new(),
+    finished: Condvar::new(),
+};
+
+struct Worker {
+    start: Once,
+    queue: Mutex<Queue>,
+    queued: Condvar,
+    /// Signalled whenever a compute finishes running.
+    finished: Condvar,
+}
+
+struct Queue {
+    jobs: VecDeque<Job>,
+    /// Address of the tree the worker is computing.
+    running: Option<usize>,
+}
+
+struct Job {
+    token: u64,
+    tree: *mut LayoutTree,
+    work: Work,
+    callback: Option<LayoutComputeDoneFn>,
+    user_data: *mut c_void,
+}
+
+enum Work {
+    Compute(NodeId, Size<AvailableSpace>),
+    /// Free the detached subtree with this root.
+    Free(NodeId),
+}
+
+// The host hands the tree and user data over until the completion call
+unsafe impl Send for Job {}
+
+impl Job {
+    fn run(&self) -> u8 {
+        let ran = match self.work {
+            Work::Compute(root, avail) => ffi_guard(|| {
+                let (_lock, tree) = sync::exclusive(self.tree);
+                if !tree.contains(root) {
+                    return false;
+                }
+                compute(tree, root, avail);
+                true
+            }),
+            Work::Free(root) => self.free(root),
+        };
+        if ran { COMPUTE_DONE } else { COMPUTE_FAILED }
+    }
+
+    /// Free the subtree a slice at a time, letting go of the tree in between.
+    /// False if it was no longer detached, or a slice failed.
+    fn free(&self, root: NodeId) -> bool {
+        let mut pending = Vec::new();
+        let mut found = None;
+        loop {
+            let more = ffi_guard(|| {
+                let (_lock, tree) = sync::exclusive(self.tree);
+                if found.is_none() {
+                    found = Some(tree.detached.remove(&root));
+                    if found == Some(false) {
+                        return false;
+                    }
+                    pending.push(root);
+                }
+                !free_detached(tree, &mut pending, FREE_SLICE)
+            });
+            if !more {
+                return found == Some(true) && pending.is_empty();
+            }
+        }
+    }
+
+    fn complete(self, status: u8) {
+        if let Some(callback) = self.callback {
+            callback(self.user_data, self.token, status);
+        }
+    }
+}
+
+fn run_worker() {
+    loop {
+        let job = {
+            let mut queue = WORKER.queue.lock().unwrap_or_else(|e| e.into_inner());
+            loop {
+                match queue.jobs.pop_front() {
+                    Some(job) => {
+                        queue.running = Some(job.tree as usize);
+                        break job;
+                    }
+                    None => queue = WORKER.queued.wait(queue).unwrap_or_else(|e| e.into_inner()),
+                }
+            }
+        };
+        let status = job.run();
+        // Done with the tree, so the completion call may free it
+        WORKER.queue.lock().unwrap_or_else(|e| e.into_inner()).running = None;
+        WORKER.finished.notify_all();
+        job.complete(status);
+    }
+}
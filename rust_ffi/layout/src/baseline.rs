@@ -0,0 +1,122 @@
+//! Leaf baselines, for baseline alignment.
+//!
+//! Taffy lays leaves out without a baseline, so baseline-aligned items fall back to
+//! their bottom edge. Text leaves know the baseline of their first line, and the
+//! host can give any leaf one with `layout_set_baseline` (a control it measured
+//! itself, say). After a pass each such leaf's baseline is written into its cached
+//! layout, where Taffy's flexbox and grid look for it, and the containers above it
+//! lay out again if they align by baseline. Containers then report the first
+//! baselines of their items as usual.
+
+use taffy::prelude::*;
+use taffy::{CacheTree, LayoutOutput, Point, RunMode};
+
+use crate::error::ffi_guard;
+use crate::{is_descendant_or_self, sync, LayoutTree, TreeState, NodeContext};
+
+/// Give a leaf a first baseline `offset` px below the top of its content box;
+/// NaN removes it. Text leaves without one use the baseline of their first line.
+/// Nodes with children ignore it.
+#[no_mangle]
+pub extern "C" fn layout_set_baseline(tree: *mut LayoutTree, node: u64, offset: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        let value = (!offset.is_nan()).then_some(offset);
+        let mut context = match tree.tree.get_node_context(id) {
+            Some(context) if context.baseline == value => return,
+            Some(context) => context.clone(),
+            None if value.is_none() => return,
+            None => NodeContext::default(),
+        };
+        context.baseline = value;
+        // Replacing the context marks the node dirty
+        let _ = tree.tree.set_node_context(id, Some(context));
+        if value.is_some() {
+            tree.baseline_leaves.insert(id);
+        }
+    })
+}
+
+/// The offset set with `layout_set_baseline`, or NaN.
+#[no_mangle]
+pub extern "C" fn layout_get_baseline(tree: *const LayoutTree, node: u64) -> f32 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return f32::NAN;
+        }
+        tree.tree.get_node_context(id).and_then(|c| c.baseline).unwrap_or(f32::NAN)
+    })
+}
+
+/// Write the baselines of the leaves under `root` laid out in the last pass.
+/// Returns true if containers that align by them were marked dirty and must be
+/// laid out again. Leaves of panels laid out in parallel settle in their own
+/// scratch trees.
+pub(crate) fn settle(tree: &mut TreeState, root: NodeId) -> bool {
+    if tree.baseline_leaves.is_empty() {
+        return false;
+    }
+    let (live, taffy) = (&tree.live, &tree.tree);
+    tree.baseline_leaves.retain(|&id| live.contains(&id) && taffy.get_node_context(id).and_then(leaf_baseline).is_some());
+    let leaves: Vec<NodeId> = tree.baseline_leaves.iter().copied()
+        .filter(|&id| !tree.parallel.layouts.contains_key(&id) && is_descendant_or_self(&tree.tree, id, root))
+        .collect();
+    settle_leaves(&mut tree.tree, &leaves, false)
+}
+
+/// `settle` for any Taffy tree. With `aligned_above` the tree's root counts as
+/// aligning by baseline, for a tree that is part of a larger one.
+pub(crate) fn settle_leaves(tree: &mut TaffyTree<NodeContext>, leaves: &[NodeId], aligned_above: bool) -> bool {
+    let mut relayout = false;
+    for &leaf in leaves {
+        if tree.child_count(leaf) > 0 {
+            continue;
+        }
+        let Some(offset) = tree.get_node_context(leaf).and_then(leaf_baseline) else { continue };
+        let layout = *tree.unrounded_layout(leaf);
+        let baseline = layout.padding.top + layout.border.top + offset;
+        // Flex and grid items are laid out at their known size, which this entry
+        // answers whatever the available space
+        let known = Size { width: Some(layout.size.width), height: Some(layout.size.height) };
+        let Some(output) = tree.cache_get(leaf, known, Size::MAX_CONTENT, RunMode::PerformLayout) else { continue };
+        if output.first_baselines.y == Some(baseline) {
+            continue;
+        }
+        let output = LayoutOutput { first_baselines: Point { x: None, y: Some(baseline) }, ..output };
+        tree.cache_store(leaf, known, Size::MAX_CONTENT, RunMode::PerformLayout, output);
+        if aligned_by_baseline(tree, leaf, aligned_above) {
+            // Dirties the ancestors and leaves the leaf's own cache as stored
+            let _ = tree.mark_dirty(tree.parent(leaf).unwrap());
+            relayout = true;
+        }
+    }
+    relayout
+}
+
+fn leaf_baseline(context: &NodeContext) -> Option<f32> {
+    #[cfg(feature = "text")]
+    if context.baseline.is_none() {
+        return context.text.as_ref().and_then(|text| text.first_baseline());
+    }
+    context.baseline
+}
+
+/// Whether the node or one of its ancestors is aligned by baseline, so that the
+/// node's baseline can move something.
+fn aligned_by_baseline(tree: &TaffyTree<NodeContext>, mut id: NodeId, aligned_above: bool) -> bool {
+    while let Some(parent) = tree.parent(id) {
+        let container = tree.style(parent).unwrap();
+        let align = tree.style(id).unwrap().align_self.or(container.align_items);
+        if align == Some(AlignItems::Baseline) && matches!(container.display, Display::Flex | Display::Grid) {
+            return true;
+        }
+        id = parent;
+    }
+    aligned_above
+}
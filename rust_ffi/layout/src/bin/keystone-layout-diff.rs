@@ -0,0 +1,143 @@
+//! keystone-layout-diff - replay two layout snapshots and report geometry changes.
+//!
+//! Usage: keystone-layout-diff <before.json> <after.json> [--threshold <px>]
+//!        keystone-layout-diff --check <fixture.json> [--threshold <px>]
+//!
+//! Nodes are matched by child-index path from the root. Prints every node whose
+//! parent-relative rect moved by more than the threshold (default 0.5px) and every
+//! place the two hierarchies stop matching. With `--check`, recomputes a fixture
+//! from `layout_export_fixture` and prints every node that misses its expected
+//! rect. Exits 0 when nothing differs, 1 when something does and 2 on bad input.
+
+use std::process::ExitCode;
+
+use keystone_layout::snapshot::Snapshot;
+use keystone_layout::{layout_child_count, layout_get_child, layout_get_result, LayoutTree};
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("keystone-layout-diff: {e}");
+            eprintln!("usage: keystone-layout-diff <before> <after> [--threshold <px>]");
+            eprintln!("       keystone-layout-diff --check <fixture> [--threshold <px>]");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run() -> Result<usize, String> {
+    let mut paths = Vec::new();
+    let mut threshold = 0.5;
+    let mut check = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--threshold" {
+            let value = args.next().ok_or("--threshold needs a value")?;
+            threshold = value.parse().map_err(|_| format!("bad threshold '{value}'"))?;
+        } else if arg == "--check" {
+            check = true;
+        } else {
+            paths.push(arg);
+        }
+    }
+    if check {
+        let [fixture] = paths.as_slice() else { return Err("expected one fixture file".into()) };
+        let failures = load(fixture)?.verify(threshold);
+        for failure in &failures {
+            println!("{failure}");
+        }
+        if !failures.is_empty() {
+            println!("{} node(s) off their expected rect", failures.len());
+        }
+        return Ok(failures.len());
+    }
+    let [before, after] = paths.as_slice() else { return Err("expected two snapshot files".into()) };
+    let differences = diff(&load(before)?, &load(after)?, threshold);
+    for difference in &differences {
+        println!("{difference}");
+    }
+    if !differences.is_empty() {
+        println!("{} difference(s)", differences.len());
+    }
+    Ok(differences.len())
+}
+
+/// Compute both snapshots and describe every difference, in tree order.
+fn diff(before: &Snapshot, after: &Snapshot, threshold: f32) -> Vec<String> {
+    let (before_tree, before_root) = before.compute();
+    let (after_tree, after_root) = after.compute();
+    let mut diff = Diff { before: &before_tree, after: &after_tree, threshold, differences: Vec::new() };
+    diff.node("root", before_root, after_root);
+    diff.differences
+}
+
+fn load(path: &str) -> Result<Snapshot, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    Snapshot::from_json(&text).map_err(|e| format!("{path}: {e}"))
+}
+
+struct Diff<'a> {
+    before: &'a LayoutTree,
+    after: &'a LayoutTree,
+    threshold: f32,
+    differences: Vec<String>,
+}
+
+impl Diff<'_> {
+    fn node(&mut self, path: &str, before: u64, after: u64) {
+        let (a, b) = (rect(self.before, before), rect(self.after, after));
+        if a.iter().zip(&b).any(|(a, b)| (a - b).abs() > self.threshold) {
+            self.differences.push(format!("{path}: ({}) -> ({})", fmt_rect(a), fmt_rect(b)));
+        }
+        let (before_count, after_count) = (layout_child_count(self.before, before), layout_child_count(self.after, after));
+        if before_count != after_count {
+            self.differences.push(format!("{path}: {before_count} children -> {after_count} children"));
+        }
+        for i in 0..before_count.min(after_count) {
+            let child_path = format!("{path}/{i}");
+            self.node(&child_path, layout_get_child(self.before, before, i), layout_get_child(self.after, after, i));
+        }
+    }
+}
+
+fn rect(tree: &LayoutTree, node: u64) -> [f32; 4] {
+    let (mut x, mut y, mut w, mut h) = (0.0, 0.0, 0.0, 0.0);
+    layout_get_result(tree, node, &mut x, &mut y, &mut w, &mut h);
+    [x, y, w, h]
+}
+
+fn fmt_rect(r: [f32; 4]) -> String {
+    format!("x={} y={} w={} h={}", r[0], r[1], r[2], r[3])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(first_width: &str, children: usize) -> Snapshot {
+        let child = |width: &str| format!(r#"{{"style": {{"width": "{width}", "height": "10px"}}}}"#);
+        let mut nodes = vec![child(first_width)];
+        nodes.extend((1..children).map(|_| child("20px")));
+        let json = format!(
+            r#"{{"version": 1, "width": 200, "height": 100, "root": {{"style": {{"width": "200px"}}, "children": [{}]}}}}"#,
+            nodes.join(", "),
+        );
+        Snapshot::from_json(&json).unwrap()
+    }
+
+    #[test]
+    fn reports_moves_above_the_threshold_and_hierarchy_changes() {
+        assert_eq!(diff(&snapshot("30px", 2), &snapshot("30px", 2), 0.5), Vec::<String>::new());
+        // Below the threshold nothing is reported
+        assert_eq!(diff(&snapshot("30px", 2), &snapshot("30.4px", 2), 0.5), Vec::<String>::new());
+
+        let differences = diff(&snapshot("30px", 2), &snapshot("40px", 3), 0.5);
+        assert_eq!(differences, [
+            "root: 2 children -> 3 children",
+            "root/0: (x=0 y=0 w=30 h=10) -> (x=0 y=0 w=40 h=10)",
+            "root/1: (x=30 y=0 w=20 h=10) -> (x=40 y=0 w=20 h=10)",
+        ]);
+    }
+}
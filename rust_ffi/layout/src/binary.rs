@@ -0,0 +1,485 @@
+//! Compact binary tree snapshots, for saving a tree every frame (undo stacks)
+//! where JSON is too slow. The content is that of `layout_tree_to_json`: every
+//! root's hierarchy, styles and node names.
+//!
+//! All integers are little-endian u32 unless noted:
+//!
+//! - header: magic `KLTB`, u16 format version, u16 reserved (0)
+//! - strings: count, then each as byte length + UTF-8
+//! - styles: count, then each as property count + (name, value) string indices
+//! - nodes: root count, node count, then every node in pre-order as record byte
+//!   length, style index, name string index + 1 (0 = unnamed), child count
+//!
+//! Identical strings and styles are stored once. Readers skip the unread rest of
+//! a node record and ignore anything after the nodes, so later versions can add
+//! fields there without bumping the version; an incompatible change bumps it, and
+//! older readers reject the data.
+//!
+//! Result caches (`layout_export_result_cache`) hold computed layouts instead: magic
+//! `KLRC`, u16 version, u16 reserved, node count, then for every node of the
+//! subtree in pre-order its child count and 20 f32s: location, size, content
+//! size, scrollbar size, then border, padding and margin (left, right, top, bottom).
+
+use std::collections::{BTreeMap, HashMap};
+
+use taffy::prelude::*;
+use taffy::Point;
+
+use crate::error::{ffi_guard, set_last_error};
+use crate::snapshot::{SnapshotNode, TreeSnapshot, SNAPSHOT_VERSION};
+use crate::{stamp_epochs, sync, LayoutTree, TreeState};
+
+const MAGIC: &[u8; 4] = b"KLTB";
+const RESULT_MAGIC: &[u8; 4] = b"KLRC";
+
+/// Bumped whenever the binary layout changes incompatibly.
+pub const BINARY_VERSION: u16 = 1;
+
+/// Byte length of the fields a version 1 node record carries.
+const NODE_RECORD_LEN: u32 = 12;
+
+impl TreeSnapshot {
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        for root in &self.roots {
+            writer.intern_node(root);
+        }
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        put(&mut out, writer.strings.len() as u32);
+        for s in &writer.strings {
+            put(&mut out, s.len() as u32);
+            out.extend_from_slice(s.as_bytes());
+        }
+        put(&mut out, writer.styles.len() as u32);
+        for style in &writer.styles {
+            put(&mut out, style.len() as u32);
+            for &(name, value) in style {
+                put(&mut out, name);
+                put(&mut out, value);
+            }
+        }
+        put(&mut out, self.roots.len() as u32);
+        put(&mut out, writer.nodes.len() as u32);
+        for node in &writer.nodes {
+            put(&mut out, NODE_RECORD_LEN);
+            for &field in node {
+                put(&mut out, field);
+            }
+        }
+        out
+    }
+
+    /// Decode and validate binary snapshot data, including every style property.
+    pub fn from_binary(data: &[u8]) -> Result<TreeSnapshot, String> {
+        let mut r = Reader { data, pos: 0 };
+        if r.bytes(4)? != MAGIC {
+            return Err("not a binary layout tree".into());
+        }
+        let version = u16::from_le_bytes(r.bytes(2)?.try_into().unwrap());
+        if version > BINARY_VERSION {
+            return Err(format!("binary tree version {version} is newer than supported ({BINARY_VERSION})"));
+        }
+        r.bytes(2)?;
+
+        let mut strings = Vec::new();
+        for _ in 0..r.u32()? {
+            let len = r.u32()? as usize;
+            let s = std::str::from_utf8(r.bytes(len)?).map_err(|_| "string is not UTF-8".to_string())?;
+            strings.push(s.to_string());
+        }
+        let string = |i: u32| strings.get(i as usize).cloned().ok_or_else(|| format!("bad string index {i}"));
+        let mut styles = Vec::new();
+        for _ in 0..r.u32()? {
+            let mut style = BTreeMap::new();
+            for _ in 0..r.u32()? {
+                let name = string(r.u32()?)?;
+                style.insert(name, string(r.u32()?)?);
+            }
+            styles.push(style);
+        }
+
+        let root_count = r.u32()?;
+        let node_count = r.u32()?;
+        let mut records = Vec::new();
+        for _ in 0..node_count {
+            let len = r.u32()?;
+            if len < NODE_RECORD_LEN {
+                return Err(format!("node record of {len} bytes is too short"));
+            }
+            let mut record = Reader { data: r.bytes(len as usize)?, pos: 0 };
+            records.push([record.u32()?, record.u32()?, record.u32()?]);
+        }
+        let mut next = records.iter();
+        let mut roots = Vec::new();
+        for _ in 0..root_count {
+            roots.push(read_node(&mut next, &styles, &string, 0)?);
+        }
+        if next.next().is_some() {
+            return Err("nodes left over after the last root".into());
+        }
+        let snapshot = TreeSnapshot { version: SNAPSHOT_VERSION, roots };
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+}
+
+fn read_node<'a>(
+    records: &mut impl Iterator<Item = &'a [u32; 3]>, styles: &[BTreeMap<String, String>],
+    string: &impl Fn(u32) -> Result<String, String>, depth: usize,
+) -> Result<SnapshotNode, String> {
+    // Deeper than any real tree; stops crafted data from overflowing the stack
+    if depth > 4096 {
+        return Err("tree is nested too deeply".into());
+    }
+    let &[style, name, child_count] = records.next().ok_or("ran out of nodes")?;
+    let style = styles.get(style as usize).cloned().ok_or_else(|| format!("bad style index {style}"))?;
+    let name = if name == 0 { None } else { Some(string(name - 1)?) };
+    let mut children = Vec::new();
+    for _ in 0..child_count {
+        children.push(read_node(records, styles, string, depth + 1)?);
+    }
+    Ok(SnapshotNode { name, style, children, expected: None })
+}
+
+#[derive(Default)]
+struct Writer<'a> {
+    strings: Vec<&'a str>,
+    string_index: HashMap<&'a str, u32>,
+    styles: Vec<Vec<(u32, u32)>>,
+    style_index: HashMap<Vec<(u32, u32)>, u32>,
+    /// Style index, name index + 1 and child count of each node, in pre-order.
+    nodes: Vec<[u32; 3]>,
+}
+
+impl<'a> Writer<'a> {
+    fn intern_string(&mut self, s: &'a str) -> u32 {
+        if let Some(&i) = self.string_index.get(s) {
+            return i;
+        }
+        let i = self.strings.len() as u32;
+        self.strings.push(s);
+        self.string_index.insert(s, i);
+        i
+    }
+
+    fn intern_node(&mut self, node: &'a SnapshotNode) {
+        let style: Vec<(u32, u32)> = node.style.iter()
+            .map(|(name, value)| (self.intern_string(name), self.intern_string(value)))
+            .collect();
+        let style = match self.style_index.get(&style) {
+            Some(&i) => i,
+            None => {
+                let i = self.styles.len() as u32;
+                self.styles.push(style.clone());
+                self.style_index.insert(style, i);
+                i
+            }
+        };
+        let name = node.name.as_deref().map_or(0, |n| self.intern_string(n) + 1);
+        self.nodes.push([style, name, node.children.len() as u32]);
+        for child in &node.children {
+            self.intern_node(child);
+        }
+    }
+}
+
+fn put(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.data.len()).ok_or("data is truncated")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+}
+
+/// Serialize the whole tree in the binary format. Returns the size in bytes; the
+/// data is written only if `cap` is at least that, so call with a null buffer to
+/// size one.
+#[no_mangle]
+pub extern "C" fn layout_tree_serialize(tree: *const LayoutTree, out: *mut u8, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let data = TreeSnapshot::capture(tree).to_binary();
+        if !out.is_null() && cap >= data.len() {
+            unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), out, data.len()) };
+        }
+        data.len()
+    })
+}
+
+/// Build a new tree from `layout_tree_serialize` data. Nodes get new IDs. Returns
+/// null and sets the last error if the data is invalid or from a newer format
+/// version. Free the tree with `layout_tree_free`.
+#[no_mangle]
+pub extern "C" fn layout_tree_deserialize(data: *const u8, len: usize) -> *mut LayoutTree {
+    ffi_guard(|| {
+        let data = if data.is_null() { &[][..] } else { unsafe { std::slice::from_raw_parts(data, len) } };
+        match TreeSnapshot::from_binary(data) {
+            Ok(snapshot) => Box::into_raw(Box::new(snapshot.build())),
+            Err(e) => {
+                set_last_error(&e);
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+// ============================================================================
+// Result caches (warm start)
+// ============================================================================
+
+/// Serialize the computed layouts of `root`'s subtree. Returns the size in bytes
+/// (0 for an unknown node); the data is written only if `cap` is at least that.
+#[no_mangle]
+pub extern "C" fn layout_export_result_cache(tree: *const LayoutTree, root: u64, out: *mut u8, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let root = NodeId::from(root);
+        if !tree.contains(root) {
+            return 0;
+        }
+        let nodes = preorder(tree, root);
+        let mut data = Vec::with_capacity(12 + nodes.len() * 84);
+        data.extend_from_slice(RESULT_MAGIC);
+        data.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        put(&mut data, nodes.len() as u32);
+        for id in nodes {
+            put(&mut data, tree.tree.child_count(id) as u32);
+            let l = tree.final_layout(id);
+            let values = [
+                l.location.x, l.location.y, l.size.width, l.size.height,
+                l.content_size.width, l.content_size.height, l.scrollbar_size.width, l.scrollbar_size.height,
+                l.border.left, l.border.right, l.border.top, l.border.bottom,
+                l.padding.left, l.padding.right, l.padding.top, l.padding.bottom,
+                l.margin.left, l.margin.right, l.margin.top, l.margin.bottom,
+            ];
+            for v in values {
+                data.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        if !out.is_null() && cap >= data.len() {
+            unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), out, data.len()) };
+        }
+        data.len()
+    })
+}
+
+/// Seed `root`'s subtree with layouts from `layout_export_result_cache`, so the
+/// first frame after a restore can paint before the first compute. The subtree
+/// must have the exported shape (same child count at every node); its getters
+/// report the imported layouts until `root` is next computed. Returns false and
+/// sets the last error if the data is invalid or the shape differs.
+#[no_mangle]
+pub extern "C" fn layout_import_result_cache(tree: *mut LayoutTree, root: u64, data: *const u8, len: usize) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let root = NodeId::from(root);
+        if !tree.contains(root) {
+            set_last_error("unknown root node");
+            return false;
+        }
+        let data = if data.is_null() { &[][..] } else { unsafe { std::slice::from_raw_parts(data, len) } };
+        match read_result_cache(tree, root, data) {
+            Ok(layouts) => {
+                tree.layout_overrides.extend(layouts);
+                stamp_epochs(tree, root);
+                true
+            }
+            Err(e) => {
+                set_last_error(&e);
+                false
+            }
+        }
+    })
+}
+
+fn read_result_cache(tree: &TreeState, root: NodeId, data: &[u8]) -> Result<Vec<(NodeId, Layout)>, String> {
+    let mut r = Reader { data, pos: 0 };
+    if r.bytes(4)? != RESULT_MAGIC {
+        return Err("not a layout result cache".into());
+    }
+    let version = u16::from_le_bytes(r.bytes(2)?.try_into().unwrap());
+    if version > BINARY_VERSION {
+        return Err(format!("result cache version {version} is newer than supported ({BINARY_VERSION})"));
+    }
+    r.bytes(2)?;
+    let nodes = preorder(tree, root);
+    let count = r.u32()? as usize;
+    if count != nodes.len() {
+        return Err(format!("cache has {count} nodes, the subtree has {}", nodes.len()));
+    }
+    let mut layouts = Vec::with_capacity(count);
+    for id in nodes {
+        if r.u32()? as usize != tree.tree.child_count(id) {
+            return Err("subtree does not have the cached shape".into());
+        }
+        let mut v = [0.0f32; 20];
+        for value in &mut v {
+            *value = f32::from_bits(r.u32()?);
+        }
+        let mut layout = Layout::new();
+        layout.location = Point { x: v[0], y: v[1] };
+        layout.size = Size { width: v[2], height: v[3] };
+        layout.content_size = Size { width: v[4], height: v[5] };
+        layout.scrollbar_size = Size { width: v[6], height: v[7] };
+        layout.border = Rect { left: v[8], right: v[9], top: v[10], bottom: v[11] };
+        layout.padding = Rect { left: v[12], right: v[13], top: v[14], bottom: v[15] };
+        layout.margin = Rect { left: v[16], right: v[17], top: v[18], bottom: v[19] };
+        layouts.push((id, layout));
+    }
+    Ok(layouts)
+}
+
+fn preorder(tree: &TreeState, root: NodeId) -> Vec<NodeId> {
+    let mut nodes = Vec::new();
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        nodes.push(id);
+        stack.extend(tree.tree.children(id).unwrap().into_iter().rev());
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+
+    use super::*;
+    use crate::snapshot::{layout_string_free, layout_tree_to_json};
+    use crate::{
+        layout_add_child, layout_compute, layout_get_result, layout_new_node, layout_set_flex_direction,
+        layout_set_flex_grow, layout_set_height, layout_set_node_name, layout_set_padding, layout_set_width,
+        layout_tree_free, layout_tree_new,
+    };
+
+    /// A named panel with padding, three children sharing a style and a second root.
+    fn sample_tree() -> (*mut LayoutTree, u64) {
+        let tree = layout_tree_new();
+        let root = layout_new_node(tree);
+        let name = CString::new("panel").unwrap();
+        layout_set_node_name(tree, root, name.as_ptr());
+        layout_set_width(tree, root, 300.0);
+        layout_set_height(tree, root, 120.0);
+        layout_set_padding(tree, root, 4, 10.0);
+        layout_set_flex_direction(tree, root, 1);
+        for _ in 0..3 {
+            let child = layout_new_node(tree);
+            layout_set_flex_grow(tree, child, 1.0);
+            layout_add_child(tree, root, child);
+        }
+        let other = layout_new_node(tree);
+        layout_set_width(tree, other, 50.0);
+        (tree, root)
+    }
+
+    fn json(tree: *const LayoutTree) -> String {
+        let raw = layout_tree_to_json(tree);
+        let json = unsafe { CStr::from_ptr(raw) }.to_str().unwrap().to_string();
+        layout_string_free(raw);
+        json
+    }
+
+    fn serialize(tree: *const LayoutTree) -> Vec<u8> {
+        let mut data = vec![0; layout_tree_serialize(tree, std::ptr::null_mut(), 0)];
+        assert_eq!(layout_tree_serialize(tree, data.as_mut_ptr(), data.len()), data.len());
+        data
+    }
+
+    fn export_cache(tree: *const LayoutTree, root: u64) -> Vec<u8> {
+        let mut data = vec![0; layout_export_result_cache(tree, root, std::ptr::null_mut(), 0)];
+        assert_eq!(layout_export_result_cache(tree, root, data.as_mut_ptr(), data.len()), data.len());
+        data
+    }
+
+    fn results(tree: *const LayoutTree, root: u64) -> Vec<[f32; 4]> {
+        let nodes = preorder(sync::shared(tree).1, NodeId::from(root));
+        nodes.into_iter().map(|id| {
+            let mut r = [0.0; 4];
+            let [x, y, w, h] = &mut r;
+            layout_get_result(tree, id.into(), x, y, w, h);
+            r
+        }).collect()
+    }
+
+    #[test]
+    fn round_trip_keeps_the_tree() {
+        let (tree, _) = sample_tree();
+        let data = serialize(tree);
+        let copy = layout_tree_deserialize(data.as_ptr(), data.len());
+        assert!(!copy.is_null());
+        assert_eq!(json(copy), json(tree));
+        // Shared styles and names are stored once, so a second trip is byte-identical
+        assert_eq!(serialize(copy), data);
+        layout_tree_free(copy);
+        layout_tree_free(tree);
+    }
+
+    #[test]
+    fn truncated_data_is_rejected() {
+        let (tree, _) = sample_tree();
+        let data = serialize(tree);
+        for len in 0..data.len() {
+            assert!(TreeSnapshot::from_binary(&data[..len]).is_err(), "accepted {len} of {} bytes", data.len());
+        }
+        assert!(layout_tree_deserialize(data.as_ptr(), data.len() - 1).is_null());
+        assert!(layout_tree_deserialize(std::ptr::null(), 0).is_null());
+        layout_tree_free(tree);
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let (tree, root) = sample_tree();
+        layout_compute(tree, root, 300.0, 120.0);
+        let newer = (BINARY_VERSION + 1).to_le_bytes();
+
+        let mut data = serialize(tree);
+        data[4..6].copy_from_slice(&newer);
+        let error = TreeSnapshot::from_binary(&data).err().unwrap();
+        assert!(error.contains("newer"), "{error}");
+
+        let mut cache = export_cache(tree, root);
+        cache[4..6].copy_from_slice(&newer);
+        assert!(!layout_import_result_cache(tree, root, cache.as_ptr(), cache.len()));
+        layout_tree_free(tree);
+    }
+
+    #[test]
+    fn result_cache_seeds_an_uncomputed_copy() {
+        let (tree, root) = sample_tree();
+        layout_compute(tree, root, 300.0, 120.0);
+        let cache = export_cache(tree, root);
+        let expected = results(tree, root);
+        assert_eq!(expected[1], [10.0, 10.0, 93.0, 100.0]);
+
+        let data = serialize(tree);
+        let copy = layout_tree_deserialize(data.as_ptr(), data.len());
+        // Deserialized nodes get new IDs
+        let copy_root = u64::from(sync::shared(copy).1.name_index["panel"][0]);
+        assert!(layout_import_result_cache(copy, copy_root, cache.as_ptr(), cache.len()));
+        assert_eq!(results(copy, copy_root), expected);
+
+        // A subtree of another shape is refused
+        let leaf = layout_new_node(copy);
+        assert!(!layout_import_result_cache(copy, leaf, cache.as_ptr(), cache.len()));
+        assert!(!layout_import_result_cache(copy, copy_root, cache.as_ptr(), cache.len() - 4));
+        layout_tree_free(copy);
+        layout_tree_free(tree);
+    }
+}
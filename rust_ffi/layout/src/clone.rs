@@ -0,0 +1,238 @@
+//! Whole-tree clones and subtree deep copies, for stamping out item templates and
+//! moving subtrees between trees.
+//!
+//! A copied node gets the source node's host style, name and layout options:
+//! context pointer, height from width, baseline, direction, logical properties,
+//! safe-area insets, overflow min-size and margin collapse modes, balanced
+//! wrapping, clip escape, gap override and style resolver.
+//! Scroll offsets, scroll links, virtual list windows and content versions are
+//! state of the original and are not copied.
+
+use std::ffi::c_void;
+
+use taffy::prelude::*;
+
+use crate::error::ffi_guard;
+use crate::{
+    apply_logical_edges, apply_logical_insets, apply_safe_area_insets, GapAfter, LayoutTree, TreeState, LogicalEdges,
+    LogicalInsets, NodeContext, StyleResolver, StyleResolverFn, MUTATION_CHILD_ADDED, MUTATION_CREATED,
+};
+use crate::sync::{self, TreeGuard};
+
+/// A new tree identical to `tree`, with the same node IDs. The clone starts with
+/// an empty journal (still enabled if the source's is), no compute stream, no
+/// compute stats, no open frame and no animation completion callbacks. A
+/// thread-safe tree's clone is thread-safe with a lock of its own. Free it with
+/// `layout_tree_free`.
+#[no_mangle]
+pub extern "C" fn layout_tree_clone(tree: *const LayoutTree) -> *mut LayoutTree {
+    ffi_guard(|| {
+        let threadsafe = sync::is_threadsafe(tree);
+        let (_lock, tree) = sync::shared(tree);
+        LayoutTree::into_raw(duplicate(tree), threadsafe)
+    })
+}
+
+/// The state of `layout_tree_clone`'s copy.
+pub(crate) fn duplicate(tree: &TreeState) -> TreeState {
+    let mut copy = tree.clone();
+    copy.journal = copy.journal.map(|_| Vec::new());
+    copy.stream = None;
+    copy.compute_stats.clear();
+    copy.frame = None;
+    copy.keyframes.forget_callbacks();
+    copy
+}
+
+/// Deep-copy `src_node` and its descendants from `src_tree` into `dst_tree`,
+/// appending the copy to `dst_parent` (0 leaves it a root). The trees may be the
+/// same. Returns the copy's root, or 0 if `src_node` or a non-zero `dst_parent` is
+/// unknown.
+#[no_mangle]
+pub extern "C" fn layout_copy_subtree(
+    src_tree: *const LayoutTree, src_node: u64, dst_tree: *mut LayoutTree, dst_parent: u64,
+) -> u64 {
+    ffi_guard(|| {
+        let Some((_locks, src, dst)) = lock_pair(src_tree, false, dst_tree) else { return 0 };
+        copy_subtree(src, src_node, dst, dst_parent, usize::MAX).map_or(0, u64::from)
+    })
+}
+
+/// Move a subtree to another tree: copy it under `dst_parent` at `index` (clamped;
+/// 0 parent leaves it a root), then remove the original. Node IDs are per tree, so
+/// the subtree gets new ones; returns the new root, or 0 on failure with neither
+/// tree changed. Within a single tree use `layout_reparent`, which keeps IDs.
+#[no_mangle]
+pub extern "C" fn layout_move_subtree(
+    src_tree: *mut LayoutTree, node: u64, dst_tree: *mut LayoutTree, dst_parent: u64, index: usize,
+) -> u64 {
+    ffi_guard(|| {
+        if src_tree == dst_tree {
+            return 0;
+        }
+        let Some((_locks, src, dst)) = lock_pair(src_tree, true, dst_tree) else { return 0 };
+        let Some(root) = copy_subtree(src, node, dst, dst_parent, index) else { return 0 };
+        unsafe { &mut *src.cast_mut() }.remove_subtree(NodeId::from(node));
+        root.into()
+    })
+}
+
+/// Lock the destination for writing and the source for reading (or writing, for a
+/// move), in address order so that two opposite copies between the same trees
+/// cannot deadlock. Returns the guards and the states, which are the same when the
+/// trees are; None if either tree is null.
+fn lock_pair(
+    src_tree: *const LayoutTree, src_exclusive: bool, dst_tree: *mut LayoutTree,
+) -> Option<(Vec<Option<TreeGuard>>, *const TreeState, *mut TreeState)> {
+    if src_tree.is_null() || dst_tree.is_null() {
+        return None;
+    }
+    let lock_src = || {
+        if src_exclusive {
+            let (guard, state) = sync::exclusive(src_tree.cast_mut());
+            (guard, std::ptr::from_mut(state).cast_const())
+        } else {
+            let (guard, state) = sync::shared(src_tree);
+            (guard, std::ptr::from_ref(state))
+        }
+    };
+    let lock_dst = || {
+        let (guard, state) = sync::exclusive(dst_tree);
+        (guard, std::ptr::from_mut(state))
+    };
+    let ((src_guard, src), (dst_guard, dst)) = if src_tree < dst_tree.cast_const() {
+        let src = lock_src();
+        (src, lock_dst())
+    } else {
+        let dst = lock_dst();
+        (lock_src(), dst)
+    };
+    Some((vec![src_guard, dst_guard], src, dst))
+}
+
+/// Raw states, because the source and destination may be the same tree.
+fn copy_subtree(
+    src_tree: *const TreeState, src_node: u64, dst_tree: *mut TreeState, dst_parent: u64, index: usize,
+) -> Option<NodeId> {
+    // Capture first so the source borrow ends before the destination is written
+    let template = {
+        let src = unsafe { &*src_tree };
+        let id = NodeId::from(src_node);
+        if !src.contains(id) {
+            return None;
+        }
+        capture(src, id)
+    };
+    let dst = unsafe { &mut *dst_tree };
+    let parent = NodeId::from(dst_parent);
+    if dst_parent != 0 && !dst.contains(parent) {
+        return None;
+    }
+    let root = build(dst, &template);
+    if dst_parent != 0 {
+        let index = index.min(dst.tree.child_count(parent));
+        let _ = dst.tree.insert_child_at_index(parent, index, root);
+        dst.record(MUTATION_CHILD_ADDED, parent, root);
+    }
+    Some(root)
+}
+
+struct NodeTemplate {
+    style: Style,
+    context: Option<NodeContext>,
+    name: Option<String>,
+    direction: Option<u8>,
+    logical_insets: Option<LogicalInsets>,
+    logical_edges: Option<LogicalEdges>,
+    safe_area_insets: Option<[f32; 4]>,
+    overflow_min_content: bool,
+    accumulate_margins: bool,
+    wrap_balance: bool,
+    clip_escape: bool,
+    layout_boundary: bool,
+    gap_after: Option<f32>,
+    resolver: Option<(StyleResolverFn, *mut c_void)>,
+    children: Vec<NodeTemplate>,
+}
+
+fn capture(tree: &TreeState, id: NodeId) -> NodeTemplate {
+    NodeTemplate {
+        style: tree.host_style(id),
+        context: tree.tree.get_node_context(id).cloned(),
+        name: tree.names.get(&id).cloned(),
+        direction: tree.directions.get(&id).copied(),
+        logical_insets: tree.logical_insets.get(&id).copied(),
+        logical_edges: tree.logical_edges.get(&id).copied(),
+        safe_area_insets: tree.safe_area_insets.get(&id).copied(),
+        overflow_min_content: tree.overflow_min_content.contains_key(&id),
+        accumulate_margins: tree.accumulate_margins.contains_key(&id),
+        wrap_balance: tree.wrap_balance.contains_key(&id),
+        clip_escape: tree.clip_escape.contains(&id),
+        layout_boundary: tree.layout_boundaries.contains(&id),
+        gap_after: tree.gap_after.get(&id).map(|g| g.gap),
+        resolver: tree.style_resolvers.get(&id).map(|r| (r.callback, r.user_data)),
+        children: tree.tree.children(id).unwrap().into_iter().map(|child| capture(tree, child)).collect(),
+    }
+}
+
+fn build(tree: &mut TreeState, template: &NodeTemplate) -> NodeId {
+    let id = tree.tree.new_leaf(Style::default()).unwrap();
+    tree.live.insert(id);
+    tree.record(MUTATION_CREATED, id, id);
+
+    // Options first, so storing the style derives the effective style from them
+    if template.overflow_min_content {
+        tree.overflow_min_content.insert(id, template.style.overflow);
+    }
+    if template.accumulate_margins {
+        tree.accumulate_margins.insert(id, template.style.clone());
+    }
+    if template.wrap_balance {
+        tree.wrap_balance.insert(id, [0.0; 2]);
+    }
+    if template.clip_escape {
+        tree.clip_escape.insert(id);
+    }
+    if let Some(direction) = template.direction {
+        tree.directions.insert(id, direction);
+    }
+    if template.layout_boundary {
+        tree.layout_boundaries.insert(id);
+    }
+    if let Some(gap) = template.gap_after {
+        tree.gap_after.insert(id, GapAfter { gap, margin: [0.0; 4] });
+    }
+    if let Some((callback, user_data)) = template.resolver {
+        tree.style_resolvers.insert(id, StyleResolver { callback, user_data, last: None });
+    }
+    if let Some(name) = &template.name {
+        tree.set_name(id, Some(name.clone()));
+    }
+    if let Some(context) = &template.context {
+        let _ = tree.tree.set_node_context(id, Some(context.clone()));
+        tree.baseline_leaves.insert(id);
+    }
+    tree.store_style(id, template.style.clone());
+    tree.style_generation += 1;
+
+    // Re-resolve against the destination's direction and safe area
+    if let Some(insets) = template.logical_insets {
+        tree.logical_insets.insert(id, insets);
+        apply_logical_insets(tree, id);
+    }
+    if let Some(edges) = template.logical_edges {
+        tree.logical_edges.insert(id, edges);
+        apply_logical_edges(tree, id);
+    }
+    if let Some(offsets) = template.safe_area_insets {
+        tree.safe_area_insets.insert(id, offsets);
+        apply_safe_area_insets(tree, id);
+    }
+
+    for child in &template.children {
+        let child = build(tree, child);
+        let _ = tree.tree.add_child(id, child);
+        tree.record(MUTATION_CHILD_ADDED, id, child);
+    }
+    id
+}
@@ -0,0 +1,713 @@
+//! CSS property vocabulary: reads and writes `Style` fields as CSS-style
+//! property/value pairs (`"width"` -> `"50%"`, `"grid-row"` -> `"2 / span 3"`).
+//!
+//! This is the engine-independent spelling of a style. Snapshots store styles in
+//! it, so saved layouts survive changes to Taffy's in-memory representation.
+
+use taffy::prelude::*;
+use taffy::{CompactLength, GridTemplateArea, GridTemplateComponent, GridTemplateRepetition, MinMax, Overflow, RepetitionCount};
+
+/// Every property this module understands, in output order.
+pub const PROPERTIES: &[&str] = &[
+    "display", "box-sizing", "position", "overflow-x", "overflow-y", "scrollbar-width",
+    "left", "top", "right", "bottom",
+    "width", "height", "min-width", "min-height", "max-width", "max-height", "aspect-ratio",
+    "margin-left", "margin-top", "margin-right", "margin-bottom",
+    "padding-left", "padding-top", "padding-right", "padding-bottom",
+    "border-left-width", "border-top-width", "border-right-width", "border-bottom-width",
+    "align-items", "align-self", "justify-items", "justify-self", "align-content", "justify-content",
+    "row-gap", "column-gap",
+    "flex-direction", "flex-wrap", "flex-basis", "flex-grow", "flex-shrink",
+    "grid-template-rows", "grid-template-columns", "grid-template-areas", "grid-auto-rows", "grid-auto-columns", "grid-auto-flow",
+    "grid-row", "grid-column",
+];
+
+/// The properties of `style` that differ from `Style::default()`, in `PROPERTIES` order.
+pub fn style_to_properties(style: &Style) -> Vec<(&'static str, String)> {
+    let default = Style::default();
+    PROPERTIES.iter()
+        .filter_map(|&name| {
+            let value = get_property(style, name)?;
+            (Some(&value) != get_property(&default, name).as_ref()).then_some((name, value))
+        })
+        .collect()
+}
+
+/// The value of one property, or None for an unknown property name.
+pub fn get_property(style: &Style, name: &str) -> Option<String> {
+    let value = match name {
+        "display" => keyword(DISPLAY, style.display),
+        "box-sizing" => keyword(BOX_SIZING, style.box_sizing),
+        "position" => keyword(POSITION, style.position),
+        "overflow-x" => keyword(OVERFLOW, style.overflow.x),
+        "overflow-y" => keyword(OVERFLOW, style.overflow.y),
+        "scrollbar-width" => number(style.scrollbar_width),
+        "left" => length(style.inset.left.into_raw()),
+        "top" => length(style.inset.top.into_raw()),
+        "right" => length(style.inset.right.into_raw()),
+        "bottom" => length(style.inset.bottom.into_raw()),
+        "width" => length(style.size.width.into_raw()),
+        "height" => length(style.size.height.into_raw()),
+        "min-width" => length(style.min_size.width.into_raw()),
+        "min-height" => length(style.min_size.height.into_raw()),
+        "max-width" => length(style.max_size.width.into_raw()),
+        "max-height" => length(style.max_size.height.into_raw()),
+        "aspect-ratio" => style.aspect_ratio.map_or_else(|| "auto".into(), number),
+        "margin-left" => length(style.margin.left.into_raw()),
+        "margin-top" => length(style.margin.top.into_raw()),
+        "margin-right" => length(style.margin.right.into_raw()),
+        "margin-bottom" => length(style.margin.bottom.into_raw()),
+        "padding-left" => length(style.padding.left.into_raw()),
+        "padding-top" => length(style.padding.top.into_raw()),
+        "padding-right" => length(style.padding.right.into_raw()),
+        "padding-bottom" => length(style.padding.bottom.into_raw()),
+        "border-left-width" => length(style.border.left.into_raw()),
+        "border-top-width" => length(style.border.top.into_raw()),
+        "border-right-width" => length(style.border.right.into_raw()),
+        "border-bottom-width" => length(style.border.bottom.into_raw()),
+        "align-items" => optional_keyword(ALIGN_ITEMS, style.align_items),
+        "align-self" => optional_keyword(ALIGN_ITEMS, style.align_self),
+        "justify-items" => optional_keyword(ALIGN_ITEMS, style.justify_items),
+        "justify-self" => optional_keyword(ALIGN_ITEMS, style.justify_self),
+        "align-content" => optional_keyword(ALIGN_CONTENT, style.align_content),
+        "justify-content" => optional_keyword(ALIGN_CONTENT, style.justify_content),
+        "row-gap" => length(style.gap.height.into_raw()),
+        "column-gap" => length(style.gap.width.into_raw()),
+        "flex-direction" => keyword(FLEX_DIRECTION, style.flex_direction),
+        "flex-wrap" => keyword(FLEX_WRAP, style.flex_wrap),
+        "flex-basis" => length(style.flex_basis.into_raw()),
+        "flex-grow" => number(style.flex_grow),
+        "flex-shrink" => number(style.flex_shrink),
+        "grid-template-rows" => template(&style.grid_template_rows),
+        "grid-template-columns" => template(&style.grid_template_columns),
+        "grid-template-areas" => areas(&style.grid_template_areas),
+        "grid-auto-rows" => tracks(&style.grid_auto_rows),
+        "grid-auto-columns" => tracks(&style.grid_auto_columns),
+        "grid-auto-flow" => keyword(GRID_AUTO_FLOW, style.grid_auto_flow),
+        "grid-row" => grid_line(&style.grid_row),
+        "grid-column" => grid_line(&style.grid_column),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Put one property back to its `Style::default()` value.
+pub fn reset_property(style: &mut Style, name: &str) -> Result<(), String> {
+    let default = get_property(&Style::default(), name).ok_or_else(|| format!("unknown property '{name}'"))?;
+    set_property(style, name, &default)
+}
+
+/// Parse `value` and store it into `style`. The error names what was wrong.
+pub fn set_property(style: &mut Style, name: &str, value: &str) -> Result<(), String> {
+    let value = value.trim();
+    match name {
+        "display" => style.display = parse_keyword(DISPLAY, value)?,
+        "box-sizing" => style.box_sizing = parse_keyword(BOX_SIZING, value)?,
+        "position" => style.position = parse_keyword(POSITION, value)?,
+        "overflow-x" => style.overflow.x = parse_keyword(OVERFLOW, value)?,
+        "overflow-y" => style.overflow.y = parse_keyword(OVERFLOW, value)?,
+        "scrollbar-width" => style.scrollbar_width = parse_number(value)?,
+        "left" => style.inset.left = parse_lpa(value)?,
+        "top" => style.inset.top = parse_lpa(value)?,
+        "right" => style.inset.right = parse_lpa(value)?,
+        "bottom" => style.inset.bottom = parse_lpa(value)?,
+        "width" => style.size.width = parse_dimension(value)?,
+        "height" => style.size.height = parse_dimension(value)?,
+        "min-width" => style.min_size.width = parse_dimension(value)?,
+        "min-height" => style.min_size.height = parse_dimension(value)?,
+        "max-width" => style.max_size.width = parse_dimension(value)?,
+        "max-height" => style.max_size.height = parse_dimension(value)?,
+        "aspect-ratio" => {
+            style.aspect_ratio = if value == "auto" { None } else { Some(parse_number(value)?) };
+        }
+        "margin-left" => style.margin.left = parse_lpa(value)?,
+        "margin-top" => style.margin.top = parse_lpa(value)?,
+        "margin-right" => style.margin.right = parse_lpa(value)?,
+        "margin-bottom" => style.margin.bottom = parse_lpa(value)?,
+        "padding-left" => style.padding.left = parse_lp(value)?,
+        "padding-top" => style.padding.top = parse_lp(value)?,
+        "padding-right" => style.padding.right = parse_lp(value)?,
+        "padding-bottom" => style.padding.bottom = parse_lp(value)?,
+        "border-left-width" => style.border.left = parse_lp(value)?,
+        "border-top-width" => style.border.top = parse_lp(value)?,
+        "border-right-width" => style.border.right = parse_lp(value)?,
+        "border-bottom-width" => style.border.bottom = parse_lp(value)?,
+        "align-items" => style.align_items = parse_optional_keyword(ALIGN_ITEMS, value)?,
+        "align-self" => style.align_self = parse_optional_keyword(ALIGN_ITEMS, value)?,
+        "justify-items" => style.justify_items = parse_optional_keyword(ALIGN_ITEMS, value)?,
+        "justify-self" => style.justify_self = parse_optional_keyword(ALIGN_ITEMS, value)?,
+        "align-content" => style.align_content = parse_optional_keyword(ALIGN_CONTENT, value)?,
+        "justify-content" => style.justify_content = parse_optional_keyword(ALIGN_CONTENT, value)?,
+        "row-gap" => style.gap.height = parse_lp(value)?,
+        "column-gap" => style.gap.width = parse_lp(value)?,
+        "flex-direction" => style.flex_direction = parse_keyword(FLEX_DIRECTION, value)?,
+        "flex-wrap" => style.flex_wrap = parse_keyword(FLEX_WRAP, value)?,
+        "flex-basis" => style.flex_basis = parse_dimension(value)?,
+        "flex-grow" => style.flex_grow = parse_number(value)?,
+        "flex-shrink" => style.flex_shrink = parse_number(value)?,
+        "grid-template-rows" => style.grid_template_rows = parse_template(value)?,
+        "grid-template-columns" => style.grid_template_columns = parse_template(value)?,
+        "grid-template-areas" => style.grid_template_areas = parse_areas(value)?,
+        "grid-auto-rows" => style.grid_auto_rows = parse_tracks(value)?,
+        "grid-auto-columns" => style.grid_auto_columns = parse_tracks(value)?,
+        "grid-auto-flow" => style.grid_auto_flow = parse_keyword(GRID_AUTO_FLOW, value)?,
+        "grid-row" => style.grid_row = parse_grid_line(value)?,
+        "grid-column" => style.grid_column = parse_grid_line(value)?,
+        _ => return Err(format!("unknown property '{name}'")),
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Declarations
+// ============================================================================
+
+/// Apply a CSS declaration block (`"display: flex; padding: 8px 12px; gap: 4px"`) to
+/// `style`, in order. Besides every property in `PROPERTIES` it takes the
+/// shorthands `margin`, `padding`, `inset`, `border-width`, `gap`, `overflow`,
+/// `flex` and `flex-flow`. Comments are skipped. On error `style` may be partly
+/// updated; the error names the declaration that failed.
+pub fn apply_declarations(style: &mut Style, text: &str) -> Result<(), String> {
+    for declaration in split_declarations(&strip_comments(text)) {
+        let declaration = declaration.trim();
+        if declaration.is_empty() {
+            continue;
+        }
+        let (name, value) = declaration.split_once(':').ok_or_else(|| format!("'{declaration}': expected name: value"))?;
+        let name = name.trim().to_ascii_lowercase();
+        set_declaration(style, &name, value).map_err(|e| format!("{name}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Set one property or one of the shorthands `apply_declarations` takes.
+pub fn set_declaration(style: &mut Style, name: &str, value: &str) -> Result<(), String> {
+    for (longhand, value) in expand_shorthand(name, value.trim())? {
+        set_property(style, &longhand, &value)?;
+    }
+    Ok(())
+}
+
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = rest[start + 2..].find("*/").map_or("", |end| &rest[start + 2 + end + 2..]);
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Split on semicolons outside quotes (grid-template-areas) and parentheses.
+fn split_declarations(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut start, mut depth, mut quote) = (0, 0, None);
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' | '\'' if quote.is_none() => quote = Some(c),
+            c if Some(c) == quote => quote = None,
+            '(' if quote.is_none() => depth += 1,
+            ')' if quote.is_none() => depth -= 1,
+            ';' if quote.is_none() && depth <= 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// The longhands a declaration sets; a longhand maps to itself.
+fn expand_shorthand(name: &str, value: &str) -> Result<Vec<(String, String)>, String> {
+    let values: Vec<&str> = value.split_whitespace().collect();
+    let pairs = |names: &[&str], values: Vec<String>| names.iter().map(|n| n.to_string()).zip(values).collect();
+    let edges = |prefix: &str, suffix: &str| -> Result<Vec<(String, String)>, String> {
+        // CSS order: top, right, bottom, left
+        let [top, right, bottom, left] = match values[..] {
+            [all] => [all; 4],
+            [vertical, horizontal] => [vertical, horizontal, vertical, horizontal],
+            [top, horizontal, bottom] => [top, horizontal, bottom, horizontal],
+            [top, right, bottom, left] => [top, right, bottom, left],
+            _ => return Err(format!("expected 1 to 4 values, got '{value}'")),
+        };
+        Ok([("top", top), ("right", right), ("bottom", bottom), ("left", left)].iter()
+            .map(|(edge, v)| (format!("{prefix}{edge}{suffix}"), v.to_string()))
+            .collect())
+    };
+    Ok(match name {
+        "margin" => edges("margin-", "")?,
+        "padding" => edges("padding-", "")?,
+        "inset" => edges("", "")?,
+        "border-width" => edges("border-", "-width")?,
+        "gap" => match values[..] {
+            [both] => pairs(&["row-gap", "column-gap"], vec![both.into(), both.into()]),
+            [row, column] => pairs(&["row-gap", "column-gap"], vec![row.into(), column.into()]),
+            _ => return Err(format!("expected 1 or 2 values, got '{value}'")),
+        },
+        "overflow" => match values[..] {
+            [both] => pairs(&["overflow-x", "overflow-y"], vec![both.into(), both.into()]),
+            [x, y] => pairs(&["overflow-x", "overflow-y"], vec![x.into(), y.into()]),
+            _ => return Err(format!("expected 1 or 2 values, got '{value}'")),
+        },
+        "flex-flow" => values.iter()
+            .map(|&v| {
+                let longhand = if parse_keyword(FLEX_WRAP, v).is_ok() { "flex-wrap" } else { "flex-direction" };
+                (longhand.to_string(), v.to_string())
+            })
+            .collect(),
+        "flex" => {
+            let flex = |grow: &str, shrink: &str, basis: &str| {
+                pairs(&["flex-grow", "flex-shrink", "flex-basis"], vec![grow.into(), shrink.into(), basis.into()])
+            };
+            match values[..] {
+                ["none"] => flex("0", "0", "auto"),
+                ["auto"] => flex("1", "1", "auto"),
+                // As in CSS, numbers fill grow then shrink; a lone number sets basis 0%
+                [grow] if parse_number(grow).is_ok() => flex(grow, "1", "0%"),
+                [basis] => flex("1", "1", basis),
+                [grow, shrink] if parse_number(shrink).is_ok() => flex(grow, shrink, "0%"),
+                [grow, basis] => flex(grow, "1", basis),
+                [grow, shrink, basis] => flex(grow, shrink, basis),
+                _ => return Err(format!("expected 1 to 3 values, got '{value}'")),
+            }
+        }
+        _ => vec![(name.to_string(), value.to_string())],
+    })
+}
+
+// ============================================================================
+// Keywords
+// ============================================================================
+
+const DISPLAY: &[(&str, Display)] =
+    &[("flex", Display::Flex), ("grid", Display::Grid), ("block", Display::Block), ("none", Display::None)];
+const BOX_SIZING: &[(&str, BoxSizing)] =
+    &[("border-box", BoxSizing::BorderBox), ("content-box", BoxSizing::ContentBox)];
+const POSITION: &[(&str, Position)] = &[("relative", Position::Relative), ("absolute", Position::Absolute)];
+const OVERFLOW: &[(&str, Overflow)] = &[
+    ("visible", Overflow::Visible), ("hidden", Overflow::Hidden), ("scroll", Overflow::Scroll), ("clip", Overflow::Clip),
+];
+const FLEX_DIRECTION: &[(&str, FlexDirection)] = &[
+    ("row", FlexDirection::Row), ("column", FlexDirection::Column),
+    ("row-reverse", FlexDirection::RowReverse), ("column-reverse", FlexDirection::ColumnReverse),
+];
+const FLEX_WRAP: &[(&str, FlexWrap)] =
+    &[("nowrap", FlexWrap::NoWrap), ("wrap", FlexWrap::Wrap), ("wrap-reverse", FlexWrap::WrapReverse)];
+const ALIGN_ITEMS: &[(&str, AlignItems)] = &[
+    ("start", AlignItems::Start), ("end", AlignItems::End),
+    ("flex-start", AlignItems::FlexStart), ("flex-end", AlignItems::FlexEnd),
+    ("center", AlignItems::Center), ("baseline", AlignItems::Baseline), ("stretch", AlignItems::Stretch),
+];
+const ALIGN_CONTENT: &[(&str, AlignContent)] = &[
+    ("start", AlignContent::Start), ("end", AlignContent::End),
+    ("flex-start", AlignContent::FlexStart), ("flex-end", AlignContent::FlexEnd),
+    ("center", AlignContent::Center), ("stretch", AlignContent::Stretch),
+    ("space-between", AlignContent::SpaceBetween), ("space-evenly", AlignContent::SpaceEvenly),
+    ("space-around", AlignContent::SpaceAround),
+];
+const GRID_AUTO_FLOW: &[(&str, GridAutoFlow)] = &[
+    ("row", GridAutoFlow::Row), ("column", GridAutoFlow::Column),
+    ("row dense", GridAutoFlow::RowDense), ("column dense", GridAutoFlow::ColumnDense),
+];
+
+fn keyword<T: Copy + PartialEq>(table: &[(&str, T)], value: T) -> String {
+    table.iter().find(|(_, v)| *v == value).map(|(name, _)| name.to_string()).unwrap()
+}
+
+fn optional_keyword<T: Copy + PartialEq>(table: &[(&str, T)], value: Option<T>) -> String {
+    value.map_or_else(|| "normal".into(), |v| keyword(table, v))
+}
+
+fn parse_keyword<T: Copy>(table: &[(&str, T)], value: &str) -> Result<T, String> {
+    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    table.iter().find(|(name, _)| *name == value).map(|(_, v)| *v).ok_or_else(|| format!("unknown keyword '{value}'"))
+}
+
+/// `normal` and `auto` both mean "not set".
+fn parse_optional_keyword<T: Copy>(table: &[(&str, T)], value: &str) -> Result<Option<T>, String> {
+    if value == "normal" || value == "auto" { Ok(None) } else { parse_keyword(table, value).map(Some) }
+}
+
+// ============================================================================
+// Lengths
+// ============================================================================
+
+fn number(v: f32) -> String {
+    format!("{v}")
+}
+
+fn parse_number(value: &str) -> Result<f32, String> {
+    value.parse().map_err(|_| format!("expected a number, got '{value}'"))
+}
+
+/// Any length-like value: `auto`, `12px`, `50%`, `1fr`, `min-content`, `fit-content(20px)`...
+fn length(raw: CompactLength) -> String {
+    let v = raw.value();
+    match raw.tag() {
+        CompactLength::LENGTH_TAG => format!("{v}px"),
+        CompactLength::PERCENT_TAG => format!("{}%", v * 100.0),
+        CompactLength::FR_TAG => format!("{v}fr"),
+        CompactLength::MIN_CONTENT_TAG => "min-content".into(),
+        CompactLength::MAX_CONTENT_TAG => "max-content".into(),
+        CompactLength::FIT_CONTENT_PX_TAG => format!("fit-content({v}px)"),
+        CompactLength::FIT_CONTENT_PERCENT_TAG => format!("fit-content({}%)", v * 100.0),
+        _ => "auto".into(),
+    }
+}
+
+fn parse_length(value: &str) -> Result<CompactLength, String> {
+    let bad = || format!("bad length '{value}'");
+    let num = |s: &str| s.trim().parse::<f32>().map_err(|_| bad());
+    Ok(match value {
+        "auto" => CompactLength::auto(),
+        "min-content" => CompactLength::min_content(),
+        "max-content" => CompactLength::max_content(),
+        _ if value.starts_with("fit-content(") && value.ends_with(')') => {
+            match parse_length(&value["fit-content(".len()..value.len() - 1])? {
+                l if l.tag() == CompactLength::LENGTH_TAG => CompactLength::fit_content_px(l.value()),
+                l if l.tag() == CompactLength::PERCENT_TAG => CompactLength::fit_content_percent(l.value()),
+                _ => return Err(bad()),
+            }
+        }
+        _ if value.ends_with("px") => CompactLength::length(num(&value[..value.len() - 2])?),
+        _ if value.ends_with('%') => CompactLength::percent(num(&value[..value.len() - 1])? / 100.0),
+        _ if value.ends_with("fr") => CompactLength::fr(num(&value[..value.len() - 2])?),
+        // Unitless numbers are px, as in the numeric setters
+        _ => CompactLength::length(num(value)?),
+    })
+}
+
+fn parse_tagged(value: &str, allowed: &[usize]) -> Result<CompactLength, String> {
+    let raw = parse_length(value)?;
+    if allowed.contains(&raw.tag()) { Ok(raw) } else { Err(format!("'{value}' is not allowed here")) }
+}
+
+const LP_TAGS: &[usize] = &[CompactLength::LENGTH_TAG, CompactLength::PERCENT_TAG];
+const LPA_TAGS: &[usize] = &[CompactLength::LENGTH_TAG, CompactLength::PERCENT_TAG, CompactLength::AUTO_TAG];
+
+fn parse_lp(value: &str) -> Result<LengthPercentage, String> {
+    parse_tagged(value, LP_TAGS).map(|raw| unsafe { LengthPercentage::from_raw(raw) })
+}
+
+fn parse_lpa(value: &str) -> Result<LengthPercentageAuto, String> {
+    parse_tagged(value, LPA_TAGS).map(|raw| unsafe { LengthPercentageAuto::from_raw(raw) })
+}
+
+fn parse_dimension(value: &str) -> Result<Dimension, String> {
+    parse_tagged(value, LPA_TAGS).map(|raw| unsafe { Dimension::from_raw(raw) })
+}
+
+// ============================================================================
+// Grid
+// ============================================================================
+
+/// `1fr` and `fit-content(x)` are shorthands for `minmax(auto, ...)`; everything
+/// else that is not `minmax(...)` has the same min and max.
+fn track(t: &TrackSizingFunction) -> String {
+    let (min, max) = (t.min.into_raw(), t.max.into_raw());
+    let min_is_auto = min.tag() == CompactLength::AUTO_TAG;
+    let shorthand = matches!(
+        max.tag(),
+        CompactLength::FR_TAG | CompactLength::FIT_CONTENT_PX_TAG | CompactLength::FIT_CONTENT_PERCENT_TAG
+    );
+    if min == max || (min_is_auto && shorthand) {
+        length(max)
+    } else {
+        format!("minmax({}, {})", length(min), length(max))
+    }
+}
+
+fn parse_track(value: &str) -> Result<TrackSizingFunction, String> {
+    const MIN_TAGS: &[usize] = &[
+        CompactLength::LENGTH_TAG, CompactLength::PERCENT_TAG, CompactLength::AUTO_TAG,
+        CompactLength::MIN_CONTENT_TAG, CompactLength::MAX_CONTENT_TAG,
+    ];
+    let (min, max) = match value.strip_prefix("minmax(").and_then(|v| v.strip_suffix(')')) {
+        Some(args) => {
+            let (min, max) = args.split_once(',').ok_or_else(|| format!("bad track '{value}'"))?;
+            (parse_tagged(min.trim(), MIN_TAGS)?, parse_length(max.trim())?)
+        }
+        None => {
+            let max = parse_length(value)?;
+            let min = if MIN_TAGS.contains(&max.tag()) { max } else { CompactLength::auto() };
+            (min, max)
+        }
+    };
+    Ok(MinMax {
+        min: unsafe { MinTrackSizingFunction::from_raw(min) },
+        max: unsafe { MaxTrackSizingFunction::from_raw(max) },
+    })
+}
+
+fn tracks(list: &[TrackSizingFunction]) -> String {
+    if list.is_empty() {
+        return "none".into();
+    }
+    list.iter().map(track).collect::<Vec<_>>().join(" ")
+}
+
+fn parse_tracks(value: &str) -> Result<Vec<TrackSizingFunction>, String> {
+    if value == "none" {
+        return Ok(Vec::new());
+    }
+    split_top_level(value).into_iter().map(parse_track).collect()
+}
+
+fn template(list: &[GridTemplateComponent<String>]) -> String {
+    if list.is_empty() {
+        return "none".into();
+    }
+    list.iter()
+        .map(|component| match component {
+            GridTemplateComponent::Single(t) => track(t),
+            GridTemplateComponent::Repeat(r) => {
+                let count = match r.count {
+                    RepetitionCount::AutoFill => "auto-fill".into(),
+                    RepetitionCount::AutoFit => "auto-fit".into(),
+                    RepetitionCount::Count(n) => n.to_string(),
+                };
+                format!("repeat({count}, {})", tracks(&r.tracks))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_template(value: &str) -> Result<Vec<GridTemplateComponent<String>>, String> {
+    if value == "none" {
+        return Ok(Vec::new());
+    }
+    split_top_level(value).into_iter()
+        .map(|part| match part.strip_prefix("repeat(").and_then(|v| v.strip_suffix(')')) {
+            Some(args) => {
+                let (count, list) = args.split_once(',').ok_or_else(|| format!("bad repeat '{part}'"))?;
+                let count = match count.trim() {
+                    "auto-fill" => RepetitionCount::AutoFill,
+                    "auto-fit" => RepetitionCount::AutoFit,
+                    n => RepetitionCount::Count(n.parse().map_err(|_| format!("bad repeat count '{n}'"))?),
+                };
+                let tracks = parse_tracks(list.trim())?;
+                let line_names = vec![Vec::new(); tracks.len() + 1];
+                Ok(GridTemplateComponent::Repeat(GridTemplateRepetition { count, tracks, line_names }))
+            }
+            None => parse_track(part).map(GridTemplateComponent::Single),
+        })
+        .collect()
+}
+
+/// One quoted string per row, as in CSS: `"head head" "nav main"`.
+fn areas(list: &[GridTemplateArea<String>]) -> String {
+    if list.is_empty() {
+        return "none".into();
+    }
+    let rows = list.iter().map(|a| a.row_end - 1).max().unwrap_or(0) as usize;
+    let columns = list.iter().map(|a| a.column_end - 1).max().unwrap_or(0) as usize;
+    let mut cells = vec![vec!["."; columns]; rows];
+    for area in list {
+        for row in &mut cells[area.row_start as usize - 1..area.row_end as usize - 1] {
+            for cell in &mut row[area.column_start as usize - 1..area.column_end as usize - 1] {
+                *cell = &area.name;
+            }
+        }
+    }
+    cells.iter().map(|row| format!("\"{}\"", row.join(" "))).collect::<Vec<_>>().join(" ")
+}
+
+fn parse_areas(value: &str) -> Result<Vec<GridTemplateArea<String>>, String> {
+    if value == "none" {
+        return Ok(Vec::new());
+    }
+    let rows: Vec<&str> = value.split('"').skip(1).step_by(2).collect();
+    grid_areas(&rows).ok_or_else(|| format!("bad grid-template-areas '{value}'"))
+}
+
+/// Build areas from rows of cell names. `.` cells stay unnamed. None unless every
+/// row has the same number of cells and every name covers a filled rectangle.
+pub(crate) fn grid_areas(rows: &[&str]) -> Option<Vec<GridTemplateArea<String>>> {
+    let cells: Vec<Vec<&str>> = rows.iter().map(|row| row.split_whitespace().collect()).collect();
+    let columns = cells.first().map_or(0, |row| row.len());
+    if cells.iter().any(|row| row.len() != columns) {
+        return None;
+    }
+    let mut areas: Vec<GridTemplateArea<String>> = Vec::new();
+    for (r, row) in cells.iter().enumerate() {
+        for (c, &name) in row.iter().enumerate() {
+            if name.chars().all(|ch| ch == '.') {
+                continue;
+            }
+            let (r, c) = (r as u16 + 1, c as u16 + 1);
+            match areas.iter_mut().find(|a| a.name == name) {
+                Some(a) => {
+                    a.row_start = a.row_start.min(r);
+                    a.row_end = a.row_end.max(r + 1);
+                    a.column_start = a.column_start.min(c);
+                    a.column_end = a.column_end.max(c + 1);
+                }
+                None => areas.push(GridTemplateArea {
+                    name: name.to_string(), row_start: r, row_end: r + 1, column_start: c, column_end: c + 1,
+                }),
+            }
+        }
+    }
+    let rectangular = areas.iter().all(|a| {
+        (a.row_start..a.row_end).all(|r| {
+            (a.column_start..a.column_end).all(|c| cells[r as usize - 1][c as usize - 1] == a.name)
+        })
+    });
+    rectangular.then_some(areas)
+}
+
+/// Whitespace-separated items, keeping parenthesised groups together.
+fn split_top_level(value: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0, None);
+    for (i, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(s) = start.take() {
+                    parts.push(&value[s..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        parts.push(&value[s..]);
+    }
+    parts
+}
+
+/// `start / end`, where each side is `auto`, a line number, `span N`, or a line name
+/// optionally followed by its occurrence.
+fn grid_line(line: &Line<GridPlacement>) -> String {
+    let placement = |p: &GridPlacement| match p {
+        GridPlacement::Auto => "auto".to_string(),
+        GridPlacement::Line(l) => l.as_i16().to_string(),
+        GridPlacement::Span(n) => format!("span {n}"),
+        GridPlacement::NamedLine(name, 1) => name.clone(),
+        GridPlacement::NamedLine(name, n) => format!("{name} {n}"),
+        GridPlacement::NamedSpan(name, 1) => format!("span {name}"),
+        GridPlacement::NamedSpan(name, n) => format!("span {name} {n}"),
+    };
+    match line.end {
+        GridPlacement::Auto => placement(&line.start),
+        _ => format!("{} / {}", placement(&line.start), placement(&line.end)),
+    }
+}
+
+fn parse_grid_line(value: &str) -> Result<Line<GridPlacement>, String> {
+    let placement = |s: &str| -> Result<GridPlacement, String> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let (span, words) = match words.split_first() {
+            Some((&"span", rest)) => (true, rest),
+            _ => (false, words.as_slice()),
+        };
+        let bad = || format!("bad grid line '{s}'");
+        Ok(match (span, words) {
+            (false, ["auto"]) => GridPlacement::Auto,
+            (false, [n]) if n.parse::<i16>().is_ok() => GridPlacement::from_line_index(n.parse().unwrap()),
+            (true, [n]) if n.parse::<u16>().is_ok() => GridPlacement::from_span(n.parse().unwrap()),
+            (false, [name]) => GridPlacement::NamedLine(name.to_string(), 1),
+            (false, [name, n]) => GridPlacement::NamedLine(name.to_string(), n.parse().map_err(|_| bad())?),
+            (true, [name]) => GridPlacement::NamedSpan(name.to_string(), 1),
+            (true, [name, n]) => GridPlacement::NamedSpan(name.to_string(), n.parse().map_err(|_| bad())?),
+            _ => return Err(bad()),
+        })
+    };
+    let (start, end) = value.split_once('/').unwrap_or((value, "auto"));
+    Ok(Line { start: placement(start.trim())?, end: placement(end.trim())? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declared(text: &str) -> Style {
+        let mut style = Style::default();
+        apply_declarations(&mut style, text).unwrap();
+        style
+    }
+
+    #[test]
+    fn declarations_expand_shorthands() {
+        let style = declared("display: flex; FLEX-FLOW: column wrap; /* spacing */ padding: 8px 12px; gap: 4px 2%;");
+        assert_eq!(style.display, Display::Flex);
+        assert_eq!((style.flex_direction, style.flex_wrap), (FlexDirection::Column, FlexWrap::Wrap));
+        let px = LengthPercentage::length;
+        assert_eq!(style.padding, Rect { left: px(12.0), right: px(12.0), top: px(8.0), bottom: px(8.0) });
+        assert_eq!(style.gap, Size { width: LengthPercentage::percent(0.02), height: px(4.0) });
+
+        let style = declared("margin: 1px 2px 3px; inset: auto 5px; flex: 2");
+        assert_eq!(style.margin.left, LengthPercentageAuto::length(2.0));
+        assert_eq!(style.margin.bottom, LengthPercentageAuto::length(3.0));
+        assert_eq!(style.inset.top, LengthPercentageAuto::auto());
+        assert_eq!((style.flex_grow, style.flex_shrink, style.flex_basis), (2.0, 1.0, Dimension::percent(0.0)));
+        assert_eq!(declared("flex: none").flex_shrink, 0.0);
+        assert_eq!(declared("flex: 1 30px").flex_basis, Dimension::length(30.0));
+    }
+
+    #[test]
+    fn bad_declarations_name_what_failed() {
+        let mut style = Style::default();
+        let error = |text: &str| apply_declarations(&mut Style::default(), text).unwrap_err();
+        assert_eq!(error("colour: red"), "colour: unknown property 'colour'");
+        assert!(error("display: inline").contains("unknown keyword 'inline'"));
+        assert!(error("width 10px").contains("expected name: value"));
+        assert!(error("padding: 1px 2px 3px 4px 5px").starts_with("padding: expected 1 to 4 values"));
+        assert!(error("gap: 1px 2px 3px").starts_with("gap: expected 1 or 2 values"));
+        assert_eq!(error("width: 1fr"), "width: '1fr' is not allowed here");
+        assert!(error("flex-grow: lots").contains("expected a number"));
+        // Declarations before the bad one are applied
+        assert!(apply_declarations(&mut style, "width: 10px; height: wide").is_err());
+        assert_eq!(style.size.width, Dimension::length(10.0));
+    }
+
+    #[test]
+    fn properties_read_back_as_written() {
+        let style = declared(
+            "display: grid; width: 50%; aspect-ratio: 1.5; align-items: center; \
+             grid-template-columns: 100px repeat(2, minmax(10px, 1fr)); grid-auto-flow: column dense; \
+             grid-row: 2 / span 3; grid-column: main-start / main-end",
+        );
+        let properties = style_to_properties(&style);
+        let mut copy = Style::default();
+        for (name, value) in &properties {
+            set_property(&mut copy, name, value).unwrap();
+        }
+        assert_eq!(style_to_properties(&copy), properties);
+        assert_eq!(get_property(&style, "grid-row").unwrap(), "2 / span 3");
+        assert_eq!(get_property(&style, "align-self").unwrap(), "normal");
+        assert_eq!(get_property(&style, "colour"), None);
+        // Strings may hold semicolons without ending the declaration
+        let style = declared("grid-template-areas: \"a;b a;b\"; display: grid");
+        assert_eq!(style.grid_template_areas[0].name, "a;b");
+    }
+    fn area(name: &str, rows: (u16, u16), columns: (u16, u16)) -> GridTemplateArea<String> {
+        GridTemplateArea { name: name.into(), row_start: rows.0, row_end: rows.1, column_start: columns.0, column_end: columns.1 }
+    }
+
+    #[test]
+    fn template_areas_must_be_rectangles() {
+        let areas = grid_areas(&["head head", "nav  main", ".    main"]).unwrap();
+        assert_eq!(areas, [area("head", (1, 2), (1, 3)), area("nav", (2, 3), (1, 2)), area("main", (2, 4), (2, 3))]);
+        assert_eq!(grid_areas(&[]).unwrap(), []);
+        // An L shape, a name split in two, and rows of different lengths
+        assert_eq!(grid_areas(&["a a", "a b"]), None);
+        assert_eq!(grid_areas(&["a b a"]), None);
+        assert_eq!(grid_areas(&["a b", "a"]), None);
+
+        let mut style = Style::default();
+        set_property(&mut style, "grid-template-areas", "\"head head\" \"nav main\"").unwrap();
+        assert_eq!(get_property(&style, "grid-template-areas").unwrap(), "\"head head\" \"nav main\"");
+        assert!(set_property(&mut style, "grid-template-areas", "\"a a\" \"a b\"").is_err());
+        set_property(&mut style, "grid-template-areas", "none").unwrap();
+        assert!(style.grid_template_areas.is_empty());
+    }
+}
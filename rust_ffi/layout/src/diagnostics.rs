@@ -0,0 +1,444 @@
+//! Per-node diagnostics: how the engine actually interpreted a node's style, a
+//! text or Graphviz dump of a subtree with its computed rects, and whole-tree
+//! statistics for a host diagnostics panel.
+//!
+//! Everything here is derived from styles and the last computed layout, so it is
+//! only meaningful after `layout_compute`.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{c_char, CString};
+use std::fmt::Write;
+use std::mem::size_of;
+
+use taffy::prelude::*;
+use taffy::{Cache, DetailedLayoutInfo, Layout, MaybeResolve, Overflow, PrintTree, ResolveOrZero};
+
+use crate::error::ffi_guard;
+use crate::{align_items_code, css, sync, LayoutTree, TreeState, NodeContext};
+
+/// Cross-axis (flex) / block-axis (grid) alignment was coerced to another value.
+pub const DEBUG_ALIGN_FALLBACK: u32 = 1 << 0;
+/// Main-axis (flex) / inline-axis (grid) self-alignment was coerced or ignored.
+pub const DEBUG_JUSTIFY_FALLBACK: u32 = 1 << 1;
+/// A flex item's automatic minimum size (its min-content floor) kept it from
+/// shrinking any further while its line still overflows.
+pub const DEBUG_MIN_SIZE_FLOOR: u32 = 1 << 2;
+/// Sizing constraints contradicted each other and the engine settled them by a
+/// fallback rule: an aspect ratio with both sizes fixed or overridden by min/max,
+/// a percentage height against a parent with no definite height, or min > max.
+pub const DEBUG_CONSTRAINT_CONFLICT: u32 = 1 << 3;
+
+/// Alignment codes use the setter mapping (1=start, 2=center, 3=end, 4=stretch,
+/// 5=baseline); 0 means "not requested" or "does not apply".
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutDebugInfo {
+    pub flags: u32,
+    pub align_requested: u8,
+    pub align_effective: u8,
+    pub justify_requested: u8,
+    pub justify_effective: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutTreeStats {
+    /// Live nodes, detached subtrees included.
+    pub node_count: u64,
+    /// Parentless nodes that are not detached.
+    pub root_count: u64,
+    /// Nodes that need relayout.
+    pub dirty_count: u64,
+    /// Duration of the last compute that ran, in microseconds (0 before the first).
+    pub last_compute_us: u64,
+    /// Rough heap footprint of the tree: node storage plus every side table.
+    pub memory_bytes: u64,
+    /// Levels in the deepest root-to-leaf chain outside detached subtrees; a lone
+    /// root is 1.
+    pub max_depth: u32,
+}
+
+/// Fill `out` with diagnostics for `node`. Returns false for an unknown node.
+#[no_mangle]
+pub extern "C" fn layout_get_debug_info(tree: *const LayoutTree, node: u64, out: &mut LayoutDebugInfo) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return false;
+        }
+        let style = tree.tree.style(id).unwrap();
+        let mut info = LayoutDebugInfo::default();
+        if constraint_conflict(tree, id, style) {
+            info.flags |= DEBUG_CONSTRAINT_CONFLICT;
+        }
+        if let Some(parent) = tree.tree.parent(id) {
+            let parent_style = tree.tree.style(parent).unwrap();
+            resolve_alignment(tree, style, parent, parent_style, &mut info);
+            if parent_style.display == Display::Flex && min_size_floored(tree, id, style, parent, parent_style) {
+                info.flags |= DEBUG_MIN_SIZE_FLOOR;
+            }
+        }
+        *out = info;
+        true
+    })
+}
+
+/// Fill `out` with statistics for the whole tree. Counting walks every node, so
+/// call it from a diagnostics view rather than every frame.
+#[no_mangle]
+pub extern "C" fn layout_tree_stats(tree: *const LayoutTree, out: &mut LayoutTreeStats) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let mut stats = LayoutTreeStats {
+            node_count: tree.live.len() as u64,
+            last_compute_us: tree.last_compute_us,
+            memory_bytes: approximate_memory(tree) as u64,
+            ..LayoutTreeStats::default()
+        };
+        for &id in &tree.live {
+            if tree.tree.dirty(id).unwrap_or(false) {
+                stats.dirty_count += 1;
+            }
+            if tree.tree.parent(id).is_none() && !tree.detached.contains(&id) {
+                stats.root_count += 1;
+            }
+        }
+        let mut stack: Vec<(NodeId, u32)> = tree.live.iter()
+            .filter(|&&id| tree.tree.parent(id).is_none() && !tree.detached.contains(&id))
+            .map(|&id| (id, 1))
+            .collect();
+        while let Some((id, depth)) = stack.pop() {
+            stats.max_depth = stats.max_depth.max(depth);
+            stack.extend(tree.tree.children(id).unwrap().into_iter().map(|child| (child, depth + 1)));
+        }
+        *out = stats;
+    })
+}
+
+/// Text dump of `root`'s subtree in the style of `taffy::print_tree`: one line per
+/// node with its kind, ID, name, rect, content size and the style properties that
+/// differ from the defaults. Null for an unknown node; free the string with
+/// `layout_string_free`.
+#[no_mangle]
+pub extern "C" fn layout_debug_print(tree: *const LayoutTree, root: u64) -> *mut c_char {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let root = NodeId::from(root);
+        if !tree.contains(root) {
+            return std::ptr::null_mut();
+        }
+        let mut text = String::from("TREE\n");
+        dump_node(tree, root, false, "", &mut text);
+        CString::new(text).unwrap_or_default().into_raw()
+    })
+}
+
+/// Graphviz DOT graph of `root`'s subtree: one box per node labeled with its kind,
+/// ID, name, size and position, and an edge from each parent to its children in
+/// child order. `Display::None` nodes are drawn dashed. Null for an unknown node;
+/// free the string with `layout_string_free`.
+#[no_mangle]
+pub extern "C" fn layout_export_dot(tree: *const LayoutTree, root: u64) -> *mut c_char {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let root = NodeId::from(root);
+        if !tree.contains(root) {
+            return std::ptr::null_mut();
+        }
+        let mut dot = String::from("digraph layout {\n    node [shape=box, fontname=\"monospace\"];\n");
+        for id in tree.subtree(root) {
+            let layout = tree.final_layout(id);
+            let mut label = format!("{} #{}", tree.tree.get_debug_label(id), u64::from(id));
+            if let Some(name) = tree.names.get(&id) {
+                let _ = write!(label, "\n\"{name}\"");
+            }
+            let _ = write!(
+                label, "\n{}x{} at ({}, {})",
+                layout.size.width, layout.size.height, layout.location.x, layout.location.y,
+            );
+            let label = label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            let dashed = if tree.tree.style(id).unwrap().display == Display::None { ", style=dashed" } else { "" };
+            let _ = writeln!(dot, "    n{} [label=\"{label}\"{dashed}];", u64::from(id));
+            for child in tree.tree.children(id).unwrap() {
+                let _ = writeln!(dot, "    n{} -> n{};", u64::from(id), u64::from(child));
+            }
+        }
+        dot.push_str("}\n");
+        CString::new(dot).unwrap_or_default().into_raw()
+    })
+}
+
+// ============================================================================
+// Alignment
+// ============================================================================
+
+fn resolve_alignment(
+    tree: &TreeState, style: &Style, parent: NodeId, parent_style: &Style, info: &mut LayoutDebugInfo,
+) {
+    let align = style.align_self.or(parent_style.align_items);
+    let justify = style.justify_self.or(parent_style.justify_items);
+    let absolute = style.position == Position::Absolute;
+
+    let (align_effective, justify_effective) = match parent_style.display {
+        Display::Flex => {
+            let is_row = matches!(parent_style.flex_direction, FlexDirection::Row | FlexDirection::RowReverse);
+            let (cross_size, m_start, m_end) = if is_row {
+                (style.size.height, style.margin.top, style.margin.bottom)
+            } else {
+                (style.size.width, style.margin.left, style.margin.right)
+            };
+            let effective = match align.unwrap_or(AlignItems::Stretch) {
+                AlignItems::Stretch if absolute || !cross_size.is_auto() || m_start.is_auto() || m_end.is_auto() => {
+                    AlignItems::FlexStart
+                }
+                // Baselines are only aligned along rows, and only when at least two in-flow items take part
+                AlignItems::Baseline if absolute || !is_row || baseline_peers(tree, parent, parent_style) < 2 => {
+                    AlignItems::FlexStart
+                }
+                other => other,
+            };
+            // justify-self has no meaning for flex items
+            (Some(effective), None)
+        }
+        Display::Grid => {
+            let v = align.unwrap_or(if !style.size.height.is_auto() || style.aspect_ratio.is_some() {
+                AlignItems::Start
+            } else {
+                AlignItems::Stretch
+            });
+            let h = justify.unwrap_or(if !style.size.width.is_auto() { AlignItems::Start } else { AlignItems::Stretch });
+            let v_fixed = !style.size.height.is_auto() || style.margin.top.is_auto() || style.margin.bottom.is_auto();
+            let h_fixed = !style.size.width.is_auto() || style.margin.left.is_auto() || style.margin.right.is_auto();
+            let stretch_to_start = |a: AlignItems, fixed: bool| match a {
+                AlignItems::Stretch if fixed => AlignItems::Start,
+                other => other,
+            };
+            (Some(stretch_to_start(v, v_fixed)), Some(stretch_to_start(h, h_fixed)))
+        }
+        // Block containers and hidden parents ignore item alignment entirely
+        _ => (None, None),
+    };
+
+    info.align_requested = align.map_or(0, align_items_code);
+    info.align_effective = align_effective.map_or(0, align_items_code);
+    info.justify_requested = justify.map_or(0, align_items_code);
+    info.justify_effective = justify_effective.map_or(0, align_items_code);
+    if info.align_requested != 0 && info.align_requested != info.align_effective {
+        info.flags |= DEBUG_ALIGN_FALLBACK;
+    }
+    if info.justify_requested != 0 && info.justify_requested != info.justify_effective {
+        info.flags |= DEBUG_JUSTIFY_FALLBACK;
+    }
+}
+
+/// In-flow children of a flex container that resolve to baseline alignment.
+fn baseline_peers(tree: &TreeState, parent: NodeId, parent_style: &Style) -> usize {
+    tree.tree.children(parent).unwrap_or_default().into_iter()
+        .filter_map(|child| tree.tree.style(child).ok())
+        .filter(|s| in_flow(s))
+        .filter(|s| s.align_self.or(parent_style.align_items) == Some(AlignItems::Baseline))
+        .count()
+}
+
+// ============================================================================
+// Automatic minimum size
+// ============================================================================
+
+/// An item is held by its automatic minimum when it sits at its content-based floor
+/// while the line's flex base sizes add up to more than the container can hold, so
+/// shrinking was needed. Flex base sizes come from definite basis/size styles, or else
+/// from the item's laid-out content extent, which is what the floor is computed from.
+fn min_size_floored(tree: &TreeState, id: NodeId, style: &Style, parent: NodeId, parent_style: &Style) -> bool {
+    let is_row = matches!(parent_style.flex_direction, FlexDirection::Row | FlexDirection::RowReverse);
+    let min_main = if is_row { style.min_size.width } else { style.min_size.height };
+    let scroll_container = matches!(style.overflow.x, Overflow::Hidden | Overflow::Scroll)
+        || matches!(style.overflow.y, Overflow::Hidden | Overflow::Scroll);
+    if style.flex_shrink <= 0.0 || !min_main.is_auto() || scroll_container || !in_flow(style) {
+        return false;
+    }
+    let (item, container) = (tree.rounded_layout(id), tree.rounded_layout(parent));
+    let axis = MainAxis { is_row };
+    let final_main = axis.size(item.size);
+    if final_main <= 0.0 {
+        return false;
+    }
+    let inner = axis.size(container.size) - axis.edges(container.padding) - axis.edges(container.border)
+        - axis.size(container.scrollbar_size);
+    let floor = content_floor(axis, style, item, inner);
+    // Half a pixel of slack absorbs rounding
+    if (final_main - floor).abs() > 0.5 {
+        return false;
+    }
+
+    let demand = if parent_style.flex_wrap == FlexWrap::NoWrap {
+        let gap = if is_row { parent_style.gap.width } else { parent_style.gap.height };
+        let gap = gap.resolve_or_zero(Some(inner), |_, _| 0.0);
+        let mut count = 0;
+        let mut total = 0.0;
+        for child in tree.tree.children(parent).unwrap_or_default() {
+            let Ok(s) = tree.tree.style(child) else { continue };
+            let l = tree.rounded_layout(child);
+            if in_flow(s) {
+                total += flex_base(axis, s, l, inner) + axis.edges(l.margin);
+                count += 1;
+            }
+        }
+        total + gap * (count - 1) as f32
+    } else {
+        // A wrapped item only has to shrink when it does not fit on a line by itself
+        flex_base(axis, style, item, inner) + axis.edges(item.margin)
+    };
+    demand > inner + 0.5
+}
+
+#[derive(Clone, Copy)]
+struct MainAxis {
+    is_row: bool,
+}
+
+impl MainAxis {
+    fn size(self, size: Size<f32>) -> f32 {
+        if self.is_row { size.width } else { size.height }
+    }
+
+    fn edges(self, r: Rect<f32>) -> f32 {
+        if self.is_row { r.left + r.right } else { r.top + r.bottom }
+    }
+
+    fn dimension(self, size: Size<Dimension>) -> Dimension {
+        if self.is_row { size.width } else { size.height }
+    }
+}
+
+/// Content-based minimum: the extent of the laid-out content plus trailing padding and
+/// border, capped by a definite preferred size.
+fn content_floor(axis: MainAxis, style: &Style, layout: &Layout, inner: f32) -> f32 {
+    let pb = axis.edges(layout.padding) + axis.edges(layout.border);
+    let (content, end_pb) = if axis.is_row {
+        (layout.content_size.width, layout.padding.right + layout.border.right)
+    } else {
+        (layout.content_size.height, layout.padding.bottom + layout.border.bottom)
+    };
+    let floor = (content + end_pb).max(pb);
+    match axis.dimension(style.size).maybe_resolve(Some(inner), |_, _| 0.0) {
+        Some(preferred) => floor.min(preferred),
+        None => floor,
+    }
+}
+
+fn flex_base(axis: MainAxis, style: &Style, layout: &Layout, inner: f32) -> f32 {
+    style.flex_basis.maybe_resolve(Some(inner), |_, _| 0.0)
+        .or_else(|| axis.dimension(style.size).maybe_resolve(Some(inner), |_, _| 0.0))
+        .unwrap_or_else(|| content_floor(axis, style, layout, inner))
+}
+
+// ============================================================================
+// Constraint conflicts
+// ============================================================================
+
+fn constraint_conflict(tree: &TreeState, id: NodeId, style: &Style) -> bool {
+    let fixed = |d: Dimension| d.into_option().is_some();
+    let min_over_max = |min: Dimension, max: Dimension| match (min.into_option(), max.into_option()) {
+        (Some(min), Some(max)) => min > max,
+        _ => false,
+    };
+    if min_over_max(style.min_size.width, style.max_size.width)
+        || min_over_max(style.min_size.height, style.max_size.height)
+    {
+        return true;
+    }
+
+    if let Some(ratio) = style.aspect_ratio {
+        if fixed(style.size.width) && fixed(style.size.height) {
+            return true;
+        }
+        // The ratio only survives if the final box still has it
+        let l = tree.rounded_layout(id);
+        if l.size.height > 0.0 && (l.size.width - l.size.height * ratio).abs() > 0.5 {
+            return true;
+        }
+    }
+
+    // Percentage heights against an auto-height parent silently become auto
+    let percent_height = [style.size.height, style.min_size.height, style.max_size.height]
+        .iter().any(|d| d.into_raw().uses_percentage());
+    if percent_height {
+        if let Some(parent) = tree.tree.parent(id) {
+            if tree.tree.style(parent).is_ok_and(|p| p.size.height.is_auto()) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn in_flow(style: &Style) -> bool {
+    style.position != Position::Absolute && style.display != Display::None
+}
+
+// ============================================================================
+// Tree dump
+// ============================================================================
+
+fn dump_node(tree: &TreeState, id: NodeId, has_sibling: bool, lines: &str, out: &mut String) {
+    let layout = tree.final_layout(id);
+    let fork = if has_sibling { "├── " } else { "└── " };
+    let _ = write!(out, "{lines}{fork}{} #{}", tree.tree.get_debug_label(id), u64::from(id));
+    if let Some(name) = tree.names.get(&id) {
+        let _ = write!(out, " {name:?}");
+    }
+    let _ = write!(
+        out,
+        " [x: {} y: {} w: {} h: {} content: {}x{}]",
+        layout.location.x, layout.location.y, layout.size.width, layout.size.height,
+        layout.content_size.width, layout.content_size.height,
+    );
+    let properties = css::style_to_properties(tree.tree.style(id).unwrap());
+    if !properties.is_empty() {
+        let declarations: Vec<String> = properties.iter().map(|(name, value)| format!("{name}: {value}")).collect();
+        let _ = write!(out, " {{ {} }}", declarations.join("; "));
+    }
+    out.push('\n');
+
+    let lines = format!("{lines}{}", if has_sibling { "│   " } else { "    " });
+    let children = tree.tree.children(id).unwrap();
+    for (index, &child) in children.iter().enumerate() {
+        dump_node(tree, child, index + 1 < children.len(), &lines, out);
+    }
+}
+
+// ============================================================================
+// Memory estimate
+// ============================================================================
+
+/// Allocated bytes, counted from capacities and the inline size of each entry.
+/// Heap data behind entries (grid track lists, child vectors) is only counted
+/// where it is cheap to reach.
+fn approximate_memory(tree: &TreeState) -> usize {
+    fn map<K, V>(map: &HashMap<K, V>) -> usize {
+        map.capacity() * (size_of::<K>() + size_of::<V>())
+    }
+    fn set<K>(set: &HashSet<K>) -> usize {
+        set.capacity() * size_of::<K>()
+    }
+    // Taffy's per-node storage: style, unrounded and final layout, cache, detailed
+    // info, context slot, child list and parent link
+    let node = size_of::<Style>() + 2 * size_of::<Layout>() + size_of::<Cache>() + size_of::<DetailedLayoutInfo>()
+        + size_of::<NodeContext>() + size_of::<Vec<NodeId>>() + size_of::<Option<NodeId>>();
+    let children: usize = tree.live.iter().map(|&id| tree.tree.child_count(id) * size_of::<NodeId>()).sum();
+    let names: usize = tree.names.values().map(String::capacity).sum::<usize>()
+        + tree.name_index.iter().map(|(name, ids)| name.capacity() + ids.capacity() * size_of::<NodeId>()).sum::<usize>();
+
+    tree.live.len() * node + children + names + size_of::<TreeState>()
+        + set(&tree.live) + set(&tree.detached) + set(&tree.clip_escape) + set(&tree.layout_boundaries)
+        + set(&tree.baseline_leaves)
+        + tree.journal.as_ref().map_or(0, |j| j.capacity() * size_of::<crate::LayoutMutation>())
+        + map(&tree.directions) + map(&tree.logical_insets) + map(&tree.logical_edges)
+        + map(&tree.safe_area_insets) + map(&tree.style_resolvers)
+        + map(&tree.reported_rects) + map(&tree.scroll_offsets) + map(&tree.scroll_links) + map(&tree.animations)
+        + map(&tree.overflow_min_content) + map(&tree.host_spacing) + map(&tree.compute_stats)
+        + map(&tree.gap_after) + map(&tree.wrap_balance) + map(&tree.virtual_lists)
+        + map(&tree.accumulate_margins) + map(&tree.content_versions) + map(&tree.layout_overrides) + map(&tree.names)
+        + map(&tree.name_index) + map(&tree.node_epochs)
+}
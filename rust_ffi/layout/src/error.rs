@@ -0,0 +1,138 @@
+//! Panic containment and diagnostics reporting at the FFI boundary.
+//!
+//! A panic unwinding out of an `extern "C"` function aborts the host process, so
+//! every exported function runs its body through `ffi_guard`. A caught panic makes
+//! the call return its failure value (false, 0 or null; nothing for void calls)
+//! and leaves the panic message for `layout_last_error_message`. The tree may have
+//! been left half-updated by the failed call; hosts should treat it as suspect and
+//! rebuild it.
+//!
+//! Errors, panics and inputs the library had to fall back on or adjust (unknown
+//! enum codes, out-of-range values) are also reported to the host's log callback,
+//! if one is registered with `layout_set_log_callback`.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// The value an entry point returns when its body panicked.
+pub(crate) trait FfiFailure {
+    fn failure() -> Self;
+}
+
+macro_rules! zero_failure {
+    ($($t:ty),*) => {
+        $(impl FfiFailure for $t {
+            fn failure() -> Self { Default::default() }
+        })*
+    };
+}
+
+zero_failure!((), bool, u8, u32, u64, usize, f32);
+
+impl<T> FfiFailure for *mut T {
+    fn failure() -> Self { std::ptr::null_mut() }
+}
+
+impl<T> FfiFailure for *const T {
+    fn failure() -> Self { std::ptr::null() }
+}
+
+/// Run an entry point's body, turning a panic into its failure value.
+pub(crate) fn ffi_guard<R: FfiFailure>(body: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => value,
+        Err(payload) => {
+            set_last_error(&panic_message(payload.as_ref()));
+            R::failure()
+        }
+    }
+}
+
+pub(crate) fn set_last_error(message: &str) {
+    // Interior NULs would truncate the C string; drop them
+    log(LOG_ERROR, message);
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+pub const LOG_DEBUG: u8 = 0;
+pub const LOG_INFO: u8 = 1;
+pub const LOG_WARN: u8 = 2;
+pub const LOG_ERROR: u8 = 3;
+
+/// Receives one engine diagnostic: its level and a UTF-8 message that is only
+/// valid during the call. May be called from any thread that uses the library.
+pub type LayoutLogFn = extern "C" fn(user_data: *mut c_void, level: u8, message: *const c_char);
+
+#[derive(Clone, Copy)]
+struct Logger {
+    callback: LayoutLogFn,
+    user_data: *mut c_void,
+    min_level: u8,
+}
+
+// The host owns `user_data` and promised it may be used from any thread
+unsafe impl Send for Logger {}
+
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+
+/// Route engine diagnostics at `min_level` and above (`LOG_DEBUG` .. `LOG_ERROR`)
+/// to `callback`, for every tree. None stops logging.
+#[no_mangle]
+pub extern "C" fn layout_set_log_callback(callback: Option<LayoutLogFn>, user_data: *mut c_void, min_level: u8) {
+    ffi_guard(|| {
+        let logger = callback.map(|callback| Logger { callback, user_data, min_level });
+        *LOGGER.lock().unwrap_or_else(|e| e.into_inner()) = logger;
+    })
+}
+
+/// Report a diagnostic to the host's log callback, if it wants this level.
+pub(crate) fn log(level: u8, message: &str) {
+    // Copy the logger out so a callback that calls back into the library can't deadlock
+    let Some(logger) = *LOGGER.lock().unwrap_or_else(|e| e.into_inner()) else { return };
+    if level < logger.min_level {
+        return;
+    }
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    (logger.callback)(logger.user_data, level, message.as_ptr());
+}
+
+/// `code` if it is below `count`, the number of codes `what` defines; otherwise
+/// 0, the default every enum setter falls back to, with a warning.
+pub(crate) fn checked_code(what: &str, code: u8, count: u8) -> u8 {
+    if code < count {
+        return code;
+    }
+    log(LOG_WARN, &format!("unknown {what} code {code}; using the default"));
+    0
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// The message of the last error on this thread, or null if there is none. The
+/// string belongs to the library and stays valid until the next error on this
+/// thread or `layout_clear_last_error`.
+#[no_mangle]
+pub extern "C" fn layout_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(std::ptr::null(), |s| s.as_ptr()))
+}
+
+#[no_mangle]
+pub extern "C" fn layout_clear_last_error() {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+}
@@ -0,0 +1,467 @@
+//! Fonts for the text subsystem: a process-wide database of font faces, filled
+//! from the system font directories, and the few TrueType/OpenType tables text
+//! measurement reads (`cmap`, `hmtx`, `hhea`, `head`, `OS/2`, `name`).
+//!
+//! This stands in for cosmic-text, which the text feature was meant to use: the
+//! crate takes no new dependencies, so fonts are read here and text is measured
+//! one glyph per character (see `text` and KNOWN_LIMITATIONS.md).
+//!
+//! The system fonts are indexed on a background thread started with the first
+//! tree, without holding the database, so the first text set rarely waits for
+//! the scan and registrations never do. Faces are indexed by family, weight and
+//! style when found and only loaded when text first uses them. The application
+//! can register its own fonts (`layout_fonts_add_file`, `layout_fonts_add_memory`)
+//! and choose the fallback families. Text keeps the faces it was shaped with, so
+//! changes apply to text set afterwards.
+
+use std::ffi::c_char;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Once};
+use std::thread;
+
+use crate::{c_str, in_buffer};
+use crate::error::ffi_guard;
+
+/// Families tried, in order, for text without a family or with an unknown one, and
+/// for characters the chosen face lacks, unless the application sets its own.
+const DEFAULT_FAMILIES: &[&str] = &[
+    "Segoe UI", "SF Pro Text", ".SF NS", "Helvetica Neue", "Helvetica", "Arial", "Noto Sans", "DejaVu Sans",
+    "Liberation Sans", "Cantarell", "Segoe UI Symbol", "Apple Symbols", "Noto Sans Symbols", "Noto Sans CJK SC",
+    "PingFang SC", "Microsoft YaHei", "Segoe UI Emoji", "Apple Color Emoji", "Noto Color Emoji",
+];
+
+static FONTS: Mutex<FontDb> = Mutex::new(FontDb { entries: Vec::new(), fallbacks: None, scanned: false });
+static SCAN: Once = Once::new();
+/// Signalled once the system fonts are in `FONTS`.
+static SCANNED: Condvar = Condvar::new();
+
+/// Register the faces of the font file or collection at the UTF-8 `path`. A
+/// family registered by the application hides the system's faces of that family.
+/// Returns the number of faces added, 0 if the file cannot be read or holds no
+/// usable face.
+#[no_mangle]
+pub extern "C" fn layout_fonts_add_file(path: *const c_char) -> u32 {
+    ffi_guard(|| {
+        let Some(path) = c_str(path).map(Path::new) else { return 0 };
+        let Ok(data) = std::fs::read(path) else { return 0 };
+        registered().add_data(data.into(), Some(path), true) as u32
+    })
+}
+
+/// Register the faces of a font file or collection in memory, like
+/// `layout_fonts_add_file`. The bytes are copied.
+#[no_mangle]
+pub extern "C" fn layout_fonts_add_memory(bytes: *const u8, len: usize) -> u32 {
+    ffi_guard(|| {
+        let data: &[u8] = if bytes.is_null() { &[] } else { unsafe { std::slice::from_raw_parts(bytes, len) } };
+        registered().add_data(data.into(), None, true) as u32
+    })
+}
+
+/// Replace the fallback families: tried in order for text without a family or
+/// with an unknown one, and for characters the chosen face lacks. A count of 0
+/// restores the built-in list of common system families. Returns false, with
+/// nothing changed, if `families` is null with a non-zero count or a name is null
+/// or not UTF-8.
+#[no_mangle]
+pub extern "C" fn layout_fonts_set_fallback_chain(families: *const *const c_char, count: usize) -> bool {
+    ffi_guard(|| {
+        let Some(names) = in_buffer(families, count) else { return false };
+        let Some(names) = names.iter().map(|&name| c_str(name).map(str::to_owned)).collect::<Option<Vec<_>>>() else {
+            return false;
+        };
+        registered().fallbacks = (!names.is_empty()).then_some(names);
+        true
+    })
+}
+
+/// The database as the application's registrations see it, without scanning the
+/// system fonts.
+fn registered() -> MutexGuard<'static, FontDb> {
+    FONTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The database, with the system fonts scanned in. Waits for the scan, off the
+/// database's lock, if it has not finished yet.
+pub(crate) fn fonts() -> MutexGuard<'static, FontDb> {
+    preload();
+    let mut fonts = registered();
+    while !fonts.scanned {
+        fonts = SCANNED.wait(fonts).unwrap_or_else(|e| e.into_inner());
+    }
+    fonts
+}
+
+/// Start indexing the system fonts in the background, once per process. Where no
+/// thread can be started, the scan runs here instead.
+pub(crate) fn preload() {
+    SCAN.call_once(|| {
+        if thread::Builder::new().name("keystone-layout-fonts".into()).spawn(scan_system).is_err() {
+            let _ = panic::catch_unwind(scan_system);
+        }
+    });
+}
+
+fn scan_system() {
+    let _done = ScanDone;
+    let mut system = FontDb { entries: Vec::new(), fallbacks: None, scanned: false };
+    for dir in system_font_dirs() {
+        system.add_dir(&dir);
+    }
+    registered().entries.append(&mut system.entries);
+}
+
+/// Marks the scan finished when dropped, so a scan that panics leaves the
+/// database without system fonts instead of keeping text waiting for them.
+struct ScanDone;
+
+impl Drop for ScanDone {
+    fn drop(&mut self) {
+        registered().scanned = true;
+        SCANNED.notify_all();
+    }
+}
+
+pub(crate) struct FontDb {
+    entries: Vec<Entry>,
+    /// Set with `layout_fonts_set_fallback_chain`; None for `DEFAULT_FAMILIES`.
+    fallbacks: Option<Vec<String>>,
+    scanned: bool,
+}
+
+struct Entry {
+    family: String,
+    weight: u16,
+    italic: bool,
+    /// Added by the application rather than found among the system fonts.
+    registered: bool,
+    source: Source,
+    /// Loaded on first use; None once loading failed.
+    face: Option<Option<Arc<Face>>>,
+}
+
+enum Source {
+    File(PathBuf, u32),
+    Memory(Arc<[u8]>, u32),
+}
+
+impl FontDb {
+    /// Index every face of a font file or collection. Returns the number added.
+    fn add_data(&mut self, data: Arc<[u8]>, path: Option<&Path>, registered: bool) -> usize {
+        let mut added = 0;
+        for index in 0..face_count(&data) {
+            let Some((family, weight, italic)) = describe(&data, index) else { continue };
+            let source = match path {
+                Some(path) => Source::File(path.to_path_buf(), index),
+                None => Source::Memory(data.clone(), index),
+            };
+            self.entries.push(Entry { family, weight, italic, registered, source, face: None });
+            added += 1;
+        }
+        added
+    }
+
+    fn add_dir(&mut self, dir: &Path) {
+        let Ok(entries) = std::fs::read_dir(dir) else { return };
+        let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+        paths.sort();
+        for path in paths {
+            if path.is_dir() {
+                self.add_dir(&path);
+            } else if is_font_file(&path) {
+                if let Ok(data) = std::fs::read(&path) {
+                    self.add_data(data.into(), Some(&path), false);
+                }
+            }
+        }
+    }
+
+    /// The face that best matches: the family's face nearest in style and weight,
+    /// or the first fallback family's when the family is unknown or not given.
+    pub(crate) fn resolve(&mut self, family: Option<&str>, weight: u16, italic: bool) -> Option<Arc<Face>> {
+        let families: Vec<String> = family.map(str::to_owned).into_iter().chain(self.fallback_families()).collect();
+        for family in families {
+            if let Some(face) = self.find(&family, weight, italic) {
+                return Some(face);
+            }
+        }
+        (0..self.entries.len()).find_map(|index| self.load(index))
+    }
+
+    /// Faces to take characters from that the chosen face lacks: the nearest face
+    /// of each fallback family that is installed.
+    pub(crate) fn fallbacks(&mut self, weight: u16, italic: bool) -> Vec<Arc<Face>> {
+        self.fallback_families().iter().filter_map(|family| self.find(family, weight, italic)).collect()
+    }
+
+    fn fallback_families(&self) -> Vec<String> {
+        match &self.fallbacks {
+            Some(families) => families.clone(),
+            None => DEFAULT_FAMILIES.iter().map(|&family| family.to_owned()).collect(),
+        }
+    }
+
+    fn find(&mut self, family: &str, weight: u16, italic: bool) -> Option<Arc<Face>> {
+        let mut candidates: Vec<usize> = (0..self.entries.len())
+            .filter(|&index| self.entries[index].family.eq_ignore_ascii_case(family))
+            .collect();
+        if candidates.iter().any(|&index| self.entries[index].registered) {
+            candidates.retain(|&index| self.entries[index].registered);
+        }
+        // Matching style first, then nearest weight, then the order faces were added
+        candidates.sort_by_key(|&index| {
+            let entry = &self.entries[index];
+            (entry.italic != italic, entry.weight.abs_diff(weight), index)
+        });
+        candidates.into_iter().find_map(|index| self.load(index))
+    }
+
+    fn load(&mut self, index: usize) -> Option<Arc<Face>> {
+        let entry = &mut self.entries[index];
+        entry.face
+            .get_or_insert_with(|| {
+                let (data, face_index) = match &entry.source {
+                    Source::File(path, face_index) => (std::fs::read(path).ok()?.into(), *face_index),
+                    Source::Memory(data, face_index) => (data.clone(), *face_index),
+                };
+                Face::parse(data, face_index).map(Arc::new)
+            })
+            .clone()
+    }
+}
+
+fn is_font_file(path: &Path) -> bool {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    matches!(extension.as_str(), "ttf" | "otf" | "ttc" | "otc")
+}
+
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    if cfg!(target_os = "macos") {
+        dirs.extend(["/System/Library/Fonts", "/Library/Fonts"].map(PathBuf::from));
+        dirs.extend(home.map(|home| home.join("Library/Fonts")));
+    } else if cfg!(windows) {
+        let windir = std::env::var_os("WINDIR").map_or_else(|| PathBuf::from("C:\\Windows"), PathBuf::from);
+        dirs.push(windir.join("Fonts"));
+        dirs.extend(std::env::var_os("LOCALAPPDATA").map(|local| PathBuf::from(local).join("Microsoft\\Windows\\Fonts")));
+    } else {
+        dirs.extend(["/usr/share/fonts", "/usr/local/share/fonts"].map(PathBuf::from));
+        if let Some(home) = home {
+            dirs.push(home.join(".local/share/fonts"));
+            dirs.push(home.join(".fonts"));
+        }
+    }
+    dirs
+}
+
+// ============================================================================
+// Faces
+// ============================================================================
+
+/// One face with the tables measurement needs; metrics are in font units.
+pub(crate) struct Face {
+    data: Arc<[u8]>,
+    pub(crate) units_per_em: f32,
+    pub(crate) ascent: f32,
+    /// Below the baseline, positive.
+    pub(crate) descent: f32,
+    pub(crate) line_gap: f32,
+    cmap: (usize, u16),
+    hmtx: usize,
+    h_metrics: u16,
+}
+
+impl Face {
+    fn parse(data: Arc<[u8]>, index: u32) -> Option<Face> {
+        let tables = Tables::of(&data, index)?;
+        let head = tables.find(b"head")?;
+        let hhea = tables.find(b"hhea")?;
+        let units_per_em = u16_at(&data, head + 18)?;
+        let h_metrics = u16_at(&data, hhea + 34)?;
+        if units_per_em == 0 || h_metrics == 0 {
+            return None;
+        }
+        Some(Face {
+            units_per_em: units_per_em as f32,
+            ascent: i16_at(&data, hhea + 4)? as f32,
+            descent: -(i16_at(&data, hhea + 6)? as f32),
+            line_gap: i16_at(&data, hhea + 8)? as f32,
+            cmap: best_cmap(&data, tables.find(b"cmap")?)?,
+            hmtx: tables.find(b"hmtx")?,
+            h_metrics,
+            data,
+        })
+    }
+
+    /// The glyph for `ch`, or None if the face lacks it.
+    pub(crate) fn glyph(&self, ch: char) -> Option<u16> {
+        let code = ch as u32;
+        let (table, format) = self.cmap;
+        let data = &self.data;
+        let glyph = match format {
+            4 => {
+                let code = u16::try_from(code).ok()?;
+                let seg_x2 = u16_at(data, table + 6)? as usize;
+                let ends = table + 14;
+                let (mut low, mut high) = (0, seg_x2 / 2);
+                while low < high {
+                    let mid = (low + high) / 2;
+                    if u16_at(data, ends + mid * 2)? < code { low = mid + 1 } else { high = mid }
+                }
+                let segment = low * 2;
+                if segment >= seg_x2 {
+                    return None;
+                }
+                let start = u16_at(data, ends + seg_x2 + 2 + segment)?;
+                if start > code {
+                    return None;
+                }
+                let delta = u16_at(data, ends + 2 * seg_x2 + 2 + segment)?;
+                let range_at = ends + 3 * seg_x2 + 2 + segment;
+                let range = u16_at(data, range_at)? as usize;
+                if range == 0 {
+                    code.wrapping_add(delta)
+                } else {
+                    match u16_at(data, range_at + range + 2 * (code - start) as usize)? {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(delta),
+                    }
+                }
+            }
+            _ => {
+                let groups = u32_at(data, table + 12)? as usize;
+                let (mut low, mut high) = (0, groups);
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let group = table + 16 + mid * 12;
+                    if u32_at(data, group + 4)? < code {
+                        low = mid + 1;
+                    } else if u32_at(data, group)? > code {
+                        high = mid;
+                    } else {
+                        let glyph = u32_at(data, group + 8)? + code - u32_at(data, group)?;
+                        return u16::try_from(glyph).ok().filter(|&g| g != 0);
+                    }
+                }
+                return None;
+            }
+        };
+        (glyph != 0).then_some(glyph)
+    }
+
+    /// Horizontal advance of the glyph, in font units.
+    pub(crate) fn advance(&self, glyph: u16) -> f32 {
+        let metric = glyph.min(self.h_metrics - 1) as usize;
+        u16_at(&self.data, self.hmtx + metric * 4).unwrap_or(0) as f32
+    }
+}
+
+/// The table directory of one face of a font file or collection.
+struct Tables<'a> {
+    data: &'a [u8],
+    directory: usize,
+}
+
+impl<'a> Tables<'a> {
+    fn of(data: &'a [u8], index: u32) -> Option<Self> {
+        let directory = if data.get(0..4)? == b"ttcf" {
+            u32_at(data, 12 + 4 * index as usize)? as usize
+        } else if index == 0 {
+            0
+        } else {
+            return None;
+        };
+        Some(Tables { data, directory })
+    }
+
+    fn find(&self, tag: &[u8; 4]) -> Option<usize> {
+        let count = u16_at(self.data, self.directory + 4)? as usize;
+        (0..count).find_map(|i| {
+            let record = self.directory + 12 + i * 16;
+            (self.data.get(record..record + 4)? == tag).then(|| u32_at(self.data, record + 8).map(|o| o as usize))?
+        })
+    }
+}
+
+fn face_count(data: &[u8]) -> u32 {
+    if data.get(0..4) == Some(b"ttcf") { u32_at(data, 8).unwrap_or(0).min(256) } else { 1 }
+}
+
+/// Family name, weight class and whether the face is italic or oblique.
+fn describe(data: &[u8], index: u32) -> Option<(String, u16, bool)> {
+    let tables = Tables::of(data, index)?;
+    let family = family_name(data, tables.find(b"name")?)?;
+    let (weight, italic) = match tables.find(b"OS/2") {
+        Some(os2) => (u16_at(data, os2 + 4)?, u16_at(data, os2 + 62)? & 0x201 != 0),
+        None => (400, u16_at(data, tables.find(b"head")? + 44)? & 2 != 0),
+    };
+    Some((family, weight, italic))
+}
+
+/// The typographic family name if there is one, else the family name; English
+/// where the font has several languages.
+fn family_name(data: &[u8], name: usize) -> Option<String> {
+    let count = u16_at(data, name + 2)? as usize;
+    let strings = name + u16_at(data, name + 4)? as usize;
+    let mut best: Option<(u8, String)> = None;
+    for i in 0..count {
+        let record = name + 6 + i * 12;
+        let platform = u16_at(data, record)?;
+        let language = u16_at(data, record + 4)?;
+        let id = u16_at(data, record + 6)?;
+        let length = u16_at(data, record + 8)? as usize;
+        let offset = strings + u16_at(data, record + 10)? as usize;
+        let bytes = data.get(offset..offset + length)?;
+        let text = match platform {
+            0 | 3 => String::from_utf16_lossy(&bytes.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect::<Vec<_>>()),
+            1 => bytes.iter().map(|&b| b as char).collect(),
+            _ => continue,
+        };
+        let english = platform != 3 || language == 0x409;
+        let rank = match id {
+            16 => 0,
+            1 => 2,
+            _ => continue,
+        } + u8::from(!english);
+        if best.as_ref().is_none_or(|(best, _)| rank < *best) && !text.is_empty() {
+            best = Some((rank, text));
+        }
+    }
+    best.map(|(_, text)| text)
+}
+
+/// The Unicode subtable to map characters with: format 12 (full range) if there
+/// is one, else format 4 (BMP).
+fn best_cmap(data: &[u8], cmap: usize) -> Option<(usize, u16)> {
+    let count = u16_at(data, cmap + 2)? as usize;
+    let mut best = None;
+    for i in 0..count {
+        let record = cmap + 4 + i * 8;
+        let platform = u16_at(data, record)?;
+        let encoding = u16_at(data, record + 2)?;
+        let table = cmap + u32_at(data, record + 4)? as usize;
+        let unicode = platform == 0 || (platform == 3 && matches!(encoding, 1 | 10));
+        let format = u16_at(data, table)?;
+        if !unicode || !matches!(format, 4 | 12) {
+            continue;
+        }
+        if best.is_none_or(|(_, best)| format > best) {
+            best = Some((table, format));
+        }
+    }
+    best
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn i16_at(data: &[u8], at: usize) -> Option<i16> {
+    u16_at(data, at).map(|v| v as i16)
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
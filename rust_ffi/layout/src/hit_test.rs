@@ -0,0 +1,130 @@
+//! Hit testing against computed layout.
+//!
+//! Points are in the root space of `root`'s tree, the same space as
+//! `layout_get_absolute_rect`. Later siblings paint over earlier ones and children
+//! over their parent, so candidates come back in that order reversed. Nodes with
+//! `Display::None` are skipped along with their subtree, and a node whose overflow
+//! is not visible clips hits on its descendants to its padding box on that axis.
+//! Clipping follows the paint list, so clip-escaping nodes stay hittable outside
+//! their ancestors, and so do scroll offsets, so the children of a scrolled
+//! container are hit where they are displayed.
+
+use taffy::prelude::*;
+use taffy::Point;
+
+use crate::paint::{children_clip, children_origin, node_bounds, node_clip, Bounds, UNCLIPPED};
+use crate::error::ffi_guard;
+use crate::{out_buffer, root_origin, sync, LayoutTree, TreeState};
+
+/// The frontmost node under (x, y) in `root`'s subtree, or 0 if nothing is hit.
+#[no_mangle]
+pub extern "C" fn layout_hit_test(tree: *const LayoutTree, root: u64, x: f32, y: f32) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let mut hit = 0;
+        hit_test(tree, NodeId::from(root), Point { x, y }, &mut |id| {
+            hit = id.into();
+            false
+        });
+        hit
+    })
+}
+
+/// Every node under (x, y) in `root`'s subtree, front to back. Returns the total
+/// number hit; only the first `cap` are written.
+#[no_mangle]
+pub extern "C" fn layout_hit_test_all(tree: *const LayoutTree, root: u64, x: f32, y: f32, out: *mut u64, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let out = out_buffer(out, cap);
+        let mut count = 0;
+        hit_test(tree, NodeId::from(root), Point { x, y }, &mut |id| {
+            if let Some(slot) = out.get_mut(count) {
+                *slot = id.into();
+            }
+            count += 1;
+            true
+        });
+        count
+    })
+}
+
+/// Feed hits to `visit` front to back until it returns false.
+fn hit_test(tree: &TreeState, root: NodeId, point: Point<f32>, visit: &mut impl FnMut(NodeId) -> bool) {
+    let Some((_, origin)) = root_origin(tree, root) else { return };
+    let location = tree.final_layout(root).location;
+    let parent_origin = Point { x: origin.x - location.x, y: origin.y - location.y };
+    visit_node(tree, root, parent_origin, UNCLIPPED, point, visit);
+}
+
+/// Returns false once `visit` asked to stop.
+fn visit_node(
+    tree: &TreeState, id: NodeId, parent_origin: Point<f32>, clip: Bounds, point: Point<f32>,
+    visit: &mut impl FnMut(NodeId) -> bool,
+) -> bool {
+    let style = tree.tree.style(id).unwrap();
+    if style.display == Display::None {
+        return true;
+    }
+    let clip = node_clip(tree, id, clip);
+    let layout = tree.final_layout(id);
+    let rect = node_bounds(layout, parent_origin);
+    let child_clip = children_clip(style, layout, rect, clip);
+    // A clip-escaping descendant can be hit outside this node's clip
+    if contains(child_clip, point) || !tree.clip_escape.is_empty() {
+        let origin = children_origin(tree, id, rect);
+        for child in tree.tree.children(id).unwrap().into_iter().rev() {
+            if !visit_node(tree, child, origin, child_clip, point, visit) {
+                return false;
+            }
+        }
+    }
+    if contains(rect, point) && contains(clip, point) {
+        return visit(id);
+    }
+    true
+}
+
+fn contains(r: Bounds, p: Point<f32>) -> bool {
+    p.x >= r[0] && p.x < r[2] && p.y >= r[1] && p.y < r[3]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll::{layout_get_visible_children, layout_set_scroll_offset};
+    use crate::{
+        layout_add_child, layout_compute, layout_new_node, layout_set_flex_direction, layout_set_flex_shrink,
+        layout_set_height, layout_set_overflow, layout_set_width, layout_tree_free, layout_tree_new,
+    };
+
+    #[test]
+    fn scrolled_container_hits_displayed_children() {
+        // A 200px column scroll container of five 100px children, scrolled to 250
+        let tree = layout_tree_new();
+        let list = layout_new_node(tree);
+        layout_set_width(tree, list, 200.0);
+        layout_set_height(tree, list, 200.0);
+        layout_set_flex_direction(tree, list, 0);
+        layout_set_overflow(tree, list, 2);
+        let children: Vec<u64> = (0..5).map(|_| {
+            let child = layout_new_node(tree);
+            layout_set_height(tree, child, 100.0);
+            layout_set_flex_shrink(tree, child, 0.0);
+            layout_add_child(tree, list, child);
+            child
+        }).collect();
+        layout_compute(tree, list, 200.0, 200.0);
+        layout_set_scroll_offset(tree, list, 0.0, 250.0);
+
+        let mut visible = [0; 5];
+        assert_eq!(layout_get_visible_children(tree, list, visible.as_mut_ptr(), 5), 3);
+        assert_eq!(visible[..3], children[2..]);
+        assert_eq!(layout_hit_test(tree, list, 10.0, 10.0), children[2]);
+        assert_eq!(layout_hit_test(tree, list, 10.0, 60.0), children[3]);
+        let mut hits = [0; 4];
+        assert_eq!(layout_hit_test_all(tree, list, 10.0, 199.0, hits.as_mut_ptr(), 4), 2);
+        assert_eq!(hits[..2], [children[4], list]);
+        layout_tree_free(tree);
+    }
+}
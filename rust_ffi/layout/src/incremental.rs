@@ -0,0 +1,201 @@
+//! Incremental relayout.
+//!
+//! `layout_compute_incremental` lays out again only what a change can reach: the
+//! subtree of the nearest layout boundary above the changed node, a node whose
+//! size does not depend on its contents. Fixed-size nodes are boundaries by
+//! themselves, and the host can declare others with `layout_set_is_layout_boundary`.
+//! The boundary keeps its rect and the rest of the tree keeps its layout. Should a
+//! fixed-size boundary's size change after all (its own size was changed), the
+//! relayout moves on to the next boundary up, and past the last one to the whole
+//! tree.
+//!
+//! The subtree is laid out off the tree the way `parallel` lays out panels, and
+//! shares its bookkeeping, so results match those of a full compute.
+
+use taffy::prelude::*;
+
+use crate::error::ffi_guard;
+use crate::{
+    apply_gap_overrides, compute, defer_compute, distribute_pixels_inside, parallel, resolve_logical, stamp_epochs,
+    stream_subtrees, sync, virtual_list, LayoutTree, TreeState,
+};
+
+/// Lay out again after a change to `changed_node`, starting at its nearest layout
+/// boundary: a declared boundary other than the changed node itself, or a flex or
+/// grid node of fixed px size that does not flex. Boundaries whose subtree has
+/// style resolvers or balanced wrapping are passed over, as those need a full
+/// compute. The whole tree is computed against the previous
+/// compute's space if its root was computed last, its current size otherwise.
+/// Returns true if the change reached past the first boundary: it changed size or
+/// there was none, so its ancestors were laid out too. Inside a frame the whole
+/// tree's compute is deferred and true is returned. Unknown nodes return false.
+#[no_mangle]
+pub extern "C" fn layout_compute_incremental(tree: *mut LayoutTree, changed_node: u64) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(changed_node);
+        if !tree.contains(id) {
+            return false;
+        }
+        let mut reached = false;
+        if tree.frame.is_none() {
+            // A change to a declared boundary's own style can resize it
+            let mut from = if tree.layout_boundaries.contains(&id) { tree.tree.parent(id) } else { Some(id) };
+            while let Some(boundary) = from.and_then(|node| find_boundary(tree, node)) {
+                if relayout(tree, boundary) {
+                    return reached;
+                }
+                reached = true;
+                from = tree.tree.parent(boundary);
+            }
+        }
+        let mut top = id;
+        while let Some(parent) = tree.tree.parent(top) {
+            top = parent;
+        }
+        let avail = match tree.last_compute {
+            Some((root, avail)) if root == top => avail,
+            _ => {
+                let size = tree.unrounded_layout(top).size;
+                Size { width: AvailableSpace::Definite(size.width), height: AvailableSpace::Definite(size.height) }
+            }
+        };
+        if !defer_compute(tree, top, avail, None) {
+            compute(tree, top, avail);
+        }
+        true
+    })
+}
+
+/// Declare that the node's size never depends on its children, so that a change
+/// inside it is laid out by `layout_compute_incremental` without its ancestors:
+/// the node keeps the size it was last laid out at. Its size may still follow
+/// its parent and its own style, which a full compute picks up; Taffy's own
+/// caching is unaffected, so a full compute still relayouts its ancestors.
+#[no_mangle]
+pub extern "C" fn layout_set_is_layout_boundary(tree: *mut LayoutTree, node: u64, boundary: bool) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        if boundary {
+            tree.layout_boundaries.insert(id);
+        } else {
+            tree.layout_boundaries.remove(&id);
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_get_is_layout_boundary(tree: *const LayoutTree, node: u64) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.layout_boundaries.contains(&NodeId::from(node))
+    })
+}
+
+/// The nearest boundary at or above `id`. Roots are not boundaries, and nor are
+/// nodes inside a panel, whose own layouts live in `parallel`.
+fn find_boundary(tree: &TreeState, mut id: NodeId) -> Option<NodeId> {
+    loop {
+        let parent = tree.tree.parent(id)?;
+        let boundary = tree.layout_boundaries.contains(&id) || parallel::is_panel(tree, id);
+        if boundary && !tree.parallel.layouts.contains_key(&id) {
+            return Some(id);
+        }
+        id = parent;
+    }
+}
+
+/// Lay out the boundary's subtree at its current size, which a declared boundary
+/// keeps. False, with nothing laid out, if that would change the boundary's size.
+fn relayout(tree: &mut TreeState, boundary: NodeId) -> bool {
+    let inside = tree.subtree(boundary);
+    if inside.iter().any(|id| tree.style_resolvers.contains_key(id) || tree.wrap_balance.contains_key(id)) {
+        return false;
+    }
+    let mut top = boundary;
+    while let Some(parent) = tree.tree.parent(top) {
+        top = parent;
+    }
+    parallel::drop_stale(tree, top);
+    parallel::drop_inside(tree, boundary);
+    if !tree.directions.is_empty() {
+        resolve_logical(tree, boundary);
+    }
+    apply_gap_overrides(tree, boundary);
+    let size = tree.unrounded_layout(boundary).size;
+    let fixed = parallel::is_panel(tree, boundary);
+    let mut job = parallel::copy_panel(tree, boundary, (!fixed).then_some(size));
+    job.run();
+    let Some(output) = job.output().filter(|output| output.size == size) else { return false };
+    parallel::keep_panel(tree, boundary, job, output);
+    let origin = parallel::origin_in(tree, boundary, top).unwrap();
+    parallel::settle_panel(tree, boundary, origin);
+    distribute_pixels_inside(tree, boundary, origin);
+    virtual_list::place(tree, boundary);
+    stamp_epochs(tree, boundary);
+    stream_subtrees(tree, boundary, &[]);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        layout_add_child, layout_compute, layout_get_result, layout_new_node, layout_set_flex_grow, layout_set_flex_shrink,
+        layout_set_height, layout_set_width, layout_tree_free, layout_tree_new,
+    };
+
+    fn rect(tree: *const LayoutTree, node: u64) -> [f32; 4] {
+        let (mut x, mut y, mut w, mut h) = (0.0, 0.0, 0.0, 0.0);
+        layout_get_result(tree, node, &mut x, &mut y, &mut w, &mut h);
+        [x, y, w, h]
+    }
+
+    #[test]
+    fn changes_reach_past_a_boundary_only_when_it_resizes() {
+        // A row: a fixed-size sidebar holding one item, then content growing into the rest
+        let tree = layout_tree_new();
+        let root = layout_new_node(tree);
+        layout_set_width(tree, root, 300.0);
+        let sidebar = layout_new_node(tree);
+        layout_set_width(tree, sidebar, 100.0);
+        layout_set_height(tree, sidebar, 200.0);
+        layout_set_flex_shrink(tree, sidebar, 0.0);
+        let item = layout_new_node(tree);
+        layout_set_width(tree, item, 40.0);
+        layout_set_height(tree, item, 20.0);
+        layout_add_child(tree, sidebar, item);
+        let content = layout_new_node(tree);
+        layout_set_flex_grow(tree, content, 1.0);
+        layout_add_child(tree, root, sidebar);
+        layout_add_child(tree, root, content);
+        layout_compute(tree, root, 300.0, 200.0);
+        assert_eq!(rect(tree, content), [100.0, 0.0, 200.0, 200.0]);
+
+        // Inside the sidebar the change stops at it
+        layout_set_width(tree, item, 60.0);
+        assert!(!layout_compute_incremental(tree, item));
+        assert_eq!(rect(tree, item), [0.0, 0.0, 60.0, 20.0]);
+        assert_eq!(rect(tree, content), [100.0, 0.0, 200.0, 200.0]);
+
+        // Resizing the sidebar itself lays out its parent too
+        layout_set_width(tree, sidebar, 120.0);
+        assert!(layout_compute_incremental(tree, item));
+        assert_eq!(rect(tree, sidebar), [0.0, 0.0, 120.0, 200.0]);
+        assert_eq!(rect(tree, content), [120.0, 0.0, 180.0, 200.0]);
+
+        // Without a boundary above it the whole tree is laid out
+        layout_set_flex_grow(tree, content, 0.0);
+        assert!(layout_compute_incremental(tree, content));
+        assert_eq!(rect(tree, content), [120.0, 0.0, 0.0, 200.0]);
+
+        let removed = layout_new_node(tree);
+        crate::layout_remove_node(tree, removed);
+        assert!(!layout_compute_incremental(tree, removed));
+        layout_tree_free(tree);
+    }
+}
@@ -1,17 +1,609 @@
 //! Keystone Layout - Taffy flexbox/grid engine with C FFI
 //!
 //! Exposes a flat C API for C# P/Invoke. Each LayoutTree is an opaque handle
-//! wrapping a TaffyTree. Nodes are referenced by u64 IDs.
+//! wrapping a TaffyTree. Nodes are referenced by u64 IDs that carry a generation,
+//! so a handle kept past `layout_remove_node` never aliases a node created later.
+//! Entry points reject such stale handles: queries return their failure value
+//! (false, 0 or null), and style setters and child-list calls change nothing and
+//! leave the error for `layout_last_error_message`.
+
+// Every pointer argument comes from the C# host, which owns its validity.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use std::cell::UnsafeCell;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use taffy::prelude::*;
-use taffy::{GridTemplateComponent, MinMax, Overflow};
+use taffy::{CompactLength, DetailedLayoutInfo, GridTemplateComponent, GridTemplateRepetition, MinMax, Overflow, Point, RepetitionCount};
+
+use crate::background::LayoutComputeDoneFn;
+use crate::error::{checked_code, ffi_guard, log, set_last_error, LOG_WARN};
+
+pub mod animation;
+pub mod background;
+pub mod baseline;
+pub mod binary;
+pub mod clone;
+pub mod css;
+pub mod diagnostics;
+pub mod error;
+#[cfg(feature = "text")]
+pub mod font;
+pub mod hit_test;
+pub mod incremental;
+pub mod paint;
+pub mod parallel;
+pub mod scroll;
+pub mod snapshot;
+pub mod sync;
+#[cfg(feature = "text")]
+pub mod text;
+pub mod virtual_list;
 
 // ============================================================================
 // Opaque handle
 // ============================================================================
 
+/// The tree behind a host handle. Entry points only ever borrow the handle
+/// shared and reach the state through `sync`, which takes a thread-safe tree's
+/// lock before it hands out a reference to the state.
 pub struct LayoutTree {
-    tree: TaffyTree,
+    state: UnsafeCell<TreeState>,
+    /// Set for trees from `layout_tree_new_threadsafe`.
+    lock: Option<Arc<sync::TreeLock>>,
+}
+
+impl LayoutTree {
+    fn from_state(state: TreeState, threadsafe: bool) -> Self {
+        LayoutTree { state: UnsafeCell::new(state), lock: threadsafe.then(Arc::default) }
+    }
+
+    /// A heap handle for the host, freed by `layout_tree_free`.
+    fn into_raw(state: TreeState, threadsafe: bool) -> *mut LayoutTree {
+        #[cfg(feature = "text")]
+        font::preload();
+        Box::into_raw(Box::new(Self::from_state(state, threadsafe)))
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct TreeState {
+    tree: TaffyTree<NodeContext>,
+    /// Every node currently alive in `tree`. Taffy panics on unknown IDs, so
+    /// entry points that promise to tolerate them check here first.
+    live: HashSet<NodeId>,
+    /// Nodes Taffy's storage has room for, as far as the tree has sized it; see
+    /// `layout_tree_reserve`.
+    node_capacity: usize,
+    /// Mutations since the last drain. None while journaling is off.
+    journal: Option<Vec<LayoutMutation>>,
+    /// 0=LTR, 1=RTL. Logical insets resolve against this.
+    direction: u8,
+    /// Directions set on nodes, which their subtrees inherit over the tree's.
+    directions: HashMap<NodeId, u8>,
+    /// Logical insets as set by the host, re-resolved whenever direction changes.
+    logical_insets: HashMap<NodeId, LogicalInsets>,
+    /// Start/end padding, margins and insets set one edge at a time, re-resolved
+    /// the same way.
+    logical_edges: HashMap<NodeId, LogicalEdges>,
+    /// Safe-area insets of the window: left, top, right, bottom.
+    safe_area: [f32; 4],
+    /// Per-edge offsets from the safe area, re-resolved whenever it changes. NaN
+    /// marks an edge that is not anchored to the safe area.
+    safe_area_insets: HashMap<NodeId, [f32; 4]>,
+    /// Bumped by every style write; lets compute tell whether resolvers changed anything.
+    style_generation: u64,
+    style_resolvers: HashMap<NodeId, StyleResolver>,
+    /// Roots of subtrees unlinked by `layout_detach_subtree`, awaiting destruction.
+    detached: HashSet<NodeId>,
+    skip_clean_compute: bool,
+    /// What computing a node that has a parent does; see `layout_tree_set_nested_compute`.
+    nested_compute: u8,
+    /// Root and available space of the previous compute.
+    last_compute: Option<(NodeId, Size<AvailableSpace>)>,
+    /// How long the previous compute that ran took, in microseconds.
+    last_compute_us: u64,
+    /// Rects as last reported by `layout_compute_with_changes`: x, y, w, h.
+    reported_rects: HashMap<NodeId, [f32; 4]>,
+    /// Scroll offsets set by the host, keyed by scroll container.
+    scroll_offsets: HashMap<NodeId, Point<f32>>,
+    /// Scroll links keyed by the node whose style they drive.
+    scroll_links: HashMap<NodeId, Vec<scroll::ScrollLink>>,
+    /// Running style transitions per node; see `animation`.
+    animations: HashMap<NodeId, Vec<animation::Transition>>,
+    /// Keyframe animations and their playbacks; see `animation`.
+    keyframes: animation::Keyframes,
+    /// Leaves given a baseline, by the host or as text, since they were last
+    /// checked; see `baseline`.
+    baseline_leaves: HashSet<NodeId>,
+    /// Nodes in content min-size mode, with the overflow the host asked for.
+    overflow_min_content: HashMap<NodeId, Point<Overflow>>,
+    /// Multiplier for px padding, margins and gaps; see `layout_tree_set_spacing_scale`.
+    spacing_scale: f32,
+    /// Unscaled spacing of every node, kept while `spacing_scale` is not 1 and for
+    /// nodes carrying balance padding or gap-override margins.
+    host_spacing: HashMap<NodeId, Spacing>,
+    stream: Option<ComputeStream>,
+    /// Per-label timings from `layout_compute_labeled`.
+    compute_stats: HashMap<String, LayoutComputeStats>,
+    /// Nodes that ignore ancestor clipping; see `paint`.
+    clip_escape: HashSet<NodeId>,
+    /// Host-declared layout boundaries; see `layout_set_is_layout_boundary`.
+    layout_boundaries: HashSet<NodeId>,
+    /// Gap overrides keyed by the child they follow; see `layout_set_gap_after`.
+    gap_after: HashMap<NodeId, GapAfter>,
+    /// Balanced wrap containers with the left and right padding balancing added.
+    wrap_balance: HashMap<NodeId, [f32; 2]>,
+    /// Virtual lists with their windows; see `virtual_list`.
+    virtual_lists: HashMap<NodeId, virtual_list::VirtualList>,
+    /// Nodes whose block children's margins accumulate, with the style the host set.
+    accumulate_margins: HashMap<NodeId, Style>,
+    /// Host-supplied content versions; see `layout_set_content_version`.
+    content_versions: HashMap<NodeId, u64>,
+    /// See `layout_tree_set_rounding`; mirrors the Taffy tree's setting.
+    rounding: bool,
+    /// Device pixels per logical pixel; see `layout_tree_set_scale_factor`.
+    scale_factor: f32,
+    /// See `layout_tree_set_pixel_distribution`; 0 leaves rounding to Taffy.
+    pixel_distribution: u8,
+    /// Layouts that take precedence over Taffy's: re-rounded by pixel distribution,
+    /// or imported by `layout_import_result_cache` until the next compute.
+    layout_overrides: HashMap<NodeId, Layout>,
+    /// Host-assigned node names; see `layout_set_node_name`.
+    names: HashMap<NodeId, String>,
+    /// Nodes under each name, for `layout_find_by_name`.
+    name_index: HashMap<String, Vec<NodeId>>,
+    /// Open frame from `layout_begin_frame`, with the computes it has deferred.
+    frame: Option<Frame>,
+    /// Registered surface roots in registration order; see `layout_add_root`.
+    roots: Vec<NodeId>,
+    /// The handle the current exclusive call came in through, which callbacks get
+    /// to call back into the tree.
+    handle: *mut LayoutTree,
+    /// Number of computes that have run; see `layout_get_compute_epoch`.
+    epoch: u64,
+    /// Each node's last reported layout and the epoch in which it last changed.
+    node_epochs: HashMap<NodeId, (u64, Layout)>,
+    /// Profile of the last compute while `layout_enable_profiling` is on.
+    profiler: Option<Profiler>,
+    /// See `layout_tree_set_parallelism`, with the layouts of subtrees laid out
+    /// off the Taffy tree.
+    parallel: parallel::Parallel,
+}
+
+/// What the wrapper stores with each node inside Taffy.
+#[derive(Clone)]
+struct NodeContext {
+    /// Opaque host pointer from `layout_set_context`.
+    user_data: *mut c_void,
+    /// Percent of content width and px offset; see `layout_set_height_from_width`.
+    height_from_width: Option<[f32; 2]>,
+    /// Natural width and height; see `layout_set_intrinsic_size`.
+    intrinsic_size: Option<[f32; 2]>,
+    /// See `layout_set_baseline`.
+    baseline: Option<f32>,
+    /// See `layout_set_text`.
+    #[cfg(feature = "text")]
+    text: Option<Arc<text::Shaped>>,
+}
+
+impl Default for NodeContext {
+    fn default() -> Self {
+        NodeContext {
+            user_data: std::ptr::null_mut(),
+            height_from_width: None,
+            intrinsic_size: None,
+            baseline: None,
+            #[cfg(feature = "text")]
+            text: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct LogicalInsets {
+    start: f32,
+    top: f32,
+    end: f32,
+    bottom: f32,
+    /// Whether they were last resolved right to left.
+    rtl: bool,
+}
+
+/// Start and end of each logical property; None where the property was not set
+/// logically.
+#[derive(Clone, Copy, Default)]
+struct LogicalEdges {
+    padding: [Option<LengthPercentage>; 2],
+    margin: [Option<LengthPercentageAuto>; 2],
+    inset: [Option<LengthPercentageAuto>; 2],
+    /// Whether they were last resolved right to left; None before the first time.
+    rtl: Option<bool>,
+}
+
+#[derive(Clone, Copy)]
+struct GapAfter {
+    /// Spacing wanted between the child and its next sibling, before spacing scale.
+    gap: f32,
+    /// Margin currently added to reach it: left, top, right, bottom.
+    margin: [f32; 4],
+}
+
+#[derive(Clone, Copy)]
+struct Spacing {
+    padding: Rect<LengthPercentage>,
+    margin: Rect<LengthPercentageAuto>,
+    gap: Size<LengthPercentage>,
+}
+
+impl Spacing {
+    fn of(style: &Style) -> Self {
+        Spacing { padding: style.padding, margin: style.margin, gap: style.gap }
+    }
+
+    fn write(self, style: &mut Style) {
+        style.padding = self.padding;
+        style.margin = self.margin;
+        style.gap = self.gap;
+    }
+
+    /// Percentages and auto are left alone; only px lengths scale.
+    fn scaled(self, factor: f32) -> Self {
+        let lp = |v: LengthPercentage| {
+            let raw = v.into_raw();
+            if raw.tag() == CompactLength::LENGTH_TAG { LengthPercentage::length(raw.value() * factor) } else { v }
+        };
+        let lpa = |v: LengthPercentageAuto| {
+            let raw = v.into_raw();
+            if raw.tag() == CompactLength::LENGTH_TAG { LengthPercentageAuto::length(raw.value() * factor) } else { v }
+        };
+        Spacing {
+            padding: self.padding.map(lp),
+            margin: self.margin.map(lpa),
+            gap: self.gap.map(lp),
+        }
+    }
+}
+
+impl TreeState {
+    fn new() -> Self {
+        Self::with_capacity(16)
+    }
+
+    fn with_capacity(nodes: usize) -> Self {
+        TreeState {
+            tree: TaffyTree::with_capacity(nodes),
+            live: HashSet::with_capacity(nodes),
+            node_capacity: nodes,
+            journal: None,
+            direction: 0,
+            directions: HashMap::new(),
+            logical_insets: HashMap::new(),
+            logical_edges: HashMap::new(),
+            safe_area: [0.0; 4],
+            safe_area_insets: HashMap::new(),
+            style_generation: 0,
+            style_resolvers: HashMap::new(),
+            detached: HashSet::new(),
+            skip_clean_compute: false,
+            nested_compute: NESTED_COMPUTE_AS_ROOT,
+            last_compute: None,
+            last_compute_us: 0,
+            reported_rects: HashMap::new(),
+            scroll_offsets: HashMap::new(),
+            scroll_links: HashMap::new(),
+            animations: HashMap::new(),
+            keyframes: animation::Keyframes::default(),
+            baseline_leaves: HashSet::new(),
+            overflow_min_content: HashMap::new(),
+            spacing_scale: 1.0,
+            host_spacing: HashMap::new(),
+            stream: None,
+            compute_stats: HashMap::new(),
+            clip_escape: HashSet::new(),
+            layout_boundaries: HashSet::new(),
+            gap_after: HashMap::new(),
+            wrap_balance: HashMap::new(),
+            virtual_lists: HashMap::new(),
+            accumulate_margins: HashMap::new(),
+            content_versions: HashMap::new(),
+            rounding: true,
+            scale_factor: 1.0,
+            pixel_distribution: 0,
+            layout_overrides: HashMap::new(),
+            names: HashMap::new(),
+            name_index: HashMap::new(),
+            frame: None,
+            roots: Vec::new(),
+            handle: std::ptr::null_mut(),
+            epoch: 0,
+            node_epochs: HashMap::new(),
+            profiler: None,
+            parallel: parallel::Parallel::default(),
+        }
+    }
+
+    fn contains(&self, id: NodeId) -> bool {
+        self.live.contains(&id)
+    }
+
+    /// `contains` for calls that change the tree: a stale or unknown handle also
+    /// sets the last error, so the host can tell a rejected write from a no-op.
+    fn expect_live(&self, id: NodeId) -> bool {
+        if self.contains(id) {
+            return true;
+        }
+        set_last_error(&format!("stale or unknown node handle {}", u64::from(id)));
+        false
+    }
+
+    /// The node's computed layout; None for stale or unknown handles.
+    fn layout_of(&self, id: NodeId) -> Option<&Layout> {
+        self.contains(id).then(|| self.final_layout(id))
+    }
+
+    /// The layout reported to the host for a live node.
+    fn final_layout(&self, id: NodeId) -> &Layout {
+        self.layout_overrides.get(&id).unwrap_or_else(|| self.rounded_layout(id))
+    }
+
+    /// The node's layout as Taffy rounded it, or Taffy's unrounded one while
+    /// rounding is off. Subtrees laid out in parallel keep theirs in `parallel`.
+    fn rounded_layout(&self, id: NodeId) -> &Layout {
+        match self.parallel.layouts.get(&id) {
+            Some(kept) => &kept.rounded,
+            None => self.tree.layout(id).unwrap(),
+        }
+    }
+
+    fn unrounded_layout(&self, id: NodeId) -> &Layout {
+        match self.parallel.layouts.get(&id) {
+            Some(kept) => &kept.unrounded,
+            None => self.tree.unrounded_layout(id),
+        }
+    }
+
+    fn detailed_layout_info(&self, id: NodeId) -> &DetailedLayoutInfo {
+        self.parallel.details.get(&id).unwrap_or_else(|| self.tree.detailed_layout_info(id))
+    }
+
+    /// A logical length in device pixels.
+    fn to_physical(&self, v: f32) -> f32 {
+        let v = v * self.scale_factor;
+        if self.rounding { v.round() } else { v }
+    }
+
+    /// Name or unname a node, keeping the name index in step.
+    fn set_name(&mut self, id: NodeId, name: Option<String>) {
+        if let Some(old) = self.names.remove(&id) {
+            if let Some(ids) = self.name_index.get_mut(&old) {
+                ids.retain(|&n| n != id);
+                if ids.is_empty() {
+                    self.name_index.remove(&old);
+                }
+            }
+        }
+        if let Some(name) = name {
+            self.name_index.entry(name.clone()).or_default().push(id);
+            self.names.insert(id, name);
+        }
+    }
+
+    /// Remove one node and everything the wrapper keeps about it.
+    fn remove_node(&mut self, id: NodeId) {
+        let _ = self.tree.remove(id);
+        self.live.remove(&id);
+        self.directions.remove(&id);
+        self.logical_insets.remove(&id);
+        self.logical_edges.remove(&id);
+        self.safe_area_insets.remove(&id);
+        self.style_resolvers.remove(&id);
+        self.detached.remove(&id);
+        self.reported_rects.remove(&id);
+        self.overflow_min_content.remove(&id);
+        self.host_spacing.remove(&id);
+        self.clip_escape.remove(&id);
+        self.layout_boundaries.remove(&id);
+        self.gap_after.remove(&id);
+        self.wrap_balance.remove(&id);
+        self.virtual_lists.remove(&id);
+        self.accumulate_margins.remove(&id);
+        self.content_versions.remove(&id);
+        self.layout_overrides.remove(&id);
+        self.set_name(id, None);
+        self.node_epochs.remove(&id);
+        self.roots.retain(|&root| root != id);
+        scroll::forget_node(self, id);
+        self.baseline_leaves.remove(&id);
+        animation::forget_node(self, id);
+        parallel::forget_node(self, id);
+        self.record(MUTATION_REMOVED, id, id);
+    }
+
+    /// Shrink side tables that hold far more capacity than entries, e.g. after a
+    /// large subtree was freed.
+    fn trim_side_tables(&mut self) {
+        macro_rules! trim {
+            ($($table:ident),*) => {
+                $(if spare(self.$table.len(), self.$table.capacity()) {
+                    self.$table.shrink_to_fit();
+                })*
+            };
+        }
+        trim!(live, directions, logical_insets, logical_edges, safe_area_insets, style_resolvers, detached,
+            reported_rects, scroll_offsets, scroll_links, animations, baseline_leaves, overflow_min_content,
+            host_spacing, clip_escape, layout_boundaries, gap_after, wrap_balance, virtual_lists, accumulate_margins,
+            content_versions, layout_overrides, names, name_index, node_epochs, roots);
+    }
+
+    /// Remove every node, keeping the capacity of Taffy's storage and the side tables.
+    fn clear(&mut self) {
+        if self.journal.is_some() {
+            let mut tops: Vec<NodeId> = self.live.iter().copied().filter(|&id| self.tree.parent(id).is_none()).collect();
+            tops.sort_by_key(|&id| u64::from(id));
+            for top in tops {
+                for id in self.subtree(top) {
+                    self.record(MUTATION_REMOVED, id, id);
+                }
+            }
+        }
+        self.tree.clear();
+        macro_rules! clear {
+            ($($table:ident),*) => {
+                $(self.$table.clear();)*
+            };
+        }
+        clear!(live, directions, logical_insets, logical_edges, safe_area_insets, style_resolvers, detached,
+            reported_rects, scroll_offsets, scroll_links, animations, baseline_leaves, overflow_min_content,
+            host_spacing, clip_escape, layout_boundaries, gap_after, wrap_balance, virtual_lists, accumulate_margins,
+            content_versions, layout_overrides, names, name_index, node_epochs, roots);
+        self.keyframes.stop_all();
+        self.parallel.clear();
+        self.last_compute = None;
+    }
+
+    /// Registered roots that are still roots, in registration order.
+    fn registered_roots(&self) -> Vec<NodeId> {
+        self.roots.iter().copied().filter(|&id| self.tree.parent(id).is_none() && !self.detached.contains(&id)).collect()
+    }
+
+    /// `root` followed by all of its descendants, breadth-first.
+    fn subtree(&self, root: NodeId) -> Vec<NodeId> {
+        let mut nodes = vec![root];
+        let mut i = 0;
+        while i < nodes.len() {
+            nodes.extend(self.tree.children(nodes[i]).unwrap());
+            i += 1;
+        }
+        nodes
+    }
+
+    /// Remove `root` and all of its descendants. Returns the removed IDs, root first.
+    fn remove_subtree(&mut self, root: NodeId) -> Vec<NodeId> {
+        let removed = self.subtree(root);
+        for &id in &removed {
+            self.remove_node(id);
+        }
+        removed
+    }
+
+    /// The node's style as the host set it. The style Taffy lays out can differ:
+    /// spacing is scaled, balanced wrapping pads the container, content min-size
+    /// mode turns hidden/scroll into clip and accumulating blocks lay out as grids.
+    fn host_style(&self, id: NodeId) -> Style {
+        if let Some(style) = self.accumulate_margins.get(&id) {
+            return style.clone();
+        }
+        let mut style = self.tree.style(id).unwrap().clone();
+        if let Some(&overflow) = self.overflow_min_content.get(&id) {
+            style.overflow = overflow;
+        }
+        if let Some(spacing) = self.host_spacing.get(&id) {
+            spacing.write(&mut style);
+        }
+        style
+    }
+
+    /// Store a host style, deriving the style Taffy lays out from it.
+    fn store_style(&mut self, id: NodeId, mut style: Style) {
+        let accumulate = match self.accumulate_margins.get_mut(&id) {
+            Some(host) => {
+                *host = style.clone();
+                style.display == Display::Block
+            }
+            None => false,
+        };
+        if let Some(requested) = self.overflow_min_content.get_mut(&id) {
+            *requested = style.overflow;
+            // Clip is the one non-visible overflow that is not a scroll container
+            style.overflow = style.overflow.map(|o| if o == Overflow::Visible { o } else { Overflow::Clip });
+        }
+        let extra = self.wrap_balance.get(&id).copied().unwrap_or_default();
+        let margin = self.gap_after.get(&id).map_or([0.0; 4], |g| g.margin);
+        if self.spacing_scale != 1.0 || extra != [0.0; 2] || margin != [0.0; 4] {
+            let spacing = Spacing::of(&style);
+            self.host_spacing.insert(id, spacing);
+            spacing.scaled(self.spacing_scale).write(&mut style);
+            style.padding.left = add_px(style.padding.left, extra[0]);
+            style.padding.right = add_px(style.padding.right, extra[1]);
+            style.margin.left = add_px_auto(style.margin.left, margin[0]);
+            style.margin.top = add_px_auto(style.margin.top, margin[1]);
+            style.margin.right = add_px_auto(style.margin.right, margin[2]);
+            style.margin.bottom = add_px_auto(style.margin.bottom, margin[3]);
+        } else {
+            self.host_spacing.remove(&id);
+        }
+        if accumulate {
+            stack_without_collapsing(&mut style);
+        }
+        let _ = self.tree.set_style(id, style);
+    }
+
+    fn add_child(&mut self, parent: NodeId, child: NodeId) {
+        if !self.expect_live(parent) || !self.expect_live(child) {
+            return;
+        }
+        if self.tree.add_child(parent, child).is_ok() {
+            self.record(MUTATION_CHILD_ADDED, parent, child);
+        }
+    }
+
+    fn record(&mut self, kind: u8, node: NodeId, other: NodeId) {
+        let Some(journal) = self.journal.as_mut() else { return };
+        let entry = LayoutMutation { kind, node: node.into(), other: other.into() };
+        // Back-to-back style edits on one node collapse into a single entry
+        if journal.last().is_some_and(|last| *last == entry) {
+            return;
+        }
+        journal.push(entry);
+    }
+}
+
+// ============================================================================
+// Version introspection
+// ============================================================================
+
+/// Bumped whenever an exported signature, `#[repr(C)]` struct or enum code changes
+/// incompatibly. Loaders compare it before calling anything else.
+pub const LAYOUT_ABI_VERSION: u32 = 1;
+
+pub const FEATURE_FLEXBOX: u64 = 1 << 0;
+pub const FEATURE_GRID: u64 = 1 << 1;
+pub const FEATURE_BLOCK: u64 = 1 << 2;
+/// JSON and binary snapshots, fixtures and result caches.
+pub const FEATURE_SERIALIZATION: u64 = 1 << 3;
+/// `layout_compute_group` may run trees on worker threads.
+pub const FEATURE_PARALLEL_GROUP: u64 = 1 << 4;
+/// Built with debug assertions: slower, with extra internal checks.
+pub const FEATURE_DEBUG_ASSERTIONS: u64 = 1 << 5;
+/// Text leaves measured in the library (`layout_set_text`).
+pub const FEATURE_TEXT: u64 = 1 << 6;
+
+#[no_mangle]
+pub extern "C" fn layout_abi_version() -> u32 {
+    LAYOUT_ABI_VERSION
+}
+
+/// The crate version, e.g. `"0.1.0"`. The string is static; do not free it.
+#[no_mangle]
+pub extern "C" fn layout_version_string() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// `FEATURE_*` bits of what this binary was built with.
+#[no_mangle]
+pub extern "C" fn layout_feature_flags() -> u64 {
+    let mut flags = FEATURE_FLEXBOX | FEATURE_GRID | FEATURE_BLOCK | FEATURE_SERIALIZATION | FEATURE_PARALLEL_GROUP;
+    if cfg!(debug_assertions) {
+        flags |= FEATURE_DEBUG_ASSERTIONS;
+    }
+    if cfg!(feature = "text") {
+        flags |= FEATURE_TEXT;
+    }
+    flags
 }
 
 // ============================================================================
@@ -19,323 +611,4108 @@ pub struct LayoutTree {
 // ============================================================================
 
 #[no_mangle]
-pub extern "C" fn layout_tree_new() -> *mut LayoutTree {
-    Box::into_raw(Box::new(LayoutTree {
-        tree: TaffyTree::new(),
-    }))
+pub extern "C" fn layout_tree_new() -> *mut LayoutTree {
+    ffi_guard(|| {
+        LayoutTree::into_raw(TreeState::new(), false)
+    })
+}
+
+/// A new empty tree with room for `nodes` nodes, so that building it does not keep
+/// reallocating node storage.
+#[no_mangle]
+pub extern "C" fn layout_tree_with_capacity(nodes: usize) -> *mut LayoutTree {
+    ffi_guard(|| {
+        LayoutTree::into_raw(TreeState::with_capacity(nodes), false)
+    })
+}
+
+/// Make room for `extra` nodes more than the tree holds. Taffy cannot reserve
+/// storage after creation, so this creates and frees `extra` placeholder nodes,
+/// costing about what creating them would; a tree built from scratch is better
+/// made by `layout_tree_with_capacity`. Does nothing if the room is already there.
+#[no_mangle]
+pub extern "C" fn layout_tree_reserve(tree: *mut LayoutTree, extra: usize) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let wanted = tree.live.len().saturating_add(extra);
+        if wanted <= tree.node_capacity.max(tree.live.len()) {
+            return;
+        }
+        tree.live.reserve(extra);
+        let placeholders: Vec<NodeId> = (0..extra).map(|_| tree.tree.new_leaf(Style::DEFAULT).unwrap()).collect();
+        for id in placeholders {
+            let _ = tree.tree.remove(id);
+        }
+        tree.node_capacity = wanted;
+    })
+}
+
+/// Remove every node but keep the tree's settings and storage, for hosts that
+/// rebuild the whole tree each frame. Handles from before the clear stay invalid;
+/// while journaling, every node is journaled as removed.
+#[no_mangle]
+pub extern "C" fn layout_tree_clear(tree: *mut LayoutTree) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        tree.clear();
+    })
+}
+
+/// Free a tree. Its queued background computes are cancelled and a running one is
+/// waited for. A thread-safe tree also lets calls in flight on other threads
+/// finish first; calls still waiting for it then fail.
+#[no_mangle]
+pub extern "C" fn layout_tree_free(ptr: *mut LayoutTree) {
+    ffi_guard(|| {
+        if !ptr.is_null() {
+            background::forget(ptr);
+            let _lock = sync::retire(ptr);
+            unsafe { drop(Box::from_raw(ptr)) };
+        }
+    })
+}
+
+/// 0=LTR, 1=RTL. Re-resolves every logical inset and edge set so far, outside
+/// subtrees with a direction of their own.
+#[no_mangle]
+pub extern "C" fn layout_tree_set_direction(tree: *mut LayoutTree, direction: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let direction = checked_code("direction", direction, 2);
+        if tree.direction == direction {
+            return;
+        }
+        tree.direction = direction;
+        let ids: Vec<NodeId> = tree.logical_insets.keys().chain(tree.logical_edges.keys()).copied().collect();
+        for id in ids {
+            apply_logical(tree, id);
+        }
+    })
+}
+
+/// Safe-area insets of the window (notches, rounded corners, system bars). Nodes
+/// anchored with `layout_set_inset_safe_area` follow the new values.
+#[no_mangle]
+pub extern "C" fn layout_tree_set_safe_area(tree: *mut LayoutTree, left: f32, top: f32, right: f32, bottom: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let area = [left, top, right, bottom];
+        if tree.safe_area == area {
+            return;
+        }
+        tree.safe_area = area;
+        let ids: Vec<NodeId> = tree.safe_area_insets.keys().copied().collect();
+        for id in ids {
+            apply_safe_area_insets(tree, id);
+        }
+    })
+}
+
+/// Scale every px padding, margin and gap by `factor` at layout time, for density
+/// modes. Node styles keep their own values: the getters report them unscaled and
+/// later writes are scaled as they land. Negative or non-finite factors are ignored.
+#[no_mangle]
+pub extern "C" fn layout_tree_set_spacing_scale(tree: *mut LayoutTree, factor: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if !factor.is_finite() || factor < 0.0 {
+            log(LOG_WARN, &format!("spacing scale {factor} ignored; it must be finite and not negative"));
+            return;
+        }
+        if factor == tree.spacing_scale {
+            return;
+        }
+        let styles: Vec<(NodeId, Style)> = tree.live.iter().map(|&id| (id, tree.host_style(id))).collect();
+        tree.host_spacing.clear();
+        tree.spacing_scale = factor;
+        for (id, style) in styles {
+            tree.store_style(id, style);
+        }
+        tree.style_generation += 1;
+    })
+}
+
+// ============================================================================
+// Node creation
+// ============================================================================
+
+#[no_mangle]
+pub extern "C" fn layout_new_node(tree: *mut LayoutTree) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = tree.tree.new_leaf(Style::default()).unwrap();
+        tree.live.insert(id);
+        tree.record(MUTATION_CREATED, id, id);
+        id.into()
+    })
+}
+
+/// A flexible-space leaf for toolbars and dialogs: along its parent's main axis it
+/// takes at least `min_px` and a `flex` share of the free space, and it never
+/// shrinks below `min_px`. It is an ordinary node with a preset style and no
+/// measure function, the cheapest kind of node Taffy lays out; restyle or remove
+/// it like any other.
+#[no_mangle]
+pub extern "C" fn layout_new_spacer(tree: *mut LayoutTree, min_px: f32, flex: f32) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let style = Style {
+            flex_basis: Dimension::length(min_px.max(0.0)),
+            flex_grow: flex.max(0.0),
+            flex_shrink: 0.0,
+            ..Style::default()
+        };
+        let id = tree.tree.new_leaf(style).unwrap();
+        tree.live.insert(id);
+        tree.record(MUTATION_CREATED, id, id);
+        id.into()
+    })
+}
+
+/// Returns 0 if any child handle is stale or unknown, or `children` is null with a
+/// non-zero count.
+#[no_mangle]
+pub extern "C" fn layout_new_node_with_children(
+    tree: *mut LayoutTree, children: *const u64, count: usize,
+) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(kids) = in_buffer(children, count) else { return 0 };
+        let kids: Vec<NodeId> = kids.iter().map(|&id| NodeId::from(id)).collect();
+        if !kids.iter().all(|&kid| tree.expect_live(kid)) {
+            return 0;
+        }
+        let id = tree.tree.new_with_children(Style::default(), &kids).unwrap();
+        tree.live.insert(id);
+        tree.record(MUTATION_CREATED, id, id);
+        for &kid in &kids {
+            tree.record(MUTATION_CHILD_ADDED, id, kid);
+        }
+        id.into()
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_add_child(tree: *mut LayoutTree, parent: u64, child: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        tree.add_child(NodeId::from(parent), NodeId::from(child));
+    })
+}
+
+/// Replace the whole child list of `parent`. Children attached elsewhere are moved.
+/// A count of 0 (`children` may be null) removes them all; a null array with a
+/// non-zero count changes nothing.
+#[no_mangle]
+pub extern "C" fn layout_set_children(tree: *mut LayoutTree, parent: u64, children: *const u64, count: usize) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let parent = NodeId::from(parent);
+        let Some(kids) = in_buffer(children, count) else { return };
+        let kids: Vec<NodeId> = kids.iter().map(|&id| NodeId::from(id)).collect();
+        if !tree.expect_live(parent) || !kids.iter().all(|&kid| tree.expect_live(kid)) {
+            return;
+        }
+        let old = tree.tree.children(parent).unwrap();
+        let moved: Vec<(NodeId, NodeId)> = kids.iter()
+            .filter_map(|&kid| tree.tree.parent(kid).filter(|&p| p != parent).map(|p| (p, kid)))
+            .collect();
+        if tree.tree.set_children(parent, &kids).is_err() {
+            return;
+        }
+        for (from, kid) in moved {
+            tree.record(MUTATION_CHILD_REMOVED, from, kid);
+        }
+        for kid in old {
+            tree.record(MUTATION_CHILD_REMOVED, parent, kid);
+        }
+        for kid in kids {
+            tree.record(MUTATION_CHILD_ADDED, parent, kid);
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_insert_child_at(tree: *mut LayoutTree, parent: u64, child: u64, index: usize) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let (parent, child) = (NodeId::from(parent), NodeId::from(child));
+        if !tree.expect_live(parent) || !tree.expect_live(child) {
+            return;
+        }
+        if tree.tree.insert_child_at_index(parent, index, child).is_ok() {
+            tree.record(MUTATION_CHILD_ADDED, parent, child);
+        }
+    })
+}
+
+/// Swap the child at `index` for `new_child`. Returns the displaced child, or 0 on failure.
+#[no_mangle]
+pub extern "C" fn layout_replace_child_at(tree: *mut LayoutTree, parent: u64, index: usize, new_child: u64) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let (parent, new_child) = (NodeId::from(parent), NodeId::from(new_child));
+        if !tree.expect_live(parent) || !tree.expect_live(new_child) {
+            return 0;
+        }
+        match tree.tree.replace_child_at_index(parent, index, new_child) {
+            Ok(old) => {
+                tree.record(MUTATION_CHILD_REMOVED, parent, old);
+                tree.record(MUTATION_CHILD_ADDED, parent, new_child);
+                old.into()
+            }
+            Err(_) => 0,
+        }
+    })
+}
+
+/// Move `node`, with its subtree and styles intact, under `new_parent` at `index`
+/// (clamped; `usize::MAX` appends). The node leaves its current parent first, so
+/// moving within one parent reorders. A detached subtree becomes attached again.
+/// Returns false for unknown nodes or when `new_parent` is `node` or inside its
+/// subtree.
+#[no_mangle]
+pub extern "C" fn layout_reparent(tree: *mut LayoutTree, node: u64, new_parent: u64, index: usize) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let (id, parent) = (NodeId::from(node), NodeId::from(new_parent));
+        if !tree.expect_live(id) || !tree.expect_live(parent) || is_descendant_or_self(&tree.tree, parent, id) {
+            return false;
+        }
+        if let Some(old) = tree.tree.parent(id) {
+            let _ = tree.tree.remove_child(old, id);
+            tree.record(MUTATION_CHILD_REMOVED, old, id);
+        }
+        tree.detached.remove(&id);
+        let index = index.min(tree.tree.child_count(parent));
+        let _ = tree.tree.insert_child_at_index(parent, index, id);
+        tree.record(MUTATION_CHILD_ADDED, parent, id);
+        true
+    })
+}
+
+/// Detach the child at `index` (the child itself stays alive). Returns it, or 0 on failure.
+#[no_mangle]
+pub extern "C" fn layout_remove_child_at(tree: *mut LayoutTree, parent: u64, index: usize) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let parent = NodeId::from(parent);
+        if !tree.expect_live(parent) {
+            return 0;
+        }
+        match tree.tree.remove_child_at_index(parent, index) {
+            Ok(old) => {
+                tree.record(MUTATION_CHILD_REMOVED, parent, old);
+                old.into()
+            }
+            Err(_) => 0,
+        }
+    })
+}
+
+/// Remove one node. Its children stay alive and become roots; use
+/// `layout_remove_subtree` to remove them too.
+#[no_mangle]
+pub extern "C" fn layout_remove_node(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if tree.expect_live(id) {
+            tree.remove_node(id);
+        }
+    })
+}
+
+/// Remove `node` and all of its descendants. Every invalidated ID is written to
+/// `out_removed`, `node` first and then breadth-first. Returns the number removed
+/// (0 for an unknown node); only the first `cap` IDs are written.
+#[no_mangle]
+pub extern "C" fn layout_remove_subtree(tree: *mut LayoutTree, node: u64, out_removed: *mut u64, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.expect_live(id) {
+            return 0;
+        }
+        let removed = tree.remove_subtree(id);
+        let out = out_buffer(out_removed, cap);
+        for (slot, &id) in out.iter_mut().zip(&removed) {
+            *slot = id.into();
+        }
+        removed.len()
+    })
+}
+
+// ============================================================================
+// Node context
+// ============================================================================
+
+/// Attach an opaque host pointer (e.g. a GCHandle) to a node, so hit-test and
+/// traversal results map straight back to managed objects. The library never
+/// dereferences it. Null clears it. Setting a context does not dirty the node.
+#[no_mangle]
+pub extern "C" fn layout_set_context(tree: *mut LayoutTree, node: u64, context: *mut c_void) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        // Updating in place keeps Taffy from treating the node as dirty
+        match tree.tree.get_node_context_mut(id) {
+            Some(existing) => existing.user_data = context,
+            None if !context.is_null() => {
+                let _ = tree.tree.set_node_context(id, Some(NodeContext { user_data: context, ..NodeContext::default() }));
+            }
+            None => {}
+        }
+    })
+}
+
+/// The pointer set with `layout_set_context`, or null.
+#[no_mangle]
+pub extern "C" fn layout_get_context(tree: *const LayoutTree, node: u64) -> *mut c_void {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return std::ptr::null_mut();
+        }
+        tree.tree.get_node_context(id).map_or(std::ptr::null_mut(), |c| c.user_data)
+    })
+}
+
+// ============================================================================
+// Node names
+// ============================================================================
+
+/// Name a node (UTF-8) for tooling and serialized trees. Null or an empty name
+/// clears it. Names need not be unique.
+#[no_mangle]
+pub extern "C" fn layout_set_node_name(tree: *mut LayoutTree, node: u64, name: *const c_char) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        tree.set_name(id, c_str(name).filter(|n| !n.is_empty()).map(str::to_string));
+    })
+}
+
+/// The first node named `name` in `root`'s subtree, parents before children and
+/// siblings in order (`root` itself included). 0 if there is none.
+#[no_mangle]
+pub extern "C" fn layout_find_by_name(tree: *const LayoutTree, root: u64, name: *const c_char) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let root = NodeId::from(root);
+        let (Some(name), true) = (c_str(name), tree.contains(root)) else { return 0 };
+        let Some(ids) = tree.name_index.get(name) else { return 0 };
+        let within = |mut id: NodeId| loop {
+            if id == root {
+                return true;
+            }
+            match tree.tree.parent(id) {
+                Some(parent) => id = parent,
+                None => return false,
+            }
+        };
+        let matches: Vec<NodeId> = ids.iter().copied().filter(|&id| within(id)).collect();
+        if matches.len() <= 1 {
+            return matches.first().map_or(0, |&id| id.into());
+        }
+        // Several in the subtree: the first one a depth-first walk reaches
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            if matches.contains(&id) {
+                return id.into();
+            }
+            stack.extend(tree.tree.children(id).unwrap().into_iter().rev());
+        }
+        0
+    })
+}
+
+/// The node's name, or null if it has none. Free it with `layout_string_free`.
+#[no_mangle]
+pub extern "C" fn layout_get_node_name(tree: *const LayoutTree, node: u64) -> *mut c_char {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        match tree.names.get(&NodeId::from(node)) {
+            Some(name) => CString::new(name.as_str()).unwrap_or_default().into_raw(),
+            None => std::ptr::null_mut(),
+        }
+    })
+}
+
+// ============================================================================
+// Detached subtrees (deferred destruction)
+// ============================================================================
+
+/// Unlink `node` from its parent so it no longer takes part in layout, leaving
+/// its nodes allocated until `layout_destroy_detached`. Returns a handle, or 0
+/// if the node is unknown or already detached.
+#[no_mangle]
+pub extern "C" fn layout_detach_subtree(tree: *mut LayoutTree, node: u64) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) || tree.detached.contains(&id) {
+            return 0;
+        }
+        if let Some(parent) = tree.tree.parent(id) {
+            let _ = tree.tree.remove_child(parent, id);
+            tree.record(MUTATION_CHILD_REMOVED, parent, id);
+        }
+        tree.detached.insert(id);
+        id.into()
+    })
+}
+
+/// Free every node of a detached subtree on the background worker thread, and
+/// call `callback` (which may be null) from there once they are gone. Returns a
+/// token for `layout_compute_cancel`, or 0 for an unknown handle. Until the
+/// callback the tree belongs to the worker as for `layout_compute_async`: use a
+/// thread-safe tree to keep working with it meanwhile, which the worker frees in
+/// short slices. The callback gets `COMPUTE_FAILED` if `layout_tree_maintain`
+/// started on the subtree first and frees the rest.
+#[no_mangle]
+pub extern "C" fn layout_destroy_detached(
+    tree: *mut LayoutTree, handle: u64, callback: Option<LayoutComputeDoneFn>, user_data: *mut c_void,
+) -> u64 {
+    ffi_guard(|| {
+        let id = NodeId::from(handle);
+        if !sync::shared(tree).1.detached.contains(&id) {
+            return 0;
+        }
+        background::queue_free(tree, id, callback, user_data)
+    })
+}
+
+/// Free nodes of detached subtrees from the top, `pending` holding the roots left
+/// to free, for about `budget`. Freeing a root orphans its children, which take
+/// its place in `pending`. Frees at least one node; returns false if the budget
+/// ran out first.
+fn free_detached(tree: &mut TreeState, pending: &mut Vec<NodeId>, budget: Duration) -> bool {
+    let start = Instant::now();
+    let mut freed = false;
+    while let Some(root) = pending.pop() {
+        if freed && start.elapsed() >= budget {
+            pending.push(root);
+            return false;
+        }
+        if !tree.contains(root) {
+            continue;
+        }
+        pending.extend(tree.tree.children(root).unwrap());
+        tree.remove_node(root);
+        freed = true;
+    }
+    true
+}
+
+// ============================================================================
+// Idle maintenance
+// ============================================================================
+
+/// Do deferred cleanup for up to about `budget_us` microseconds: free detached
+/// subtrees, then release spare capacity of the tree's side tables. A large
+/// subtree is freed from the top across several calls; once its root is gone its
+/// handle is no longer valid for `layout_destroy_detached`, and the rest of it is
+/// left to later calls. Node storage itself is never compacted, since that would
+/// change node IDs. Each call makes some progress even with a zero budget.
+/// Returns false if the budget ran out first; call again on a later idle tick.
+#[no_mangle]
+pub extern "C" fn layout_tree_maintain(tree: *mut LayoutTree, budget_us: u64) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let mut pending: Vec<NodeId> = tree.detached.drain().collect();
+        let done = free_detached(tree, &mut pending, Duration::from_micros(budget_us));
+        // What is left stays detached for later calls
+        tree.detached.extend(pending);
+        if done {
+            tree.trim_side_tables();
+        }
+        done
+    })
+}
+
+// ============================================================================
+// Mutation journal
+// ============================================================================
+
+pub const MUTATION_STYLE: u8 = 0;
+pub const MUTATION_CREATED: u8 = 1;
+pub const MUTATION_REMOVED: u8 = 2;
+pub const MUTATION_CHILD_ADDED: u8 = 3;
+pub const MUTATION_CHILD_REMOVED: u8 = 4;
+
+/// One journal entry. `other` is the child for structural entries, otherwise equal to `node`.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct LayoutMutation {
+    pub kind: u8,
+    pub node: u64,
+    pub other: u64,
+}
+
+/// Turn journaling on or off. Turning it off discards anything not yet drained.
+#[no_mangle]
+pub extern "C" fn layout_set_journal_enabled(tree: *mut LayoutTree, enabled: bool) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        tree.journal = match (enabled, tree.journal.take()) {
+            (true, existing) => Some(existing.unwrap_or_default()),
+            (false, _) => None,
+        };
+    })
+}
+
+/// Copy up to `cap` pending mutations (oldest first) into `out` and drop them from the journal.
+/// Returns the number written. With a null `out`, returns the pending count without draining.
+#[no_mangle]
+pub extern "C" fn layout_drain_mutations(
+    tree: *mut LayoutTree, out: *mut LayoutMutation, cap: usize,
+) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(journal) = tree.journal.as_mut() else { return 0 };
+        if out.is_null() {
+            return journal.len();
+        }
+        let n = journal.len().min(cap);
+        let dst = unsafe { std::slice::from_raw_parts_mut(out, n) };
+        for (slot, entry) in dst.iter_mut().zip(journal.drain(..n)) {
+            *slot = entry;
+        }
+        n
+    })
+}
+
+// ============================================================================
+// Style setters
+// ============================================================================
+
+#[no_mangle]
+pub extern "C" fn layout_set_display(tree: *mut LayoutTree, node: u64, display: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| {
+            s.display = match checked_code("display", display, 4) {
+                1 => Display::None,
+                2 => Display::Grid,
+                3 => Display::Block,
+                _ => Display::Flex,
+            };
+        });
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_flex_direction(tree: *mut LayoutTree, node: u64, dir: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| {
+            s.flex_direction = match checked_code("flex direction", dir, 4) {
+                1 => FlexDirection::Row,
+                2 => FlexDirection::ColumnReverse,
+                3 => FlexDirection::RowReverse,
+                _ => FlexDirection::Column,
+            };
+        });
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_flex_wrap(tree: *mut LayoutTree, node: u64, wrap: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| {
+            s.flex_wrap = match checked_code("flex wrap", wrap, 3) {
+                1 => FlexWrap::Wrap,
+                2 => FlexWrap::WrapReverse,
+                _ => FlexWrap::NoWrap,
+            };
+        });
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_flex_grow(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.flex_grow = val);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_flex_shrink(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.flex_shrink = val);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_flex_basis(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.flex_basis = Dimension::length(val));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_align_items(tree: *mut LayoutTree, node: u64, val: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.align_items = Some(map_align_items(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_justify_content(tree: *mut LayoutTree, node: u64, val: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.justify_content = Some(map_justify_content(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_align_self(tree: *mut LayoutTree, node: u64, val: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.align_self = Some(map_align_self(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_align_content(tree: *mut LayoutTree, node: u64, val: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.align_content = Some(map_justify_content(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_justify_items(tree: *mut LayoutTree, node: u64, val: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.justify_items = Some(map_align_items(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_justify_self(tree: *mut LayoutTree, node: u64, val: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.justify_self = Some(map_align_self(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_width(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.size.width = Dimension::length(val));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_height(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.size.height = Dimension::length(val));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_width_percent(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.size.width = Dimension::percent(val / 100.0));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_height_percent(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.size.height = Dimension::percent(val / 100.0));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_min_width(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.min_size.width = Dimension::length(val));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_min_height(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.min_size.height = Dimension::length(val));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_max_width(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.max_size.width = Dimension::length(val));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_max_height(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.max_size.height = Dimension::length(val));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_width_auto(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.size.width = Dimension::auto());
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_height_auto(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.size.height = Dimension::auto());
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_min_width_auto(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.min_size.width = Dimension::auto());
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_min_height_auto(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.min_size.height = Dimension::auto());
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_max_width_auto(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.max_size.width = Dimension::auto());
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_max_height_auto(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.max_size.height = Dimension::auto());
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_padding(tree: *mut LayoutTree, node: u64, edge: u8, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| set_edge(&mut s.padding, edge, LengthPercentage::length(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_margin(tree: *mut LayoutTree, node: u64, edge: u8, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| set_edge(&mut s.margin, edge, LengthPercentageAuto::length(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_border(tree: *mut LayoutTree, node: u64, edge: u8, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| set_edge(&mut s.border, edge, LengthPercentage::length(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_padding_percent(tree: *mut LayoutTree, node: u64, edge: u8, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| set_edge(&mut s.padding, edge, LengthPercentage::percent(val / 100.0)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_margin_percent(tree: *mut LayoutTree, node: u64, edge: u8, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| set_edge(&mut s.margin, edge, LengthPercentageAuto::percent(val / 100.0)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_gap_row(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.gap.height = LengthPercentage::length(val));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_gap_column(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.gap.width = LengthPercentage::length(val));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_gap_all(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| {
+            s.gap.width = LengthPercentage::length(val);
+            s.gap.height = LengthPercentage::length(val);
+        });
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_gap_row_percent(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.gap.height = LengthPercentage::percent(val / 100.0));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_gap_column_percent(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.gap.width = LengthPercentage::percent(val / 100.0));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_gap_all_percent(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| {
+            s.gap.width = LengthPercentage::percent(val / 100.0);
+            s.gap.height = LengthPercentage::percent(val / 100.0);
+        });
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_position_type(tree: *mut LayoutTree, node: u64, val: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| {
+            s.position = match checked_code("position", val, 2) {
+                1 => Position::Absolute,
+                _ => Position::Relative,
+            };
+        });
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_position(tree: *mut LayoutTree, node: u64, edge: u8, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| set_edge(&mut s.inset, edge, LengthPercentageAuto::length(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_position_percent(tree: *mut LayoutTree, node: u64, edge: u8, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| set_edge(&mut s.inset, edge, LengthPercentageAuto::percent(val / 100.0)));
+    })
+}
+
+/// Insets in logical terms: `start`/`end` map to left/right under LTR and are
+/// swapped under RTL. NaN leaves an edge auto.
+#[no_mangle]
+pub extern "C" fn layout_set_inset_logical(
+    tree: *mut LayoutTree, node: u64, start: f32, top: f32, end: f32, bottom: f32,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        tree.logical_insets.insert(id, LogicalInsets { start, top, end, bottom, rtl: false });
+        if let Some(edges) = tree.logical_edges.get_mut(&id) {
+            edges.inset = [None; 2];
+        }
+        apply_logical_insets(tree, id);
+    })
+}
+
+/// Anchor one inset edge to the tree's safe area: the edge resolves to the safe-area
+/// inset on that side plus `offset`, and follows later `layout_tree_set_safe_area`
+/// calls. Meant for absolutely positioned floating buttons and toasts. Edge codes
+/// as `layout_set_position`; a NaN offset stops anchoring the edge and leaves its
+/// current inset in place.
+#[no_mangle]
+pub extern "C" fn layout_set_inset_safe_area(tree: *mut LayoutTree, node: u64, edge: u8, offset: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        let offsets = tree.safe_area_insets.entry(id).or_insert([f32::NAN; 4]);
+        let mut edges = Rect { left: offsets[0], top: offsets[1], right: offsets[2], bottom: offsets[3] };
+        set_edge(&mut edges, edge, offset);
+        *offsets = [edges.left, edges.top, edges.right, edges.bottom];
+        if offsets.iter().all(|o| o.is_nan()) {
+            tree.safe_area_insets.remove(&id);
+            return;
+        }
+        apply_safe_area_insets(tree, id);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_aspect_ratio(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.aspect_ratio = Some(val));
+    })
+}
+
+// ============================================================================
+// Direction
+// ============================================================================
+
+pub const DIRECTION_LTR: u8 = 0;
+pub const DIRECTION_RTL: u8 = 1;
+/// Follow the parent, or the tree's direction at a root.
+pub const DIRECTION_INHERIT: u8 = 2;
+
+/// Set the direction `node` and its subtree resolve logical start/end properties
+/// against, over the tree's direction. Descendants with a direction of their own
+/// keep it. Only logical properties follow it: rows and grid tracks are not
+/// mirrored.
+#[no_mangle]
+pub extern "C" fn layout_set_direction(tree: *mut LayoutTree, node: u64, direction: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        let direction = match checked_code("direction", direction, 3) {
+            DIRECTION_INHERIT => None,
+            direction => Some(direction),
+        };
+        let changed = match direction {
+            Some(direction) => tree.directions.insert(id, direction) != Some(direction),
+            None => tree.directions.remove(&id).is_some(),
+        };
+        if changed {
+            resolve_logical(tree, id);
+        }
+    })
+}
+
+/// The direction set on the node itself (`DIRECTION_INHERIT` if none).
+#[no_mangle]
+pub extern "C" fn layout_get_direction(tree: *const LayoutTree, node: u64) -> u8 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.directions.get(&NodeId::from(node)).copied().unwrap_or(DIRECTION_INHERIT)
+    })
+}
+
+/// The direction the node resolves logical properties against.
+#[no_mangle]
+pub extern "C" fn layout_get_resolved_direction(tree: *const LayoutTree, node: u64) -> u8 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return tree.direction;
+        }
+        direction_of(tree, id)
+    })
+}
+
+/// Padding on the node's start edge: left under LTR, right under RTL.
+#[no_mangle]
+pub extern "C" fn layout_set_padding_start(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        set_logical(tree, node, |e| e.padding[0] = Some(LengthPercentage::length(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_padding_end(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        set_logical(tree, node, |e| e.padding[1] = Some(LengthPercentage::length(val)));
+    })
+}
+
+/// Margin on the node's start edge; NaN for auto.
+#[no_mangle]
+pub extern "C" fn layout_set_margin_start(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        set_logical(tree, node, |e| e.margin[0] = Some(lpa_or_auto(val)));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_margin_end(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        set_logical(tree, node, |e| e.margin[1] = Some(lpa_or_auto(val)));
+    })
+}
+
+/// Inset of the node's start edge; NaN for auto. Updates the start of
+/// `layout_set_inset_logical` when that was used.
+#[no_mangle]
+pub extern "C" fn layout_set_inset_start(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        set_logical_inset(tree, node, 0, val);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_inset_end(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        set_logical_inset(tree, node, 1, val);
+    })
+}
+
+fn set_logical(tree: &mut TreeState, node: u64, f: impl FnOnce(&mut LogicalEdges)) {
+    let id = NodeId::from(node);
+    if !tree.contains(id) {
+        return;
+    }
+    f(tree.logical_edges.entry(id).or_default());
+    apply_logical_edges(tree, id);
+}
+
+fn set_logical_inset(tree: &mut TreeState, node: u64, side: usize, val: f32) {
+    let id = NodeId::from(node);
+    if let Some(insets) = tree.logical_insets.get_mut(&id) {
+        if side == 0 { insets.start = val } else { insets.end = val }
+        apply_logical_insets(tree, id);
+        return;
+    }
+    set_logical(tree, node, |e| e.inset[side] = Some(lpa_or_auto(val)));
+}
+
+/// The direction of the node's nearest ancestor-or-self with one, else the tree's.
+fn direction_of(tree: &TreeState, mut id: NodeId) -> u8 {
+    if tree.directions.is_empty() {
+        return tree.direction;
+    }
+    loop {
+        if let Some(&direction) = tree.directions.get(&id) {
+            return direction;
+        }
+        match tree.tree.parent(id) {
+            Some(parent) => id = parent,
+            None => return tree.direction,
+        }
+    }
+}
+
+/// Re-resolve the logical properties under `root` whose direction changed, such
+/// as after a subtree moved under a parent with another direction.
+fn resolve_logical(tree: &mut TreeState, root: NodeId) {
+    if tree.logical_insets.is_empty() && tree.logical_edges.is_empty() {
+        return;
+    }
+    let stale: Vec<NodeId> = tree.logical_insets.iter().map(|(&id, l)| (id, l.rtl))
+        .chain(tree.logical_edges.iter().map(|(&id, e)| (id, e.rtl.unwrap_or(false))))
+        .filter(|&(id, rtl)| is_descendant_or_self(&tree.tree, id, root) && rtl != (direction_of(tree, id) == DIRECTION_RTL))
+        .map(|(id, _)| id)
+        .collect();
+    for id in stale {
+        apply_logical(tree, id);
+    }
+}
+
+fn apply_logical(tree: &mut TreeState, id: NodeId) {
+    apply_logical_insets(tree, id);
+    apply_logical_edges(tree, id);
+}
+
+fn apply_logical_edges(tree: &mut TreeState, id: NodeId) {
+    let rtl = direction_of(tree, id) == DIRECTION_RTL;
+    let Some(edges) = tree.logical_edges.get_mut(&id) else { return };
+    let flipped = edges.rtl.is_some_and(|was| was != rtl);
+    edges.rtl = Some(rtl);
+    let edges = *edges;
+    mutate_style(tree, id.into(), |s| {
+        set_start_end(&mut s.padding, edges.padding, rtl, flipped.then_some(LengthPercentage::length(0.0)));
+        set_start_end(&mut s.margin, edges.margin, rtl, flipped.then_some(LengthPercentageAuto::length(0.0)));
+        set_start_end(&mut s.inset, edges.inset, rtl, flipped.then_some(LengthPercentageAuto::auto()));
+    });
+}
+
+/// Write start and end to their physical sides. After a flip, `reset` first
+/// clears the sides they were on.
+fn set_start_end<T: Copy>(rect: &mut Rect<T>, [start, end]: [Option<T>; 2], rtl: bool, reset: Option<T>) {
+    let (left, right) = if rtl { (end, start) } else { (start, end) };
+    if let Some(reset) = reset {
+        if right.is_some() {
+            rect.left = reset;
+        }
+        if left.is_some() {
+            rect.right = reset;
+        }
+    }
+    if let Some(left) = left {
+        rect.left = left;
+    }
+    if let Some(right) = right {
+        rect.right = right;
+    }
+}
+
+// ============================================================================
+// CSS Grid — template + placement
+// ============================================================================
+
+#[no_mangle]
+pub extern "C" fn layout_set_grid_template_columns(
+    tree: *mut LayoutTree, node: u64, vals: *const f32, count: usize,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(vals) = in_buffer(vals, count) else { return };
+        let tracks = parse_track_list(vals);
+        mutate_style(tree, node, |s| s.grid_template_columns = tracks.clone());
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_grid_template_rows(
+    tree: *mut LayoutTree, node: u64, vals: *const f32, count: usize,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(vals) = in_buffer(vals, count) else { return };
+        let tracks = parse_track_list(vals);
+        mutate_style(tree, node, |s| s.grid_template_rows = tracks.clone());
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_grid_placement(
+    tree: *mut LayoutTree, node: u64,
+    row: i16, col: i16, span_rows: u16, span_cols: u16,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| {
+            if row != 0 {
+                s.grid_row = Line {
+                    start: GridPlacement::from_line_index(row),
+                    end: GridPlacement::from_span(span_rows.max(1)),
+                };
+            }
+            if col != 0 {
+                s.grid_column = Line {
+                    start: GridPlacement::from_line_index(col),
+                    end: GridPlacement::from_span(span_cols.max(1)),
+                };
+            }
+        });
+    })
+}
+
+/// One entry of a grid template. kind: 0=single track, 1=repeat(`repeat` times),
+/// 2=repeat(auto-fill), 3=repeat(auto-fit), 4=one more track of the repeat before
+/// it: a repeat entry and the kind 4 entries after it make one repeat of their
+/// tracks, so `repeat(2, 100px 1fr)` is a kind 1 entry followed by a kind 4 one.
+/// `min`/`max` units extend `LayoutValue`'s: 3=fr, 4=min-content, 5=max-content,
+/// 6=fit-content(px), 7=fit-content(percent). Distinct min and max make a
+/// `minmax(min, max)` track. fr and fit-content are only valid as a maximum; as a
+/// minimum they read as auto, which is what `1fr` and `fit-content(x)` mean in CSS.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct GridTrackDesc {
+    pub kind: u8,
+    pub repeat: u16,
+    pub min: LayoutValue,
+    pub max: LayoutValue,
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_grid_template_columns_desc(
+    tree: *mut LayoutTree, node: u64, tracks: *const GridTrackDesc, count: usize,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(tracks) = in_buffer(tracks, count) else { return };
+        let tracks = parse_track_descs(tracks);
+        mutate_style(tree, node, |s| s.grid_template_columns = tracks);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_grid_template_rows_desc(
+    tree: *mut LayoutTree, node: u64, tracks: *const GridTrackDesc, count: usize,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(tracks) = in_buffer(tracks, count) else { return };
+        let tracks = parse_track_descs(tracks);
+        mutate_style(tree, node, |s| s.grid_template_rows = tracks);
+    })
+}
+
+/// Sizes of implicitly created rows, in the scalar encoding of the templates. With
+/// several tracks the pattern repeats.
+#[no_mangle]
+pub extern "C" fn layout_set_grid_auto_rows(tree: *mut LayoutTree, node: u64, vals: *const f32, count: usize) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(vals) = in_buffer(vals, count) else { return };
+        let tracks = parse_tracks(vals);
+        mutate_style(tree, node, |s| s.grid_auto_rows = tracks);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_grid_auto_columns(tree: *mut LayoutTree, node: u64, vals: *const f32, count: usize) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(vals) = in_buffer(vals, count) else { return };
+        let tracks = parse_tracks(vals);
+        mutate_style(tree, node, |s| s.grid_auto_columns = tracks);
+    })
+}
+
+/// Descriptor variants for implicit tracks such as `minmax(100px, auto)`; `kind`
+/// and `repeat` are ignored. A null array with a non-zero count changes nothing.
+#[no_mangle]
+pub extern "C" fn layout_set_grid_auto_rows_desc(
+    tree: *mut LayoutTree, node: u64, tracks: *const GridTrackDesc, count: usize,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(tracks) = in_buffer(tracks, count) else { return };
+        let tracks = tracks.iter().map(|d| track_sizing(d.min, d.max)).collect();
+        mutate_style(tree, node, |s| s.grid_auto_rows = tracks);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_grid_auto_columns_desc(
+    tree: *mut LayoutTree, node: u64, tracks: *const GridTrackDesc, count: usize,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(tracks) = in_buffer(tracks, count) else { return };
+        let tracks = tracks.iter().map(|d| track_sizing(d.min, d.max)).collect();
+        mutate_style(tree, node, |s| s.grid_auto_columns = tracks);
+    })
+}
+
+/// 0=row, 1=column, 2=row dense, 3=column dense.
+#[no_mangle]
+pub extern "C" fn layout_set_grid_auto_flow(tree: *mut LayoutTree, node: u64, flow: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| {
+            s.grid_auto_flow = match checked_code("grid auto flow", flow, 4) {
+                1 => GridAutoFlow::Column,
+                2 => GridAutoFlow::RowDense,
+                3 => GridAutoFlow::ColumnDense,
+                _ => GridAutoFlow::Row,
+            };
+        });
+    })
+}
+
+/// Named areas, one UTF-8 string per row (`"header header"`, `"nav main"`); `.`
+/// marks an unnamed cell. Returns false, leaving the style alone, if `rows` is
+/// null with a non-zero count, a row is not valid UTF-8, rows differ in length,
+/// or a name does not form a rectangle.
+#[no_mangle]
+pub extern "C" fn layout_set_grid_template_areas(
+    tree: *mut LayoutTree, node: u64, rows: *const *const c_char, count: usize,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(rows) = in_buffer(rows, count) else { return false };
+        let rows: Option<Vec<&str>> = rows.iter().map(|&row| c_str(row)).collect();
+        let Some(areas) = rows.and_then(|rows| css::grid_areas(&rows)) else { return false };
+        mutate_style(tree, node, |s| s.grid_template_areas = areas);
+        true
+    })
+}
+
+/// Place the item into the named area of its grid container, on both axes
+/// (`grid-area: name`). An empty or null name returns it to auto-placement.
+#[no_mangle]
+pub extern "C" fn layout_set_grid_area_name(tree: *mut LayoutTree, node: u64, name: *const c_char) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let line = match c_str(name) {
+            Some(name) if !name.is_empty() => {
+                let named = GridPlacement::NamedLine(name.to_string(), 1);
+                Line { start: named.clone(), end: named }
+            }
+            _ => Line { start: GridPlacement::Auto, end: GridPlacement::Auto },
+        };
+        mutate_style(tree, node, |s| {
+            s.grid_row = line.clone();
+            s.grid_column = line;
+        });
+    })
+}
+
+/// One end of a grid placement. kind: 0=auto, 1=line (negative counts from the
+/// end), 2=span.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutGridLine {
+    pub kind: u8,
+    pub value: i16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutGridPlacement {
+    pub row_start: LayoutGridLine,
+    pub row_end: LayoutGridLine,
+    pub column_start: LayoutGridLine,
+    pub column_end: LayoutGridLine,
+}
+
+/// Set both axes of a grid item's placement at once; every end is explicit, so
+/// `span 2 / 5` and auto starts can be expressed.
+#[no_mangle]
+pub extern "C" fn layout_set_grid_lines(tree: *mut LayoutTree, node: u64, placement: &LayoutGridPlacement) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let p = *placement;
+        mutate_style(tree, node, |s| {
+            s.grid_row = Line { start: map_grid_line(p.row_start), end: map_grid_line(p.row_end) };
+            s.grid_column = Line { start: map_grid_line(p.column_start), end: map_grid_line(p.column_end) };
+        });
+    })
+}
+
+/// One axis of a grid placement; kinds as in `LayoutGridLine` (0=auto, 1=line,
+/// negative counting from the end, 2=span). A line value of 0 reads as auto.
+#[no_mangle]
+pub extern "C" fn layout_set_grid_row(
+    tree: *mut LayoutTree, node: u64, start_kind: u8, start: i16, end_kind: u8, end: i16,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let line = grid_axis(start_kind, start, end_kind, end);
+        mutate_style(tree, node, |s| s.grid_row = line);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_grid_column(
+    tree: *mut LayoutTree, node: u64, start_kind: u8, start: i16, end_kind: u8, end: i16,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let line = grid_axis(start_kind, start, end_kind, end);
+        mutate_style(tree, node, |s| s.grid_column = line);
+    })
+}
+
+/// Return the node to auto-placement on both axes, e.g. when a virtualized grid
+/// recycles it.
+#[no_mangle]
+pub extern "C" fn layout_clear_grid_placement(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| {
+            s.grid_row = Line { start: GridPlacement::Auto, end: GridPlacement::Auto };
+            s.grid_column = Line { start: GridPlacement::Auto, end: GridPlacement::Auto };
+        });
+    })
+}
+
+/// Where the engine placed a grid item: the lines it starts and ends at, numbered
+/// as in `layout_set_grid_row` (1 = first line of the explicit grid). Implicit
+/// tracks the placement added before the explicit grid have lines numbered 0 and
+/// below.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutGridArea {
+    pub row_start: i16,
+    pub row_end: i16,
+    pub column_start: i16,
+    pub column_end: i16,
+}
+
+/// The resolved placement of a grid item as of the last compute, auto-placed
+/// items included. Returns false if the node's parent is not a grid or the node
+/// is not placed in it (display none, absolutely positioned).
+#[no_mangle]
+pub extern "C" fn layout_get_resolved_grid_position(tree: *const LayoutTree, node: u64, out: &mut LayoutGridArea) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return false;
+        }
+        let Some(parent) = tree.tree.parent(id) else { return false };
+        if tree.tree.style(parent).unwrap().display != Display::Grid {
+            return false;
+        }
+        let DetailedLayoutInfo::Grid(info) = tree.detailed_layout_info(parent) else { return false };
+        // Grid items are the in-flow children, in order
+        let index = tree.tree.children(parent).unwrap().into_iter()
+            .filter(|&child| {
+                let s = tree.tree.style(child).unwrap();
+                s.display != Display::None && s.position != Position::Absolute
+            })
+            .position(|child| child == id);
+        let Some(item) = index.and_then(|i| info.items.get(i)) else { return false };
+        let rows = info.rows.negative_implicit_tracks as i16;
+        let columns = info.columns.negative_implicit_tracks as i16;
+        *out = LayoutGridArea {
+            row_start: item.row_start as i16 - rows,
+            row_end: item.row_end as i16 - rows,
+            column_start: item.column_start as i16 - columns,
+            column_end: item.column_end as i16 - columns,
+        };
+        true
+    })
+}
+
+fn grid_axis(start_kind: u8, start: i16, end_kind: u8, end: i16) -> Line<GridPlacement> {
+    Line {
+        start: map_grid_line(LayoutGridLine { kind: start_kind, value: start }),
+        end: map_grid_line(LayoutGridLine { kind: end_kind, value: end }),
+    }
+}
+
+// ============================================================================
+// Property reset
+// ============================================================================
+
+/// Restore `Style::default()` on a node in one call, for recycling pooled nodes.
+/// Logical properties and safe-area insets are dropped with the rest of the style;
+/// node options such as the direction, overflow min-size or margin collapse mode
+/// are kept.
+#[no_mangle]
+pub extern "C" fn layout_reset_style(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        tree.logical_insets.remove(&id);
+        tree.logical_edges.remove(&id);
+        tree.safe_area_insets.remove(&id);
+        mutate_style(tree, node, |s| *s = Style::default());
+    })
+}
+
+/// Put one style property back to its default. `name` is a CSS property name as in
+/// `css::PROPERTIES` ("grid-row", "margin-left", ...). Returns false for an unknown
+/// node or property.
+#[no_mangle]
+pub extern "C" fn layout_reset_style_property(tree: *mut LayoutTree, node: u64, name: *const c_char) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        let Some(name) = c_str(name) else { return false };
+        if !tree.contains(id) || css::get_property(&Style::default(), name).is_none() {
+            return false;
+        }
+        mutate_style(tree, node, |s| {
+            let _ = css::reset_property(s, name);
+        });
+        true
+    })
+}
+
+// ============================================================================
+// Style text
+// ============================================================================
+
+/// Apply a CSS declaration block such as `"display:flex; padding:8px 12px; gap:4px"`
+/// on top of the node's current style; see `css::apply_declarations` for what it
+/// accepts. All or nothing: if any declaration fails, the style is left unchanged,
+/// false is returned and the last error names the declaration.
+#[no_mangle]
+pub extern "C" fn layout_set_style_text(tree: *mut LayoutTree, node: u64, text: *const c_char) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return false;
+        }
+        let Some(text) = c_str(text) else { return false };
+        let mut style = tree.host_style(id);
+        if let Err(e) = css::apply_declarations(&mut style, text) {
+            set_last_error(&e);
+            return false;
+        }
+        mutate_style(tree, node, |s| *s = style);
+        true
+    })
+}
+
+// ============================================================================
+// Overflow
+// ============================================================================
+
+#[no_mangle]
+pub extern "C" fn layout_set_overflow(tree: *mut LayoutTree, node: u64, overflow: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| {
+            let v = map_overflow(overflow);
+            s.overflow.x = v;
+            s.overflow.y = v;
+        });
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_overflow_x(tree: *mut LayoutTree, node: u64, overflow: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.overflow.x = map_overflow(overflow));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_set_overflow_y(tree: *mut LayoutTree, node: u64, overflow: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.overflow.y = map_overflow(overflow));
+    })
+}
+
+/// How a hidden/scroll axis affects the node's automatic minimum size as a flex or
+/// grid item. 0 = collapse: as in CSS, the node may shrink to zero on that axis
+/// (set an explicit min size for a floor). 1 = content: the node keeps its
+/// content-based minimum, like a visible axis. In content mode such axes are laid
+/// out as clip: content is still clipped and the host may scroll it, but the engine
+/// reserves no scrollbar gutter. The style getters keep reporting the set overflow.
+#[no_mangle]
+pub extern "C" fn layout_set_overflow_min_size(tree: *mut LayoutTree, node: u64, mode: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        let style = tree.host_style(id);
+        if mode == 1 {
+            tree.overflow_min_content.insert(id, style.overflow);
+        } else if tree.overflow_min_content.remove(&id).is_none() {
+            return;
+        }
+        tree.store_style(id, style);
+        tree.style_generation += 1;
+    })
+}
+
+/// Gutter reserved for the scrollbar of each `Overflow::Scroll` axis.
+#[no_mangle]
+pub extern "C" fn layout_set_scrollbar_width(tree: *mut LayoutTree, node: u64, val: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        mutate_style(tree, node, |s| s.scrollbar_width = val);
+    })
+}
+
+// ============================================================================
+// Gap overrides
+// ============================================================================
+
+/// Use `px` instead of the container's gap between `child` and its next in-flow
+/// sibling, e.g. for wider spacing between menu sections. Applies inside flex
+/// containers along the main axis and scales with the tree's spacing scale. The
+/// difference is laid out as extra margin after the child, so at the end of a
+/// wrapped line it counts toward line breaking, and an auto or percentage margin
+/// on that side leaves the override without effect. A negative `px` removes it.
+#[no_mangle]
+pub extern "C" fn layout_set_gap_after(tree: *mut LayoutTree, child: u64, px: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(child);
+        if !tree.contains(id) {
+            return;
+        }
+        if px < 0.0 || px.is_nan() {
+            if let Some(g) = tree.gap_after.get(&id).copied() {
+                let style = tree.host_style(id);
+                tree.gap_after.remove(&id);
+                if g.margin != [0.0; 4] {
+                    tree.store_style(id, style);
+                }
+            }
+            return;
+        }
+        tree.gap_after.entry(id).or_insert(GapAfter { gap: px, margin: [0.0; 4] }).gap = px;
+        // Resolved against the parent on the next compute
+        let _ = tree.tree.mark_dirty(id);
+    })
+}
+
+/// Bring the override margins under `root` in line with the current tree.
+fn apply_gap_overrides(tree: &mut TreeState, root: NodeId) {
+    let pending: Vec<(NodeId, [f32; 4])> = tree.gap_after.iter()
+        .filter(|(&id, _)| is_descendant_or_self(&tree.tree, id, root))
+        .filter_map(|(&id, g)| {
+            let margin = gap_override_margin(tree, id, g.gap);
+            (margin != g.margin).then_some((id, margin))
+        })
+        .collect();
+    for (id, margin) in pending {
+        let style = tree.host_style(id);
+        tree.gap_after.get_mut(&id).unwrap().margin = margin;
+        tree.store_style(id, style);
+    }
+}
+
+fn gap_override_margin(tree: &TreeState, id: NodeId, gap: f32) -> [f32; 4] {
+    let Some(parent) = tree.tree.parent(id) else { return [0.0; 4] };
+    let style = tree.tree.style(parent).unwrap();
+    if style.display != Display::Flex {
+        return [0.0; 4];
+    }
+    // No gap follows the last in-flow child
+    let in_flow = |child: &NodeId| {
+        let s = tree.tree.style(*child).unwrap();
+        s.display != Display::None && s.position != Position::Absolute
+    };
+    let children = tree.tree.children(parent).unwrap();
+    let index = children.iter().position(|&c| c == id).unwrap();
+    if !children[index + 1..].iter().any(in_flow) {
+        return [0.0; 4];
+    }
+    let row = matches!(style.flex_direction, FlexDirection::Row | FlexDirection::RowReverse);
+    let container_gap = if row { style.gap.width } else { style.gap.height }.into_raw();
+    let container_gap = if container_gap.tag() == CompactLength::LENGTH_TAG { container_gap.value() } else { 0.0 };
+    let delta = gap * tree.spacing_scale - container_gap;
+    let mut margin = [0.0; 4];
+    let side = match style.flex_direction {
+        FlexDirection::Row => 2,
+        FlexDirection::RowReverse => 0,
+        FlexDirection::Column => 3,
+        FlexDirection::ColumnReverse => 1,
+    };
+    margin[side] = delta;
+    margin
+}
+
+// ============================================================================
+// Block margin collapsing
+// ============================================================================
+
+/// How the vertical margins of a block container's children combine. 0 = collapse:
+/// adjacent margins collapse as in CSS. 1 = accumulate: margins add up as in most
+/// UI toolkits, and children's margins stay inside the container rather than
+/// collapsing through its edges. Only `Display::Block` containers are affected; the
+/// mode is kept across display changes.
+#[no_mangle]
+pub extern "C" fn layout_set_margin_collapse(tree: *mut LayoutTree, node: u64, mode: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        let style = tree.host_style(id);
+        if mode == 1 {
+            tree.accumulate_margins.insert(id, style.clone());
+        } else if tree.accumulate_margins.remove(&id).is_none() {
+            return;
+        }
+        tree.store_style(id, style);
+        tree.style_generation += 1;
+    })
+}
+
+/// Lay an accumulating block out as a one-column grid: grid items stack in source
+/// order, fill the width, never shrink and never collapse margins.
+fn stack_without_collapsing(style: &mut Style) {
+    style.display = Display::Grid;
+    style.grid_template_columns = vec![GridTemplateComponent::Single(MinMax {
+        min: MinTrackSizingFunction::length(0.0),
+        max: MaxTrackSizingFunction::fr(1.0),
+    })];
+    style.grid_template_rows = Vec::new();
+    style.grid_template_areas = Vec::new();
+    style.grid_auto_rows = Vec::new();
+    style.grid_auto_flow = GridAutoFlow::Row;
+    // Rows keep their content height instead of stretching into spare space
+    style.align_content = Some(AlignContent::Start);
+    style.gap = Size { width: LengthPercentage::length(0.0), height: LengthPercentage::length(0.0) };
+}
+
+// ============================================================================
+// Height from width
+// ============================================================================
+
+/// Size a leaf's content height from its own content width: height =
+/// `percent`% of width + `offset` px (a 16:9 media card with a 40px caption is
+/// 56.25 and 40). The width comes from the usual sizing (style, stretch, flex);
+/// a definite style height still wins. Nodes with children ignore it.
+#[no_mangle]
+pub extern "C" fn layout_set_height_from_width(tree: *mut LayoutTree, node: u64, percent: f32, offset: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        set_height_from_width(tree, node, Some([percent / 100.0, offset]));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_clear_height_from_width(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        set_height_from_width(tree, node, None);
+    })
+}
+
+fn set_height_from_width(tree: &mut TreeState, node: u64, value: Option<[f32; 2]>) {
+    let id = NodeId::from(node);
+    if !tree.contains(id) {
+        return;
+    }
+    let mut context = match tree.tree.get_node_context(id) {
+        Some(context) if context.height_from_width == value => return,
+        Some(context) => context.clone(),
+        None if value.is_none() => return,
+        None => NodeContext::default(),
+    };
+    context.height_from_width = value;
+    // Replacing the context marks the node dirty
+    let _ = tree.tree.set_node_context(id, Some(context));
+}
+
+/// Taffy's measure function for leaves; only text and height-from-width leaves
+/// have a size of their own.
+fn measure_leaf(
+    known: Size<Option<f32>>, _available: Size<AvailableSpace>, _id: NodeId, context: Option<&mut NodeContext>,
+    _style: &Style,
+) -> Size<f32> {
+    #[cfg(feature = "text")]
+    if let Some(text) = context.as_ref().and_then(|c| c.text.as_ref()) {
+        return text.measure(known, _available);
+    }
+    if let Some(natural) = context.as_ref().and_then(|c| c.intrinsic_size) {
+        return measure_intrinsic(natural, known, _available);
+    }
+    let Some([ratio, offset]) = context.and_then(|c| c.height_from_width) else { return Size::ZERO };
+    let width = known.width.unwrap_or(0.0);
+    Size { width, height: known.height.unwrap_or((width * ratio + offset).max(0.0)) }
+}
+
+// ============================================================================
+// Intrinsic size
+// ============================================================================
+
+/// Size a leaf like an image of `natural_w` x `natural_h` px: a known width or
+/// height gives the other through the aspect ratio, otherwise it takes its natural
+/// size, narrowed to a definite available width. Like an image with `max-width:
+/// 100%` it can shrink to nothing, so flex items scale down instead of
+/// overflowing. Sizes that are not positive are ignored; nodes with children
+/// ignore it.
+#[no_mangle]
+pub extern "C" fn layout_set_intrinsic_size(tree: *mut LayoutTree, node: u64, natural_w: f32, natural_h: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        // Written to reject NaN as well
+        if !(natural_w > 0.0 && natural_h > 0.0 && natural_w.is_finite() && natural_h.is_finite()) {
+            return;
+        }
+        set_intrinsic_size(tree, node, Some([natural_w, natural_h]));
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_clear_intrinsic_size(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        set_intrinsic_size(tree, node, None);
+    })
+}
+
+fn set_intrinsic_size(tree: &mut TreeState, node: u64, value: Option<[f32; 2]>) {
+    let id = NodeId::from(node);
+    if !tree.contains(id) {
+        return;
+    }
+    let mut context = match tree.tree.get_node_context(id) {
+        Some(context) if context.intrinsic_size == value => return,
+        Some(context) => context.clone(),
+        None if value.is_none() => return,
+        None => NodeContext::default(),
+    };
+    context.intrinsic_size = value;
+    // Replacing the context marks the node dirty
+    let _ = tree.tree.set_node_context(id, Some(context));
+}
+
+fn measure_intrinsic([natural_w, natural_h]: [f32; 2], known: Size<Option<f32>>, available: Size<AvailableSpace>) -> Size<f32> {
+    let ratio = natural_h / natural_w;
+    match (known.width, known.height) {
+        (Some(width), Some(height)) => Size { width, height },
+        (Some(width), None) => Size { width, height: width * ratio },
+        (None, Some(height)) => Size { width: height / ratio, height },
+        (None, None) => {
+            let width = match available.width {
+                AvailableSpace::Definite(space) => natural_w.min(space.max(0.0)),
+                AvailableSpace::MinContent => 0.0,
+                AvailableSpace::MaxContent => natural_w,
+            };
+            Size { width, height: width * ratio }
+        }
+    }
+}
+
+// ============================================================================
+// Balanced wrapping
+// ============================================================================
+
+/// Balance the lines of a wrapping row flex container, like `text-wrap: balance`
+/// for items: after layout the container is narrowed, with inline padding, to the
+/// smallest width that still needs the same number of lines, so the last line is
+/// not left with a lone item. The padding goes on the side that keeps the
+/// container's justification, split for centered and spaced content. The container
+/// keeps its own size, and getters report the host's padding.
+///
+/// Balancing measures items at their laid-out size, so it suits items that do not
+/// flex-grow. Column containers and containers with percentage inline padding are
+/// left unbalanced.
+#[no_mangle]
+pub extern "C" fn layout_set_flex_wrap_balance(tree: *mut LayoutTree, node: u64, enabled: bool) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) || tree.wrap_balance.contains_key(&id) == enabled {
+            return;
+        }
+        let style = tree.host_style(id);
+        if enabled {
+            tree.wrap_balance.insert(id, [0.0; 2]);
+        } else {
+            tree.wrap_balance.remove(&id);
+        }
+        tree.store_style(id, style);
+        let _ = tree.tree.mark_dirty(id);
+        tree.style_generation += 1;
+    })
+}
+
+/// Re-balance every balanced container under `root` against the current layout.
+/// Returns true if any container's padding changed and layout must run again.
+fn balance_wraps(tree: &mut TreeState, root: NodeId) -> bool {
+    let targets: Vec<(NodeId, [f32; 2])> = tree.wrap_balance.iter()
+        .filter(|(&id, _)| is_descendant_or_self(&tree.tree, id, root))
+        .map(|(&id, &extra)| (id, balanced_padding(tree, id, extra)))
+        .collect();
+    let mut changed = false;
+    for (id, extra) in targets {
+        let current = tree.wrap_balance[&id];
+        if (extra[0] - current[0]).abs() < 0.01 && (extra[1] - current[1]).abs() < 0.01 {
+            continue;
+        }
+        let style = tree.host_style(id);
+        tree.wrap_balance.insert(id, extra);
+        tree.store_style(id, style);
+        changed = true;
+    }
+    changed
+}
+
+/// The left and right padding that balances `id`, which currently carries `extra`.
+fn balanced_padding(tree: &TreeState, id: NodeId, extra: [f32; 2]) -> [f32; 2] {
+    let style = tree.tree.style(id).unwrap();
+    let reverse = style.flex_direction == FlexDirection::RowReverse;
+    let balanceable = style.display == Display::Flex
+        && style.flex_wrap != FlexWrap::NoWrap
+        && (reverse || style.flex_direction == FlexDirection::Row)
+        && [style.padding.left, style.padding.right].iter().all(|p| p.into_raw().tag() != CompactLength::PERCENT_TAG);
+    if !balanceable {
+        return [0.0; 2];
+    }
+
+    let layout = tree.final_layout(id);
+    let full = layout.content_box_width() + extra[0] + extra[1];
+    let gap = style.gap.width.into_raw();
+    let gap = if gap.tag() == CompactLength::PERCENT_TAG { gap.value() * full } else { gap.value() };
+    let items: Vec<f32> = tree.tree.children(id).unwrap().into_iter()
+        .filter(|&child| {
+            let s = tree.tree.style(child).unwrap();
+            s.display != Display::None && s.position != Position::Absolute
+        })
+        .map(|child| {
+            let l = tree.final_layout(child);
+            l.size.width + l.margin.left + l.margin.right
+        })
+        .collect();
+    let lines = |width: f32| {
+        let (mut count, mut used) = (0, 0.0);
+        for &w in &items {
+            if count == 0 || used + gap + w > width {
+                count += 1;
+                used = w;
+            } else {
+                used += gap + w;
+            }
+        }
+        count
+    };
+    let target = lines(full);
+    if target < 2 {
+        return [0.0; 2];
+    }
+
+    // Narrowest width that keeps the line count
+    let (mut lo, mut hi) = (items.iter().copied().fold(0.0, f32::max), full);
+    for _ in 0..24 {
+        let mid = (lo + hi) / 2.0;
+        if lines(mid) == target { hi = mid } else { lo = mid }
+    }
+    // Headroom so rounding in the real layout never pushes an item to a new line
+    let spare = (full - hi - 0.5).max(0.0);
+    let packed_right = match style.justify_content {
+        Some(JustifyContent::Center | JustifyContent::SpaceAround | JustifyContent::SpaceEvenly) => {
+            return [spare / 2.0; 2];
+        }
+        Some(JustifyContent::End) => true,
+        Some(JustifyContent::Start) => false,
+        Some(JustifyContent::FlexEnd) => !reverse,
+        _ => reverse,
+    };
+    if packed_right { [spare, 0.0] } else { [0.0, spare] }
+}
+
+fn add_px(v: LengthPercentage, extra: f32) -> LengthPercentage {
+    let raw = v.into_raw();
+    if extra == 0.0 || raw.tag() != CompactLength::LENGTH_TAG {
+        return v;
+    }
+    LengthPercentage::length(raw.value() + extra)
+}
+
+fn add_px_auto(v: LengthPercentageAuto, extra: f32) -> LengthPercentageAuto {
+    let raw = v.into_raw();
+    if extra == 0.0 || raw.tag() != CompactLength::LENGTH_TAG {
+        return v;
+    }
+    LengthPercentageAuto::length(raw.value() + extra)
+}
+
+// ============================================================================
+// Style getters
+// ============================================================================
+
+/// A length-like style value. unit: 0=auto, 1=px, 2=percent (0-100 like the setters).
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct LayoutValue {
+    pub unit: u8,
+    pub value: f32,
+}
+
+impl LayoutValue {
+    fn from_raw(raw: CompactLength) -> Self {
+        match raw.tag() {
+            CompactLength::LENGTH_TAG => LayoutValue { unit: 1, value: raw.value() },
+            CompactLength::PERCENT_TAG => LayoutValue { unit: 2, value: raw.value() * 100.0 },
+            _ => LayoutValue::default(),
+        }
+    }
+}
+
+/// Snapshot of a node's style. Enum fields use the same u8 codes as the setters
+/// (0 for an unset alignment); edge arrays are left, top, right, bottom.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutStyleDesc {
+    pub display: u8,
+    pub position_type: u8,
+    pub flex_direction: u8,
+    pub flex_wrap: u8,
+    pub align_items: u8,
+    pub align_self: u8,
+    pub align_content: u8,
+    pub justify_content: u8,
+    pub justify_items: u8,
+    pub justify_self: u8,
+    pub overflow_x: u8,
+    pub overflow_y: u8,
+    pub flex_grow: f32,
+    pub flex_shrink: f32,
+    pub flex_basis: LayoutValue,
+    pub width: LayoutValue,
+    pub height: LayoutValue,
+    pub min_width: LayoutValue,
+    pub min_height: LayoutValue,
+    pub max_width: LayoutValue,
+    pub max_height: LayoutValue,
+    /// NaN when unset.
+    pub aspect_ratio: f32,
+    pub padding: [LayoutValue; 4],
+    pub margin: [LayoutValue; 4],
+    pub border: [LayoutValue; 4],
+    pub inset: [LayoutValue; 4],
+    pub gap_row: LayoutValue,
+    pub gap_column: LayoutValue,
+    pub scrollbar_width: f32,
+}
+
+/// Fill `out` with the node's current style. Returns false for an unknown node.
+#[no_mangle]
+pub extern "C" fn layout_get_style(tree: *const LayoutTree, node: u64, out: &mut LayoutStyleDesc) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return false;
+        }
+        *out = style_desc(&tree.host_style(id));
+        true
+    })
+}
+
+fn style_desc(s: &Style) -> LayoutStyleDesc {
+    fn edges<T: Copy>(r: Rect<T>, raw: impl Fn(T) -> CompactLength) -> [LayoutValue; 4] {
+        [r.left, r.top, r.right, r.bottom].map(|v| LayoutValue::from_raw(raw(v)))
+    }
+    let dim = |d: Dimension| LayoutValue::from_raw(d.into_raw());
+    LayoutStyleDesc {
+        display: display_code(s.display),
+        position_type: if s.position == Position::Absolute { 1 } else { 0 },
+        flex_direction: flex_direction_code(s.flex_direction),
+        flex_wrap: flex_wrap_code(s.flex_wrap),
+        align_items: s.align_items.map_or(0, align_items_code),
+        align_self: s.align_self.map_or(0, align_items_code),
+        align_content: s.align_content.map_or(0, justify_content_code),
+        justify_content: s.justify_content.map_or(0, justify_content_code),
+        justify_items: s.justify_items.map_or(0, align_items_code),
+        justify_self: s.justify_self.map_or(0, align_items_code),
+        overflow_x: overflow_code(s.overflow.x),
+        overflow_y: overflow_code(s.overflow.y),
+        flex_grow: s.flex_grow,
+        flex_shrink: s.flex_shrink,
+        flex_basis: dim(s.flex_basis),
+        width: dim(s.size.width),
+        height: dim(s.size.height),
+        min_width: dim(s.min_size.width),
+        min_height: dim(s.min_size.height),
+        max_width: dim(s.max_size.width),
+        max_height: dim(s.max_size.height),
+        aspect_ratio: s.aspect_ratio.unwrap_or(f32::NAN),
+        padding: edges(s.padding, LengthPercentage::into_raw),
+        margin: edges(s.margin, LengthPercentageAuto::into_raw),
+        border: edges(s.border, LengthPercentage::into_raw),
+        inset: edges(s.inset, LengthPercentageAuto::into_raw),
+        gap_row: LayoutValue::from_raw(s.gap.height.into_raw()),
+        gap_column: LayoutValue::from_raw(s.gap.width.into_raw()),
+        scrollbar_width: s.scrollbar_width,
+    }
+}
+
+/// Read `prop_count` numeric properties (`PROPERTY_*` codes) for each of `count`
+/// nodes in one call. `out` receives a row-major `count` x `prop_count` matrix of
+/// host values; unknown nodes, unknown codes and values that are not plain lengths
+/// read as NaN. Nothing is written if an array is null.
+#[no_mangle]
+pub extern "C" fn layout_dump_properties(
+    tree: *const LayoutTree, nodes: *const u64, count: usize, property_ids: *const u8, prop_count: usize, out: *mut f32,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let (Some(nodes), Some(properties), Some(len)) =
+            (in_buffer(nodes, count), in_buffer(property_ids, prop_count), count.checked_mul(prop_count))
+        else {
+            return;
+        };
+        let out = out_buffer(out, len);
+        for (&node, row) in nodes.iter().zip(out.chunks_mut(prop_count.max(1))) {
+            let id = NodeId::from(node);
+            let style = tree.contains(id).then(|| tree.host_style(id));
+            for (&property, cell) in properties.iter().zip(row) {
+                *cell = style.as_ref().map_or(f32::NAN, |s| get_property(s, property));
+            }
+        }
+    })
+}
+
+// ============================================================================
+// Style resolvers (container-query-like adjustments)
+// ============================================================================
+
+/// Called with the content-box size of the node's parent (the available space for
+/// a root). The callback may call any style setter on `tree`/`node`.
+pub type StyleResolverFn =
+    extern "C" fn(user_data: *mut c_void, tree: *mut LayoutTree, node: u64, parent_w: f32, parent_h: f32);
+
+#[derive(Clone)]
+struct StyleResolver {
+    callback: StyleResolverFn,
+    user_data: *mut c_void,
+    /// Parent size the callback last saw; it is only re-invoked when this changes.
+    last: Option<(f32, f32)>,
+}
+
+/// A resolver that keeps flipping styles gets cut off after this many relayouts.
+const MAX_RESOLVER_PASSES: usize = 4;
+
+/// Install (or with a null callback, remove) the style resolver for `node`.
+#[no_mangle]
+pub extern "C" fn layout_set_style_resolver(
+    tree: *mut LayoutTree, node: u64, callback: Option<StyleResolverFn>, user_data: *mut c_void,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        match callback {
+            Some(callback) if tree.contains(id) => {
+                tree.style_resolvers.insert(id, StyleResolver { callback, user_data, last: None });
+            }
+            _ => { tree.style_resolvers.remove(&id); }
+        }
+    })
+}
+
+/// Invoke resolvers under `root` whose parent size changed, relaying out while
+/// they keep changing styles.
+fn run_style_resolvers(tree: &mut TreeState, root: NodeId, avail: Size<AvailableSpace>) {
+    for _ in 0..MAX_RESOLVER_PASSES {
+        let pending: Vec<(NodeId, f32, f32)> = tree.style_resolvers.iter()
+            .filter(|(&id, _)| is_descendant_or_self(&tree.tree, id, root))
+            .filter_map(|(&id, r)| {
+                let (w, h) = resolver_parent_size(tree, id, root, avail);
+                (r.last != Some((w, h))).then_some((id, w, h))
+            })
+            .collect();
+        if pending.is_empty() {
+            return;
+        }
+
+        let generation = tree.style_generation;
+        for (id, w, h) in pending {
+            // An earlier callback in this pass may have removed the node or its resolver
+            let Some(r) = tree.style_resolvers.get_mut(&id) else { continue };
+            r.last = Some((w, h));
+            let (callback, user_data) = (r.callback, r.user_data);
+            callback(user_data, tree.handle, id.into(), w, h);
+        }
+        if tree.style_generation == generation {
+            return;
+        }
+        layout_pass(tree, root, avail);
+    }
+}
+
+fn resolver_parent_size(tree: &TreeState, id: NodeId, root: NodeId, avail: Size<AvailableSpace>) -> (f32, f32) {
+    match tree.tree.parent(id).filter(|_| id != root) {
+        Some(parent) => {
+            let l = tree.final_layout(parent);
+            (l.content_box_width(), l.content_box_height())
+        }
+        None => (avail.width.unwrap_or(0.0), avail.height.unwrap_or(0.0)),
+    }
+}
+
+fn is_descendant_or_self(tree: &TaffyTree<NodeContext>, mut node: NodeId, ancestor: NodeId) -> bool {
+    loop {
+        if node == ancestor {
+            return true;
+        }
+        match tree.parent(node) {
+            Some(p) => node = p,
+            None => return false,
+        }
+    }
+}
+
+// ============================================================================
+// Layout computation + results
+// ============================================================================
+
+#[no_mangle]
+pub extern "C" fn layout_compute(tree: *mut LayoutTree, node: u64, width: f32, height: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        compute_definite(tree, NodeId::from(node), width, height);
+    })
+}
+
+/// `layout_compute` for a tree the caller holds.
+fn compute_definite(tree: &mut TreeState, root: NodeId, width: f32, height: f32) {
+    let avail = Size {
+        width: AvailableSpace::Definite(width),
+        height: AvailableSpace::Definite(height),
+    };
+    if !defer_compute(tree, root, avail, None) {
+        compute(tree, root, avail);
+    }
+}
+
+/// `layout_compute` with a kind per axis: 0 = definite (the given size), 1 =
+/// min-content, 2 = max-content. The size is ignored for the content kinds, so a
+/// popup or tooltip root with auto size shrinks or grows to its content; read the
+/// result and position it, then compute it definite if it has to fit a screen.
+/// Unknown kinds select definite.
+#[no_mangle]
+pub extern "C" fn layout_compute_with_space(
+    tree: *mut LayoutTree, node: u64, width_kind: u8, width: f32, height_kind: u8, height: f32,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let avail = Size { width: map_available_space(width_kind, width), height: map_available_space(height_kind, height) };
+        if !defer_compute(tree, NodeId::from(node), avail, None) {
+            compute(tree, NodeId::from(node), avail);
+        }
+    })
+}
+
+/// Compute, then write into `out` the IDs under `node` whose rect differs from the
+/// one reported by the previous call (every node counts as changed the first time).
+/// Returns the total number changed; only the first `cap` are written. Inside a
+/// frame the compute is deferred and 0 is returned; the changes are reported by
+/// the first call after `layout_end_frame`.
+#[no_mangle]
+pub extern "C" fn layout_compute_with_changes(
+    tree: *mut LayoutTree, node: u64, width: f32, height: f32, out: *mut u64, cap: usize,
+) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let root = NodeId::from(node);
+        let in_frame = tree.frame.is_some();
+        compute_definite(tree, root, width, height);
+        if in_frame || !tree.contains(root) {
+            return 0;
+        }
+        let out = out_buffer(out, cap);
+        let mut count = 0;
+        for id in tree.subtree(root) {
+            let l = tree.final_layout(id);
+            let rect = [l.location.x, l.location.y, l.size.width, l.size.height];
+            if tree.reported_rects.insert(id, rect) != Some(rect) {
+                if let Some(slot) = out.get_mut(count) {
+                    *slot = id.into();
+                }
+                count += 1;
+            }
+        }
+        count
+    })
+}
+
+/// Compute several trees as one step, e.g. the windows of linked split views:
+/// tree `i` computes `roots[i]` against `sizes[2i]` x `sizes[2i + 1]`. Compute
+/// streams are held back until every tree is done, then deliver tree by tree. With
+/// `parallel` each tree computes on a thread of its own, the calling thread or
+/// one of the pool that parallel layout uses, so style resolvers must be safe to
+/// call from any thread. Thread-safe trees are held for the whole group; with
+/// `parallel`, their callbacks must not call back into them. Open frames do not
+/// defer group computes. Returns false without computing anything if an array
+/// or a tree is null, a tree is listed twice, or a root is unknown.
+#[no_mangle]
+pub extern "C" fn layout_compute_group(
+    trees: *const *mut LayoutTree, roots: *const u64, sizes: *const f32, count: usize, parallel: bool,
+) -> bool {
+    ffi_guard(|| {
+        if count == 0 {
+            return true;
+        }
+        let sizes = count.checked_mul(2).and_then(|len| in_buffer(sizes, len));
+        let (Some(trees), Some(roots), Some(sizes)) = (in_buffer(trees, count), in_buffer(roots, count), sizes) else {
+            return false;
+        };
+        let mut seen = HashSet::new();
+        if trees.iter().any(|&tree| tree.is_null() || !seen.insert(tree)) {
+            return false;
+        }
+        // Address order, so overlapping groups on two threads cannot deadlock
+        let mut by_address = trees.to_vec();
+        by_address.sort();
+        let mut locks = Vec::with_capacity(count);
+        let mut states = HashMap::with_capacity(count);
+        for &tree in &by_address {
+            let (lock, state) = sync::exclusive(tree);
+            locks.push(lock);
+            states.insert(tree, std::ptr::from_mut(state));
+        }
+        if trees.iter().zip(roots).any(|(tree, &root)| !unsafe { &*states[tree] }.contains(NodeId::from(root))) {
+            return false;
+        }
+
+        let jobs: Vec<GroupJob> = trees.iter().zip(roots).zip(sizes.chunks_exact(2))
+            .map(|((tree, &root), size)| GroupJob {
+                tree: states[tree],
+                root: NodeId::from(root),
+                avail: Size { width: AvailableSpace::Definite(size[0]), height: AvailableSpace::Definite(size[1]) },
+            })
+            .collect();
+        let mut runs: Vec<(&GroupJob, bool)> = jobs.iter().map(|job| (job, false)).collect();
+        let threads = if parallel { runs.len() } else { 1 };
+        parallel::run_each(&mut runs, threads, |(job, ran)| *ran = job.run());
+        for (job, ran) in runs {
+            if ran {
+                stream_subtrees(unsafe { &*job.tree }, job.root, &[]);
+            }
+        }
+        true
+    })
+}
+
+/// One tree of `layout_compute_group`. The group checked that no two jobs share a
+/// tree, so each job has its tree to itself.
+struct GroupJob {
+    tree: *mut TreeState,
+    root: NodeId,
+    avail: Size<AvailableSpace>,
+}
+
+unsafe impl Sync for GroupJob {}
+
+impl GroupJob {
+    /// Compute with the stream held back; returns whether a layout ran.
+    fn run(&self) -> bool {
+        let tree = unsafe { &mut *self.tree };
+        let stream = tree.stream.take();
+        let ran = compute(tree, self.root, self.avail);
+        tree.stream = stream;
+        ran
+    }
+}
+
+/// When enabled, `layout_compute` returns immediately if the root is clean and
+/// the root and available size match the previous compute.
+#[no_mangle]
+pub extern "C" fn layout_tree_set_skip_clean_compute(tree: *mut LayoutTree, enabled: bool) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        tree.skip_clean_compute = enabled;
+    })
+}
+
+/// Computing a node that has a parent lays it out as if it were a root against the
+/// given size. Its rect inside the parent is replaced until the parent is computed.
+pub const NESTED_COMPUTE_AS_ROOT: u8 = 0;
+/// Computing a node that has a parent relayouts the whole tree it belongs to, from
+/// its topmost ancestor, with the given size as that root's available space.
+pub const NESTED_COMPUTE_IN_PLACE: u8 = 1;
+
+/// Choose what `layout_compute` and the other compute entry points do when handed
+/// a node that has a parent: `NESTED_COMPUTE_AS_ROOT` (the default) or
+/// `NESTED_COMPUTE_IN_PLACE`. Unknown modes fall back to the default.
+#[no_mangle]
+pub extern "C" fn layout_tree_set_nested_compute(tree: *mut LayoutTree, mode: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        tree.nested_compute = checked_code("nested compute", mode, 2);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_tree_get_nested_compute(tree: *const LayoutTree) -> u8 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.nested_compute
+    })
+}
+
+/// Lay out `node`'s subtree as if it were a root against `width` x `height`,
+/// leaving the tree untouched: for drag previews of a widget that stays in place.
+/// Returns a copy of the tree, with the same node IDs, in which `node` is unlinked
+/// from its parent and computed; read the preview from it and free it with
+/// `layout_tree_free`. Null for an unknown node.
+#[no_mangle]
+pub extern "C" fn layout_compute_detached(tree: *const LayoutTree, node: u64, width: f32, height: f32) -> *mut LayoutTree {
+    ffi_guard(|| {
+        let threadsafe = sync::is_threadsafe(tree);
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return std::ptr::null_mut();
+        }
+        let avail = Size { width: AvailableSpace::Definite(width), height: AvailableSpace::Definite(height) };
+        compute_detached(tree, id, avail, threadsafe)
+    })
+}
+
+/// How big `node` would be as a root against `avail_w` x `avail_h`, without
+/// touching the tree: no stored layout, dirty flag or frame changes, and no
+/// compute stream calls. An infinite or NaN axis measures max-content. Writes the
+/// border-box size and returns true, or false for an unknown node. Each call lays
+/// out a copy of the tree, and style resolvers are handed that copy, so styles
+/// they set last only for the measure; prefer a real compute when the layout is
+/// wanted anyway.
+#[no_mangle]
+pub extern "C" fn layout_measure_node(
+    tree: *const LayoutTree, node: u64, avail_w: f32, avail_h: f32, out_w: &mut f32, out_h: &mut f32,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return false;
+        }
+        let space = |v: f32| if v.is_finite() { AvailableSpace::Definite(v) } else { AvailableSpace::MaxContent };
+        let copy = compute_detached(tree, id, Size { width: space(avail_w), height: space(avail_h) }, false);
+        let size = sync::shared(copy).1.final_layout(id).size;
+        layout_tree_free(copy);
+        *out_w = size.width;
+        *out_h = size.height;
+        true
+    })
+}
+
+/// A same-ID copy of `tree`, with a handle of its own, in which `id` is unlinked
+/// from its parent and computed. Callbacks during the compute, style resolvers
+/// among them, are handed the copy, so what they change stays out of `tree`.
+fn compute_detached(tree: &TreeState, id: NodeId, avail: Size<AvailableSpace>, threadsafe: bool) -> *mut LayoutTree {
+    let mut copy = clone::duplicate(tree);
+    if let Some(parent) = copy.tree.parent(id) {
+        let _ = copy.tree.remove_child(parent, id);
+    }
+    copy.journal = None;
+    let handle = LayoutTree::into_raw(copy, threadsafe);
+    let (_lock, copy) = sync::exclusive(handle);
+    compute(copy, id, avail);
+    handle
+}
+
+/// Mark a node (and its ancestors) as needing relayout.
+#[no_mangle]
+pub extern "C" fn layout_mark_dirty(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if tree.contains(id) {
+            let _ = tree.tree.mark_dirty(id);
+        }
+    })
+}
+
+/// False for handles that were never issued or whose node has been removed.
+#[no_mangle]
+pub extern "C" fn layout_node_is_valid(tree: *const LayoutTree, node: u64) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.contains(NodeId::from(node))
+    })
+}
+
+/// Tag a node's content (text, image) with a host-chosen version. Cached sizing
+/// for the node is kept while the version is unchanged; a new version drops only
+/// this node's cache and its ancestors', so editing one label does not relayout
+/// unrelated siblings. Nodes start at version 0.
+#[no_mangle]
+pub extern "C" fn layout_set_content_version(tree: *mut LayoutTree, node: u64, version: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        let previous = tree.content_versions.insert(id, version).unwrap_or(0);
+        if previous != version {
+            let _ = tree.tree.mark_dirty(id);
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_get_content_version(tree: *const LayoutTree, node: u64) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.content_versions.get(&NodeId::from(node)).copied().unwrap_or(0)
+    })
+}
+
+/// The epoch of the latest compute: 0 before the first, then one more for each
+/// compute that runs (skipped clean computes don't count) and each imported
+/// result cache.
+#[no_mangle]
+pub extern "C" fn layout_get_compute_epoch(tree: *const LayoutTree) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.epoch
+    })
+}
+
+/// The epoch in which the node's layout (rect, content size, border, padding or
+/// margin) last changed, so caches keyed by it stay valid while it is unchanged.
+/// 0 for unknown nodes and nodes no compute has reached yet.
+#[no_mangle]
+pub extern "C" fn layout_get_node_epoch(tree: *const LayoutTree, node: u64) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.node_epochs.get(&NodeId::from(node)).map_or(0, |&(epoch, _)| epoch)
+    })
+}
+
+/// Start a new epoch and stamp it on every node under `root` whose layout differs
+/// from the one stamped before.
+fn stamp_epochs(tree: &mut TreeState, root: NodeId) {
+    tree.epoch += 1;
+    for id in tree.subtree(root) {
+        let layout = *tree.final_layout(id);
+        if tree.node_epochs.get(&id).map(|&(_, last)| last) != Some(layout) {
+            tree.node_epochs.insert(id, (tree.epoch, layout));
+        }
+    }
+}
+
+/// True if the node's layout must be recomputed. Unknown nodes report false.
+#[no_mangle]
+pub extern "C" fn layout_is_dirty(tree: *const LayoutTree, node: u64) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        tree.contains(id) && tree.tree.dirty(id).unwrap_or(false)
+    })
+}
+
+/// Returns false if no layout ran (unknown root, or a skipped clean compute).
+fn compute(tree: &mut TreeState, root: NodeId, avail: Size<AvailableSpace>) -> bool {
+    if !tree.contains(root) {
+        return false;
+    }
+    let mut root = root;
+    if tree.nested_compute == NESTED_COMPUTE_IN_PLACE {
+        while let Some(parent) = tree.tree.parent(root) {
+            root = parent;
+        }
+    }
+    if !tree.directions.is_empty() {
+        resolve_logical(tree, root);
+    }
+    apply_gap_overrides(tree, root);
+    if tree.skip_clean_compute
+        && tree.last_compute == Some((root, avail))
+        && !tree.tree.dirty(root).unwrap_or(true)
+    {
+        return false;
+    }
+    let start = Instant::now();
+    begin_profile(tree, root);
+    forget_overrides(tree, root);
+    let streamed = streamed_panels(tree, root);
+    if !streamed.is_empty() {
+        parallel::lay_out_in_turn(tree, root, avail, &streamed, deliver);
+    }
+    layout_pass(tree, root, avail);
+    run_style_resolvers(tree, root, avail);
+    if balance_wraps(tree, root) {
+        layout_pass(tree, root, avail);
+    }
+    distribute_pixels(tree, root);
+    virtual_list::place(tree, root);
+    stamp_epochs(tree, root);
+    tree.last_compute = Some((root, avail));
+    tree.last_compute_us = start.elapsed().as_micros() as u64;
+    if let Some(profiler) = &mut tree.profiler {
+        profiler.entries[0].time_ns = start.elapsed().as_nanos() as u64;
+    }
+    stream_subtrees(tree, root, &streamed);
+    true
+}
+
+/// Aggregated timings of the computes run under one label. Times are microseconds.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutComputeStats {
+    pub count: u64,
+    /// Computes that returned early because nothing was dirty.
+    pub skipped: u64,
+    pub total_us: u64,
+    pub max_us: u64,
+    pub last_us: u64,
+}
+
+/// `layout_compute`, with its duration attributed to `label` (UTF-8). A null
+/// label computes without recording anything.
+#[no_mangle]
+pub extern "C" fn layout_compute_labeled(
+    tree: *mut LayoutTree, node: u64, width: f32, height: f32, label: *const c_char,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(label) = c_str(label) else {
+            compute_definite(tree, NodeId::from(node), width, height);
+            return;
+        };
+        let avail = Size { width: AvailableSpace::Definite(width), height: AvailableSpace::Definite(height) };
+        if !defer_compute(tree, NodeId::from(node), avail, Some(label)) {
+            compute_labeled(tree, NodeId::from(node), avail, label);
+        }
+    })
+}
+
+fn compute_labeled(tree: &mut TreeState, root: NodeId, avail: Size<AvailableSpace>, label: &str) {
+    let start = Instant::now();
+    let ran = compute(tree, root, avail);
+    let elapsed = start.elapsed().as_micros() as u64;
+    let stats = tree.compute_stats.entry(label.to_string()).or_default();
+    if ran {
+        stats.count += 1;
+        stats.total_us += elapsed;
+        stats.max_us = stats.max_us.max(elapsed);
+        stats.last_us = elapsed;
+    } else {
+        stats.skipped += 1;
+    }
+}
+
+/// Stats recorded under `label`. Returns false if nothing was recorded under it.
+#[no_mangle]
+pub extern "C" fn layout_get_compute_stats(
+    tree: *const LayoutTree, label: *const c_char, out: &mut LayoutComputeStats,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let Some(stats) = c_str(label).and_then(|label| tree.compute_stats.get(label)) else { return false };
+        *out = *stats;
+        true
+    })
+}
+
+/// Forget the stats of every label.
+#[no_mangle]
+pub extern "C" fn layout_reset_compute_stats(tree: *mut LayoutTree) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        tree.compute_stats.clear();
+    })
+}
+
+/// Receives each subtree root whose geometry is final. Results may be read from
+/// `tree` for the delivered subtree and, once the root is delivered, for every
+/// node; the tree must not be mutated from inside the callback.
+pub type LayoutStreamFn = extern "C" fn(user_data: *mut c_void, tree: *const LayoutTree, subtree: u64);
+
+/// Have every compute that runs deliver its results piecewise. Children of the
+/// root that are panels (fixed-size flex or grid containers with many nodes, as
+/// for parallel layout) are delivered as soon as each one is laid out, in child
+/// order and while the panels after it are still to do, so a heavy first frame
+/// can start painting early. Then, with the compute done, come the root's own
+/// box and the rest of its children in child order. Pass None to turn streaming
+/// off. Everything is delivered at the end instead while later steps could still
+/// move a panel: style resolvers, balanced wrapping, pixel distribution, virtual
+/// lists, leaf baselines outside the panels, parallel layout and profiling.
+#[no_mangle]
+pub extern "C" fn layout_set_compute_stream(
+    tree: *mut LayoutTree, callback: Option<LayoutStreamFn>, user_data: *mut c_void,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        tree.stream = callback.map(|callback| ComputeStream { callback, user_data });
+    })
+}
+
+#[derive(Clone)]
+struct ComputeStream {
+    callback: LayoutStreamFn,
+    user_data: *mut c_void,
+}
+
+/// Deliver `root` and those of its children that were not `streamed` already.
+fn stream_subtrees(tree: &TreeState, root: NodeId, streamed: &[NodeId]) {
+    deliver(tree, root);
+    for child in tree.tree.children(root).unwrap() {
+        if !streamed.contains(&child) {
+            deliver(tree, child);
+        }
+    }
+}
+
+fn deliver(tree: &TreeState, subtree: NodeId) {
+    if let Some(stream) = &tree.stream {
+        (stream.callback)(stream.user_data, tree.handle, subtree.into());
+    }
+}
+
+/// The children of `root` a streamed compute delivers as it lays them out; none
+/// unless only the panels themselves affect where they end up.
+fn streamed_panels(tree: &TreeState, root: NodeId) -> Vec<NodeId> {
+    let progressive = tree.stream.is_some()
+        && !parallel::runs_parallel(tree)
+        && tree.profiler.is_none()
+        && tree.style_resolvers.is_empty()
+        && tree.wrap_balance.is_empty()
+        && tree.virtual_lists.is_empty()
+        && !(tree.rounding && (tree.scale_factor != 1.0 || tree.pixel_distribution != 0))
+        && !tree.parallel.layouts.contains_key(&root);
+    if !progressive {
+        return Vec::new();
+    }
+    // Baseline-aligned panels take part in their line's baseline, which an empty
+    // stand-in does not have
+    let align_items = tree.tree.style(root).unwrap().align_items;
+    let panels: Vec<NodeId> = tree.tree.children(root).unwrap().into_iter()
+        .filter(|&child| {
+            parallel::is_panel(tree, child)
+                && tree.tree.style(child).unwrap().align_self.or(align_items) != Some(AlignItems::Baseline)
+                && tree.subtree(child).len() >= parallel::MIN_PANEL_NODES
+        })
+        .collect();
+    let settles_outside = tree.baseline_leaves.iter().any(|&leaf| {
+        is_descendant_or_self(&tree.tree, leaf, root)
+            && !panels.iter().any(|&panel| is_descendant_or_self(&tree.tree, leaf, panel))
+    });
+    if settles_outside { Vec::new() } else { panels }
+}
+
+/// The node's parent-relative rect in logical pixels, snapped to device pixels at
+/// the tree's scale factor. Unknown nodes leave the outputs unchanged.
+#[no_mangle]
+pub extern "C" fn layout_get_result(
+    tree: *const LayoutTree, node: u64,
+    out_x: &mut f32, out_y: &mut f32, out_w: &mut f32, out_h: &mut f32,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        if let Some(layout) = tree.layout_of(NodeId::from(node)) {
+            *out_x = layout.location.x;
+            *out_y = layout.location.y;
+            *out_w = layout.size.width;
+            *out_h = layout.size.height;
+        }
+    })
+}
+
+/// The node's parent-relative rect before rounding to whole pixels, as Taffy
+/// computed it; pixel distribution does not apply. Equal to `layout_get_result`
+/// while rounding is off. Unknown nodes leave the outputs unchanged.
+#[no_mangle]
+pub extern "C" fn layout_get_result_unrounded(
+    tree: *const LayoutTree, node: u64,
+    out_x: &mut f32, out_y: &mut f32, out_w: &mut f32, out_h: &mut f32,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if tree.contains(id) {
+            let layout = tree.unrounded_layout(id);
+            *out_x = layout.location.x;
+            *out_y = layout.location.y;
+            *out_w = layout.size.width;
+            *out_h = layout.size.height;
+        }
+    })
+}
+
+/// `layout_get_result` in device pixels. Whole numbers while rounding is on.
+#[no_mangle]
+pub extern "C" fn layout_get_result_physical(
+    tree: *const LayoutTree, node: u64,
+    out_x: &mut f32, out_y: &mut f32, out_w: &mut f32, out_h: &mut f32,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        if let Some(layout) = tree.layout_of(NodeId::from(node)) {
+            *out_x = tree.to_physical(layout.location.x);
+            *out_y = tree.to_physical(layout.location.y);
+            *out_w = tree.to_physical(layout.size.width);
+            *out_h = tree.to_physical(layout.size.height);
+        }
+    })
+}
+
+/// True if the node's parent-relative rect is within `epsilon` of the expected one
+/// on every component. Unknown nodes never match.
+#[no_mangle]
+pub extern "C" fn layout_assert_rect(
+    tree: *const LayoutTree, node: u64, x: f32, y: f32, w: f32, h: f32, epsilon: f32,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        rect_matches(tree, NodeId::from(node), [x, y, w, h], epsilon)
+    })
+}
+
+fn rect_matches(tree: &TreeState, id: NodeId, expected: [f32; 4], epsilon: f32) -> bool {
+    if !tree.contains(id) {
+        return false;
+    }
+    let l = tree.final_layout(id);
+    [l.location.x - expected[0], l.location.y - expected[1], l.size.width - expected[2], l.size.height - expected[3]]
+        .iter().all(|d| d.abs() <= epsilon)
+}
+
+/// Batch `layout_assert_rect`: `expected` holds x, y, w, h for each node. Returns the
+/// index of the first node that does not match, or `count` if all of them do; 0 if
+/// either array is null.
+#[no_mangle]
+pub extern "C" fn layout_assert_rects(
+    tree: *const LayoutTree, nodes: *const u64, expected: *const f32, count: usize, epsilon: f32,
+) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let expected = count.checked_mul(4).and_then(|len| in_buffer(expected, len));
+        let (Some(nodes), Some(expected)) = (in_buffer(nodes, count), expected) else { return 0 };
+        nodes.iter().zip(expected.chunks_exact(4))
+            .position(|(&node, r)| !rect_matches(tree, NodeId::from(node), [r[0], r[1], r[2], r[3]], epsilon))
+            .unwrap_or(count)
+    })
+}
+
+/// Resolved border widths from the last compute.
+#[no_mangle]
+pub extern "C" fn layout_get_border(
+    tree: *const LayoutTree, node: u64,
+    out_left: &mut f32, out_top: &mut f32, out_right: &mut f32, out_bottom: &mut f32,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        if let Some(layout) = tree.layout_of(NodeId::from(node)) {
+            *out_left = layout.border.left;
+            *out_top = layout.border.top;
+            *out_right = layout.border.right;
+            *out_bottom = layout.border.bottom;
+        }
+    })
+}
+
+/// The node's content box: its border box less border, padding and scrollbar
+/// gutters, from the last compute. In its parent's space like `layout_get_result`,
+/// or in its root's space if `absolute`. Returns false for an unknown node.
+#[no_mangle]
+pub extern "C" fn layout_get_content_box(
+    tree: *const LayoutTree, node: u64, absolute: bool,
+    out_x: &mut f32, out_y: &mut f32, out_w: &mut f32, out_h: &mut f32,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        let Some((_, origin)) = root_origin(tree, id) else { return false };
+        let l = tree.final_layout(id);
+        let (x, y) = if absolute { (origin.x, origin.y) } else { (l.location.x, l.location.y) };
+        *out_x = x + l.border.left + l.padding.left;
+        *out_y = y + l.border.top + l.padding.top;
+        *out_w = (l.content_box_width() - l.scrollbar_size.width).max(0.0);
+        *out_h = (l.content_box_height() - l.scrollbar_size.height).max(0.0);
+        true
+    })
+}
+
+/// Extent of the node's content (children and their overflow plus padding), measured
+/// from its border-box origin. Larger than the node itself when content overflows.
+#[no_mangle]
+pub extern "C" fn layout_get_content_size(tree: *const LayoutTree, node: u64, out_w: &mut f32, out_h: &mut f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        if let Some(layout) = tree.layout_of(NodeId::from(node)) {
+            *out_w = layout.content_size.width;
+            *out_h = layout.content_size.height;
+        }
+    })
+}
+
+/// How far a scroll container can scroll on each axis: the content extent beyond
+/// the box once border and scrollbar gutters are accounted for, floored at zero.
+#[no_mangle]
+pub extern "C" fn layout_get_scroll_size(tree: *const LayoutTree, node: u64, out_w: &mut f32, out_h: &mut f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        if let Some(layout) = tree.layout_of(NodeId::from(node)) {
+            *out_w = layout.scroll_width();
+            *out_h = layout.scroll_height();
+        }
+    })
+}
+
+/// One line of a flex container as laid out.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutFlexLine {
+    /// In-flow children on the line; lines take children in order, skipping
+    /// `Display::None` and absolutely positioned ones.
+    pub item_count: u32,
+    pub cross_size: f32,
+    /// Cross-axis position of the line in the container's space, like child locations.
+    pub offset: f32,
+}
+
+/// The lines a flex container broke its items into, in order. Returns the line
+/// count (0 for a non-flex or unknown node); only the first `cap` are written. A
+/// single-line container's line spans its content box, and with
+/// `align-content: stretch` wrapped lines share the spare cross space as in layout.
+#[no_mangle]
+pub extern "C" fn layout_get_flex_lines(tree: *const LayoutTree, node: u64, out: *mut LayoutFlexLine, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return 0;
+        }
+        let lines = flex_lines(tree, id);
+        let out = out_buffer(out, cap);
+        for (slot, line) in out.iter_mut().zip(&lines) {
+            *slot = *line;
+        }
+        lines.len()
+    })
 }
 
-#[no_mangle]
-pub extern "C" fn layout_tree_free(ptr: *mut LayoutTree) {
-    if !ptr.is_null() {
-        unsafe { drop(Box::from_raw(ptr)) };
+fn flex_lines(tree: &TreeState, id: NodeId) -> Vec<LayoutFlexLine> {
+    let style = tree.tree.style(id).unwrap();
+    if style.display != Display::Flex {
+        return Vec::new();
+    }
+    let layout = tree.final_layout(id);
+    let row = matches!(style.flex_direction, FlexDirection::Row | FlexDirection::RowReverse);
+    let reverse = matches!(style.flex_direction, FlexDirection::RowReverse | FlexDirection::ColumnReverse);
+    // (main start, main end, cross start, cross end) of each item's margin box
+    let items: Vec<[f32; 4]> = tree.tree.children(id).unwrap().into_iter()
+        .filter(|&child| {
+            let s = tree.tree.style(child).unwrap();
+            s.display != Display::None && s.position != Position::Absolute
+        })
+        .map(|child| {
+            let l = tree.final_layout(child);
+            let x = [l.location.x - l.margin.left, l.location.x + l.size.width + l.margin.right];
+            let y = [l.location.y - l.margin.top, l.location.y + l.size.height + l.margin.bottom];
+            if row { [x[0], x[1], y[0], y[1]] } else { [y[0], y[1], x[0], x[1]] }
+        })
+        .collect();
+    if items.is_empty() {
+        return Vec::new();
+    }
+
+    let (cross_start, cross_end) = if row {
+        (layout.padding.top + layout.border.top, layout.size.height - layout.padding.bottom - layout.border.bottom)
+    } else {
+        (layout.padding.left + layout.border.left, layout.size.width - layout.padding.right - layout.border.right)
+    };
+    if style.flex_wrap == FlexWrap::NoWrap {
+        return vec![LayoutFlexLine {
+            item_count: items.len() as u32,
+            cross_size: (cross_end - cross_start).max(0.0),
+            offset: cross_start,
+        }];
+    }
+
+    // An item starts a new line when it does not follow the previous one along the main axis
+    const EPSILON: f32 = 0.01;
+    let mut lines: Vec<(u32, f32, f32)> = Vec::new();
+    let mut previous: Option<[f32; 4]> = None;
+    for item in &items {
+        let follows = previous.is_some_and(|p| {
+            if reverse { item[1] <= p[0] + EPSILON } else { item[0] >= p[1] - EPSILON }
+        });
+        match lines.last_mut() {
+            Some(line) if follows => {
+                line.0 += 1;
+                line.1 = line.1.min(item[2]);
+                line.2 = line.2.max(item[3]);
+            }
+            _ => lines.push((1, item[2], item[3])),
+        }
+        previous = Some(*item);
     }
+
+    let mut lines: Vec<LayoutFlexLine> = lines.into_iter()
+        .map(|(item_count, start, end)| LayoutFlexLine { item_count, cross_size: end - start, offset: start })
+        .collect();
+    let stretch = matches!(style.align_content, None | Some(AlignContent::Stretch));
+    if stretch && style.flex_wrap == FlexWrap::Wrap {
+        let gap = if row { style.gap.height } else { style.gap.width }.into_raw();
+        let gap = if gap.tag() == CompactLength::PERCENT_TAG { gap.value() * (cross_end - cross_start) } else { gap.value() };
+        let used: f32 = lines.iter().map(|l| l.cross_size).sum::<f32>() + gap * (lines.len() - 1) as f32;
+        let spare = (cross_end - cross_start - used).max(0.0) / lines.len() as f32;
+        let mut offset = cross_start;
+        for line in &mut lines {
+            line.cross_size += spare;
+            line.offset = offset;
+            offset += line.cross_size + gap;
+        }
+    }
+    lines
 }
 
-// ============================================================================
-// Node creation
-// ============================================================================
+#[no_mangle]
+pub extern "C" fn layout_child_count(tree: *const LayoutTree, node: u64) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return 0;
+        }
+        tree.tree.child_count(id)
+    })
+}
 
+/// Returns 0 for unknown nodes and out-of-range indices.
 #[no_mangle]
-pub extern "C" fn layout_new_node(tree: &mut LayoutTree) -> u64 {
-    tree.tree.new_leaf(Style::default()).unwrap().into()
+pub extern "C" fn layout_get_child(tree: *const LayoutTree, node: u64, index: usize) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return 0;
+        }
+        tree.tree.child_at_index(id, index).map_or(0, u64::from)
+    })
 }
 
+/// Returns 0 for roots and unknown nodes.
 #[no_mangle]
-pub extern "C" fn layout_new_node_with_children(
-    tree: &mut LayoutTree, children: *const u64, count: usize,
-) -> u64 {
-    let kids: Vec<NodeId> = unsafe {
-        std::slice::from_raw_parts(children, count)
-            .iter().map(|&id| NodeId::from(id)).collect()
-    };
-    tree.tree.new_with_children(Style::default(), &kids).unwrap().into()
+pub extern "C" fn layout_get_parent(tree: *const LayoutTree, node: u64) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return 0;
+        }
+        tree.tree.parent(id).map_or(0, u64::from)
+    })
 }
 
+/// Write the node's ancestors into `out`, nearest parent first and root last.
+/// Returns the full ancestor count; only the first `cap` are written.
 #[no_mangle]
-pub extern "C" fn layout_add_child(tree: &mut LayoutTree, parent: u64, child: u64) {
-    let _ = tree.tree.add_child(NodeId::from(parent), NodeId::from(child));
+pub extern "C" fn layout_get_ancestors(tree: *const LayoutTree, node: u64, out: *mut u64, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return 0;
+        }
+        let out = out_buffer(out, cap);
+        let mut count = 0;
+        let mut current = tree.tree.parent(id);
+        while let Some(ancestor) = current {
+            if let Some(slot) = out.get_mut(count) {
+                *slot = ancestor.into();
+            }
+            count += 1;
+            current = tree.tree.parent(ancestor);
+        }
+        count
+    })
 }
 
+/// Deepest node that is `a` or an ancestor of `a` and also `b` or an ancestor of `b`.
+/// Returns 0 if the nodes are in disconnected subtrees.
 #[no_mangle]
-pub extern "C" fn layout_remove_node(tree: &mut LayoutTree, node: u64) {
-    let _ = tree.tree.remove(NodeId::from(node));
+pub extern "C" fn layout_common_ancestor(tree: *const LayoutTree, a: u64, b: u64) -> u64 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let (a, b) = (NodeId::from(a), NodeId::from(b));
+        if !tree.contains(a) || !tree.contains(b) {
+            return 0;
+        }
+        let mut chain = vec![a];
+        while let Some(parent) = tree.tree.parent(*chain.last().unwrap()) {
+            chain.push(parent);
+        }
+        let mut current = Some(b);
+        while let Some(node) = current {
+            if chain.contains(&node) {
+                return node.into();
+            }
+            current = tree.tree.parent(node);
+        }
+        0
+    })
 }
 
 // ============================================================================
-// Style setters
+// Roots
 // ============================================================================
 
+/// Register a parentless node as an independent surface root (a window, palette or
+/// popup), computed by `layout_compute_all`. Registering twice keeps the first
+/// position. A root stops being registered once it is removed, detached or given
+/// a parent. False for an unknown node or one with a parent.
 #[no_mangle]
-pub extern "C" fn layout_set_display(tree: &mut LayoutTree, node: u64, display: u8) {
-    mutate_style(tree, node, |s| {
-        s.display = match display {
-            1 => Display::None,
-            2 => Display::Grid,
-            3 => Display::Block,
-            _ => Display::Flex,
-        };
-    });
+pub extern "C" fn layout_add_root(tree: *mut LayoutTree, node: u64) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) || tree.tree.parent(id).is_some() || tree.detached.contains(&id) {
+            return false;
+        }
+        tree.roots = tree.registered_roots();
+        if !tree.roots.contains(&id) {
+            tree.roots.push(id);
+        }
+        true
+    })
 }
 
+/// Unregister a root; the node itself stays. False if it was not registered.
 #[no_mangle]
-pub extern "C" fn layout_set_flex_direction(tree: &mut LayoutTree, node: u64, dir: u8) {
-    mutate_style(tree, node, |s| {
-        s.flex_direction = match dir {
-            1 => FlexDirection::Row,
-            2 => FlexDirection::ColumnReverse,
-            3 => FlexDirection::RowReverse,
-            _ => FlexDirection::Column,
-        };
-    });
+pub extern "C" fn layout_remove_root(tree: *mut LayoutTree, node: u64) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        let registered = tree.registered_roots().contains(&id);
+        tree.roots.retain(|&root| root != id);
+        registered
+    })
 }
 
+/// The registered roots in registration order. Returns how many there are; only
+/// the first `cap` are written.
 #[no_mangle]
-pub extern "C" fn layout_set_flex_wrap(tree: &mut LayoutTree, node: u64, wrap: u8) {
-    mutate_style(tree, node, |s| {
-        s.flex_wrap = match wrap {
-            1 => FlexWrap::Wrap,
-            2 => FlexWrap::WrapReverse,
-            _ => FlexWrap::NoWrap,
-        };
-    });
+pub extern "C" fn layout_roots(tree: *const LayoutTree, out: *mut u64, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let roots = tree.registered_roots();
+        for (slot, &root) in out_buffer(out, cap).iter_mut().zip(&roots) {
+            *slot = root.into();
+        }
+        roots.len()
+    })
 }
 
+/// Compute every registered root, in `layout_roots` order, each against its own
+/// width and height from `sizes` (two floats per root). Roots past `count` are not
+/// computed, and null `sizes` computes none. Inside a frame each compute is
+/// deferred like `layout_compute`. Returns the number of registered roots.
 #[no_mangle]
-pub extern "C" fn layout_set_flex_grow(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.flex_grow = val);
+pub extern "C" fn layout_compute_all(tree: *mut LayoutTree, sizes: *const f32, count: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let roots = tree.registered_roots();
+        let count = count.min(roots.len());
+        let sizes = in_buffer(sizes, count * 2).unwrap_or_default();
+        for (&root, size) in roots.iter().zip(sizes.chunks_exact(2)) {
+            let avail = Size { width: AvailableSpace::Definite(size[0]), height: AvailableSpace::Definite(size[1]) };
+            if !defer_compute(tree, root, avail, None) {
+                compute(tree, root, avail);
+            }
+        }
+        roots.len()
+    })
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_flex_shrink(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.flex_shrink = val);
+// ============================================================================
+// Frames
+// ============================================================================
+
+#[derive(Clone, Default)]
+struct Frame {
+    /// Nested `layout_begin_frame` calls still open.
+    depth: u32,
+    /// Root, available space and stats label of each deferred compute, one per root.
+    computes: Vec<(NodeId, Size<AvailableSpace>, Option<String>)>,
 }
 
+/// Open a frame. Until the matching `layout_end_frame`, computes are deferred:
+/// mutations only mark nodes dirty, and every result getter keeps reporting the
+/// previous frame's layout. Frames nest; only the outermost end publishes.
 #[no_mangle]
-pub extern "C" fn layout_set_flex_basis(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.flex_basis = Dimension::length(val));
+pub extern "C" fn layout_begin_frame(tree: *mut LayoutTree) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        tree.frame.get_or_insert_with(Frame::default).depth += 1;
+    })
 }
 
+/// Close a frame. Closing the outermost one runs each root's deferred compute once,
+/// with the last available size requested for it during the frame (compute stats,
+/// streams and callbacks included). Returns false if no frame is open.
 #[no_mangle]
-pub extern "C" fn layout_set_align_items(tree: &mut LayoutTree, node: u64, val: u8) {
-    mutate_style(tree, node, |s| s.align_items = Some(map_align_items(val)));
+pub extern "C" fn layout_end_frame(tree: *mut LayoutTree) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let Some(frame) = &mut tree.frame else { return false };
+        frame.depth -= 1;
+        if frame.depth > 0 {
+            return true;
+        }
+        let frame = tree.frame.take().unwrap();
+        for (root, avail, label) in frame.computes {
+            match label {
+                Some(label) => compute_labeled(tree, root, avail, &label),
+                None => {
+                    compute(tree, root, avail);
+                }
+            }
+        }
+        true
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn layout_set_justify_content(tree: &mut LayoutTree, node: u64, val: u8) {
-    mutate_style(tree, node, |s| s.justify_content = Some(map_justify_content(val)));
+pub extern "C" fn layout_in_frame(tree: *const LayoutTree) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.frame.is_some()
+    })
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_align_self(tree: &mut LayoutTree, node: u64, val: u8) {
-    mutate_style(tree, node, |s| s.align_self = Some(map_align_self(val)));
+/// Queue a compute on the open frame, replacing an earlier one for the same root.
+/// False (nothing queued) when no frame is open.
+fn defer_compute(tree: &mut TreeState, root: NodeId, avail: Size<AvailableSpace>, label: Option<&str>) -> bool {
+    let Some(frame) = &mut tree.frame else { return false };
+    let label = label.map(str::to_string);
+    match frame.computes.iter_mut().find(|(id, ..)| *id == root) {
+        Some(queued) => *queued = (root, avail, label),
+        None => frame.computes.push((root, avail, label)),
+    }
+    true
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_width(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.size.width = Dimension::length(val));
+// ============================================================================
+// Profiling
+// ============================================================================
+
+/// One subtree of a profiled compute. Entry 0 is the compute root and covers the
+/// whole compute; the rest are the root's children in child order.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutProfileEntry {
+    pub node: u64,
+    /// Wall time in nanoseconds. A child's share is the engine time that led up to
+    /// each measure of a leaf in its subtree, so container-only work between leaves
+    /// is attributed approximately.
+    pub time_ns: u64,
+    /// Nodes in the subtree.
+    pub nodes: u32,
+    /// Nodes whose cached layout was still valid when the compute started.
+    pub cache_hits: u32,
+    /// Nodes that had been invalidated and were laid out again.
+    pub cache_misses: u32,
+    /// Leaf measurements the engine performed in the subtree, across all passes.
+    pub measure_calls: u32,
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_height(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.size.height = Dimension::length(val));
+#[derive(Clone)]
+struct Profiler {
+    entries: Vec<LayoutProfileEntry>,
+    /// Entry index of every node below the root's children.
+    subtree_of: HashMap<NodeId, usize>,
+    last_event: Instant,
 }
 
+/// Profile every compute from now on, keeping the last one for `layout_get_profile`.
+/// Turning profiling off discards the profile.
 #[no_mangle]
-pub extern "C" fn layout_set_width_percent(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.size.width = Dimension::percent(val / 100.0));
+pub extern "C" fn layout_enable_profiling(tree: *mut LayoutTree, enabled: bool) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if !enabled {
+            tree.profiler = None;
+        } else if tree.profiler.is_none() {
+            tree.profiler = Some(Profiler { entries: Vec::new(), subtree_of: HashMap::new(), last_event: Instant::now() });
+        }
+    })
 }
 
+/// Profile of the last compute that ran while profiling: the root's entry, then
+/// one per child of the root. Returns the number of entries (0 if profiling is off
+/// or no compute ran since it was enabled); only the first `cap` are written.
 #[no_mangle]
-pub extern "C" fn layout_set_height_percent(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.size.height = Dimension::percent(val / 100.0));
+pub extern "C" fn layout_get_profile(tree: *const LayoutTree, out: *mut LayoutProfileEntry, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let Some(profiler) = &tree.profiler else { return 0 };
+        let out = out_buffer(out, cap);
+        for (slot, entry) in out.iter_mut().zip(&profiler.entries) {
+            *slot = *entry;
+        }
+        profiler.entries.len()
+    })
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_min_width(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.min_size.width = Dimension::length(val));
+/// Reset the profiler for a compute of `root`, counting cache state before any
+/// of the compute's passes touch it.
+fn begin_profile(tree: &mut TreeState, root: NodeId) {
+    let Some(mut profiler) = tree.profiler.take() else { return };
+    profiler.entries.clear();
+    profiler.subtree_of.clear();
+    let tops = std::iter::once(root).chain(tree.tree.children(root).unwrap());
+    for (index, top) in tops.enumerate() {
+        let mut entry = LayoutProfileEntry { node: top.into(), ..LayoutProfileEntry::default() };
+        let nodes = if index == 0 { vec![root] } else { tree.subtree(top) };
+        for id in nodes {
+            if tree.tree.dirty(id).unwrap_or(true) {
+                entry.cache_misses += 1;
+            } else {
+                entry.cache_hits += 1;
+            }
+            entry.nodes += 1;
+            if index > 0 {
+                profiler.subtree_of.insert(id, index);
+            }
+        }
+        profiler.entries.push(entry);
+    }
+    // The root's entry covers the whole tree
+    let (hits, misses, nodes) = profiler.entries[1..].iter()
+        .fold((0, 0, 0), |(h, m, n), e| (h + e.cache_hits, m + e.cache_misses, n + e.nodes));
+    let root_entry = &mut profiler.entries[0];
+    root_entry.cache_hits += hits;
+    root_entry.cache_misses += misses;
+    root_entry.nodes += nodes;
+    profiler.last_event = Instant::now();
+    tree.profiler = Some(profiler);
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_min_height(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.min_size.height = Dimension::length(val));
+/// One layout pass over `root`, with independent subtrees laid out in parallel
+/// when parallelism is on.
+fn layout_pass(tree: &mut TreeState, root: NodeId, avail: Size<AvailableSpace>) {
+    parallel::before_pass(tree, root);
+    taffy_pass(tree, root, avail);
+    // Again for panels that came out a different size and for containers aligned
+    // by baselines the pass settled, at most twice
+    for _ in 0..2 {
+        let panels_held = parallel::after_pass(tree, root);
+        if !baseline::settle(tree, root) && panels_held {
+            return;
+        }
+        taffy_pass(tree, root, avail);
+    }
+    parallel::after_pass(tree, root);
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_max_width(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.max_size.width = Dimension::length(val));
+/// One Taffy layout pass over `root`, attributing leaf measurements to the
+/// profile when profiling is on.
+fn taffy_pass(tree: &mut TreeState, root: NodeId, avail: Size<AvailableSpace>) {
+    let Some(profiler) = &mut tree.profiler else {
+        let _ = tree.tree.compute_layout_with_measure(root, avail, measure_leaf);
+        return;
+    };
+    let _ = tree.tree.compute_layout_with_measure(root, avail, |known, available, id, context, style| {
+        let now = Instant::now();
+        let index = profiler.subtree_of.get(&id).copied().unwrap_or(0);
+        let entry = &mut profiler.entries[index];
+        entry.time_ns += (now - profiler.last_event).as_nanos() as u64;
+        entry.measure_calls += 1;
+        if index > 0 {
+            profiler.entries[0].measure_calls += 1;
+        }
+        profiler.last_event = now;
+        measure_leaf(known, available, id, context, style)
+    });
 }
 
+// ============================================================================
+// Rounding
+// ============================================================================
+
+/// Round computed layouts to whole pixels (the default) or report them as
+/// computed. Unrounded layouts are what `layout_get_result_unrounded` returns
+/// either way; pixel distribution needs rounding and is skipped while it is off.
 #[no_mangle]
-pub extern "C" fn layout_set_max_height(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.max_size.height = Dimension::length(val));
+pub extern "C" fn layout_tree_set_rounding(tree: *mut LayoutTree, enabled: bool) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if enabled != tree.rounding {
+            tree.rounding = enabled;
+            if enabled {
+                tree.tree.enable_rounding();
+            } else {
+                tree.tree.disable_rounding();
+            }
+            tree.last_compute = None;
+        }
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn layout_set_padding(tree: &mut LayoutTree, node: u64, edge: u8, val: f32) {
-    mutate_style(tree, node, |s| set_edge_lp(&mut s.padding, edge, val));
+pub extern "C" fn layout_tree_get_rounding(tree: *const LayoutTree) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.rounding
+    })
 }
 
+// ============================================================================
+// Scale factor
+// ============================================================================
+
+/// Round computed layouts to device pixels at `scale` device pixels per logical
+/// pixel (1 by default), so that a 1.5x or 2x window gets no half-lit seams. Rects
+/// stay logical: at 2x a node can be 10.5 wide, and `layout_get_result_physical`
+/// reads 21. Every edge snaps in root space, so adjacent edges always meet on the
+/// same device pixel. Pixel distribution applies at scale 1 only. Non-finite or
+/// non-positive scales are ignored.
 #[no_mangle]
-pub extern "C" fn layout_set_margin(tree: &mut LayoutTree, node: u64, edge: u8, val: f32) {
-    mutate_style(tree, node, |s| set_edge_lpa(&mut s.margin, edge, val));
+pub extern "C" fn layout_tree_set_scale_factor(tree: *mut LayoutTree, scale: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if !scale.is_finite() || scale <= 0.0 {
+            log(LOG_WARN, &format!("scale factor {scale} ignored; it must be finite and positive"));
+            return;
+        }
+        if scale != tree.scale_factor {
+            tree.scale_factor = scale;
+            tree.last_compute = None;
+        }
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn layout_set_gap_row(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.gap.height = LengthPercentage::length(val));
+pub extern "C" fn layout_tree_get_scale_factor(tree: *const LayoutTree) -> f32 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.scale_factor
+    })
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_gap_column(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.gap.width = LengthPercentage::length(val));
+/// Re-round `root`'s subtree from Taffy's unrounded layouts to device pixels,
+/// as Taffy rounds to logical ones but with locations snapped in root space too.
+/// `parent` is the root's parent's unrounded position in root space.
+fn snap_to_device(tree: &mut TreeState, root: NodeId, parent: Point<f32>) {
+    let scale = tree.scale_factor;
+    let snap = |v: f32| (v * scale).round() / scale;
+    // Snapped extent of [start + near, start + size - far] edges on one axis
+    let edges = |start: f32, size: f32, near: f32, far: f32| {
+        (snap(start + near) - snap(start), snap(start + size) - snap(start + size - far))
+    };
+    let mut stack = vec![(root, parent)];
+    while let Some((id, parent)) = stack.pop() {
+        let unrounded = *tree.unrounded_layout(id);
+        let x = parent.x + unrounded.location.x;
+        let y = parent.y + unrounded.location.y;
+        let mut layout = unrounded;
+        layout.location.x = snap(x) - snap(parent.x);
+        layout.location.y = snap(y) - snap(parent.y);
+        layout.size.width = snap(x + unrounded.size.width) - snap(x);
+        layout.size.height = snap(y + unrounded.size.height) - snap(y);
+        layout.content_size.width = snap(x + unrounded.content_size.width) - snap(x);
+        layout.content_size.height = snap(y + unrounded.content_size.height) - snap(y);
+        layout.scrollbar_size.width = snap(unrounded.scrollbar_size.width);
+        layout.scrollbar_size.height = snap(unrounded.scrollbar_size.height);
+        let (w, h) = (unrounded.size.width, unrounded.size.height);
+        (layout.border.left, layout.border.right) = edges(x, w, unrounded.border.left, unrounded.border.right);
+        (layout.border.top, layout.border.bottom) = edges(y, h, unrounded.border.top, unrounded.border.bottom);
+        (layout.padding.left, layout.padding.right) = edges(x, w, unrounded.padding.left, unrounded.padding.right);
+        (layout.padding.top, layout.padding.bottom) = edges(y, h, unrounded.padding.top, unrounded.padding.bottom);
+        tree.layout_overrides.insert(id, layout);
+        stack.extend(tree.tree.children(id).unwrap().into_iter().map(|child| (child, Point { x, y })));
+    }
 }
 
+// ============================================================================
+// Pixel distribution
+// ============================================================================
+
+/// How the items on a flex line share its whole pixels. 0 = engine: every edge
+/// rounds to the nearest pixel, so which item gets a leftover pixel depends on
+/// where the line sits and can hop between items when it moves. The others floor
+/// every fractional item size and hand the leftover pixels out one per item:
+/// 1 = to the first items in source order, 2 = spread evenly along the line,
+/// 3 = to the largest fractional remainders (ties go to the earlier item). Items
+/// of whole-pixel size keep it, and the line keeps its rounded extent. A
+/// descendant whose end edge is on a resized item's end edge is resized with it.
+/// Unknown strategies select 0.
 #[no_mangle]
-pub extern "C" fn layout_set_gap_all(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| {
-        s.gap.width = LengthPercentage::length(val);
-        s.gap.height = LengthPercentage::length(val);
-    });
+pub extern "C" fn layout_tree_set_pixel_distribution(tree: *mut LayoutTree, strategy: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let strategy = checked_code("pixel distribution", strategy, 4);
+        if strategy != tree.pixel_distribution {
+            tree.pixel_distribution = strategy;
+            // Nothing is dirty, but the next compute has to re-round
+            tree.last_compute = None;
+        }
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn layout_set_position_type(tree: &mut LayoutTree, node: u64, val: u8) {
-    mutate_style(tree, node, |s| {
-        s.position = match val {
-            1 => Position::Absolute,
-            _ => Position::Relative,
-        };
-    });
+pub extern "C" fn layout_tree_get_pixel_distribution(tree: *const LayoutTree) -> u8 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.pixel_distribution
+    })
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_position(tree: &mut LayoutTree, node: u64, edge: u8, val: f32) {
-    mutate_style(tree, node, |s| {
-        let v = LengthPercentageAuto::length(val);
-        match edge {
-            0 => s.inset.left = v,
-            1 => s.inset.top = v,
-            2 => s.inset.right = v,
-            3 => s.inset.bottom = v,
-            _ => { s.inset.left = v; s.inset.top = v; s.inset.right = v; s.inset.bottom = v; }
-        }
-    });
+/// Drop the layout overrides of `root`'s subtree before it is computed again.
+fn forget_overrides(tree: &mut TreeState, root: NodeId) {
+    if tree.layout_overrides.is_empty() {
+        return;
+    }
+    for id in tree.subtree(root) {
+        tree.layout_overrides.remove(&id);
+    }
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_aspect_ratio(tree: &mut LayoutTree, node: u64, val: f32) {
-    mutate_style(tree, node, |s| s.aspect_ratio = Some(val));
+/// Re-round `root`'s subtree per the tree's scale factor and pixel distribution.
+fn distribute_pixels(tree: &mut TreeState, root: NodeId) {
+    if !tree.rounding {
+        return;
+    }
+    if tree.scale_factor != 1.0 {
+        snap_to_device(tree, root, Point { x: 0.0, y: 0.0 });
+    } else if tree.pixel_distribution != 0 {
+        snap_children(tree, root, [0.0; 2]);
+    }
 }
 
-// ============================================================================
-// CSS Grid — template + placement
-// ============================================================================
+/// Re-round the inside of `id`, which sits at `origin` in root space and keeps
+/// its own layout, after it was laid out again without its ancestors.
+fn distribute_pixels_inside(tree: &mut TreeState, id: NodeId, origin: Point<f32>) {
+    if !tree.layout_overrides.is_empty() {
+        for node in tree.subtree(id).into_iter().skip(1) {
+            tree.layout_overrides.remove(&node);
+        }
+    }
+    if !tree.rounding {
+        return;
+    }
+    if tree.scale_factor != 1.0 {
+        let location = tree.unrounded_layout(id).location;
+        snap_to_device(tree, id, Point { x: origin.x - location.x, y: origin.y - location.y });
+    } else if tree.pixel_distribution != 0 {
+        let rounded = tree.rounded_layout(id).size;
+        let size = tree.final_layout(id).size;
+        snap_children(tree, id, [size.width - rounded.width, size.height - rounded.height]);
+    }
+}
 
-#[no_mangle]
-pub extern "C" fn layout_set_grid_template_columns(
-    tree: &mut LayoutTree, node: u64, vals: *const f32, count: usize,
-) {
-    let tracks = parse_track_list(vals, count);
-    mutate_style(tree, node, |s| s.grid_template_columns = tracks.clone());
+/// Re-round the children of `id`, whose width and height have just grown by
+/// `grow` over Taffy's rounded size, then their descendants.
+fn snap_children(tree: &mut TreeState, id: NodeId, grow: [f32; 2]) {
+    let children = tree.tree.children(id).unwrap();
+    // Per child: x, y, width and height change over Taffy's rounded layout
+    let mut changes = vec![[0.0f32; 4]; children.len()];
+    // Per child: the axis distribution already sized it on, growth included
+    let mut distributed = vec![None; children.len()];
+    let rounded = tree.rounded_layout(id).size;
+    let size = [rounded.width + grow[0], rounded.height + grow[1]];
+    distribute_lines(tree, id, &children, size, &mut changes, &mut distributed);
+    let parent = *tree.unrounded_layout(id);
+    for (i, &child) in children.iter().enumerate() {
+        let layout = tree.unrounded_layout(child);
+        for axis in 0..2 {
+            if grow[axis] != 0.0 && distributed[i] != Some(axis) && end_inset(&parent, layout, axis).is_some() {
+                changes[i][2 + axis] += grow[axis];
+            }
+        }
+    }
+    for (child, change) in children.into_iter().zip(changes) {
+        if change != [0.0; 4] {
+            let mut layout = *tree.rounded_layout(child);
+            layout.location.x += change[0];
+            layout.location.y += change[1];
+            layout.size.width += change[2];
+            layout.size.height += change[3];
+            tree.layout_overrides.insert(child, layout);
+        }
+        snap_children(tree, child, [change[2], change[3]]);
+    }
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_grid_template_rows(
-    tree: &mut LayoutTree, node: u64, vals: *const f32, count: usize,
-) {
-    let tracks = parse_track_list(vals, count);
-    mutate_style(tree, node, |s| s.grid_template_rows = tracks.clone());
+/// If the child's end edge on `axis` (0 = x) is on its parent's border, padding or
+/// content box end edge, the distance from that edge to the parent's far side.
+fn end_inset(parent: &Layout, child: &Layout, axis: usize) -> Option<f32> {
+    let (border, padding, end) = if axis == 0 {
+        (parent.border.right, parent.padding.right, parent.size.width - child.location.x - child.size.width)
+    } else {
+        (parent.border.bottom, parent.padding.bottom, parent.size.height - child.location.y - child.size.height)
+    };
+    [0.0, border, border + padding].into_iter().find(|inset| (inset - end).abs() < 0.01)
 }
 
-#[no_mangle]
-pub extern "C" fn layout_set_grid_placement(
-    tree: &mut LayoutTree, node: u64,
-    row: i16, col: i16, span_rows: u16, span_cols: u16,
+/// Work out the main-axis changes of a flex container's items, line by line.
+/// `size` is the container's re-rounded size.
+fn distribute_lines(
+    tree: &TreeState, id: NodeId, children: &[NodeId], size: [f32; 2], changes: &mut [[f32; 4]],
+    distributed: &mut [Option<usize>],
 ) {
-    mutate_style(tree, node, |s| {
-        if row != 0 {
-            s.grid_row = Line {
-                start: GridPlacement::from_line_index(row),
-                end: GridPlacement::from_span(span_rows.max(1)),
+    let style = tree.tree.style(id).unwrap();
+    if style.display != Display::Flex {
+        return;
+    }
+    let axis = if matches!(style.flex_direction, FlexDirection::Row | FlexDirection::RowReverse) { 0 } else { 1 };
+    let container = tree.unrounded_layout(id);
+    let in_flow: Vec<usize> = (0..children.len())
+        .filter(|&i| {
+            let s = tree.tree.style(children[i]).unwrap();
+            s.display != Display::None && s.position != Position::Absolute
+        })
+        .collect();
+    let mut rest = &in_flow[..];
+    for line in flex_lines(tree, id) {
+        let (items, tail) = rest.split_at((line.item_count as usize).min(rest.len()));
+        rest = tail;
+        // Source order: index, main start, main size
+        let spans: Vec<(usize, f32, f32)> = items.iter()
+            .map(|&i| {
+                let l = tree.unrounded_layout(children[i]);
+                if axis == 0 { (i, l.location.x, l.size.width) } else { (i, l.location.y, l.size.height) }
+            })
+            .collect();
+        let mut visual: Vec<usize> = (0..spans.len()).collect();
+        visual.sort_by(|&a, &b| spans[a].1.total_cmp(&spans[b].1));
+        // Gap after each item in visual order, margins included
+        let gaps: Vec<f32> = visual.windows(2)
+            .map(|w| (spans[w[1]].1 - spans[w[0]].1 - spans[w[0]].2).round())
+            .collect();
+        let (first, last) = (spans[visual[0]], spans[*visual.last().unwrap()]);
+        // A line that reaches the container's end edge ends on its re-rounded one
+        let end = match end_inset(container, tree.unrounded_layout(children[last.0]), axis) {
+            Some(inset) => size[axis] - inset.round(),
+            None => (last.1 + last.2).round(),
+        };
+        let budget = end - first.1.round() - gaps.iter().sum::<f32>();
+        let sizes: Vec<f32> = spans.iter().map(|s| s.2).collect();
+        let Some(pixels) = share_pixels(tree.pixel_distribution, &sizes, budget) else { continue };
+
+        let mut cursor = first.1.round();
+        for (n, &item) in visual.iter().enumerate() {
+            let i = spans[item].0;
+            let rounded = tree.rounded_layout(children[i]);
+            let (location, size) = if axis == 0 {
+                (rounded.location.x, rounded.size.width)
+            } else {
+                (rounded.location.y, rounded.size.height)
             };
+            changes[i][axis] = cursor - location;
+            changes[i][2 + axis] = pixels[item] - size;
+            distributed[i] = Some(axis);
+            cursor += pixels[item] + gaps.get(n).copied().unwrap_or(0.0);
         }
-        if col != 0 {
-            s.grid_column = Line {
-                start: GridPlacement::from_line_index(col),
-                end: GridPlacement::from_span(span_cols.max(1)),
-            };
+    }
+}
+
+/// Whole-pixel sizes for a line's items that add up to `budget`, or None if the
+/// fractional items cannot absorb the leftover one pixel each.
+fn share_pixels(strategy: u8, sizes: &[f32], budget: f32) -> Option<Vec<f32>> {
+    const EPSILON: f32 = 0.01;
+    let fractional = |s: f32| (s - s.round()).abs() >= EPSILON;
+    let mut pixels: Vec<f32> = sizes.iter().map(|&s| if fractional(s) { s.floor() } else { s.round() }).collect();
+    let eligible: Vec<usize> = (0..sizes.len()).filter(|&i| fractional(sizes[i])).collect();
+    let leftover = (budget - pixels.iter().sum::<f32>()).round();
+    if leftover < 0.0 || leftover as usize > eligible.len() {
+        return None;
+    }
+    let (k, m) = (leftover as usize, eligible.len());
+    let chosen: Vec<usize> = match strategy {
+        1 => eligible[..k].to_vec(),
+        // Item j gets a pixel when round((j + 1) * k / m) steps past round(j * k / m)
+        2 => (0..m)
+            .filter(|&j| (2 * (j + 1) * k + m) / (2 * m) > (2 * j * k + m) / (2 * m))
+            .map(|j| eligible[j])
+            .collect(),
+        _ => {
+            let mut by_remainder = eligible;
+            // Stable, so equal remainders keep source order
+            by_remainder.sort_by(|&a, &b| (sizes[b] - pixels[b]).total_cmp(&(sizes[a] - pixels[a])));
+            by_remainder.truncate(k);
+            by_remainder
         }
-    });
+    };
+    for i in chosen {
+        pixels[i] += 1.0;
+    }
+    Some(pixels)
 }
 
 // ============================================================================
-// Overflow
+// Coordinate spaces
 // ============================================================================
 
+/// Map a point from `from_node`'s local space (origin at its border-box top-left)
+/// into `to_node`'s, as displayed: each ancestor's scroll offset moves the spaces
+/// inside it. Returns false if either node is unknown or they do not share a root.
 #[no_mangle]
-pub extern "C" fn layout_set_overflow(tree: &mut LayoutTree, node: u64, overflow: u8) {
-    mutate_style(tree, node, |s| {
-        let v = match overflow {
-            1 => Overflow::Hidden,
-            2 => Overflow::Scroll,
-            _ => Overflow::Visible,
-        };
-        s.overflow.x = v;
-        s.overflow.y = v;
-    });
+pub extern "C" fn layout_convert_point(
+    tree: *const LayoutTree, from_node: u64, to_node: u64, x: f32, y: f32,
+    out_x: &mut f32, out_y: &mut f32,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let Some(delta) = space_delta(tree, NodeId::from(from_node), NodeId::from(to_node)) else { return false };
+        *out_x = x + delta.x;
+        *out_y = y + delta.y;
+        true
+    })
 }
 
-// ============================================================================
-// Layout computation + results
-// ============================================================================
-
+/// Rect variant of `layout_convert_point`; width and height pass through unchanged.
 #[no_mangle]
-pub extern "C" fn layout_compute(tree: &mut LayoutTree, node: u64, width: f32, height: f32) {
-    let avail = Size {
-        width: AvailableSpace::Definite(width),
-        height: AvailableSpace::Definite(height),
-    };
-    let _ = tree.tree.compute_layout(NodeId::from(node), avail);
+pub extern "C" fn layout_convert_rect(
+    tree: *const LayoutTree, from_node: u64, to_node: u64, x: f32, y: f32, w: f32, h: f32,
+    out_x: &mut f32, out_y: &mut f32, out_w: &mut f32, out_h: &mut f32,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let Some(delta) = space_delta(tree, NodeId::from(from_node), NodeId::from(to_node)) else { return false };
+        *out_x = x + delta.x;
+        *out_y = y + delta.y;
+        *out_w = w;
+        *out_h = h;
+        true
+    })
 }
 
+/// The node's border box in its root's space, moved by its ancestors' scroll
+/// offsets. Returns false for an unknown node.
 #[no_mangle]
-pub extern "C" fn layout_get_result(
-    tree: &LayoutTree, node: u64,
+pub extern "C" fn layout_get_absolute_rect(
+    tree: *const LayoutTree, node: u64,
     out_x: &mut f32, out_y: &mut f32, out_w: &mut f32, out_h: &mut f32,
-) {
-    if let Ok(layout) = tree.tree.layout(NodeId::from(node)) {
-        *out_x = layout.location.x;
-        *out_y = layout.location.y;
-        *out_w = layout.size.width;
-        *out_h = layout.size.height;
-    }
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        let Some((_, origin)) = root_origin(tree, id) else { return false };
+        let size = tree.final_layout(id).size;
+        *out_x = origin.x;
+        *out_y = origin.y;
+        *out_w = size.width;
+        *out_h = size.height;
+        true
+    })
 }
 
+/// `layout_get_absolute_rect` in device pixels, for the renderer.
 #[no_mangle]
-pub extern "C" fn layout_child_count(tree: &LayoutTree, node: u64) -> usize {
-    tree.tree.child_count(NodeId::from(node))
+pub extern "C" fn layout_get_absolute_rect_physical(
+    tree: *const LayoutTree, node: u64,
+    out_x: &mut f32, out_y: &mut f32, out_w: &mut f32, out_h: &mut f32,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        let Some((_, origin)) = root_origin(tree, id) else { return false };
+        let size = tree.final_layout(id).size;
+        *out_x = tree.to_physical(origin.x);
+        *out_y = tree.to_physical(origin.y);
+        *out_w = tree.to_physical(size.width);
+        *out_h = tree.to_physical(size.height);
+        true
+    })
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutNodeRect {
+    pub node: u64,
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
 }
 
+/// Root-space rects for `root` and all of its descendants, root first and then in
+/// depth-first child order. Returns the subtree size; only the first `cap` are written.
 #[no_mangle]
-pub extern "C" fn layout_get_child(tree: &LayoutTree, node: u64, index: usize) -> u64 {
-    tree.tree.child_at_index(NodeId::from(node), index).unwrap().into()
+pub extern "C" fn layout_get_absolute_rects(
+    tree: *const LayoutTree, root: u64, out: *mut LayoutNodeRect, cap: usize,
+) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let root = NodeId::from(root);
+        let Some((_, origin)) = root_origin(tree, root) else { return 0 };
+        let out = out_buffer(out, cap);
+        let location = tree.final_layout(root).location;
+        let parent_origin = Point { x: origin.x - location.x, y: origin.y - location.y };
+        let mut count = 0;
+        let mut stack = vec![(root, parent_origin)];
+        while let Some((id, parent_origin)) = stack.pop() {
+            let layout = tree.final_layout(id);
+            let origin = Point { x: parent_origin.x + layout.location.x, y: parent_origin.y + layout.location.y };
+            let scroll = scroll::offset_of(tree, id);
+            let content_origin = Point { x: origin.x - scroll.x, y: origin.y - scroll.y };
+            if let Some(slot) = out.get_mut(count) {
+                *slot = LayoutNodeRect {
+                    node: id.into(), x: origin.x, y: origin.y, w: layout.size.width, h: layout.size.height,
+                };
+            }
+            count += 1;
+            stack.extend(tree.tree.children(id).unwrap().into_iter().rev().map(|child| (child, content_origin)));
+        }
+        count
+    })
+}
+
+/// Offset that takes `from`-local coordinates to `to`-local ones.
+fn space_delta(tree: &TreeState, from: NodeId, to: NodeId) -> Option<Point<f32>> {
+    let (from_root, from_origin) = root_origin(tree, from)?;
+    let (to_root, to_origin) = root_origin(tree, to)?;
+    (from_root == to_root).then_some(Point { x: from_origin.x - to_origin.x, y: from_origin.y - to_origin.y })
+}
+
+/// The node's root and the position of the node's origin in that root's space,
+/// with every ancestor's scroll offset applied.
+fn root_origin(tree: &TreeState, node: NodeId) -> Option<(NodeId, Point<f32>)> {
+    if !tree.contains(node) {
+        return None;
+    }
+    let mut origin = Point { x: 0.0, y: 0.0 };
+    let mut current = node;
+    loop {
+        let layout = tree.final_layout(current);
+        origin.x += layout.location.x;
+        origin.y += layout.location.y;
+        match tree.tree.parent(current) {
+            Some(parent) => {
+                let scroll = scroll::offset_of(tree, parent);
+                origin.x -= scroll.x;
+                origin.y -= scroll.y;
+                current = parent;
+            }
+            None => return Some((current, origin)),
+        }
+    }
 }
 
 // ============================================================================
 // Helpers
 // ============================================================================
 
-fn mutate_style(tree: &mut LayoutTree, node: u64, f: impl FnOnce(&mut Style)) {
-    let _ = tree.tree.set_style(NodeId::from(node), {
-        let mut style = tree.tree.style(NodeId::from(node)).unwrap().clone();
-        f(&mut style);
-        style
+/// A caller-provided UTF-8 string; None for null or invalid UTF-8.
+fn c_str<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() { None } else { unsafe { CStr::from_ptr(ptr) }.to_str().ok() }
+}
+
+/// A caller-provided output buffer; null reads as zero capacity.
+fn out_buffer<'a, T>(ptr: *mut T, cap: usize) -> &'a mut [T] {
+    if ptr.is_null() { &mut [] } else { unsafe { std::slice::from_raw_parts_mut(ptr, cap) } }
+}
+
+/// A caller-provided input array of `len` elements; None if it is null but not empty.
+fn in_buffer<'a, T>(ptr: *const T, len: usize) -> Option<&'a [T]> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Some(&[]),
+        (true, _) => None,
+        (false, _) => Some(unsafe { std::slice::from_raw_parts(ptr, len) }),
+    }
+}
+
+/// A table is worth shrinking once at least three quarters of it is unused.
+fn spare(len: usize, capacity: usize) -> bool {
+    capacity > 64 && capacity > len * 4
+}
+
+/// Apply `f` to the node's host style. Stale and unknown handles are rejected
+/// with the last error set.
+fn mutate_style(tree: &mut TreeState, node: u64, f: impl FnOnce(&mut Style)) {
+    let id = NodeId::from(node);
+    if !tree.expect_live(id) {
+        return;
+    }
+    let mut style = tree.host_style(id);
+    f(&mut style);
+    tree.store_style(id, style);
+    tree.style_generation += 1;
+    tree.record(MUTATION_STYLE, id, id);
+}
+
+// Numeric style properties shared by the engine-driven style inputs (scroll
+// links, animations) and the bulk reader. Lengths are px.
+
+pub const PROPERTY_WIDTH: u8 = 0;
+pub const PROPERTY_HEIGHT: u8 = 1;
+pub const PROPERTY_MIN_WIDTH: u8 = 2;
+pub const PROPERTY_MIN_HEIGHT: u8 = 3;
+pub const PROPERTY_MAX_WIDTH: u8 = 4;
+pub const PROPERTY_MAX_HEIGHT: u8 = 5;
+pub const PROPERTY_INSET_LEFT: u8 = 6;
+pub const PROPERTY_INSET_TOP: u8 = 7;
+pub const PROPERTY_INSET_RIGHT: u8 = 8;
+pub const PROPERTY_INSET_BOTTOM: u8 = 9;
+pub const PROPERTY_FLEX_GROW: u8 = 10;
+pub const PROPERTY_GAP_ROW: u8 = 11;
+pub const PROPERTY_GAP_COLUMN: u8 = 12;
+const PROPERTY_COUNT: u8 = 13;
+
+/// Read one numeric property. NaN for an unknown code or a value that is not a
+/// plain length (auto, percentages).
+fn get_property(style: &Style, property: u8) -> f32 {
+    let px = |raw: CompactLength| if raw.tag() == CompactLength::LENGTH_TAG { raw.value() } else { f32::NAN };
+    match property {
+        PROPERTY_WIDTH => px(style.size.width.into_raw()),
+        PROPERTY_HEIGHT => px(style.size.height.into_raw()),
+        PROPERTY_MIN_WIDTH => px(style.min_size.width.into_raw()),
+        PROPERTY_MIN_HEIGHT => px(style.min_size.height.into_raw()),
+        PROPERTY_MAX_WIDTH => px(style.max_size.width.into_raw()),
+        PROPERTY_MAX_HEIGHT => px(style.max_size.height.into_raw()),
+        PROPERTY_INSET_LEFT => px(style.inset.left.into_raw()),
+        PROPERTY_INSET_TOP => px(style.inset.top.into_raw()),
+        PROPERTY_INSET_RIGHT => px(style.inset.right.into_raw()),
+        PROPERTY_INSET_BOTTOM => px(style.inset.bottom.into_raw()),
+        PROPERTY_FLEX_GROW => style.flex_grow,
+        PROPERTY_GAP_ROW => px(style.gap.height.into_raw()),
+        PROPERTY_GAP_COLUMN => px(style.gap.width.into_raw()),
+        _ => f32::NAN,
+    }
+}
+
+/// Write one numeric property. Returns false for an unknown property code.
+fn set_property(style: &mut Style, property: u8, value: f32) -> bool {
+    match property {
+        PROPERTY_WIDTH => style.size.width = Dimension::length(value),
+        PROPERTY_HEIGHT => style.size.height = Dimension::length(value),
+        PROPERTY_MIN_WIDTH => style.min_size.width = Dimension::length(value),
+        PROPERTY_MIN_HEIGHT => style.min_size.height = Dimension::length(value),
+        PROPERTY_MAX_WIDTH => style.max_size.width = Dimension::length(value),
+        PROPERTY_MAX_HEIGHT => style.max_size.height = Dimension::length(value),
+        PROPERTY_INSET_LEFT => style.inset.left = LengthPercentageAuto::length(value),
+        PROPERTY_INSET_TOP => style.inset.top = LengthPercentageAuto::length(value),
+        PROPERTY_INSET_RIGHT => style.inset.right = LengthPercentageAuto::length(value),
+        PROPERTY_INSET_BOTTOM => style.inset.bottom = LengthPercentageAuto::length(value),
+        PROPERTY_FLEX_GROW => style.flex_grow = value,
+        PROPERTY_GAP_ROW => style.gap.height = LengthPercentage::length(value),
+        PROPERTY_GAP_COLUMN => style.gap.width = LengthPercentage::length(value),
+        _ => return false,
+    }
+    true
+}
+
+fn apply_safe_area_insets(tree: &mut TreeState, id: NodeId) {
+    let Some(offsets) = tree.safe_area_insets.get(&id).copied() else { return };
+    let area = tree.safe_area;
+    let edge = |i: usize, current: LengthPercentageAuto| {
+        if offsets[i].is_nan() { current } else { LengthPercentageAuto::length(area[i] + offsets[i]) }
+    };
+    mutate_style(tree, id.into(), |s| {
+        s.inset = Rect {
+            left: edge(0, s.inset.left),
+            top: edge(1, s.inset.top),
+            right: edge(2, s.inset.right),
+            bottom: edge(3, s.inset.bottom),
+        };
     });
 }
 
+fn apply_logical_insets(tree: &mut TreeState, id: NodeId) {
+    let rtl = direction_of(tree, id) == DIRECTION_RTL;
+    let Some(l) = tree.logical_insets.get_mut(&id) else { return };
+    l.rtl = rtl;
+    let l = *l;
+    let (left, right) = if rtl { (l.end, l.start) } else { (l.start, l.end) };
+    mutate_style(tree, id.into(), |s| {
+        s.inset = Rect {
+            left: lpa_or_auto(left),
+            top: lpa_or_auto(l.top),
+            right: lpa_or_auto(right),
+            bottom: lpa_or_auto(l.bottom),
+        };
+    });
+}
+
+fn lpa_or_auto(val: f32) -> LengthPercentageAuto {
+    if val.is_nan() { LengthPercentageAuto::auto() } else { LengthPercentageAuto::length(val) }
+}
+
+// Inverse mappings for the getters. Values without a setter code fold into
+// their nearest equivalent (Start -> FlexStart, End -> FlexEnd).
+
+fn display_code(d: Display) -> u8 {
+    match d {
+        Display::None => 1,
+        Display::Grid => 2,
+        Display::Block => 3,
+        Display::Flex => 0,
+    }
+}
+
+fn flex_direction_code(d: FlexDirection) -> u8 {
+    match d {
+        FlexDirection::Row => 1,
+        FlexDirection::ColumnReverse => 2,
+        FlexDirection::RowReverse => 3,
+        FlexDirection::Column => 0,
+    }
+}
+
+fn flex_wrap_code(w: FlexWrap) -> u8 {
+    match w {
+        FlexWrap::Wrap => 1,
+        FlexWrap::WrapReverse => 2,
+        FlexWrap::NoWrap => 0,
+    }
+}
+
+pub(crate) fn align_items_code(a: AlignItems) -> u8 {
+    match a {
+        AlignItems::Start | AlignItems::FlexStart => 1,
+        AlignItems::Center => 2,
+        AlignItems::End | AlignItems::FlexEnd => 3,
+        AlignItems::Stretch => 4,
+        AlignItems::Baseline => 5,
+    }
+}
+
+fn justify_content_code(j: JustifyContent) -> u8 {
+    match j {
+        JustifyContent::Start | JustifyContent::FlexStart => 1,
+        JustifyContent::Center => 2,
+        JustifyContent::End | JustifyContent::FlexEnd => 3,
+        JustifyContent::SpaceBetween => 4,
+        JustifyContent::SpaceAround => 5,
+        JustifyContent::SpaceEvenly => 6,
+        JustifyContent::Stretch => 7,
+    }
+}
+
+fn overflow_code(o: Overflow) -> u8 {
+    match o {
+        Overflow::Hidden => 1,
+        Overflow::Scroll => 2,
+        Overflow::Clip => 3,
+        Overflow::Visible => 0,
+    }
+}
+
+fn map_grid_line(line: LayoutGridLine) -> GridPlacement {
+    match checked_code("grid line", line.kind, 3) {
+        1 if line.value != 0 => GridPlacement::from_line_index(line.value),
+        2 => {
+            if line.value < 1 {
+                log(LOG_WARN, &format!("grid span {} clamped to 1", line.value));
+            }
+            GridPlacement::from_span(line.value.max(1) as u16)
+        }
+        _ => GridPlacement::Auto,
+    }
+}
+
+fn map_available_space(kind: u8, val: f32) -> AvailableSpace {
+    match checked_code("available space", kind, 3) {
+        1 => AvailableSpace::MinContent,
+        2 => AvailableSpace::MaxContent,
+        _ => AvailableSpace::Definite(val),
+    }
+}
+
+fn map_overflow(val: u8) -> Overflow {
+    match checked_code("overflow", val, 4) {
+        1 => Overflow::Hidden,
+        2 => Overflow::Scroll,
+        3 => Overflow::Clip,
+        _ => Overflow::Visible,
+    }
+}
+
 fn map_align_items(val: u8) -> AlignItems {
-    match val {
+    match checked_code("alignment", val, 6) {
         1 => AlignItems::FlexStart,
         2 => AlignItems::Center,
         3 => AlignItems::FlexEnd,
@@ -346,19 +4723,20 @@ fn map_align_items(val: u8) -> AlignItems {
 }
 
 fn map_justify_content(val: u8) -> JustifyContent {
-    match val {
+    match checked_code("content alignment", val, 8) {
         1 => JustifyContent::FlexStart,
         2 => JustifyContent::Center,
         3 => JustifyContent::FlexEnd,
         4 => JustifyContent::SpaceBetween,
         5 => JustifyContent::SpaceAround,
         6 => JustifyContent::SpaceEvenly,
+        7 => JustifyContent::Stretch,
         _ => JustifyContent::FlexStart,
     }
 }
 
 fn map_align_self(val: u8) -> AlignSelf {
-    match val {
+    match checked_code("alignment", val, 6) {
         1 => AlignSelf::FlexStart,
         2 => AlignSelf::Center,
         3 => AlignSelf::FlexEnd,
@@ -368,8 +4746,8 @@ fn map_align_self(val: u8) -> AlignSelf {
     }
 }
 
-fn set_edge_lp(rect: &mut Rect<LengthPercentage>, edge: u8, val: f32) {
-    let v = LengthPercentage::length(val);
+/// Edge: 0=left, 1=top, 2=right, 3=bottom, anything else = all four.
+fn set_edge<T: Copy>(rect: &mut Rect<T>, edge: u8, v: T) {
     match edge {
         0 => rect.left = v,
         1 => rect.top = v,
@@ -379,28 +4757,238 @@ fn set_edge_lp(rect: &mut Rect<LengthPercentage>, edge: u8, val: f32) {
     }
 }
 
-fn set_edge_lpa(rect: &mut Rect<LengthPercentageAuto>, edge: u8, val: f32) {
-    let v = LengthPercentageAuto::length(val);
-    match edge {
-        0 => rect.left = v,
-        1 => rect.top = v,
-        2 => rect.right = v,
-        3 => rect.bottom = v,
-        _ => { rect.left = v; rect.top = v; rect.right = v; rect.bottom = v; }
+/// Parse a template from `GridTrackDesc`s: each single or repeat entry starts a
+/// component, and continuation entries (kind 4) add their tracks to the repeat
+/// before them, so `[repeat 2 of 100px, continue 1fr]` is `repeat(2, 100px 1fr)`.
+/// A continuation with no repeat before it is a single track.
+fn parse_track_descs(descs: &[GridTrackDesc]) -> Vec<GridTemplateComponent<String>> {
+    let mut components = Vec::with_capacity(descs.len());
+    for d in descs {
+        let track = track_sizing(d.min, d.max);
+        let count = match d.kind {
+            1 => RepetitionCount::Count(d.repeat.max(1)),
+            2 => RepetitionCount::AutoFill,
+            3 => RepetitionCount::AutoFit,
+            4 => {
+                if let Some(GridTemplateComponent::Repeat(repeat)) = components.last_mut() {
+                    repeat.tracks.push(track);
+                    repeat.line_names.push(Vec::new());
+                } else {
+                    components.push(GridTemplateComponent::Single(track));
+                }
+                continue;
+            }
+            _ => {
+                components.push(GridTemplateComponent::Single(track));
+                continue;
+            }
+        };
+        components.push(GridTemplateComponent::Repeat(GridTemplateRepetition {
+            count,
+            tracks: vec![track],
+            line_names: vec![Vec::new(), Vec::new()],
+        }));
     }
+    components
+}
+
+fn track_sizing(min: LayoutValue, max: LayoutValue) -> TrackSizingFunction {
+    let min = match min.unit {
+        1 => MinTrackSizingFunction::length(min.value),
+        2 => MinTrackSizingFunction::percent(min.value / 100.0),
+        4 => MinTrackSizingFunction::min_content(),
+        5 => MinTrackSizingFunction::max_content(),
+        _ => MinTrackSizingFunction::auto(),
+    };
+    let max = match max.unit {
+        1 => MaxTrackSizingFunction::length(max.value),
+        2 => MaxTrackSizingFunction::percent(max.value / 100.0),
+        3 => MaxTrackSizingFunction::fr(max.value),
+        4 => MaxTrackSizingFunction::min_content(),
+        5 => MaxTrackSizingFunction::max_content(),
+        6 => MaxTrackSizingFunction::fit_content_px(max.value),
+        7 => MaxTrackSizingFunction::fit_content_percent(max.value / 100.0),
+        _ => MaxTrackSizingFunction::auto(),
+    };
+    MinMax { min, max }
 }
 
 /// Parse track list from f32 array. val > 0 = px, val < 0 = fr, val == 0 = auto.
-fn parse_track_list(vals: *const f32, count: usize) -> Vec<GridTemplateComponent<String>> {
-    let slice = unsafe { std::slice::from_raw_parts(vals, count) };
-    slice.iter().map(|&v| {
-        let tsf = if v > 0.0 {
+fn parse_track_list(vals: &[f32]) -> Vec<GridTemplateComponent<String>> {
+    parse_tracks(vals).into_iter().map(GridTemplateComponent::from).collect()
+}
+
+/// The scalar track encoding: >0 px, <0 fr (by magnitude), 0 auto.
+fn parse_tracks(vals: &[f32]) -> Vec<TrackSizingFunction> {
+    vals.iter().map(|&v| {
+        if v > 0.0 {
             MinMax { min: MinTrackSizingFunction::length(v), max: MaxTrackSizingFunction::length(v) }
         } else if v < 0.0 {
             MinMax { min: MinTrackSizingFunction::length(0.0), max: MaxTrackSizingFunction::fr(v.abs()) }
         } else {
             MinMax { min: MinTrackSizingFunction::auto(), max: MaxTrackSizingFunction::auto() }
-        };
-        GridTemplateComponent::from(tsf)
+        }
     }).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern "C" fn halve(user_data: *mut c_void, tree: *mut LayoutTree, node: u64, parent_w: f32, _parent_h: f32) {
+        unsafe { *(user_data as *mut *mut LayoutTree) = tree };
+        layout_set_width(tree, node, parent_w / 2.0);
+    }
+
+    fn size(tree: *const LayoutTree, node: u64) -> (f32, f32) {
+        let (mut x, mut y, mut w, mut h) = (0.0, 0.0, 0.0, 0.0);
+        layout_get_result(tree, node, &mut x, &mut y, &mut w, &mut h);
+        (w, h)
+    }
+
+    fn last_error() -> Option<String> {
+        let message = error::layout_last_error_message();
+        (!message.is_null()).then(|| unsafe { std::ffi::CStr::from_ptr(message) }.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn stale_handles_are_rejected_with_an_error() {
+        let tree = layout_tree_new();
+        let parent = layout_new_node(tree);
+        let stale = layout_new_node(tree);
+        layout_remove_node(tree, stale);
+        // The freed slot is reused (the low 32 bits), under a new generation
+        let reused = layout_new_node(tree);
+        assert_ne!(reused, stale);
+        assert_eq!(reused as u32, stale as u32);
+
+        error::layout_clear_last_error();
+        layout_set_width(tree, stale, 80.0);
+        assert!(last_error().is_some_and(|e| e.contains(&stale.to_string())));
+        assert!(sync::shared(tree).1.tree.style(NodeId::from(reused)).unwrap().size.width.is_auto());
+
+        error::layout_clear_last_error();
+        layout_add_child(tree, parent, stale);
+        assert!(last_error().is_some());
+        assert_eq!(layout_child_count(tree, parent), 0);
+
+        error::layout_clear_last_error();
+        layout_add_child(tree, parent, reused);
+        assert_eq!(last_error(), None);
+        assert_eq!(layout_get_child(tree, parent, 0), reused);
+        layout_tree_free(tree);
+    }
+
+    extern "C" fn freed(user_data: *mut c_void, _token: u64, status: u8) {
+        let sender = unsafe { &*(user_data as *const std::sync::mpsc::Sender<u8>) };
+        sender.send(status).unwrap();
+    }
+
+    #[test]
+    fn detached_subtrees_are_freed_on_the_worker() {
+        let tree = sync::layout_tree_new_threadsafe();
+        let root = layout_new_node(tree);
+        let view = layout_new_node(tree);
+        layout_add_child(tree, root, view);
+        let mut nodes = vec![view];
+        for _ in 0..40 {
+            let row = layout_new_node(tree);
+            layout_add_child(tree, view, row);
+            for _ in 0..50 {
+                let cell = layout_new_node(tree);
+                layout_add_child(tree, row, cell);
+                nodes.push(cell);
+            }
+            nodes.push(row);
+        }
+        let handle = layout_detach_subtree(tree, view);
+        assert_eq!(layout_child_count(tree, root), 0);
+
+        let (sender, receiver) = std::sync::mpsc::channel::<u8>();
+        let token = layout_destroy_detached(tree, handle, Some(freed), &sender as *const _ as *mut c_void);
+        assert_ne!(token, 0);
+        // The tree stays usable while the worker frees
+        layout_set_width(tree, root, 100.0);
+        assert_eq!(receiver.recv().unwrap(), background::COMPUTE_DONE);
+        assert!(nodes.iter().all(|&node| !layout_node_is_valid(tree, node)));
+        // The handle is used up
+        assert_eq!(layout_destroy_detached(tree, handle, None, std::ptr::null_mut()), 0);
+        layout_compute(tree, root, 100.0, 100.0);
+        assert_eq!(size(tree, root).0, 100.0);
+        layout_tree_free(tree);
+    }
+
+    #[test]
+    fn null_child_arrays_clear_or_are_refused() {
+        let tree = layout_tree_new();
+        let parent = layout_new_node(tree);
+        let kids = [layout_new_node(tree), layout_new_node(tree)];
+        layout_set_children(tree, parent, kids.as_ptr(), 2);
+        layout_set_children(tree, parent, std::ptr::null(), 2);
+        assert_eq!(layout_child_count(tree, parent), 2);
+        layout_set_children(tree, parent, std::ptr::null(), 0);
+        assert_eq!(layout_child_count(tree, parent), 0);
+        assert_eq!(layout_new_node_with_children(tree, std::ptr::null(), 1), 0);
+        assert_ne!(layout_new_node_with_children(tree, std::ptr::null(), 0), 0);
+        layout_tree_free(tree);
+    }
+
+    #[test]
+    fn track_descriptors_extend_the_repeat_before_them() {
+        let px = |value| LayoutValue { unit: 1, value };
+        let fr = LayoutValue { unit: 3, value: 1.0 };
+        let descs = [
+            GridTrackDesc { kind: 1, repeat: 2, min: px(50.0), max: px(50.0) },
+            GridTrackDesc { kind: 4, repeat: 0, min: LayoutValue::default(), max: fr },
+            GridTrackDesc { kind: 0, repeat: 0, min: px(20.0), max: px(20.0) },
+        ];
+        let tree = layout_tree_new();
+        let grid = layout_new_node(tree);
+        layout_set_display(tree, grid, 2);
+        layout_set_width(tree, grid, 420.0);
+        layout_set_height(tree, grid, 10.0);
+        layout_set_grid_template_columns_desc(tree, grid, descs.as_ptr(), descs.len());
+        // A null array is ignored rather than read
+        layout_set_grid_template_columns_desc(tree, grid, std::ptr::null(), 3);
+        let cells: Vec<u64> = (0..5).map(|_| layout_new_node(tree)).collect();
+        for &cell in &cells {
+            layout_add_child(tree, grid, cell);
+        }
+        layout_compute(tree, grid, 420.0, 10.0);
+        let widths: Vec<f32> = cells.iter().map(|&cell| size(tree, cell).0).collect();
+        assert_eq!(widths, [50.0, 150.0, 50.0, 150.0, 20.0]);
+        layout_tree_free(tree);
+    }
+
+    #[test]
+    fn detached_computes_hand_resolvers_the_copy() {
+        for threadsafe in [false, true] {
+            let tree = if threadsafe { sync::layout_tree_new_threadsafe() } else { layout_tree_new() };
+            let root = layout_new_node(tree);
+            let card = layout_new_node(tree);
+            layout_set_width(tree, card, 40.0);
+            layout_set_height(tree, card, 10.0);
+            layout_add_child(tree, root, card);
+            layout_compute(tree, root, 300.0, 100.0);
+            let mut seen: *mut LayoutTree = std::ptr::null_mut();
+            layout_set_style_resolver(tree, card, Some(halve), &mut seen as *mut _ as *mut c_void);
+            let generation = sync::shared(tree).1.style_generation;
+
+            let (mut w, mut h) = (0.0, 0.0);
+            assert!(layout_measure_node(tree, card, 200.0, 50.0, &mut w, &mut h));
+            assert_eq!((w, h), (100.0, 10.0));
+            assert!(!seen.is_null() && seen != tree);
+
+            let copy = layout_compute_detached(tree, card, 120.0, 50.0);
+            assert_eq!(seen, copy);
+            assert_eq!(size(copy, card), (60.0, 10.0));
+            layout_tree_free(copy);
+
+            // The resolver never ran against the tree itself
+            assert_eq!(sync::shared(tree).1.style_generation, generation);
+            assert!(!layout_is_dirty(tree, root));
+            assert_eq!(size(tree, card), (40.0, 10.0));
+            layout_tree_free(tree);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Paint list export: every displayed node in paint order with its root-space rect
+//! and the clip rect its ancestors impose on it.
+//!
+//! A node whose overflow is not visible clips its descendants to its padding box on
+//! that axis. Nodes marked with `layout_set_clip_escape` (tooltips, validation
+//! badges) are laid out in place but drop every ancestor clip, the way adorner
+//! layers render; their own descendants are clipped from there as usual. A scroll
+//! container's children are listed where its scroll offset moves them.
+
+use taffy::prelude::*;
+use taffy::{Layout, Overflow, Point};
+
+use crate::error::ffi_guard;
+use crate::{out_buffer, root_origin, scroll, sync, LayoutTree, TreeState};
+
+/// Left, top, right, bottom in root space.
+pub(crate) type Bounds = [f32; 4];
+
+pub(crate) const UNCLIPPED: Bounds = [f32::NEG_INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::INFINITY];
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutPaintItem {
+    pub node: u64,
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    /// Clip rect from ancestors. Unclipped nodes report x/y = -inf and w/h = +inf.
+    pub clip_x: f32,
+    pub clip_y: f32,
+    pub clip_w: f32,
+    pub clip_h: f32,
+}
+
+/// Let `node` escape ancestor clipping in the paint list and hit testing. It is
+/// still laid out in place.
+#[no_mangle]
+pub extern "C" fn layout_set_clip_escape(tree: *mut LayoutTree, node: u64, escape: bool) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return;
+        }
+        if escape {
+            tree.clip_escape.insert(id);
+        } else {
+            tree.clip_escape.remove(&id);
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_get_clip_escape(tree: *const LayoutTree, node: u64) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.clip_escape.contains(&NodeId::from(node))
+    })
+}
+
+/// Paint list for `root`'s subtree: parents before children, siblings in order,
+/// `Display::None` subtrees left out. Returns the number of items; only the first
+/// `cap` are written.
+#[no_mangle]
+pub extern "C" fn layout_get_paint_list(tree: *const LayoutTree, root: u64, out: *mut LayoutPaintItem, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let root = NodeId::from(root);
+        let Some((_, origin)) = root_origin(tree, root) else { return 0 };
+        let out = out_buffer(out, cap);
+        let location = tree.final_layout(root).location;
+        let parent_origin = Point { x: origin.x - location.x, y: origin.y - location.y };
+        let mut count = 0;
+        let mut stack = vec![(root, parent_origin, UNCLIPPED)];
+        while let Some((id, parent_origin, clip)) = stack.pop() {
+            let style = tree.tree.style(id).unwrap();
+            if style.display == Display::None {
+                continue;
+            }
+            let clip = node_clip(tree, id, clip);
+            let layout = tree.final_layout(id);
+            let rect = node_bounds(layout, parent_origin);
+            if let Some(slot) = out.get_mut(count) {
+                *slot = LayoutPaintItem {
+                    node: id.into(),
+                    x: rect[0],
+                    y: rect[1],
+                    w: layout.size.width,
+                    h: layout.size.height,
+                    clip_x: clip[0],
+                    clip_y: clip[1],
+                    clip_w: clip[2] - clip[0],
+                    clip_h: clip[3] - clip[1],
+                };
+            }
+            count += 1;
+            let origin = children_origin(tree, id, rect);
+            let child_clip = children_clip(style, layout, rect, clip);
+            stack.extend(tree.tree.children(id).unwrap().into_iter().rev().map(|child| (child, origin, child_clip)));
+        }
+        count
+    })
+}
+
+/// The clip that applies to `id` itself given its ancestors' clip.
+pub(crate) fn node_clip(tree: &TreeState, id: NodeId, inherited: Bounds) -> Bounds {
+    if tree.clip_escape.contains(&id) { UNCLIPPED } else { inherited }
+}
+
+pub(crate) fn node_bounds(layout: &Layout, parent_origin: Point<f32>) -> Bounds {
+    let x = parent_origin.x + layout.location.x;
+    let y = parent_origin.y + layout.location.y;
+    [x, y, x + layout.size.width, y + layout.size.height]
+}
+
+/// Where the node's children are placed from: its border-box origin, moved by its
+/// scroll offset.
+pub(crate) fn children_origin(tree: &TreeState, id: NodeId, rect: Bounds) -> Point<f32> {
+    let scroll = scroll::offset_of(tree, id);
+    Point { x: rect[0] - scroll.x, y: rect[1] - scroll.y }
+}
+
+/// The clip a node passes on to its children: its own clip, narrowed to its padding
+/// box on each axis whose overflow is not visible.
+pub(crate) fn children_clip(style: &Style, layout: &Layout, rect: Bounds, clip: Bounds) -> Bounds {
+    let mut clip = clip;
+    if style.overflow.x != Overflow::Visible {
+        clip[0] = clip[0].max(rect[0] + layout.border.left);
+        clip[2] = clip[2].min(rect[2] - layout.border.right);
+    }
+    if style.overflow.y != Overflow::Visible {
+        clip[1] = clip[1].max(rect[1] + layout.border.top);
+        clip[3] = clip[3].min(rect[3] - layout.border.bottom);
+    }
+    clip
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll::layout_set_scroll_offset;
+    use crate::{
+        layout_add_child, layout_compute, layout_new_node, layout_set_flex_direction, layout_set_flex_shrink,
+        layout_set_height, layout_set_overflow, layout_set_width, layout_tree_free, layout_tree_new,
+    };
+
+    #[test]
+    fn scrolled_children_are_painted_where_displayed() {
+        let tree = layout_tree_new();
+        let list = layout_new_node(tree);
+        layout_set_width(tree, list, 200.0);
+        layout_set_height(tree, list, 200.0);
+        layout_set_flex_direction(tree, list, 0);
+        layout_set_overflow(tree, list, 2);
+        for _ in 0..5 {
+            let child = layout_new_node(tree);
+            layout_set_height(tree, child, 100.0);
+            layout_set_flex_shrink(tree, child, 0.0);
+            layout_add_child(tree, list, child);
+        }
+        layout_compute(tree, list, 200.0, 200.0);
+        layout_set_scroll_offset(tree, list, 0.0, 250.0);
+
+        let mut items = [LayoutPaintItem::default(); 6];
+        assert_eq!(layout_get_paint_list(tree, list, items.as_mut_ptr(), 6), 6);
+        let tops: Vec<f32> = items.iter().map(|item| item.y).collect();
+        assert_eq!(tops, [0.0, -250.0, -150.0, -50.0, 50.0, 150.0]);
+        // The container clips its children to its own box, not the scrolled content
+        assert_eq!((items[1].clip_y, items[1].clip_h), (0.0, 200.0));
+        layout_tree_free(tree);
+    }
+}
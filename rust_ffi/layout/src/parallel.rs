@@ -0,0 +1,716 @@
+//! Parallel layout of independent subtrees.
+//!
+//! With parallelism above 1, each layout pass first looks for panels: dirty flex
+//! or grid containers of fixed px size that cannot grow or shrink, with at least
+//! `MIN_PANEL_NODES` nodes under them. A panel's inside does not depend on
+//! anything outside it, so each one is copied to a scratch Taffy tree and laid out
+//! on a worker thread, and the main pass finds the panel's size already cached.
+//! The layouts of the nodes inside are kept here and read through the tree's
+//! layout accessors, rounded after every pass the way Taffy would have rounded
+//! them. Every panel is laid out by itself whatever the thread count, so results
+//! match a single-threaded compute exactly.
+//!
+//! A panel's nodes keep placeholder cache entries in the main tree so that
+//! changes inside it still dirty the panel; its kept layouts are dropped, and the
+//! placeholders cleared, before any pass once the panel is dirty again or a
+//! compute starts at or inside it.
+//!
+//! Panels run on a pool of worker threads shared by every tree in the process.
+//! Workers start as computes ask for more of them and then wait for the next
+//! pass, so a pass does not pay for starting threads. A thread waiting for its
+//! panels runs queued ones itself, so a parallel compute started from inside
+//! another (a style resolver computing another tree) cannot starve the pool.
+//!
+//! The pool is the crate's own rather than rayon's: the library ships as a
+//! single cdylib whose only dependencies are taffy and serde, and the pool needs
+//! just `run_each`, a scoped fork-join over a slice of jobs. Moving to rayon's
+//! global pool would replace the "Worker pool" section and nothing else.
+
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+
+use taffy::prelude::*;
+use taffy::{CacheTree, CompactLength, DetailedLayoutInfo, LayoutOutput, Point, RunMode};
+
+use crate::error::ffi_guard;
+use crate::{baseline, is_descendant_or_self, measure_leaf, sync, LayoutTree, TreeState, NodeContext};
+
+/// Smaller subtrees are cheaper to lay out in place than to copy.
+pub(crate) const MIN_PANEL_NODES: usize = 32;
+
+/// Lay independent subtrees out on up to `threads` threads, the calling thread
+/// included. 0 and 1 (the default) compute on the calling thread only. Computes
+/// run single-threaded while profiling is on.
+#[no_mangle]
+pub extern "C" fn layout_tree_set_parallelism(tree: *mut LayoutTree, threads: u32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        tree.parallel.threads = threads.max(1);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_tree_get_parallelism(tree: *const LayoutTree) -> u32 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        tree.parallel.threads
+    })
+}
+
+#[derive(Clone)]
+pub(crate) struct Parallel {
+    threads: u32,
+    /// Panels laid out off the main tree, keyed by panel.
+    panels: HashMap<NodeId, Panel>,
+    /// Layouts of the nodes inside panels.
+    pub(crate) layouts: HashMap<NodeId, KeptLayout>,
+    /// Detailed layout of the grid containers among panels and their nodes.
+    pub(crate) details: HashMap<NodeId, DetailedLayoutInfo>,
+}
+
+impl Default for Parallel {
+    fn default() -> Self {
+        Parallel { threads: 1, panels: HashMap::new(), layouts: HashMap::new(), details: HashMap::new() }
+    }
+}
+
+impl Parallel {
+    pub(crate) fn clear(&mut self) {
+        self.panels.clear();
+        self.layouts.clear();
+        self.details.clear();
+    }
+}
+
+#[derive(Clone)]
+struct Panel {
+    /// The size the panel was laid out at.
+    size: Size<f32>,
+    /// Its descendants.
+    nodes: Vec<NodeId>,
+}
+
+#[derive(Clone)]
+pub(crate) struct KeptLayout {
+    panel: NodeId,
+    pub(crate) unrounded: Layout,
+    pub(crate) rounded: Layout,
+}
+
+/// A panel copied out of the main tree.
+pub(crate) struct Job {
+    tree: TaffyTree<NodeContext>,
+    /// Scratch and main-tree ID of each node, panel first.
+    ids: Vec<(NodeId, NodeId)>,
+    avail: Size<AvailableSpace>,
+}
+
+// Contexts only carry host pointers, which measuring never reads
+unsafe impl Send for Job {}
+
+impl Job {
+    pub(crate) fn run(&mut self) {
+        let _ = self.tree.compute_layout_with_measure(self.ids[0].0, self.avail, measure_leaf);
+        // The panel may itself be aligned by the baselines inside it
+        let leaves: Vec<NodeId> = self.ids.iter().map(|&(scratch, _)| scratch).collect();
+        if baseline::settle_leaves(&mut self.tree, &leaves, true) {
+            let _ = self.tree.compute_layout_with_measure(self.ids[0].0, self.avail, measure_leaf);
+        }
+    }
+
+    /// What laying the panel out produced, once run.
+    pub(crate) fn output(&self) -> Option<LayoutOutput> {
+        self.tree.cache_get(self.ids[0].0, Size::NONE, self.avail, RunMode::PerformLayout)
+    }
+}
+
+/// Drop what is stale before a pass over `root`, then lay out the dirty panels
+/// under it in parallel.
+pub(crate) fn before_pass(tree: &mut TreeState, root: NodeId) {
+    drop_stale(tree, root);
+    if let Some(kept) = tree.parallel.layouts.get(&root) {
+        // Computing inside a panel: the root's subtree is laid out in place
+        let panel = kept.panel;
+        let inside = tree.subtree(root);
+        for &id in &inside {
+            forget(tree, id);
+        }
+        if let Some(p) = tree.parallel.panels.get_mut(&panel) {
+            p.nodes.retain(|id| !inside.contains(id));
+        }
+    }
+    if !runs_parallel(tree) {
+        return;
+    }
+    let panels = find_panels(tree, root);
+    if panels.len() < 2 {
+        return;
+    }
+    let mut jobs: Vec<Job> = panels.iter().map(|&panel| copy_panel(tree, panel, None)).collect();
+    run_each(&mut jobs, tree.parallel.threads as usize, Job::run);
+    for (panel, job) in panels.into_iter().zip(jobs) {
+        if let Some(output) = job.output() {
+            keep_panel(tree, panel, job, output);
+        }
+    }
+}
+
+/// Whether computes lay panels out on several threads.
+pub(crate) fn runs_parallel(tree: &TreeState) -> bool {
+    tree.parallel.threads >= 2 && tree.profiler.is_none()
+}
+
+/// Lay out `panels`, children of `root`, one after another for a streamed
+/// compute, calling `done` as each one's subtree is final. A first pass with
+/// every dirty panel standing in as an empty box of its fixed size places them;
+/// then each panel's inside is laid out and kept, and rounded from its place.
+/// The main pass that follows finds the panels laid out.
+pub(crate) fn lay_out_in_turn(
+    tree: &mut TreeState, root: NodeId, avail: Size<AvailableSpace>, panels: &[NodeId], done: impl Fn(&TreeState, NodeId),
+) {
+    drop_stale(tree, root);
+    for &panel in panels {
+        if tree.parallel.panels.contains_key(&panel) {
+            continue;
+        }
+        let style = tree.tree.style(panel).unwrap();
+        let size = Size { width: style.size.width.into_raw().value(), height: style.size.height.into_raw().value() };
+        let known = Size { width: Some(size.width), height: Some(size.height) };
+        let output = LayoutOutput::from_outer_size(size);
+        tree.tree.cache_store(panel, known, Size::MAX_CONTENT, RunMode::PerformLayout, output);
+        tree.tree.cache_store(panel, known, Size::MAX_CONTENT, RunMode::ComputeSize, output);
+    }
+    crate::taffy_pass(tree, root, avail);
+    for &panel in panels {
+        if !tree.parallel.panels.contains_key(&panel) {
+            let mut job = copy_panel(tree, panel, None);
+            job.run();
+            let Some(output) = job.output() else {
+                // Laid out in place by the main pass instead
+                let _ = tree.tree.mark_dirty(panel);
+                continue;
+            };
+            keep_panel(tree, panel, job, output);
+        }
+        let Some(origin) = origin_in(tree, panel, root) else { continue };
+        settle_panel(tree, panel, origin);
+        done(tree, panel);
+    }
+    // The root's content size takes in what the panels' insides overflow
+    let _ = tree.tree.mark_dirty(root);
+}
+
+/// Drop the panels that are dirty, removed, or the root of a compute.
+pub(crate) fn drop_stale(tree: &mut TreeState, root: NodeId) {
+    let stale: Vec<NodeId> = tree.parallel.panels.keys().copied()
+        .filter(|&panel| panel == root || !tree.contains(panel) || tree.tree.dirty(panel).unwrap_or(true))
+        .collect();
+    for panel in stale {
+        drop_panel(tree, panel);
+    }
+}
+
+/// Drop the panels at or under `id`.
+pub(crate) fn drop_inside(tree: &mut TreeState, id: NodeId) {
+    let inside: Vec<NodeId> = tree.parallel.panels.keys().copied()
+        .filter(|&panel| is_descendant_or_self(&tree.tree, panel, id))
+        .collect();
+    for panel in inside {
+        drop_panel(tree, panel);
+    }
+}
+
+/// Check the panels under `root` against the pass that just ran and round their
+/// nodes. Returns false if a panel was laid out at another size and the pass has
+/// to run again.
+pub(crate) fn after_pass(tree: &mut TreeState, root: NodeId) -> bool {
+    let mut resized = Vec::new();
+    let panels: Vec<NodeId> = tree.parallel.panels.keys().copied().collect();
+    for panel in panels {
+        let Some(origin) = origin_in(tree, panel, root) else { continue };
+        if tree.tree.unrounded_layout(panel).size != tree.parallel.panels[&panel].size {
+            resized.push(panel);
+            continue;
+        }
+        settle_panel(tree, panel, origin);
+    }
+    for &panel in &resized {
+        drop_panel(tree, panel);
+        let _ = tree.tree.mark_dirty(panel);
+    }
+    resized.is_empty()
+}
+
+/// Give a panel laid out at its size in the main tree placeholder cache entries,
+/// so that changes inside it propagate up to it, and round its nodes from its
+/// unrounded `origin` in compute root space.
+pub(crate) fn settle_panel(tree: &mut TreeState, panel: NodeId, origin: Point<f32>) {
+    let sizes: Vec<(NodeId, Size<f32>)> = tree.parallel.panels[&panel].nodes.iter()
+        .filter_map(|id| Some((*id, tree.parallel.layouts.get(id)?.unrounded.size)))
+        .collect();
+    for (id, size) in sizes {
+        let known = Size { width: Some(size.width), height: Some(size.height) };
+        let output = LayoutOutput::from_outer_size(size);
+        tree.tree.cache_store(id, known, Size::MAX_CONTENT, RunMode::PerformLayout, output);
+    }
+    round_panel(tree, panel, origin);
+}
+
+/// Forget a node that is being removed.
+pub(crate) fn forget_node(tree: &mut TreeState, id: NodeId) {
+    if tree.parallel.panels.contains_key(&id) {
+        drop_panel(tree, id);
+    }
+    forget(tree, id);
+}
+
+/// Drop a panel's kept layouts and clear its placeholders, leaving its nodes as
+/// dirty as the panel.
+fn drop_panel(tree: &mut TreeState, panel: NodeId) {
+    let Some(p) = tree.parallel.panels.remove(&panel) else { return };
+    tree.parallel.details.remove(&panel);
+    for id in p.nodes {
+        forget(tree, id);
+    }
+}
+
+fn forget(tree: &mut TreeState, id: NodeId) {
+    tree.parallel.details.remove(&id);
+    if tree.parallel.layouts.remove(&id).is_some() && tree.contains(id) {
+        tree.tree.cache_clear(id);
+    }
+}
+
+/// The panels to lay out in parallel under `root`, outermost first. Clean
+/// subtrees are skipped, as the pass will not lay them out anyway.
+fn find_panels(tree: &TreeState, root: NodeId) -> Vec<NodeId> {
+    let mut panels = Vec::new();
+    let mut stack = tree.tree.children(root).unwrap();
+    stack.reverse();
+    while let Some(id) = stack.pop() {
+        if !tree.tree.dirty(id).unwrap_or(false) {
+            continue;
+        }
+        if is_panel(tree, id) && tree.subtree(id).len() >= MIN_PANEL_NODES {
+            panels.push(id);
+            continue;
+        }
+        let children = tree.tree.children(id).unwrap();
+        stack.extend(children.into_iter().rev());
+    }
+    panels
+}
+
+/// Whether the node's box is fixed whatever its parent does, so that its inside
+/// can be laid out on its own.
+pub(crate) fn is_panel(tree: &TreeState, id: NodeId) -> bool {
+    let style = tree.tree.style(id).unwrap();
+    let px = |v: CompactLength| v.tag() == CompactLength::LENGTH_TAG;
+    matches!(style.display, Display::Flex | Display::Grid)
+        && px(style.size.width.into_raw())
+        && px(style.size.height.into_raw())
+        && style.min_size.width.is_auto()
+        && style.min_size.height.is_auto()
+        && style.max_size.width.is_auto()
+        && style.max_size.height.is_auto()
+        && style.flex_basis.is_auto()
+        && style.flex_grow == 0.0
+        && style.flex_shrink == 0.0
+        // Percentages would resolve against the parent
+        && [style.padding, style.border].iter().all(|edges| {
+            [edges.left, edges.right, edges.top, edges.bottom].iter().all(|v| px(v.into_raw()))
+        })
+}
+
+/// Copy the panel's subtree, with the styles and contexts Taffy lays out, into a
+/// scratch tree of its own. A `size` lays the panel out at that border-box size
+/// instead of its own, percentages resolving against its parent's content box.
+pub(crate) fn copy_panel(tree: &TreeState, panel: NodeId, size: Option<Size<f32>>) -> Job {
+    let ids = tree.subtree(panel);
+    let mut scratch = TaffyTree::with_capacity(ids.len());
+    scratch.disable_rounding();
+    let index: HashMap<NodeId, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+    let scratch_ids: Vec<NodeId> = ids.iter()
+        .map(|&id| {
+            let node = scratch.new_leaf(tree.tree.style(id).unwrap().clone()).unwrap();
+            if let Some(context) = tree.tree.get_node_context(id) {
+                let _ = scratch.set_node_context(node, Some(context.clone()));
+            }
+            node
+        })
+        .collect();
+    for (i, &id) in ids.iter().enumerate() {
+        let children: Vec<NodeId> = tree.tree.children(id).unwrap().iter().map(|c| scratch_ids[index[c]]).collect();
+        if !children.is_empty() {
+            let _ = scratch.set_children(scratch_ids[i], &children);
+        }
+    }
+    let avail = match size {
+        Some(size) => {
+            let mut style = tree.tree.style(panel).unwrap().clone();
+            style.size = Size { width: Dimension::length(size.width), height: Dimension::length(size.height) };
+            style.min_size = Size::auto();
+            style.max_size = Size::auto();
+            style.box_sizing = BoxSizing::BorderBox;
+            let _ = scratch.set_style(scratch_ids[0], style);
+            let parent = tree.tree.parent(panel).map(|parent| tree.unrounded_layout(parent));
+            let inner = |size: f32, edges: f32| AvailableSpace::Definite((size - edges).max(0.0));
+            match parent {
+                Some(p) => Size {
+                    width: inner(p.size.width, p.padding.left + p.padding.right + p.border.left + p.border.right),
+                    height: inner(p.size.height, p.padding.top + p.padding.bottom + p.border.top + p.border.bottom),
+                },
+                None => Size { width: AvailableSpace::Definite(size.width), height: AvailableSpace::Definite(size.height) },
+            }
+        }
+        None => {
+            let style = tree.tree.style(panel).unwrap();
+            Size {
+                width: AvailableSpace::Definite(style.size.width.into_raw().value()),
+                height: AvailableSpace::Definite(style.size.height.into_raw().value()),
+            }
+        }
+    };
+    Job { tree: scratch, ids: scratch_ids.into_iter().zip(ids).collect(), avail }
+}
+
+/// Keep a laid-out panel's layouts and cache its size in the main tree.
+pub(crate) fn keep_panel(tree: &mut TreeState, panel: NodeId, job: Job, output: LayoutOutput) {
+    let known = Size { width: Some(output.size.width), height: Some(output.size.height) };
+    tree.tree.cache_store(panel, known, Size::MAX_CONTENT, RunMode::PerformLayout, output);
+    tree.tree.cache_store(panel, known, Size::MAX_CONTENT, RunMode::ComputeSize, output);
+    for &(scratch, id) in &job.ids {
+        if let grid @ DetailedLayoutInfo::Grid(_) = job.tree.detailed_layout_info(scratch) {
+            tree.parallel.details.insert(id, grid.clone());
+        }
+        if id != panel {
+            let unrounded = *job.tree.unrounded_layout(scratch);
+            tree.parallel.layouts.insert(id, KeptLayout { panel, unrounded, rounded: unrounded });
+        }
+    }
+    tree.parallel.panels.insert(panel, Panel { size: output.size, nodes: job.ids[1..].iter().map(|&(_, id)| id).collect() });
+}
+
+/// The panel's position in `root` space, where Taffy's rounding starts; None if
+/// it is not under `root`.
+pub(crate) fn origin_in(tree: &TreeState, panel: NodeId, root: NodeId) -> Option<Point<f32>> {
+    let mut origin = Point { x: 0.0, y: 0.0 };
+    let mut id = panel;
+    loop {
+        let location = tree.unrounded_layout(id).location;
+        origin.x += location.x;
+        origin.y += location.y;
+        if id == root {
+            return Some(origin);
+        }
+        id = tree.tree.parent(id)?;
+    }
+}
+
+/// Round the panel's nodes as Taffy rounds, from the panel's unrounded origin.
+fn round_panel(tree: &mut TreeState, panel: NodeId, origin: Point<f32>) {
+    let rounding = tree.rounding;
+    let mut stack: Vec<(NodeId, Point<f32>)> =
+        tree.tree.children(panel).unwrap().into_iter().map(|child| (child, origin)).collect();
+    while let Some((id, parent)) = stack.pop() {
+        let Some(kept) = tree.parallel.layouts.get_mut(&id) else { continue };
+        let unrounded = kept.unrounded;
+        let x = parent.x + unrounded.location.x;
+        let y = parent.y + unrounded.location.y;
+        if rounding {
+            let mut layout = unrounded;
+            let (w, h) = (unrounded.size.width, unrounded.size.height);
+            layout.location.x = unrounded.location.x.round();
+            layout.location.y = unrounded.location.y.round();
+            layout.size.width = (x + w).round() - x.round();
+            layout.size.height = (y + h).round() - y.round();
+            layout.scrollbar_size.width = unrounded.scrollbar_size.width.round();
+            layout.scrollbar_size.height = unrounded.scrollbar_size.height.round();
+            layout.border.left = (x + unrounded.border.left).round() - x.round();
+            layout.border.right = (x + w).round() - (x + w - unrounded.border.right).round();
+            layout.border.top = (y + unrounded.border.top).round() - y.round();
+            layout.border.bottom = (y + h).round() - (y + h - unrounded.border.bottom).round();
+            layout.padding.left = (x + unrounded.padding.left).round() - x.round();
+            layout.padding.right = (x + w).round() - (x + w - unrounded.padding.right).round();
+            layout.padding.top = (y + unrounded.padding.top).round() - y.round();
+            layout.padding.bottom = (y + h).round() - (y + h - unrounded.padding.bottom).round();
+            layout.content_size.width = (x + unrounded.content_size.width).round() - x.round();
+            layout.content_size.height = (y + unrounded.content_size.height).round() - y.round();
+            kept.rounded = layout;
+        } else {
+            kept.rounded = unrounded;
+        }
+        stack.extend(tree.tree.children(id).unwrap().into_iter().map(|child| (child, Point { x, y })));
+    }
+}
+
+// ============================================================================
+// Worker pool
+// ============================================================================
+
+/// Run `f` on every job, spread over up to `threads` threads: the calling thread
+/// and pool workers, each taking a contiguous run of jobs. Returns once every job
+/// is done; a panic in any of them is raised again on the calling thread.
+pub(crate) fn run_each<J: Send>(jobs: &mut [J], threads: usize, f: impl Fn(&mut J) + Sync) {
+    let threads = threads.clamp(1, jobs.len().max(1));
+    if threads == 1 {
+        jobs.iter_mut().for_each(f);
+        return;
+    }
+    let per_thread = jobs.len().div_ceil(threads);
+    let mut chunks = jobs.chunks_mut(per_thread);
+    let own = chunks.next().unwrap();
+    let batch = Arc::new(Batch { state: Mutex::new(BatchState::default()), done: Condvar::new() });
+    let f = &f;
+    let tasks: Vec<Task> = chunks
+        .map(|chunk| {
+            let batch = batch.clone();
+            let task: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(|| chunk.iter_mut().for_each(f)));
+                batch.finish(result.err());
+            });
+            // The jobs and `f` outlive the task: this call waits for every task
+            // below before returning or unwinding
+            unsafe { std::mem::transmute::<Box<dyn FnOnce() + Send + '_>, Task>(task) }
+        })
+        .collect();
+    batch.state.lock().unwrap_or_else(|e| e.into_inner()).pending = tasks.len();
+    POOL.submit(tasks, threads - 1);
+    let own = panic::catch_unwind(AssertUnwindSafe(|| own.iter_mut().for_each(f)));
+    let failed = batch.wait();
+    if let Some(payload) = own.err().or(failed) {
+        panic::resume_unwind(payload);
+    }
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+type Panic = Box<dyn Any + Send>;
+
+static POOL: Pool = Pool {
+    queue: Mutex::new(PoolQueue { tasks: VecDeque::new(), workers: 0 }),
+    queued: Condvar::new(),
+};
+
+const POOL_WORKER_NAME: &str = "keystone-layout-pool";
+
+struct Pool {
+    queue: Mutex<PoolQueue>,
+    queued: Condvar,
+}
+
+struct PoolQueue {
+    tasks: VecDeque<Task>,
+    /// Workers started so far; they never exit.
+    workers: usize,
+}
+
+impl Pool {
+    /// Queue the tasks, first starting workers until there are at least `workers`.
+    fn submit(&self, tasks: Vec<Task>, workers: usize) {
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        while queue.workers < workers {
+            let started = std::thread::Builder::new().name(POOL_WORKER_NAME.into()).spawn(|| POOL.work());
+            if started.is_err() {
+                // Waiting callers run what no worker takes
+                break;
+            }
+            queue.workers += 1;
+        }
+        queue.tasks.extend(tasks);
+        drop(queue);
+        self.queued.notify_all();
+    }
+
+    fn take(&self) -> Option<Task> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner()).tasks.pop_front()
+    }
+
+    fn work(&self) {
+        loop {
+            let task = {
+                let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+                loop {
+                    match queue.tasks.pop_front() {
+                        Some(task) => break task,
+                        None => queue = self.queued.wait(queue).unwrap_or_else(|e| e.into_inner()),
+                    }
+                }
+            };
+            task();
+        }
+    }
+}
+
+/// The tasks of one `run_each` call.
+struct Batch {
+    state: Mutex<BatchState>,
+    done: Condvar,
+}
+
+#[derive(Default)]
+struct BatchState {
+    pending: usize,
+    /// The first task panic.
+    panic: Option<Panic>,
+}
+
+impl Batch {
+    fn finish(&self, panic: Option<Panic>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.pending -= 1;
+        if state.panic.is_none() {
+            state.panic = panic;
+        }
+        if state.pending == 0 {
+            self.done.notify_all();
+        }
+    }
+
+    /// Wait for every task, running queued tasks meanwhile. Returns the first panic.
+    fn wait(&self) -> Option<Panic> {
+        loop {
+            if self.state.lock().unwrap_or_else(|e| e.into_inner()).pending == 0 {
+                break;
+            }
+            match POOL.take() {
+                Some(task) => task(),
+                None => {
+                    // Every task of this batch has been taken, so workers finish it
+                    let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                    while state.pending > 0 {
+                        state = self.done.wait(state).unwrap_or_else(|e| e.into_inner());
+                    }
+                    break;
+                }
+            }
+        }
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).panic.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        layout_add_child, layout_compute, layout_get_result, layout_get_result_unrounded, layout_new_node,
+        layout_set_flex_basis, layout_set_flex_direction, layout_set_flex_grow, layout_set_flex_shrink,
+        layout_set_compute_stream, layout_set_flex_wrap, layout_set_gap_all, layout_set_height, layout_set_padding,
+        layout_set_width, layout_tree_free, layout_tree_new,
+    };
+    use std::ffi::c_void;
+
+    /// A wrapping dashboard of six fixed-size panels, each with 8 rows of 5 cells
+    /// that grow to fractional widths, so the panels are laid out in parallel.
+    fn dashboard(threads: u32) -> (*mut LayoutTree, u64, Vec<u64>) {
+        let tree = layout_tree_new();
+        layout_tree_set_parallelism(tree, threads);
+        let root = layout_new_node(tree);
+        layout_set_flex_wrap(tree, root, 1);
+        layout_set_gap_all(tree, root, 7.0);
+        let mut cells = Vec::new();
+        for p in 0..6 {
+            let panel = layout_new_node(tree);
+            layout_set_width(tree, panel, 203.0 + p as f32);
+            layout_set_height(tree, panel, 151.0);
+            layout_set_flex_shrink(tree, panel, 0.0);
+            layout_set_flex_direction(tree, panel, 0);
+            layout_set_padding(tree, panel, 4, 3.0);
+            for r in 0..8 {
+                let row = layout_new_node(tree);
+                layout_set_flex_grow(tree, row, 1.0 + r as f32 / 3.0);
+                layout_set_gap_all(tree, row, 1.5);
+                for c in 0..5 {
+                    let cell = layout_new_node(tree);
+                    layout_set_flex_basis(tree, cell, 5.0 * c as f32);
+                    layout_set_flex_grow(tree, cell, 1.0 + c as f32 / 7.0);
+                    layout_add_child(tree, row, cell);
+                    cells.push(cell);
+                }
+                layout_add_child(tree, panel, row);
+            }
+            layout_add_child(tree, root, panel);
+        }
+        (tree, root, cells)
+    }
+
+    fn results(tree: *const LayoutTree, root: u64) -> Vec<[f32; 8]> {
+        let nodes = sync::shared(tree).1.subtree(NodeId::from(root));
+        nodes.into_iter().map(|id| {
+            let mut r = [0.0; 8];
+            let [x, y, w, h, ux, uy, uw, uh] = &mut r;
+            layout_get_result(tree, id.into(), x, y, w, h);
+            layout_get_result_unrounded(tree, id.into(), ux, uy, uw, uh);
+            r
+        }).collect()
+    }
+
+    #[test]
+    fn parallel_layout_matches_single_threaded() {
+        let (single, single_root, single_cells) = dashboard(1);
+        let (parallel, parallel_root, parallel_cells) = dashboard(4);
+        layout_compute(single, single_root, 700.0, 500.0);
+        layout_compute(parallel, parallel_root, 700.0, 500.0);
+        assert_eq!(sync::shared(parallel).1.parallel.panels.len(), 6);
+        assert_eq!(results(parallel, parallel_root), results(single, single_root));
+
+        // Relayout after a change inside one panel, with the others kept
+        layout_set_flex_grow(single, single_cells[17], 4.0);
+        layout_set_flex_grow(parallel, parallel_cells[17], 4.0);
+        layout_compute(single, single_root, 640.0, 500.0);
+        layout_compute(parallel, parallel_root, 640.0, 500.0);
+        assert_eq!(results(parallel, parallel_root), results(single, single_root));
+        layout_tree_free(parallel);
+        layout_tree_free(single);
+    }
+
+    extern "C" fn record(user_data: *mut c_void, tree: *const LayoutTree, subtree: u64) {
+        let deliveries = unsafe { &mut *(user_data as *mut Vec<(u64, usize)>) };
+        deliveries.push((subtree, sync::shared(tree).1.parallel.panels.len()));
+    }
+
+    #[test]
+    fn streamed_panels_arrive_before_the_rest_are_laid_out() {
+        let (plain, plain_root, plain_cells) = dashboard(1);
+        let (streamed, streamed_root, streamed_cells) = dashboard(1);
+        let mut deliveries: Vec<(u64, usize)> = Vec::new();
+        layout_set_compute_stream(streamed, Some(record), &mut deliveries as *mut _ as *mut c_void);
+        layout_compute(plain, plain_root, 700.0, 500.0);
+        layout_compute(streamed, streamed_root, 700.0, 500.0);
+        let panels = sync::shared(streamed).1.tree.children(NodeId::from(streamed_root)).unwrap();
+        let mut expected: Vec<(u64, usize)> = panels.into_iter().enumerate().map(|(i, panel)| (panel.into(), i + 1)).collect();
+        expected.push((streamed_root, 6));
+        assert_eq!(deliveries, expected);
+        assert_eq!(results(streamed, streamed_root), results(plain, plain_root));
+
+        // A change inside one panel lays out only that one before delivering it
+        deliveries.clear();
+        layout_set_flex_grow(plain, plain_cells[17], 4.0);
+        layout_set_flex_grow(streamed, streamed_cells[17], 4.0);
+        layout_compute(plain, plain_root, 640.0, 500.0);
+        layout_compute(streamed, streamed_root, 640.0, 500.0);
+        assert_eq!(deliveries.len(), 7);
+        assert_eq!(results(streamed, streamed_root), results(plain, plain_root));
+        layout_set_compute_stream(streamed, None, std::ptr::null_mut());
+        layout_tree_free(streamed);
+        layout_tree_free(plain);
+    }
+
+    #[test]
+    fn pool_runs_every_job_and_reraises_panics() {
+        let mut jobs: Vec<u32> = (0..37).collect();
+        run_each(&mut jobs, 5, |job| *job *= 2);
+        assert_eq!(jobs, (0..37).map(|n| n * 2).collect::<Vec<_>>());
+
+        let result = panic::catch_unwind(|| {
+            let mut jobs: Vec<u32> = (0..8).collect();
+            run_each(&mut jobs, 4, |job| assert_ne!(*job, 6));
+        });
+        assert!(result.is_err());
+        // The pool survives a panicking job
+        run_each(&mut jobs, 5, |job| *job += 1);
+        assert_eq!(jobs[36], 73);
+    }
+}
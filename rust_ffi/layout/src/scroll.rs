@@ -0,0 +1,183 @@
+//! Scroll state and scroll-linked style inputs.
+//!
+//! A scroll link drives one numeric style property of a node from the scroll
+//! offset of one of its ancestors: `value = clamp(base + factor * offset)`. Links
+//! are evaluated whenever the source's offset changes, so parallax headers and
+//! collapsing toolbars need one FFI call per scroll event rather than a style
+//! write per linked node. `layout_get_visible_children` culls a container's
+//! children against its scrolled viewport, for virtualized lists.
+//!
+//! A container's offset moves its children up and left of where Taffy put them.
+//! Layout results stay as Taffy computed them; root-space rects and coordinate
+//! conversions report where nodes are displayed.
+
+use taffy::prelude::*;
+use taffy::Point;
+
+use crate::error::ffi_guard;
+use crate::{is_descendant_or_self, mutate_style, out_buffer, set_property, sync, LayoutTree, TreeState, PROPERTY_COUNT};
+
+#[derive(Clone, Copy)]
+pub(crate) struct ScrollLink {
+    source: NodeId,
+    property: u8,
+    /// 0 = vertical offset, 1 = horizontal offset.
+    axis: u8,
+    base: f32,
+    factor: f32,
+    min: f32,
+    max: f32,
+}
+
+impl ScrollLink {
+    fn value(&self, offset: Point<f32>) -> f32 {
+        let offset = if self.axis == 1 { offset.x } else { offset.y };
+        let mut value = self.base + self.factor * offset;
+        if !self.min.is_nan() {
+            value = value.max(self.min);
+        }
+        if !self.max.is_nan() {
+            value = value.min(self.max);
+        }
+        value
+    }
+}
+
+/// Set the scroll offset of a scroll container and re-evaluate every link that
+/// reads it. Returns false for an unknown node.
+#[no_mangle]
+pub extern "C" fn layout_set_scroll_offset(tree: *mut LayoutTree, node: u64, x: f32, y: f32) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return false;
+        }
+        let offset = Point { x, y };
+        if tree.scroll_offsets.insert(id, offset) == Some(offset) {
+            return true;
+        }
+        let linked: Vec<(NodeId, ScrollLink)> = tree.scroll_links.iter()
+            .flat_map(|(&target, links)| links.iter().filter(|l| l.source == id).map(move |l| (target, *l)))
+            .collect();
+        for (target, link) in linked {
+            apply_link(tree, target, &link);
+        }
+        true
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn layout_get_scroll_offset(tree: *const LayoutTree, node: u64, out_x: &mut f32, out_y: &mut f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let offset = offset_of(tree, NodeId::from(node));
+        *out_x = offset.x;
+        *out_y = offset.y;
+    })
+}
+
+/// The node's scroll offset; zero if it has none.
+pub(crate) fn offset_of(tree: &TreeState, id: NodeId) -> Point<f32> {
+    tree.scroll_offsets.get(&id).copied().unwrap_or(Point { x: 0.0, y: 0.0 })
+}
+
+/// The children of `container` that intersect its viewport, its padding box less
+/// scrollbars moved by its scroll offset, in child order. `Display::None`
+/// children are left out. Returns the number of visible children; only the first
+/// `cap` IDs are written. Unknown containers have none.
+#[no_mangle]
+pub extern "C" fn layout_get_visible_children(tree: *const LayoutTree, container: u64, out_ids: *mut u64, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(container);
+        if !tree.contains(id) {
+            return 0;
+        }
+        let layout = tree.final_layout(id);
+        let offset = offset_of(tree, id);
+        // Viewport in the container's own space, where its children are placed
+        let left = layout.border.left + offset.x;
+        let top = layout.border.top + offset.y;
+        let right = layout.size.width - layout.border.right - layout.scrollbar_size.width + offset.x;
+        let bottom = layout.size.height - layout.border.bottom - layout.scrollbar_size.height + offset.y;
+        let out = out_buffer(out_ids, cap);
+        let mut count = 0;
+        for child in tree.tree.children(id).unwrap() {
+            if tree.tree.style(child).unwrap().display == Display::None {
+                continue;
+            }
+            let rect = tree.final_layout(child);
+            let (x, y) = (rect.location.x, rect.location.y);
+            if x + rect.size.width <= left || x >= right || y + rect.size.height <= top || y >= bottom {
+                continue;
+            }
+            if let Some(slot) = out.get_mut(count) {
+                *slot = child.into();
+            }
+            count += 1;
+        }
+        count
+    })
+}
+
+/// Drive `property` of `node` from the scroll offset of `source`, which must be an
+/// ancestor of `node`. Axis: 0 = vertical, 1 = horizontal. NaN `min`/`max` leave that
+/// side unclamped. Replaces any earlier link on the same property and applies the
+/// link right away. Returns false for unknown nodes, a non-ancestor source or an
+/// unknown property.
+#[no_mangle]
+pub extern "C" fn layout_set_scroll_link(
+    tree: *mut LayoutTree, node: u64, source: u64, property: u8, axis: u8,
+    base: f32, factor: f32, min: f32, max: f32,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let (id, source) = (NodeId::from(node), NodeId::from(source));
+        if !tree.contains(id) || !tree.contains(source) || source == id || !is_descendant_or_self(&tree.tree, id, source) {
+            return false;
+        }
+        if property >= PROPERTY_COUNT {
+            return false;
+        }
+        let link = ScrollLink { source, property, axis: if axis == 1 { 1 } else { 0 }, base, factor, min, max };
+        let links = tree.scroll_links.entry(id).or_default();
+        links.retain(|l| l.property != property);
+        links.push(link);
+        apply_link(tree, id, &link);
+        true
+    })
+}
+
+/// Stop driving `property` of `node` from scroll. The property keeps its last value.
+#[no_mangle]
+pub extern "C" fn layout_remove_scroll_link(tree: *mut LayoutTree, node: u64, property: u8) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if let Some(links) = tree.scroll_links.get_mut(&id) {
+            links.retain(|l| l.property != property);
+            if links.is_empty() {
+                tree.scroll_links.remove(&id);
+            }
+        }
+    })
+}
+
+fn apply_link(tree: &mut TreeState, target: NodeId, link: &ScrollLink) {
+    let offset = offset_of(tree, link.source);
+    let value = link.value(offset);
+    mutate_style(tree, target.into(), |s| {
+        set_property(s, link.property, value);
+    });
+}
+
+/// Drop scroll state held for or about a node that is going away.
+pub(crate) fn forget_node(tree: &mut TreeState, id: NodeId) {
+    tree.scroll_offsets.remove(&id);
+    tree.scroll_links.remove(&id);
+    tree.scroll_links.retain(|_, links| {
+        links.retain(|l| l.source != id);
+        !links.is_empty()
+    });
+}
@@ -0,0 +1,515 @@
+//! Serialized tree snapshots: one root's hierarchy and styles plus the available
+//! size it was computed against. Used by the `keystone-layout-diff` tool to replay
+//! production layouts under a different engine version. Whole trees serialize the
+//! same way, every root in turn, for saved window layouts and golden files.
+//!
+//! Styles are stored as CSS properties (see `css`) and only where they differ from
+//! the default, so files stay readable and independent of Taffy's internals.
+//!
+//! A fixture is a snapshot whose nodes also carry the rects they had when it was
+//! exported; `keystone-layout-diff --check` recomputes it and reports every node
+//! that no longer lands there, so a misbehaving production layout becomes a
+//! regression test.
+//!
+//! The same node objects, written by hand, describe subtrees for
+//! `layout_build_from_text`; there the style map may also use the shorthands of
+//! `css::apply_declarations`.
+
+use std::collections::BTreeMap;
+use std::ffi::{c_char, CString};
+
+use serde::{Deserialize, Serialize};
+use taffy::prelude::*;
+
+use crate::css;
+use crate::error::{ffi_guard, set_last_error};
+use crate::{c_str, layout_compute, out_buffer, sync, LayoutTree, TreeState, MUTATION_CREATED};
+
+/// Bumped whenever the snapshot schema changes incompatibly.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub width: f32,
+    pub height: f32,
+    pub root: SnapshotNode,
+}
+
+/// Every root of a tree, in node ID order.
+#[derive(Serialize, Deserialize)]
+pub struct TreeSnapshot {
+    pub version: u32,
+    #[serde(default)]
+    pub roots: Vec<SnapshotNode>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SnapshotNode {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub style: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SnapshotNode>,
+    /// Fixtures only: the parent-relative x, y, width and height to expect.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<[f32; 4]>,
+}
+
+impl Snapshot {
+    /// Capture `root` and its descendants. None for an unknown node.
+    pub(crate) fn capture(tree: &TreeState, root: u64, width: f32, height: f32) -> Option<Snapshot> {
+        let root = NodeId::from(root);
+        if !tree.contains(root) {
+            return None;
+        }
+        Some(Snapshot { version: SNAPSHOT_VERSION, width, height, root: capture_node(tree, root) })
+    }
+
+    /// Capture `root` as a fixture: its snapshot plus every node's current rect. If
+    /// `root` is the node the last compute ran on, the fixture replays against that
+    /// compute's available size. Otherwise its parent sized it, so the fixture pins
+    /// the root's border box (width, height and their min and max) to its current
+    /// size and replays against that. None for an unknown node.
+    pub(crate) fn capture_fixture(tree: &TreeState, root: u64) -> Option<Snapshot> {
+        let id = NodeId::from(root);
+        if !tree.contains(id) {
+            return None;
+        }
+        let size = tree.final_layout(id).size;
+        let mut node = capture_node(tree, id);
+        let (width, height) = match tree.last_compute {
+            Some((last, avail)) if last == id => (avail.width.unwrap_or(size.width), avail.height.unwrap_or(size.height)),
+            _ => {
+                node.style.insert("box-sizing".into(), "border-box".into());
+                for (axis, length) in [("width", size.width), ("height", size.height)] {
+                    for property in [axis.to_string(), format!("min-{axis}"), format!("max-{axis}")] {
+                        node.style.insert(property, format!("{length}px"));
+                    }
+                }
+                (size.width, size.height)
+            }
+        };
+        expect_rects(tree, id, &mut node);
+        Some(Snapshot { version: SNAPSHOT_VERSION, width, height, root: node })
+    }
+
+    /// Recompute a fixture and describe every node whose rect is off by more than
+    /// `threshold` from the one it expects, as `path: expected (...) got (...)`.
+    /// The root is only checked for size: where a parent would have put it is not
+    /// part of the fixture.
+    pub fn verify(&self, threshold: f32) -> Vec<String> {
+        let (mut tree, root) = self.compute();
+        let mut failures = Vec::new();
+        verify_node(tree.state.get_mut(), NodeId::from(root), &self.root, "root", threshold, &mut failures);
+        failures
+    }
+
+    /// Parse and validate a snapshot, including every style property in it.
+    pub fn from_json(text: &str) -> Result<Snapshot, String> {
+        let snapshot: Snapshot = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        check_version(snapshot.version)?;
+        snapshot.root.validate("root")?;
+        Ok(snapshot)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Rebuild the snapshot into a fresh tree and compute it. Returns the tree and its root.
+    pub fn compute(&self) -> (LayoutTree, u64) {
+        let mut state = TreeState::new();
+        let root = build_node(&mut state, &self.root, &mut Vec::new());
+        let mut tree = LayoutTree::from_state(state, false);
+        layout_compute(&mut tree, root, self.width, self.height);
+        (tree, root)
+    }
+}
+
+impl TreeSnapshot {
+    /// Capture every root of `tree`. Detached subtrees are awaiting destruction
+    /// and are left out.
+    pub(crate) fn capture(tree: &TreeState) -> TreeSnapshot {
+        let mut roots: Vec<NodeId> = tree.live.iter().copied()
+            .filter(|&id| tree.tree.parent(id).is_none() && !tree.detached.contains(&id))
+            .collect();
+        roots.sort_by_key(|&id| u64::from(id));
+        TreeSnapshot { version: SNAPSHOT_VERSION, roots: roots.into_iter().map(|id| capture_node(tree, id)).collect() }
+    }
+
+    /// Parse and validate a tree, including every style property in it.
+    pub fn from_json(text: &str) -> Result<TreeSnapshot, String> {
+        let snapshot: TreeSnapshot = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        check_version(snapshot.version)?;
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+
+    /// Check every style property in the tree.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        for (i, root) in self.roots.iter().enumerate() {
+            root.validate(&format!("roots/{i}"))?;
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Rebuild the roots, in order, into a fresh tree.
+    pub fn build(&self) -> LayoutTree {
+        let mut tree = TreeState::new();
+        for root in &self.roots {
+            build_node(&mut tree, root, &mut Vec::new());
+        }
+        LayoutTree::from_state(tree, false)
+    }
+}
+
+fn check_version(version: u32) -> Result<(), String> {
+    if version != SNAPSHOT_VERSION {
+        return Err(format!("unsupported snapshot version {version} (expected {SNAPSHOT_VERSION})"));
+    }
+    Ok(())
+}
+
+impl SnapshotNode {
+    fn style(&self) -> Result<Style, String> {
+        let mut style = Style::default();
+        for (name, value) in &self.style {
+            css::set_declaration(&mut style, name, value).map_err(|e| format!("{name}: {e}"))?;
+        }
+        Ok(style)
+    }
+
+    fn validate(&self, path: &str) -> Result<(), String> {
+        self.style().map_err(|e| format!("{path}: {e}"))?;
+        for (i, child) in self.children.iter().enumerate() {
+            child.validate(&format!("{path}/{i}"))?;
+        }
+        Ok(())
+    }
+}
+
+fn capture_node(tree: &TreeState, id: NodeId) -> SnapshotNode {
+    let style = css::style_to_properties(tree.tree.style(id).unwrap());
+    SnapshotNode {
+        name: tree.names.get(&id).cloned(),
+        style: style.into_iter().map(|(name, value)| (name.to_string(), value)).collect(),
+        children: tree.tree.children(id).unwrap().into_iter().map(|child| capture_node(tree, child)).collect(),
+        expected: None,
+    }
+}
+
+fn expect_rects(tree: &TreeState, id: NodeId, node: &mut SnapshotNode) {
+    let l = tree.final_layout(id);
+    node.expected = Some([l.location.x, l.location.y, l.size.width, l.size.height]);
+    for (child, child_node) in tree.tree.children(id).unwrap().into_iter().zip(&mut node.children) {
+        expect_rects(tree, child, child_node);
+    }
+}
+
+fn verify_node(
+    tree: &TreeState, id: NodeId, node: &SnapshotNode, path: &str, threshold: f32, failures: &mut Vec<String>,
+) {
+    if let Some(expected) = node.expected {
+        let l = tree.final_layout(id);
+        let actual = [l.location.x, l.location.y, l.size.width, l.size.height];
+        let checked = if path == "root" { 2 } else { 0 };
+        if expected.iter().zip(&actual).skip(checked).any(|(e, a)| (e - a).abs() > threshold) {
+            let fmt = |r: [f32; 4]| format!("x={} y={} w={} h={}", r[0], r[1], r[2], r[3]);
+            failures.push(format!("{path}: expected ({}) got ({})", fmt(expected), fmt(actual)));
+        }
+    }
+    for (i, (child, child_node)) in tree.tree.children(id).unwrap().into_iter().zip(&node.children).enumerate() {
+        verify_node(tree, child, child_node, &format!("{path}/{i}"), threshold, failures);
+    }
+}
+
+/// Build `node` and its descendants, appending their IDs to `created` in pre-order.
+fn build_node(tree: &mut TreeState, node: &SnapshotNode, created: &mut Vec<u64>) -> u64 {
+    let id = tree.tree.new_leaf(Style::default()).unwrap();
+    tree.live.insert(id);
+    tree.record(MUTATION_CREATED, id, id);
+    created.push(id.into());
+    // Styles were validated when the snapshot was loaded
+    tree.store_style(id, node.style().unwrap_or_default());
+    tree.style_generation += 1;
+    if let Some(name) = &node.name {
+        tree.set_name(id, Some(name.clone()));
+    }
+    for child in &node.children {
+        let child = build_node(tree, child, created);
+        tree.add_child(id, NodeId::from(child));
+    }
+    id.into()
+}
+
+/// Parse a `layout_build_from_text` document: one node object or an array of them.
+fn parse_subtrees(text: &str) -> Result<Vec<SnapshotNode>, String> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Document {
+        One(SnapshotNode),
+        Many(Vec<SnapshotNode>),
+    }
+    let nodes = match serde_json::from_str(text).map_err(|e| e.to_string())? {
+        Document::One(node) => vec![node],
+        Document::Many(nodes) => nodes,
+    };
+    for (i, node) in nodes.iter().enumerate() {
+        node.validate(&i.to_string())?;
+    }
+    Ok(nodes)
+}
+
+/// Snapshot `root` as JSON, for `keystone-layout-diff`. Returns null for an unknown
+/// node; free the string with `layout_string_free`.
+#[no_mangle]
+pub extern "C" fn layout_export_snapshot(tree: *const LayoutTree, root: u64, width: f32, height: f32) -> *mut c_char {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        match Snapshot::capture(tree, root, width, height) {
+            Some(snapshot) => CString::new(snapshot.to_json()).unwrap().into_raw(),
+            None => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Export `node`'s subtree as a regression fixture (see `Snapshot::capture_fixture`)
+/// for `keystone-layout-diff --check`. Returns null for an unknown node; free the
+/// string with `layout_string_free`.
+#[no_mangle]
+pub extern "C" fn layout_export_fixture(tree: *const LayoutTree, node: u64) -> *mut c_char {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        match Snapshot::capture_fixture(tree, node) {
+            Some(fixture) => CString::new(fixture.to_json()).unwrap().into_raw(),
+            None => std::ptr::null_mut(),
+        }
+    })
+}
+
+/// Serialize the whole tree as JSON: every root's hierarchy, styles and node
+/// names. Styles are the ones the engine lays out with, so tree options such as
+/// spacing scale come back baked into them. Free the string with
+/// `layout_string_free`.
+#[no_mangle]
+pub extern "C" fn layout_tree_to_json(tree: *const LayoutTree) -> *mut c_char {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        CString::new(TreeSnapshot::capture(tree).to_json()).unwrap().into_raw()
+    })
+}
+
+/// Build a new tree from `layout_tree_to_json` output. Nodes get new IDs; find
+/// them again by name or position. Returns null and sets the last error if the
+/// JSON is invalid. Free the tree with `layout_tree_free`.
+#[no_mangle]
+pub extern "C" fn layout_tree_from_json(json: *const c_char) -> *mut LayoutTree {
+    ffi_guard(|| {
+        let Some(json) = c_str(json) else {
+            set_last_error("tree JSON is null or not UTF-8");
+            return std::ptr::null_mut();
+        };
+        match TreeSnapshot::from_json(json) {
+            Ok(snapshot) => Box::into_raw(Box::new(snapshot.build())),
+            Err(e) => {
+                set_last_error(&e);
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Build the subtrees a JSON document describes and append them to `parent` (0
+/// leaves them roots). The document is one node object or an array of them, each
+/// `{"name": "...", "style": {"padding": "8px 12px", ...}, "children": [...]}` with
+/// every key optional. Returns the number of nodes created and writes their IDs in
+/// document order, at most `cap`. Nothing is built if the document is invalid:
+/// the call returns 0 and sets the last error.
+#[no_mangle]
+pub extern "C" fn layout_build_from_text(
+    tree: *mut LayoutTree, parent: u64, text: *const c_char, out_ids: *mut u64, cap: usize,
+) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if parent != 0 && !tree.contains(NodeId::from(parent)) {
+            set_last_error("unknown parent node");
+            return 0;
+        }
+        let Some(text) = c_str(text) else {
+            set_last_error("document is null or not UTF-8");
+            return 0;
+        };
+        let nodes = match parse_subtrees(text) {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                set_last_error(&e);
+                return 0;
+            }
+        };
+        let mut created = Vec::new();
+        for node in &nodes {
+            let root = build_node(tree, node, &mut created);
+            if parent != 0 {
+                tree.add_child(NodeId::from(parent), NodeId::from(root));
+            }
+        }
+        let out = out_buffer(out_ids, cap);
+        for (slot, &id) in out.iter_mut().zip(&created) {
+            *slot = id;
+        }
+        created.len()
+    })
+}
+
+/// Free a string returned by this library. Null is ignored.
+#[no_mangle]
+pub extern "C" fn layout_string_free(s: *mut c_char) {
+    ffi_guard(|| {
+        if !s.is_null() {
+            unsafe { drop(CString::from_raw(s)) };
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    /// Take ownership of a string returned by this module.
+    fn owned(s: *mut c_char) -> String {
+        assert!(!s.is_null());
+        let text = unsafe { std::ffi::CStr::from_ptr(s) }.to_str().unwrap().to_owned();
+        layout_string_free(s);
+        text
+    }
+
+    #[test]
+    fn trees_round_trip_through_json() {
+        let tree = layout_tree_new();
+        let root = layout_new_node(tree);
+        layout_set_node_name(tree, root, c"window".as_ptr());
+        layout_set_flex_direction(tree, root, 1);
+        layout_set_padding(tree, root, 0, 8.0);
+        for width in [40.0, 60.0] {
+            let child = layout_new_node(tree);
+            layout_set_width(tree, child, width);
+            layout_add_child(tree, root, child);
+        }
+        let overlay = layout_new_node(tree);
+        layout_set_node_name(tree, overlay, c"overlay".as_ptr());
+
+        let json = owned(layout_tree_to_json(tree));
+        let json = CString::new(json).unwrap();
+        let copy = layout_tree_from_json(json.as_ptr());
+        assert!(!copy.is_null());
+        assert_eq!(owned(layout_tree_to_json(copy)), json.to_str().unwrap());
+
+        let saved = TreeSnapshot::from_json(json.to_str().unwrap()).unwrap();
+        assert_eq!(saved.roots.len(), 2);
+        assert_eq!(saved.roots[0].name.as_deref(), Some("window"));
+        assert_eq!(saved.roots[0].children[1].style["width"], "60px");
+        assert_eq!(saved.roots[1].name.as_deref(), Some("overlay"));
+        layout_tree_free(copy);
+        layout_tree_free(tree);
+    }
+
+    #[test]
+    fn invalid_tree_json_is_refused() {
+        let invalid = [
+            c"{\"version\": 99, \"roots\": []}",
+            c"{\"version\": 1, \"roots\": [{\"style\": {\"width\": \"wide\"}}]}",
+            c"[",
+        ];
+        for json in invalid {
+            error::layout_clear_last_error();
+            assert!(layout_tree_from_json(json.as_ptr()).is_null());
+            assert!(!error::layout_last_error_message().is_null());
+        }
+    }
+
+    #[test]
+    fn text_documents_build_subtrees_in_document_order() {
+        let tree = layout_tree_new();
+        let parent = layout_new_node(tree);
+        let doc = c"[{\"name\": \"toolbar\", \"style\": {\"padding\": \"4px 8px\"}, \"children\": [{}, {}]}, {\"name\": \"body\"}]";
+        let mut ids = [0u64; 8];
+        assert_eq!(layout_build_from_text(tree, parent, doc.as_ptr(), ids.as_mut_ptr(), ids.len()), 4);
+        let [toolbar, first, second, body, ..] = ids;
+        assert_eq!(layout_find_by_name(tree, parent, c"toolbar".as_ptr()), toolbar);
+        assert_eq!(layout_find_by_name(tree, parent, c"body".as_ptr()), body);
+        assert_eq!((layout_get_child(tree, parent, 0), layout_get_child(tree, parent, 1)), (toolbar, body));
+        assert_eq!((layout_get_child(tree, toolbar, 0), layout_get_child(tree, toolbar, 1)), (first, second));
+        let padding = sync::shared(tree).1.tree.style(NodeId::from(toolbar)).unwrap().padding;
+        assert_eq!((padding.top, padding.left), (LengthPercentage::length(4.0), LengthPercentage::length(8.0)));
+
+        // The count is the number built even when `cap` is smaller
+        let mut one = [0u64; 1];
+        assert_eq!(layout_build_from_text(tree, 0, c"{\"children\": [{}]}".as_ptr(), one.as_mut_ptr(), 1), 2);
+        layout_tree_free(tree);
+    }
+
+    #[test]
+    fn invalid_text_documents_build_nothing() {
+        let tree = layout_tree_new();
+        let parent = layout_new_node(tree);
+        let invalid = [
+            c"[{\"name\": \"ok\"}, {\"style\": {\"margin\": \"1px 2px 3px 4px 5px\"}}]",
+            c"{\"children\": 3}",
+            c"not json",
+        ];
+        for doc in invalid {
+            error::layout_clear_last_error();
+            assert_eq!(layout_build_from_text(tree, parent, doc.as_ptr(), std::ptr::null_mut(), 0), 0);
+            assert!(!error::layout_last_error_message().is_null());
+        }
+        assert_eq!(layout_child_count(tree, parent), 0);
+        assert_eq!(sync::shared(tree).1.live.len(), 1);
+
+        error::layout_clear_last_error();
+        assert_eq!(layout_build_from_text(tree, parent + 1, c"{}".as_ptr(), std::ptr::null_mut(), 0), 0);
+        assert!(!error::layout_last_error_message().is_null());
+        layout_tree_free(tree);
+    }
+
+    #[test]
+    fn fixtures_replay_until_the_layout_changes() {
+        let tree = layout_tree_new();
+        let root = layout_new_node(tree);
+        layout_set_width(tree, root, 200.0);
+        layout_set_padding(tree, root, 0, 5.0);
+        let panel = layout_new_node(tree);
+        layout_set_flex_grow(tree, panel, 1.0);
+        layout_add_child(tree, root, panel);
+        let mut cells = Vec::new();
+        for width in [30.0, 50.0] {
+            let cell = layout_new_node(tree);
+            layout_set_width(tree, cell, width);
+            layout_set_height(tree, cell, 10.0);
+            layout_add_child(tree, panel, cell);
+            cells.push(cell);
+        }
+        layout_compute(tree, root, 200.0, 100.0);
+
+        // The computed root replays against its available size, an inner node
+        // against its own pinned size
+        for node in [root, panel] {
+            let mut fixture = Snapshot::from_json(&owned(layout_export_fixture(tree, node))).unwrap();
+            assert_eq!(fixture.verify(0.01), Vec::<String>::new());
+            let panel_node = if node == root { &mut fixture.root.children[0] } else { &mut fixture.root };
+            panel_node.children[0].style.insert("width".into(), "35px".into());
+            let failures = fixture.verify(0.01);
+            assert_eq!(failures.len(), 2, "{failures:?}");
+            assert!(failures[1].contains("expected (x=30 y=0 w=50 h=10) got (x=35 y=0 w=50 h=10)"), "{failures:?}");
+        }
+        layout_remove_node(tree, cells[1]);
+        assert!(layout_export_fixture(tree, cells[1]).is_null());
+        layout_tree_free(tree);
+    }
+}
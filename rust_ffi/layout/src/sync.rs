@@ -0,0 +1,172 @@
+//! Thread-safe trees.
+//!
+//! A tree made by `layout_tree_new_threadsafe` carries a reader-writer lock that
+//! every entry point taking the tree holds for the length of the call: shared for
+//! queries, exclusive for anything that changes the tree. The host's UI thread can
+//! then read results while a background thread builds or computes, and each call
+//! sees the tree either before or after any other. A sequence of calls is not
+//! atomic; use one thread per tree, or host-side versioning, where that matters.
+//!
+//! The lock is reentrant per thread, so callbacks that run during a call (measure
+//! functions, style resolvers, compute streams) may call back into the same tree.
+//! A callback of a shared call must not change the tree; that fails with the last
+//! error set instead of deadlocking. Trees made by `layout_tree_new` have no lock
+//! and pay nothing for it.
+//!
+//! Entry points take the tree as a raw pointer and only ever borrow the handle
+//! shared; `shared` and `exclusive` lock first and only then borrow the state
+//! inside it, so no reference to the state is live on two threads at once.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::ThreadId;
+
+use crate::error::ffi_guard;
+use crate::{LayoutTree, TreeState};
+
+/// A new empty tree whose entry points lock internally. Free it with
+/// `layout_tree_free` once no other thread can still be using it.
+#[no_mangle]
+pub extern "C" fn layout_tree_new_threadsafe() -> *mut LayoutTree {
+    ffi_guard(|| LayoutTree::into_raw(TreeState::new(), true))
+}
+
+#[no_mangle]
+pub extern "C" fn layout_tree_is_threadsafe(tree: *const LayoutTree) -> bool {
+    ffi_guard(|| is_threadsafe(tree))
+}
+
+pub(crate) fn is_threadsafe(tree: *const LayoutTree) -> bool {
+    unsafe { &*tree }.lock.is_some()
+}
+
+#[derive(Default)]
+pub(crate) struct TreeLock {
+    state: Mutex<LockState>,
+    released: Condvar,
+}
+
+#[derive(Default)]
+struct LockState {
+    /// The thread holding the lock exclusively, and how many times.
+    writer: Option<(ThreadId, usize)>,
+    /// Shared holds per thread.
+    readers: HashMap<ThreadId, usize>,
+    /// Set by `layout_tree_free`; calls that were waiting for the tree fail.
+    freed: bool,
+}
+
+pub(crate) struct TreeGuard {
+    lock: Arc<TreeLock>,
+    exclusive: bool,
+}
+
+impl Drop for TreeGuard {
+    fn drop(&mut self) {
+        let me = std::thread::current().id();
+        let mut state = self.lock.state.lock().unwrap_or_else(|e| e.into_inner());
+        let nested_in_writer = matches!(state.writer, Some((owner, _)) if owner == me);
+        if self.exclusive || nested_in_writer {
+            if let Some((_, depth)) = &mut state.writer {
+                *depth -= 1;
+                if *depth == 0 {
+                    state.writer = None;
+                }
+            }
+        } else if let Some(depth) = state.readers.get_mut(&me) {
+            *depth -= 1;
+            if *depth == 0 {
+                state.readers.remove(&me);
+            }
+        }
+        drop(state);
+        self.lock.released.notify_all();
+    }
+}
+
+/// Hold `tree` for reading until the guard drops (None for a tree without a
+/// lock), and its state. The state must not be used once the guard has dropped.
+pub(crate) fn shared<'a>(tree: *const LayoutTree) -> (Option<TreeGuard>, &'a TreeState) {
+    let handle = unsafe { &*tree };
+    let guard = handle.lock.clone().map(lock_shared);
+    (guard, unsafe { &*handle.state.get() })
+}
+
+// The locks take their own reference to the lock, which outlives the handle if the
+// tree is freed while they wait
+fn lock_shared(lock: Arc<TreeLock>) -> TreeGuard {
+    let me = std::thread::current().id();
+    let mut state = lock.state.lock().unwrap_or_else(|e| e.into_inner());
+    match &mut state.writer {
+        // Inside this thread's own exclusive call
+        Some((owner, depth)) if *owner == me => *depth += 1,
+        _ => {
+            // Re-entering a shared hold must not wait for a queued writer
+            while !state.readers.contains_key(&me) && state.writer.is_some() {
+                state = lock.released.wait(state).unwrap_or_else(|e| e.into_inner());
+            }
+            check_not_freed(&state);
+            *state.readers.entry(me).or_insert(0) += 1;
+        }
+    }
+    drop(state);
+    TreeGuard { lock, exclusive: false }
+}
+
+/// Hold `tree` for writing until the guard drops (None for a tree without a
+/// lock), and its state. The state must not be used once the guard has dropped.
+/// Panics (failing the entry point) if this thread only holds it for reading.
+pub(crate) fn exclusive<'a>(tree: *mut LayoutTree) -> (Option<TreeGuard>, &'a mut TreeState) {
+    let handle = unsafe { &*tree };
+    let guard = handle.lock.clone().map(lock_exclusive);
+    let state = unsafe { &mut *handle.state.get() };
+    state.handle = tree;
+    (guard, state)
+}
+
+fn lock_exclusive(lock: Arc<TreeLock>) -> TreeGuard {
+    let me = std::thread::current().id();
+    let mut state = lock.state.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((owner, depth)) = &mut state.writer {
+        if *owner == me {
+            *depth += 1;
+            drop(state);
+            return TreeGuard { lock, exclusive: true };
+        }
+    }
+    if state.readers.contains_key(&me) {
+        drop(state);
+        panic!("the tree is being read on this thread and cannot be changed until that call returns");
+    }
+    while state.writer.is_some() || !state.readers.is_empty() {
+        state = lock.released.wait(state).unwrap_or_else(|e| e.into_inner());
+    }
+    check_not_freed(&state);
+    state.writer = Some((me, 1));
+    drop(state);
+    TreeGuard { lock, exclusive: true }
+}
+
+/// Before `tree` is freed: wait for the calls in flight on other threads, and make
+/// the calls still waiting fail once the returned guard drops. Panics if this
+/// thread is inside a call on the tree, which would go on using it.
+pub(crate) fn retire(tree: *mut LayoutTree) -> Option<TreeGuard> {
+    let lock = unsafe { &*tree }.lock.clone()?;
+    let me = std::thread::current().id();
+    {
+        let state = lock.state.lock().unwrap_or_else(|e| e.into_inner());
+        if matches!(state.writer, Some((owner, _)) if owner == me) || state.readers.contains_key(&me) {
+            drop(state);
+            panic!("a tree cannot be freed from inside one of its own calls");
+        }
+    }
+    let guard = lock_exclusive(lock);
+    guard.lock.state.lock().unwrap_or_else(|e| e.into_inner()).freed = true;
+    Some(guard)
+}
+
+fn check_not_freed(state: &LockState) {
+    if state.freed {
+        panic!("the tree was freed while this call waited for it");
+    }
+}
@@ -0,0 +1,346 @@
+//! Text leaves measured inside the library (the `text` feature).
+//!
+//! `layout_set_text` shapes a string once, against a face from `font`, and makes
+//! the node a leaf whose size is that of the wrapped text: computes measure it
+//! without calling back into the host. `layout_get_text_lines` then reports the
+//! line boxes at the node's laid-out width for the host to draw.
+//!
+//! Shaping is one glyph per character at its advance width, without kerning or
+//! ligatures; characters the face lacks come from the fallback families.
+//! Lines break after spaces, hyphens and CJK ideographs and at newlines; a word
+//! wider than the line overflows it. Trailing spaces hang past the line end.
+
+use std::ffi::c_char;
+use std::sync::Arc;
+
+use taffy::prelude::*;
+
+use crate::error::ffi_guard;
+use crate::{c_str, font, out_buffer, sync, LayoutTree, TreeState};
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct LayoutFontDesc {
+    /// UTF-8 family name; null, empty or unknown for the first fallback family.
+    pub family: *const c_char,
+    /// Font size in px.
+    pub size: f32,
+    /// 100 (thin) to 900 (black); 0 for 400.
+    pub weight: u16,
+    pub italic: bool,
+    /// Line height as a multiple of `size`; 0 for the face's own line spacing.
+    pub line_height: f32,
+}
+
+/// One wrapped line, in px relative to the node's top-left corner, with the byte
+/// range of the node's text it shows (without its line break).
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutTextLine {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Distance from the line's top to its baseline.
+    pub baseline: f32,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Make `node` a text leaf showing the UTF-8 string `utf8` in `font`. Its content
+/// size becomes that of the text, wrapped to the width it is given; a definite
+/// style size still wins, and nodes with children ignore it. Returns false, with
+/// nothing changed, for unknown nodes, invalid UTF-8, a non-positive font size or
+/// no usable font.
+#[no_mangle]
+pub extern "C" fn layout_set_text(tree: *mut LayoutTree, node: u64, utf8: *const c_char, font: &LayoutFontDesc) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        let Some(text) = c_str(utf8) else { return false };
+        if !tree.contains(id) || font.size.is_nan() || font.size <= 0.0 {
+            return false;
+        }
+        let Some(shaped) = Shaped::new(text, font) else { return false };
+        set_text(tree, id, Some(Arc::new(shaped)));
+        true
+    })
+}
+
+/// Turn a text leaf back into a plain node.
+#[no_mangle]
+pub extern "C" fn layout_clear_text(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        let id = NodeId::from(node);
+        if tree.contains(id) {
+            set_text(tree, id, None);
+        }
+    })
+}
+
+/// The text leaf's lines at its last laid-out content width. Returns the number
+/// of lines, 0 for nodes without text; only the first `cap` are written.
+#[no_mangle]
+pub extern "C" fn layout_get_text_lines(tree: *const LayoutTree, node: u64, out: *mut LayoutTextLine, cap: usize) -> usize {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        if !tree.contains(id) {
+            return 0;
+        }
+        let Some(text) = tree.tree.get_node_context(id).and_then(|c| c.text.clone()) else { return 0 };
+        // Wrap where the measure did, at the unrounded content width
+        let unrounded = tree.unrounded_layout(id);
+        let layout = tree.final_layout(id);
+        let lines = text.wrap(Some(unrounded.content_box_width()));
+        let out = out_buffer(out, cap);
+        for (index, (line, slot)) in lines.iter().zip(out.iter_mut()).enumerate() {
+            *slot = LayoutTextLine {
+                x: layout.padding.left + layout.border.left,
+                y: layout.padding.top + layout.border.top + index as f32 * text.line_height,
+                width: line.width,
+                height: text.line_height,
+                baseline: text.baseline,
+                start: text.byte(line.start),
+                end: text.byte(line.end),
+            };
+        }
+        lines.len()
+    })
+}
+
+fn set_text(tree: &mut TreeState, id: NodeId, text: Option<Arc<Shaped>>) {
+    let mut context = tree.tree.get_node_context(id).cloned().unwrap_or_default();
+    if context.text.is_none() && text.is_none() {
+        return;
+    }
+    context.text = text;
+    // Replacing the context marks the node dirty
+    let _ = tree.tree.set_node_context(id, Some(context));
+    tree.baseline_leaves.insert(id);
+}
+
+/// Text shaped at its font size, ready to wrap at any width.
+pub(crate) struct Shaped {
+    text: String,
+    clusters: Vec<Cluster>,
+    line_height: f32,
+    baseline: f32,
+}
+
+#[derive(Clone, Copy)]
+struct Cluster {
+    /// Byte offset of the character in the text.
+    start: u32,
+    advance: f32,
+    class: Break,
+}
+
+/// Where a line may break around a character.
+#[derive(Clone, Copy, PartialEq)]
+enum Break {
+    Never,
+    /// After; hangs at a line end.
+    Space,
+    After,
+    /// Before and after.
+    Around,
+    /// Must break after.
+    Line,
+}
+
+/// Clusters `start..end` of the text.
+struct Line {
+    start: usize,
+    end: usize,
+    /// Without trailing spaces.
+    width: f32,
+}
+
+impl Shaped {
+    fn new(text: &str, desc: &LayoutFontDesc) -> Option<Self> {
+        let weight = if desc.weight == 0 { 400 } else { desc.weight.clamp(1, 1000) };
+        let family = c_str(desc.family).filter(|f| !f.is_empty());
+        let (face, fallbacks) = {
+            let mut fonts = font::fonts();
+            let face = fonts.resolve(family, weight, desc.italic)?;
+            (face, fonts.fallbacks(weight, desc.italic))
+        };
+        let scale = desc.size / face.units_per_em;
+        let clusters = text.char_indices()
+            .map(|(start, ch)| {
+                let advance = match ch {
+                    '\n' | '\r' => 0.0,
+                    _ => std::iter::once(&face)
+                        .chain(&fallbacks)
+                        .find_map(|f| f.glyph(ch).map(|g| f.advance(g) * desc.size / f.units_per_em))
+                        .unwrap_or_else(|| face.advance(0) * scale),
+                };
+                Cluster { start: start as u32, advance, class: break_class(ch) }
+            })
+            .collect();
+        let natural = (face.ascent + face.descent + face.line_gap) * scale;
+        let line_height = if desc.line_height > 0.0 { desc.line_height * desc.size } else { natural };
+        // The line's extra height is shared above and below the glyphs
+        let baseline = (line_height - (face.ascent + face.descent) * scale) / 2.0 + face.ascent * scale;
+        Some(Shaped { text: text.to_owned(), clusters, line_height, baseline })
+    }
+
+    /// Taffy's measure of the leaf.
+    pub(crate) fn measure(&self, known: Size<Option<f32>>, available: Size<AvailableSpace>) -> Size<f32> {
+        let max = known.width.or(match available.width {
+            AvailableSpace::Definite(width) => Some(width),
+            AvailableSpace::MinContent => Some(0.0),
+            AvailableSpace::MaxContent => None,
+        });
+        let lines = self.wrap(max);
+        Size {
+            width: known.width.unwrap_or_else(|| lines.iter().map(|l| l.width).fold(0.0, f32::max)),
+            height: known.height.unwrap_or(lines.len() as f32 * self.line_height),
+        }
+    }
+
+    /// Greedy line breaking at `max` px wide (None for no wrapping).
+    fn wrap(&self, max: Option<f32>) -> Vec<Line> {
+        let max = max.unwrap_or(f32::INFINITY) + 0.001;
+        let mut lines = Vec::new();
+        let (mut start, mut full, mut width) = (0, 0.0, 0.0);
+        let mut word = 0;
+        while word < self.clusters.len() {
+            // A word runs to the next break opportunity, its trailing spaces included
+            let mut end = word;
+            let (mut word_full, mut word_width) = (0.0, 0.0);
+            loop {
+                let cluster = self.clusters[end];
+                word_full += cluster.advance;
+                if !matches!(cluster.class, Break::Space | Break::Line) {
+                    word_width = word_full;
+                }
+                end += 1;
+                if self.breaks_after(end - 1) {
+                    break;
+                }
+            }
+            if word > start && full + word_width > max {
+                lines.push(Line { start, end: word, width });
+                (start, full) = (word, 0.0);
+            }
+            width = full + word_width;
+            full += word_full;
+            if self.clusters[end - 1].class == Break::Line {
+                lines.push(Line { start, end: end - 1, width });
+                (start, full, width) = (end, 0.0, 0.0);
+            }
+            word = end;
+        }
+        if start < self.clusters.len() {
+            lines.push(Line { start, end: self.clusters.len(), width });
+        }
+        lines
+    }
+
+    /// Whether a line may break between cluster `index` and the next. Spaces and
+    /// line breaks stay with the word before them.
+    fn breaks_after(&self, index: usize) -> bool {
+        let Some(next) = self.clusters.get(index + 1) else { return true };
+        let class = self.clusters[index].class;
+        if class == Break::Line {
+            return true;
+        }
+        !matches!(next.class, Break::Space | Break::Line) && (class != Break::Never || next.class == Break::Around)
+    }
+
+    /// Distance from the top to the first line's baseline; None without lines.
+    pub(crate) fn first_baseline(&self) -> Option<f32> {
+        (!self.clusters.is_empty()).then_some(self.baseline)
+    }
+
+    fn byte(&self, cluster: usize) -> u32 {
+        self.clusters.get(cluster).map_or(self.text.len() as u32, |c| c.start)
+    }
+}
+
+fn break_class(ch: char) -> Break {
+    match ch {
+        '\n' => Break::Line,
+        ' ' | '\t' | '\r' | '\u{3000}' => Break::Space,
+        '-' | '\u{2010}' | '\u{2013}' | '\u{2014}' | '\u{200B}' | '/' => Break::After,
+        '\u{2E80}'..='\u{9FFF}' | '\u{AC00}'..='\u{D7AF}' | '\u{F900}'..='\u{FAFF}' | '\u{FF00}'..='\u{FFEF}'
+        | '\u{20000}'..='\u{3FFFF}' => Break::Around,
+        _ => Break::Never,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{layout_compute, layout_new_node, layout_set_width, layout_tree_free, layout_tree_new};
+
+    /// `text` shaped at a 10 px advance per character, whatever fonts exist.
+    fn monospace(text: &str) -> Shaped {
+        let clusters = text.char_indices()
+            .map(|(start, ch)| {
+                let advance = if ch == '\n' { 0.0 } else { 10.0 };
+                Cluster { start: start as u32, advance, class: break_class(ch) }
+            })
+            .collect();
+        Shaped { text: text.to_owned(), clusters, line_height: 12.0, baseline: 9.0 }
+    }
+
+    fn lines(text: &str, max: Option<f32>) -> Vec<(usize, usize, f32)> {
+        monospace(text).wrap(max).iter().map(|l| (l.start, l.end, l.width)).collect()
+    }
+
+    #[test]
+    fn lines_break_at_opportunities_that_fit() {
+        assert_eq!(lines("hello world", None), [(0, 11, 110.0)]);
+        // The space hangs past the end of the first line
+        assert_eq!(lines("hello world", Some(60.0)), [(0, 6, 50.0), (6, 11, 50.0)]);
+        assert_eq!(lines("hello world", Some(50.0)), [(0, 6, 50.0), (6, 11, 50.0)]);
+        assert_eq!(lines("a b c", Some(30.0)), [(0, 4, 30.0), (4, 5, 10.0)]);
+        assert_eq!(lines("well-known", Some(60.0)), [(0, 5, 50.0), (5, 10, 50.0)]);
+        assert_eq!(lines("漢字です", Some(20.0)), [(0, 2, 20.0), (2, 4, 20.0)]);
+        // A word wider than the line overflows it
+        assert_eq!(lines("overflowing", Some(30.0)), [(0, 11, 110.0)]);
+        assert_eq!(lines("ab\ncd", None), [(0, 2, 20.0), (3, 5, 20.0)]);
+        assert_eq!(lines("", Some(30.0)), []);
+    }
+
+    #[test]
+    fn measures_follow_the_available_width() {
+        let text = monospace("one two three");
+        let unknown = Size { width: None, height: None };
+        let measure = |width| text.measure(unknown, Size { width, height: AvailableSpace::MaxContent });
+        assert_eq!(measure(AvailableSpace::MaxContent), Size { width: 130.0, height: 12.0 });
+        assert_eq!(measure(AvailableSpace::MinContent), Size { width: 50.0, height: 36.0 });
+        assert_eq!(measure(AvailableSpace::Definite(80.0)), Size { width: 70.0, height: 24.0 });
+        let fixed = text.measure(Size { width: Some(80.0), height: None }, Size::MAX_CONTENT);
+        assert_eq!(fixed, Size { width: 80.0, height: 24.0 });
+        assert_eq!(monospace("").first_baseline(), None);
+    }
+
+    #[test]
+    fn text_leaves_report_their_lines() {
+        let tree = layout_tree_new();
+        let node = layout_new_node(tree);
+        let font = LayoutFontDesc { family: std::ptr::null(), size: 16.0, weight: 0, italic: false, line_height: 1.5 };
+        assert!(!layout_set_text(tree, node, c"text".as_ptr(), &LayoutFontDesc { size: 0.0, ..font }));
+        if !layout_set_text(tree, node, c"several words to wrap".as_ptr(), &font) {
+            // No usable system font here; the wrapping itself is covered above
+            layout_tree_free(tree);
+            return;
+        }
+        layout_set_width(tree, node, 1.0);
+        layout_compute(tree, node, 1.0, f32::INFINITY);
+        let mut out = [LayoutTextLine::default(); 8];
+        assert_eq!(layout_get_text_lines(tree, node, out.as_mut_ptr(), out.len()), 4);
+        let ranges: Vec<(u32, u32)> = out[..4].iter().map(|l| (l.start, l.end)).collect();
+        assert_eq!(ranges, [(0, 8), (8, 14), (14, 17), (17, 21)]);
+        assert!(out[..4].iter().enumerate().all(|(i, l)| l.y == i as f32 * 24.0 && l.height == 24.0));
+
+        layout_clear_text(tree, node);
+        assert_eq!(layout_get_text_lines(tree, node, out.as_mut_ptr(), out.len()), 0);
+        layout_tree_free(tree);
+    }
+}
@@ -0,0 +1,377 @@
+//! Virtualized lists.
+//!
+//! A virtual list stands for `count` items laid end to end along its main axis,
+//! each `extent` px long or as long as a measure callback says, of which only a
+//! window is realized as child nodes. The host keeps the children for items
+//! `first..first + children` and sizes them to their extents. After each compute
+//! the children's layouts move down the main axis by the extent of the items
+//! before the window, so they land where those items would be, and the list's
+//! content size covers every item, so its scroll size does. Layout getters and
+//! exports report the moved layouts; `layout_get_result_unrounded` reports Taffy's.
+//! `layout_get_virtual_range` maps the scroll offset to the items the host should
+//! realize next.
+//!
+//! The main axis is the list's flex direction, or vertical for non-flex lists;
+//! lists with a reversed flex direction are not virtualized. Items are back to
+//! back: a main-axis gap is not accounted for, so put spacing in the extents.
+
+use std::ffi::c_void;
+
+use taffy::prelude::*;
+
+use crate::error::ffi_guard;
+use crate::{is_descendant_or_self, scroll, sync, LayoutTree, TreeState};
+
+/// Extent in px of item `index` of virtual list `node`. Called under the tree's
+/// lock: it must not call back into the tree.
+pub type LayoutItemExtentFn = extern "C" fn(user_data: *mut c_void, node: u64, index: usize) -> f32;
+
+#[derive(Clone)]
+pub(crate) struct VirtualList {
+    count: usize,
+    extent: f32,
+    measure: Option<(LayoutItemExtentFn, *mut c_void)>,
+    /// Start of every item and end of the last, while there is a measure callback.
+    offsets: Vec<f32>,
+    /// Item shown by the first child.
+    first: usize,
+}
+
+impl VirtualList {
+    /// Distance from the start of the first item to the start of item `index`
+    /// (`count` for the end of the last).
+    fn offset(&self, index: usize) -> f32 {
+        let index = index.min(self.count);
+        match self.measure {
+            Some(_) => self.offsets[index],
+            None => index as f32 * self.extent,
+        }
+    }
+
+    /// Items overlapping `start..end` of the list's length.
+    fn items_in(&self, start: f32, end: f32) -> (usize, usize) {
+        if self.measure.is_none() {
+            if self.extent.is_nan() || self.extent <= 0.0 {
+                return (0, 0);
+            }
+            let first = (start / self.extent).floor().clamp(0.0, self.count as f32) as usize;
+            let last = (end / self.extent).ceil().clamp(0.0, self.count as f32) as usize;
+            return (first, last.max(first));
+        }
+        // Items ending at or before start, and starting before end
+        let first = self.offsets[1..].partition_point(|&e| e <= start);
+        let last = self.offsets[..self.count].partition_point(|&s| s < end);
+        (first, last.max(first))
+    }
+
+    fn remeasure(&mut self, node: u64) {
+        self.offsets.clear();
+        let Some((callback, user_data)) = self.measure else { return };
+        self.offsets.reserve(self.count + 1);
+        let mut sum = 0.0f64;
+        self.offsets.push(0.0);
+        for index in 0..self.count {
+            let extent = callback(user_data, node, index);
+            if extent.is_finite() && extent > 0.0 {
+                sum += extent as f64;
+            }
+            self.offsets.push(sum as f32);
+        }
+    }
+}
+
+/// Make `node` a virtual list of `count` items, or change its count; the window
+/// is clamped to it.
+#[no_mangle]
+pub extern "C" fn layout_set_virtual_item_count(tree: *mut LayoutTree, node: u64, count: usize) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if let Some(list) = list_mut(tree, node) {
+            if list.count != count {
+                list.count = count;
+                list.remeasure(node);
+                window_changed(tree, node);
+            }
+        }
+    })
+}
+
+/// Make `node` a virtual list of items `px` long, used while it has no measure
+/// callback. Lists without an extent have no items in range.
+#[no_mangle]
+pub extern "C" fn layout_set_virtual_item_extent(tree: *mut LayoutTree, node: u64, px: f32) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if let Some(list) = list_mut(tree, node) {
+            if list.extent != px {
+                list.extent = px;
+                window_changed(tree, node);
+            }
+        }
+    })
+}
+
+/// Measure the items of virtual list `node` with `callback` instead of a fixed
+/// extent (null returns to the extent). Every item is measured now and whenever
+/// the count changes or `layout_remeasure_virtual_items` is called; extents that
+/// are not positive count as 0.
+#[no_mangle]
+pub extern "C" fn layout_set_virtual_item_measure(
+    tree: *mut LayoutTree, node: u64, callback: Option<LayoutItemExtentFn>, user_data: *mut c_void,
+) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if let Some(list) = list_mut(tree, node) {
+            list.measure = callback.map(|callback| (callback, user_data));
+            list.remeasure(node);
+            window_changed(tree, node);
+        }
+    })
+}
+
+/// Measure every item of virtual list `node` again, after their contents changed.
+#[no_mangle]
+pub extern "C" fn layout_remeasure_virtual_items(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if let Some(list) = tree.virtual_lists.get_mut(&NodeId::from(node)) {
+            list.remeasure(node);
+            window_changed(tree, node);
+        }
+    })
+}
+
+/// Set the item that virtual list `node`'s first child shows; its in-flow
+/// children show the items after it in order.
+#[no_mangle]
+pub extern "C" fn layout_set_virtual_first_index(tree: *mut LayoutTree, node: u64, first: usize) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if let Some(list) = list_mut(tree, node) {
+            if list.first != first {
+                list.first = first;
+                window_changed(tree, node);
+            }
+        }
+    })
+}
+
+/// Turn a virtual list back into a plain container.
+#[no_mangle]
+pub extern "C" fn layout_clear_virtual_list(tree: *mut LayoutTree, node: u64) {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::exclusive(tree);
+        if tree.virtual_lists.remove(&NodeId::from(node)).is_some() {
+            window_changed(tree, node);
+        }
+    })
+}
+
+/// The items of virtual list `node` that its viewport shows at the current
+/// scroll offset and last layout, widened by `overscan` px on both sides:
+/// `first..end`. Returns false, leaving the outputs unchanged, for nodes that are
+/// not virtual lists or have a reversed flex direction.
+#[no_mangle]
+pub extern "C" fn layout_get_virtual_range(
+    tree: *const LayoutTree, node: u64, overscan: f32, out_first: &mut usize, out_end: &mut usize,
+) -> bool {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        let id = NodeId::from(node);
+        let Some(list) = tree.virtual_lists.get(&id) else { return false };
+        if !tree.contains(id) {
+            return false;
+        }
+        let Some(horizontal) = main_axis(tree, id) else { return false };
+        let layout = tree.final_layout(id);
+        let offset = scroll::offset_of(tree, id);
+        // The padding box less scrollbars, and where the first item starts
+        let (low, high, first, scroll) = if horizontal {
+            let high = layout.size.width - layout.border.right - layout.scrollbar_size.width;
+            (layout.border.left, high, layout.border.left + layout.padding.left, offset.x)
+        } else {
+            let high = layout.size.height - layout.border.bottom - layout.scrollbar_size.height;
+            (layout.border.top, high, layout.border.top + layout.padding.top, offset.y)
+        };
+        // The viewport, in px from the start of the first item
+        let (from, to) = (low + scroll - first, high + scroll - first);
+        let overscan = if overscan.is_finite() { overscan.max(0.0) } else { 0.0 };
+        (*out_first, *out_end) = list.items_in(from - overscan, to + overscan);
+        true
+    })
+}
+
+/// Distance in px along virtual list `node`'s main axis from the start of its
+/// first item to the start of item `index` (`count` for the end of the last), for
+/// scrolling to an item. NaN for nodes that are not virtual lists or an index
+/// past the count.
+#[no_mangle]
+pub extern "C" fn layout_get_virtual_item_offset(tree: *const LayoutTree, node: u64, index: usize) -> f32 {
+    ffi_guard(|| {
+        let (_lock, tree) = sync::shared(tree);
+        match tree.virtual_lists.get(&NodeId::from(node)) {
+            Some(list) if index <= list.count => list.offset(index),
+            _ => f32::NAN,
+        }
+    })
+}
+
+fn list_mut(tree: &mut TreeState, node: u64) -> Option<&mut VirtualList> {
+    let id = NodeId::from(node);
+    if !tree.contains(id) {
+        return None;
+    }
+    Some(tree.virtual_lists.entry(id).or_insert(VirtualList {
+        count: 0,
+        extent: 0.0,
+        measure: None,
+        offsets: Vec::new(),
+        first: 0,
+    }))
+}
+
+/// Whether the list's main axis is horizontal; None for reversed flex directions.
+fn main_axis(tree: &TreeState, id: NodeId) -> Option<bool> {
+    let style = tree.tree.style(id).unwrap();
+    match (style.display, style.flex_direction) {
+        (Display::Flex, FlexDirection::Row) => Some(true),
+        (Display::Flex, FlexDirection::RowReverse | FlexDirection::ColumnReverse) => None,
+        _ => Some(false),
+    }
+}
+
+/// Move the children of the virtual lists under `root` to their items, and size
+/// the lists' content to all items, over the layouts of the compute just run.
+pub(crate) fn place(tree: &mut TreeState, root: NodeId) {
+    if tree.virtual_lists.is_empty() {
+        return;
+    }
+    let lists: Vec<NodeId> = tree.virtual_lists.keys().copied()
+        .filter(|&id| is_descendant_or_self(&tree.tree, id, root))
+        .collect();
+    for id in lists {
+        let Some(horizontal) = main_axis(tree, id) else { continue };
+        let list = &tree.virtual_lists[&id];
+        let mut shift = list.offset(list.first);
+        let total = list.offset(list.count);
+        if tree.rounding {
+            shift = (shift * tree.scale_factor).round() / tree.scale_factor;
+        }
+        let mut layout = *tree.final_layout(id);
+        let rounded = tree.rounded_layout(id).content_size;
+        if horizontal {
+            let extent = layout.border.left + layout.padding.left + total + layout.padding.right;
+            layout.content_size.width = rounded.width.max(extent);
+        } else {
+            let extent = layout.border.top + layout.padding.top + total + layout.padding.bottom;
+            layout.content_size.height = rounded.height.max(extent);
+        }
+        tree.layout_overrides.insert(id, layout);
+        if shift == 0.0 {
+            continue;
+        }
+        for child in tree.tree.children(id).unwrap() {
+            if tree.tree.style(child).unwrap().position == Position::Absolute {
+                continue;
+            }
+            let mut layout = *tree.final_layout(child);
+            if horizontal {
+                layout.location.x += shift;
+            } else {
+                layout.location.y += shift;
+            }
+            tree.layout_overrides.insert(child, layout);
+        }
+    }
+}
+
+/// Lay the list out again on the next compute.
+fn window_changed(tree: &mut TreeState, node: u64) {
+    let _ = tree.tree.mark_dirty(NodeId::from(node));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scroll::layout_set_scroll_offset;
+    use crate::{layout_compute, layout_new_node, layout_set_height, layout_set_width, layout_tree_free, layout_tree_new};
+
+    fn fixed(count: usize, extent: f32) -> VirtualList {
+        VirtualList { count, extent, measure: None, offsets: Vec::new(), first: 0 }
+    }
+
+    /// Item `index` is `index + 1` px long.
+    extern "C" fn growing(_user_data: *mut c_void, _node: u64, index: usize) -> f32 {
+        (index + 1) as f32
+    }
+
+    #[test]
+    fn fixed_extents_window_up_to_the_ends() {
+        let list = fixed(100, 10.0);
+        assert_eq!(list.items_in(0.0, 15.0), (0, 2));
+        // Item edges: an item ending at `start` is out, one starting at `end` is out
+        assert_eq!(list.items_in(10.0, 20.0), (1, 2));
+        assert_eq!(list.items_in(-50.0, 5.0), (0, 1));
+        assert_eq!(list.items_in(-50.0, -10.0), (0, 0));
+        assert_eq!(list.items_in(990.0, 1010.0), (99, 100));
+        assert_eq!(list.items_in(1000.0, 1020.0), (100, 100));
+        assert_eq!(list.items_in(5000.0, 6000.0), (100, 100));
+        assert_eq!(list.offset(100), 1000.0);
+        assert_eq!(list.offset(500), 1000.0);
+
+        assert_eq!(fixed(100, 0.0).items_in(0.0, 100.0), (0, 0));
+        assert_eq!(fixed(100, f32::NAN).items_in(0.0, 100.0), (0, 0));
+        assert_eq!(fixed(0, 10.0).items_in(0.0, 100.0), (0, 0));
+    }
+
+    #[test]
+    fn measured_extents_window_up_to_the_ends() {
+        let mut list = fixed(4, 0.0);
+        list.measure = Some((growing, std::ptr::null_mut()));
+        list.remeasure(1);
+        assert_eq!(list.offsets, [0.0, 1.0, 3.0, 6.0, 10.0]);
+        assert_eq!(list.items_in(0.0, 1.0), (0, 1));
+        assert_eq!(list.items_in(1.0, 3.0), (1, 2));
+        assert_eq!(list.items_in(2.0, 7.0), (1, 4));
+        assert_eq!(list.items_in(-5.0, 0.0), (0, 0));
+        assert_eq!(list.items_in(10.0, 20.0), (4, 4));
+
+        list.count = 0;
+        list.remeasure(1);
+        assert_eq!(list.items_in(0.0, 10.0), (0, 0));
+        assert_eq!(list.offset(0), 0.0);
+    }
+
+    #[test]
+    fn ranges_follow_the_scroll_offset() {
+        let tree = layout_tree_new();
+        let list = layout_new_node(tree);
+        layout_set_width(tree, list, 100.0);
+        layout_set_height(tree, list, 20.0);
+        let plain = layout_new_node(tree);
+        let (mut first, mut end) = (usize::MAX, usize::MAX);
+        assert!(!layout_get_virtual_range(tree, plain, 0.0, &mut first, &mut end));
+        assert_eq!((first, end), (usize::MAX, usize::MAX));
+
+        // A row: ten items of 30 px along x
+        layout_set_virtual_item_count(tree, list, 10);
+        layout_set_virtual_item_extent(tree, list, 30.0);
+        layout_compute(tree, list, 100.0, 20.0);
+        let range = |scroll: f32, overscan: f32| {
+            layout_set_scroll_offset(tree, list, scroll, 0.0);
+            let (mut first, mut end) = (0, 0);
+            assert!(layout_get_virtual_range(tree, list, overscan, &mut first, &mut end));
+            (first, end)
+        };
+        assert_eq!(range(0.0, 0.0), (0, 4));
+        assert_eq!(range(45.0, 0.0), (1, 5));
+        assert_eq!(range(45.0, 10.0), (1, 6));
+        assert_eq!(range(0.0, f32::INFINITY), (0, 4));
+        assert_eq!(range(200.0, 0.0), (6, 10));
+        assert_eq!(range(200.0, 50.0), (5, 10));
+        assert_eq!(range(400.0, 0.0), (10, 10));
+        assert_eq!(layout_get_virtual_item_offset(tree, list, 10), 300.0);
+        assert!(layout_get_virtual_item_offset(tree, list, 11).is_nan());
+        layout_tree_free(tree);
+    }
+}